@@ -0,0 +1,49 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use lsproxy::read_file_lines;
+use tempfile::TempDir;
+
+const TOTAL_LINES: usize = 50_000;
+const START_LINE: u32 = 25_000;
+const END_LINE: u32 = 25_050;
+
+fn read_lines_benchmark(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("synthetic.py");
+    let content: String = (0..TOTAL_LINES)
+        .map(|i| format!("def function_{i}(value):\n    return value * {i}\n"))
+        .collect();
+    fs::write(&file_path, content).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("read_source_code_lines");
+
+    // How a range was read before: load the whole file and slice out the lines
+    group.bench_function("whole_file", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                let bytes = tokio::fs::read(&file_path).await.unwrap();
+                String::from_utf8_lossy(&bytes)
+                    .lines()
+                    .skip(START_LINE as usize)
+                    .take((END_LINE - START_LINE + 1) as usize)
+                    .collect::<Vec<&str>>()
+                    .join("\n")
+            })
+        })
+    });
+
+    group.bench_function("line_by_line", |b| {
+        b.iter(|| {
+            runtime
+                .block_on(read_file_lines(&file_path, START_LINE, END_LINE))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, read_lines_benchmark);
+criterion_main!(benches);
@@ -2,14 +2,22 @@ use lsp_types::{Location, LocationLink};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::hash::Hash;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, LazyLock, RwLock};
-use strum_macros::{Display, EnumString};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
 use utoipa::{IntoParams, ToSchema};
 
-use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::utils::file_utils::{uri_relative_path_string, uri_to_relative_path_string};
+use crate::utils::workspace_documents::{
+    CSHARP_EXTENSIONS, C_AND_CPP_EXTENSIONS, GOLANG_EXTENSIONS, JAVA_EXTENSIONS, PHP_EXTENSIONS,
+    PYTHON_EXTENSIONS, RUBY_EXTENSIONS, RUST_EXTENSIONS, TYPESCRIPT_AND_JAVASCRIPT_EXTENSIONS,
+};
 
 static GLOBAL_MOUNT_DIR: LazyLock<Arc<RwLock<PathBuf>>> =
     LazyLock::new(|| Arc::new(RwLock::new(PathBuf::from("/mnt/workspace"))));
@@ -44,11 +52,49 @@ pub fn set_global_mount_dir(path: impl AsRef<Path>) {
     *global_dir = path.as_ref().to_path_buf();
 }
 
+/// Machine-readable code of an API error
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumIter, Serialize, Deserialize, ToSchema,
+)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    FileNotFound,
+    IdentifierNotFound,
+    SymbolNotFound,
+    InvalidPath,
+    InvalidEdit,
+    UnsupportedFileType,
+    LspClientNotFound,
+    NotImplemented,
+    PayloadTooLarge,
+    Timeout,
+    InternalError,
+}
+
 /// Response returned when an API error occurs
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     /// Description of the error that occurred
     pub error: String,
+    /// Machine-readable code of the error
+    #[schema(example = "FILE_NOT_FOUND")]
+    pub error_code: ErrorCode,
+    /// Correlation ID of the request that failed, echoed from the `X-Request-ID` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Creates an error response, whose `request_id` is filled in by the request ID middleware.
+    pub fn new(error_code: ErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            error_code,
+            request_id: None,
+        }
+    }
 }
 
 /// Response returned by the health check endpoint
@@ -62,8 +108,108 @@ pub struct HealthResponse {
     pub languages: HashMap<SupportedLanguages, bool>,
 }
 
+/// State of a single language server process
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct LspClientStatus {
+    /// Language served by this client
+    #[schema(example = "python")]
+    pub language: String,
+    /// Process state ("running" or "exited")
+    #[schema(example = "running")]
+    pub status: String,
+    /// OS process ID of the language server
+    pub pid: Option<u32>,
+    /// Seconds since the language server process was started
+    pub uptime_secs: u64,
+    /// Number of restarts of the language server, including failed ones
+    pub restart_count: u32,
+}
+
+/// Response returned by the LSP status endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LspStatusResponse {
+    /// One entry per running language server client, sorted by language
+    pub clients: Vec<LspClientStatus>,
+}
+
+/// Response returned by the garbage collection endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GarbageCollectResponse {
+    /// Number of cache entries dropped, e.g. cached file contents and definitions
+    #[schema(example = 42)]
+    pub cleared_entries: usize,
+}
+
+/// Differences between the files listed for the language servers and the files on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyReport {
+    /// Files that were listed but no longer exist on disk, e.g. deleted before the file watcher
+    /// noticed. They have been dropped from the listed files.
+    #[schema(example = json!(["src/removed.py"]))]
+    pub stale_files: Vec<String>,
+    /// Files on disk that weren't listed yet. They have been added to the listed files.
+    #[schema(example = json!(["src/added.py"]))]
+    pub missing_from_lsp: Vec<String>,
+}
+
+/// Request to change the log filter at runtime.
+#[derive(Deserialize, ToSchema)]
+pub struct LogLevelRequest {
+    /// A filter in the `RUST_LOG` syntax: a default level and/or per-module directives.
+    #[schema(example = "lsproxy::lsp::client=debug,info")]
+    pub filter: String,
+}
+
+/// Request to change how verbosely a language server traces its protocol messages.
+#[derive(Deserialize, ToSchema)]
+pub struct SetLspTraceRequest {
+    /// The language whose server should be traced
+    #[schema(example = "python")]
+    pub language: SupportedLanguages,
+    /// The trace level
+    pub value: LspTraceValue,
+}
+
+/// How verbosely a language server traces its protocol messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LspTraceValue {
+    Off,
+    Messages,
+    Verbose,
+}
+
+impl From<LspTraceValue> for lsp_types::TraceValue {
+    fn from(value: LspTraceValue) -> Self {
+        match value {
+            LspTraceValue::Off => lsp_types::TraceValue::Off,
+            LspTraceValue::Messages => lsp_types::TraceValue::Messages,
+            LspTraceValue::Verbose => lsp_types::TraceValue::Verbose,
+        }
+    }
+}
+
+/// Request to restart a language server.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct RestartLspRequest {
+    /// The language whose server should be restarted
+    #[param(example = "python")]
+    pub language: SupportedLanguages,
+}
+
 #[derive(
-    Debug, EnumString, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema,
+    Debug,
+    EnumString,
+    EnumIter,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    ToSchema,
 )]
 #[strum(serialize_all = "lowercase")]
 pub enum SupportedLanguages {
@@ -88,8 +234,126 @@ pub enum SupportedLanguages {
     Ruby,
 }
 
+/// How much of a file is read when detecting its language from its content.
+const CONTENT_DETECTION_BYTES: u64 = 512;
+
+impl SupportedLanguages {
+    /// The name of the language for people, e.g. `C#`.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Python => "Python",
+            Self::TypeScriptJavaScript => "TypeScript/JavaScript",
+            Self::Rust => "Rust",
+            Self::CPP => "C/C++",
+            Self::CSharp => "C#",
+            Self::Java => "Java",
+            Self::Golang => "Go",
+            Self::PHP => "PHP",
+            Self::Ruby => "Ruby",
+        }
+    }
+
+    /// The file extensions handled by the language's server, without the leading dot.
+    pub fn file_extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Python => PYTHON_EXTENSIONS,
+            Self::TypeScriptJavaScript => TYPESCRIPT_AND_JAVASCRIPT_EXTENSIONS,
+            Self::Rust => RUST_EXTENSIONS,
+            Self::CPP => C_AND_CPP_EXTENSIONS,
+            Self::CSharp => CSHARP_EXTENSIONS,
+            Self::Java => JAVA_EXTENSIONS,
+            Self::Golang => GOLANG_EXTENSIONS,
+            Self::PHP => PHP_EXTENSIONS,
+            Self::Ruby => RUBY_EXTENSIONS,
+        }
+    }
+
+    /// The language id sent in `textDocument/didOpen` for the language's files.
+    ///
+    /// Files of a language server handling several languages may use a more specific id, e.g.
+    /// `javascript` or `c`, see [`detect_language_string`](crate::utils::file_utils::detect_language_string).
+    pub fn lsp_language_id(&self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::TypeScriptJavaScript => "typescript",
+            Self::Rust => "rust",
+            Self::CPP => "cpp",
+            Self::CSharp => "csharp",
+            Self::Java => "java",
+            Self::Golang => "golang",
+            Self::PHP => "php",
+            Self::Ruby => "ruby",
+        }
+    }
+
+    /// Guesses the language of a file from its first bytes, for files whose extension doesn't
+    /// identify it, e.g. extensionless scripts. Recognizes shebangs, `<?php` tags and a few
+    /// first-line markers such as `// @ts-check`.
+    pub fn detect_from_file_content(path: &str) -> Option<Self> {
+        let mut head = Vec::new();
+        std::fs::File::open(path)
+            .ok()?
+            .take(CONTENT_DETECTION_BYTES)
+            .read_to_end(&mut head)
+            .ok()?;
+        // Binary files aren't source code
+        if head.contains(&0) {
+            return None;
+        }
+
+        let text = String::from_utf8_lossy(&head);
+        let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+        if let Some(shebang) = text.strip_prefix("#!") {
+            return Self::from_shebang(shebang.lines().next().unwrap_or_default());
+        }
+        if text.starts_with("<?php") {
+            return Some(Self::PHP);
+        }
+
+        let first_line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        if ["// @ts-check", "// @ts-nocheck", "/// <reference"]
+            .iter()
+            .any(|marker| first_line.starts_with(marker))
+            || first_line.starts_with("\"use strict\"")
+            || first_line.starts_with("'use strict'")
+        {
+            Some(Self::TypeScriptJavaScript)
+        } else if first_line.starts_with('#') && first_line.contains("-*- coding") {
+            Some(Self::Python)
+        } else if first_line.starts_with("# frozen_string_literal:") {
+            Some(Self::Ruby)
+        } else {
+            None
+        }
+    }
+
+    /// Maps a shebang's interpreter, e.g. `/usr/bin/env python3`, to a language.
+    fn from_shebang(shebang: &str) -> Option<Self> {
+        let mut words = shebang.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            // Skip options such as `-S` and variable assignments
+            program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+        }
+
+        match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "python" | "pypy" => Some(Self::Python),
+            "node" | "nodejs" | "deno" | "bun" | "ts-node" | "tsx" => {
+                Some(Self::TypeScriptJavaScript)
+            }
+            "ruby" => Some(Self::Ruby),
+            "php" => Some(Self::PHP),
+            "rust-script" => Some(Self::Rust),
+            "java" => Some(Self::Java),
+            _ => None,
+        }
+    }
+}
+
 /// A position within a text document, using 0-based indexing
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+///
+/// Positions are ordered by line, then by character.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Position {
     /// 0-indexed line number.
     #[schema(example = 10)]
@@ -100,7 +364,7 @@ pub struct Position {
 }
 
 /// A position within a specific file in the workspace
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FilePosition {
     /// Path to the file, relative to the workspace root
     #[schema(example = "src/main.py")]
@@ -119,23 +383,70 @@ pub struct FileRange {
     pub range: Range,
 }
 
+impl FilePosition {
+    pub fn from_lsp_position(path: String, position: lsp_types::Position) -> Self {
+        FilePosition {
+            path,
+            position: position.into(),
+        }
+    }
+
+    /// Converts the start of an LSP location, with the path made relative to `mount_dir`.
+    pub fn from_lsp_location(location: Location, mount_dir: &Path) -> Self {
+        Self::from_lsp_position(
+            uri_relative_path_string(&location.uri, mount_dir),
+            location.range.start,
+        )
+    }
+
+    pub fn to_lsp_position(&self) -> lsp_types::Position {
+        self.position.clone().into()
+    }
+}
+
 impl FileRange {
     pub fn contains(&self, position: FilePosition) -> bool {
-        let pos = &position.position;
-        self.path == position.path
-            && self.range.start.line <= pos.line
-            && self.range.end.line >= pos.line
-            && (self.range.start.line != pos.line || self.range.start.character <= pos.character)
-            && (self.range.end.line != pos.line || self.range.end.character >= pos.character)
+        self.path == position.path && self.range.contains_position(&position.position)
+    }
+
+    pub fn from_lsp_range(path: String, range: lsp_types::Range) -> Self {
+        FileRange {
+            path,
+            range: range.into(),
+        }
+    }
+
+    /// Converts an LSP location, with the path made relative to `mount_dir`.
+    pub fn from_lsp_location(location: Location, mount_dir: &Path) -> Self {
+        Self::from_lsp_range(
+            uri_relative_path_string(&location.uri, mount_dir),
+            location.range,
+        )
+    }
+
+    pub fn to_lsp_range(&self) -> lsp_types::Range {
+        self.range.clone().into()
     }
 }
 
 impl From<FileRange> for lsp_types::Range {
     fn from(range: FileRange) -> Self {
-        lsp_types::Range::new(
-            lsp_types::Position::from(range.range.start),
-            lsp_types::Position::from(range.range.end),
-        )
+        range.range.into()
+    }
+}
+
+impl From<Range> for lsp_types::Range {
+    fn from(range: Range) -> Self {
+        lsp_types::Range::new(range.start.into(), range.end.into())
+    }
+}
+
+impl From<lsp_types::Range> for Range {
+    fn from(range: lsp_types::Range) -> Self {
+        Range {
+            start: Position::from(range.start),
+            end: Position::from(range.end),
+        }
     }
 }
 
@@ -199,7 +510,65 @@ pub struct Symbol {
 pub struct Identifier {
     pub name: String,
     pub file_range: FileRange,
-    pub kind: Option<String>,
+    pub kind: Option<IdentifierKind>,
+    /// The source text covered by `file_range`, which may be more than `name`, e.g.
+    /// `self.method_name` for an attribute usage.
+    #[serde(default)]
+    #[schema(example = "self.method_name")]
+    pub source_text: Option<String>,
+    /// The lines around the identifier, when source context was requested, e.g. with
+    /// `include_source_context` when finding referenced symbols.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "def process_user():\n    user = User()\n    print(\"Done\")")]
+    pub context_source: Option<String>,
+}
+
+/// How an identifier is used, from the ast-grep rule that matched it.
+///
+/// Serialized as the rule id, e.g. `function-call`. Rule ids without a variant, e.g. from
+/// custom rules, are kept as is in `Other`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentifierKind {
+    /// A call of a function or method, e.g. `search()`
+    FunctionCall,
+    /// A decorator, e.g. `@log_execution_time`
+    Decorator,
+    /// An instantiation of a class, e.g. `new AStar()`
+    ClassInstantiation,
+    /// An attribute, e.g. `[Serializable]`
+    AttributeUsage,
+    /// A rendered component, e.g. `<Board />`
+    ComponentRender,
+    /// Any other reference matched by the permissive rules
+    AllReferences,
+    #[serde(untagged)]
+    Other(String),
+}
+
+// The schema is a plain string rather than the derived one, which would describe `Other` as
+// an object.
+impl utoipa::PartialSchema for IdentifierKind {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        utoipa::openapi::ObjectBuilder::new()
+            .schema_type(utoipa::openapi::schema::Type::String)
+            .description(Some(
+                "The ast-grep rule that matched the identifier: `function-call`, `decorator`, \
+                 `class-instantiation`, `attribute-usage`, `component-render`, `all-references`, \
+                 or the id of a custom rule.",
+            ))
+            .examples(["function-call"])
+            .into()
+    }
+}
+
+impl ToSchema for IdentifierKind {}
+
+impl From<&str> for IdentifierKind {
+    fn from(rule_id: &str) -> Self {
+        serde_json::from_value(Value::String(rule_id.to_string()))
+            .unwrap_or_else(|_| Self::Other(rule_id.to_string()))
+    }
 }
 
 #[derive(Deserialize, ToSchema, IntoParams)]
@@ -218,12 +587,44 @@ pub struct GetDefinitionRequest {
     #[serde(default)]
     #[schema(example = false)]
     pub include_raw_response: bool,
+
+    /// Whether to search the workspace's ast-grep definitions by name when the langserver finds
+    /// no definition, or only ones outside the workspace.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub fallback_to_ast_grep: bool,
+}
+
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct DefinitionChainRequest {
+    pub position: FilePosition,
+
+    /// Maximum number of definitions to follow.
+    /// Defaults to 10.
+    #[schema(example = 10)]
+    pub max_hops: Option<u32>,
+}
+
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct CallersTreeRequest {
+    pub position: FilePosition,
+
+    /// How many levels of callers to follow, at most 10.
+    #[schema(example = 2)]
+    pub max_depth: u32,
 }
 
 #[derive(Deserialize, ToSchema, IntoParams)]
 pub struct GetReferencesRequest {
     pub identifier_position: FilePosition,
 
+    /// Whether to include the declaration of the symbol in the references.
+    /// Defaults to true.
+    #[serde(default = "default_true")]
+    #[schema(example = true)]
+    pub include_declaration: bool,
+
     /// Whether to include the source code of the symbol in the response.
     /// Defaults to none.
     #[serde(default)]
@@ -235,6 +636,20 @@ pub struct GetReferencesRequest {
     #[serde(default)]
     #[schema(example = false)]
     pub include_raw_response: bool,
+
+    /// Glob matched against workspace-relative paths; only references in matching files are
+    /// returned. e.g. `**/test_*.py` keeps only references from python test files.
+    /// Defaults to none (all files).
+    #[serde(default)]
+    #[schema(example = "**/test_*.py")]
+    pub file_pattern_filter: Option<String>,
+
+    /// Whether to search the workspace's ast-grep identifiers by name when the langserver finds
+    /// no references.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub fallback_to_ast_grep: bool,
 }
 
 /// Request to get all symbols that are referenced from a symbol at the given position, either
@@ -246,170 +661,1244 @@ pub struct GetReferencesRequest {
 /// all symbols referenced within that function's implementation.
 #[derive(Deserialize, ToSchema, IntoParams)]
 pub struct GetReferencedSymbolsRequest {
-    /// Whether to use the more permissive rules to find referenced symbols. This will be not just
-    /// code that is executed but also things like type hints and chained indirection.
+    /// Whether to scan the whole file with the more permissive rules. This finds not just code
+    /// that is executed but also things like type hints and chained indirection, anywhere in the
+    /// file rather than only within the symbol's range. The result is a superset of the one
+    /// without a full scan.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub full_scan: bool,
+
+    /// The identifier position of the symbol to find references within
+    pub identifier_position: FilePosition,
+
+    /// Whether to return each referenced symbol once rather than once per reference.
+    /// Workspace symbols are deduplicated by name and definition, and external symbols by name,
+    /// keeping the first reference.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub unique_symbols_only: bool,
+
+    /// Whether to return the workspace symbols grouped by the file they are defined in, in
+    /// `workspace_symbols_by_file`, instead of as a flat list in `workspace_symbols`.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub group_by_file: bool,
+
+    /// The maximum number of entries to return from each of `workspace_symbols`,
+    /// `external_symbols` and `not_found`. Returns all entries if omitted.
+    #[serde(default)]
+    #[schema(example = 50)]
+    pub limit: Option<usize>,
+
+    /// The number of entries to skip at the start of each of `workspace_symbols`,
+    /// `external_symbols` and `not_found`. Defaults to 0.
+    #[serde(default)]
+    #[schema(example = 0)]
+    pub offset: Option<usize>,
+
+    /// Whether to attach the source code around each reference, in its `context_source`.
+    /// Context stops being attached once the response holds about 256 KB of it.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub include_source_context: bool,
+
+    /// The number of lines before and after each reference's line to include in its
+    /// `context_source`. Defaults to 2.
+    #[serde(default)]
+    #[schema(example = 2)]
+    pub context_lines: Option<u32>,
+
+    /// Whether to leave out the workspace symbols defined in test files, e.g. `test_*.py`,
+    /// `*_test.rs`, `*Test.java` or `*.spec.ts`, to only see production code dependencies.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub exclude_test_files: bool,
+}
+
+/// Request to resolve identifiers to the symbols they are defined by, e.g. the
+/// `external_symbols` or `not_found` of a `find-referenced-symbols` response.
+#[derive(Deserialize, ToSchema)]
+pub struct FindAllDefinitionsRequest {
+    /// The identifiers to resolve, each looked up at the start of its range
+    pub identifiers: Vec<Identifier>,
+}
+
+/// Request to find all symbols referenced within a range of a file, e.g. a function body.
+#[derive(Deserialize, ToSchema)]
+pub struct GetReferencedSymbolsInRangeRequest {
+    /// The range to find referenced symbols in
+    pub file_range: FileRange,
+
+    /// Whether to use the more permissive rules to find referenced symbols, see
+    /// `find-referenced-symbols`. Only references within `file_range` are returned either way.
     /// Defaults to false.
     #[serde(default)]
     #[schema(example = false)]
     pub full_scan: bool,
+}
+
+/// Request to get the symbols in a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct FileSymbolsRequest {
+    /// The path to the file to get the symbols for, relative to the root of the workspace.
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+    /// Comma-separated symbol kinds to keep, e.g. `function,class`.
+    /// Defaults to all kinds.
+    #[serde(default)]
+    #[param(example = "function,class")]
+    pub kinds: Option<String>,
+    /// Whether to also return the comments marking work to do, i.e. containing `TODO`, `FIXME`,
+    /// `HACK` or `XXX`, with the kind `todo-comment` and the comment's text as name.
+    /// Defaults to false.
+    #[serde(default)]
+    #[param(example = false)]
+    pub include_comments: bool,
+}
+
+/// Request to get the symbols defined across the workspace.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct AllSymbolsRequest {
+    /// Comma-separated symbol kinds to keep, e.g. `class,interface`.
+    /// Defaults to all kinds except local variables.
+    #[serde(default)]
+    #[param(example = "class,interface")]
+    pub kind: Option<String>,
+}
+
+/// Request to get the references to every symbol defined in a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct AllReferencesInFileRequest {
+    /// The path to the file, relative to the root of the workspace. Also accepted as `file`.
+    #[serde(alias = "file")]
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+}
+
+/// Request to find the unused imports of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct UnusedImportsRequest {
+    /// The path to the file, relative to the root of the workspace. Also accepted as `file`.
+    #[serde(alias = "file")]
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+}
+
+/// Request to get the documentation of the symbols of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct SymbolDocsRequest {
+    /// The path to the file, relative to the root of the workspace. Also accepted as `file`.
+    #[serde(alias = "file")]
+    #[schema(example = "src/lib.rs")]
+    pub file_path: String,
+}
+
+/// Request to find symbols by their exact name.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct FindSymbolRequest {
+    /// The name of the symbol, matched exactly.
+    #[schema(example = "AStarGraph")]
+    pub name: String,
+
+    /// Only return symbols of this kind, e.g. `class` or `function`.
+    /// Defaults to none (all kinds).
+    #[serde(default)]
+    #[schema(example = "class")]
+    pub kind: Option<String>,
+}
+
+/// Response with the references to every symbol defined in a file
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AllReferencesInFileResponse {
+    /// The references to each symbol, keyed by symbol name and sorted by file and position.
+    /// Symbols sharing a name share an entry, and the declarations are included.
+    pub references: BTreeMap<String, Vec<FileRange>>,
+}
+
+/// A kind of symbol emitted by the ast-grep symbol rules, `todo-comment` from the comment rules
+/// or one of the `test-*` kinds from the test rules, serialized as the rule id.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumIter, Serialize, Deserialize, ToSchema,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SymbolKind {
+    Class,
+    Constant,
+    Enum,
+    Field,
+    Function,
+    FunctionDeclaration,
+    FunctionDefinition,
+    Global,
+    Implementation,
+    Interface,
+    LocalVariable,
+    Method,
+    Module,
+    Property,
+    Struct,
+    TestCase,
+    TestFunction,
+    TestMethod,
+    TodoComment,
+    Trait,
+    Type,
+    Variable,
+}
+
+/// A symbol kind that none of the ast-grep symbol rules emit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKind(pub String);
+
+impl fmt::Display for UnknownKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let valid_kinds: Vec<String> = SymbolKind::iter().map(|kind| kind.to_string()).collect();
+        write!(
+            f,
+            "Unknown symbol kind '{}'. Valid kinds are: {}",
+            self.0,
+            valid_kinds.join(", ")
+        )
+    }
+}
+
+impl FromStr for SymbolKind {
+    type Err = UnknownKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SymbolKind::iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| UnknownKind(s.to_string()))
+    }
+}
+
+/// Request to open a file in its language server.
+#[derive(Deserialize, ToSchema)]
+pub struct OpenFileRequest {
+    /// The path to the file to open, relative to the root of the workspace.
+    #[schema(example = "src/Main.java")]
+    pub file_path: String,
+}
+
+/// Request to get the folding ranges of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct FoldingRangesRequest {
+    /// The path to the file, relative to the root of the workspace.
+    #[serde(alias = "file")]
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+}
+
+/// A foldable region of a file, spanning whole lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FoldingRange {
+    /// First line of the region (0-indexed)
+    pub start_line: u32,
+    /// Last line of the region (0-indexed, inclusive)
+    pub end_line: u32,
+    /// Kind of region, if the language server reports one ("comment", "imports" or "region")
+    #[schema(example = "imports")]
+    pub kind: Option<String>,
+}
+
+impl From<lsp_types::FoldingRange> for FoldingRange {
+    fn from(range: lsp_types::FoldingRange) -> Self {
+        Self {
+            start_line: range.start_line,
+            end_line: range.end_line,
+            kind: range.kind.map(|kind| {
+                match kind {
+                    lsp_types::FoldingRangeKind::Comment => "comment",
+                    lsp_types::FoldingRangeKind::Imports => "imports",
+                    lsp_types::FoldingRangeKind::Region => "region",
+                }
+                .to_string()
+            }),
+        }
+    }
+}
+
+/// Response containing the folding ranges of a file, sorted by start line.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FoldingRangesResponse {
+    pub ranges: Vec<FoldingRange>,
+}
+
+/// Request to get the document links of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct DocumentLinksRequest {
+    /// The path to the file, relative to the root of the workspace.
+    #[serde(alias = "file")]
+    #[schema(example = "src/main.ts")]
+    pub file_path: String,
+}
+
+/// A link embedded in a file, such as a URL in a doc comment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DocumentLink {
+    /// Where the link appears in the file
+    pub range: FileRange,
+    /// The URI the link points to
+    #[schema(example = "https://developer.mozilla.org/en-US/docs/Web/JavaScript")]
+    pub target: String,
+}
+
+/// Response containing the links in a file, in document order.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentLinksResponse {
+    pub links: Vec<DocumentLink>,
+}
+
+/// Request to get the code lenses of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct CodeLensRequest {
+    /// The path to the file, relative to the root of the workspace.
+    #[serde(alias = "file")]
+    #[schema(example = "src/main.rs")]
+    pub file_path: String,
+}
+
+/// An annotation the language server shows above a line of code, e.g. "▶ Run" or "2 references".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CodeLens {
+    /// The code the lens applies to
+    pub range: FileRange,
+    /// The text of the lens
+    #[schema(example = "▶ Run")]
+    pub title: String,
+    /// Identifier of the language server command the lens triggers
+    #[schema(example = "rust-analyzer.runSingle")]
+    pub command: Option<String>,
+}
+
+/// Response containing the code lenses of a file, sorted by position.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CodeLensResponse {
+    pub lenses: Vec<CodeLens>,
+}
+
+/// Response indicating that the symbol at a position can be renamed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PrepareRenameResponse {
+    /// The range of the text that would be renamed
+    pub range: FileRange,
+    /// The current name, to be used as the default for the new name
+    #[schema(example = "AStarGraph")]
+    pub placeholder: String,
+}
+
+/// Request to rename the symbol at a position.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RenameSymbolRequest {
+    /// The position of the symbol to rename
+    pub identifier_position: FilePosition,
+    /// The new name of the symbol. Must be a valid identifier of the file's language.
+    #[schema(example = "PathGraph")]
+    pub new_name: String,
+}
+
+/// Query parameters of a rename.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct RenameSymbolQuery {
+    /// Whether to only return the edits of the rename rather than applying them.
+    /// Defaults to true, so that files are only modified with `dry_run=false`.
+    #[serde(default = "default_true")]
+    #[param(example = true)]
+    pub dry_run: bool,
+}
+
+/// Response to renaming a symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RenameSymbolResponse {
+    /// The edits the rename makes
+    pub edit: WorkspaceEdit,
+    /// The files that were modified, relative to the workspace root. Empty for a dry run.
+    #[schema(example = json!(["graph.py", "main.py"]))]
+    pub modified_files: Vec<String>,
+}
+
+/// Response containing the number of references to a symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReferenceCountResponse {
+    /// Number of references to the symbol, excluding its declaration
+    #[schema(example = 6)]
+    pub count: usize,
+}
+
+/// The number of references to the symbol at a position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReferenceCount {
+    /// The position the count was requested for
+    pub position: FilePosition,
+    /// Number of references to the symbol, excluding its declaration
+    #[schema(example = 6)]
+    pub count: usize,
+}
+
+/// A location where a symbol is used, classified by how it is used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SymbolUsage {
+    /// The range of the identifier at the usage
+    pub location: FileRange,
+    /// How the symbol is used: "definition", "import", "call", "type-annotation" or "reference"
+    /// for any other use
+    #[schema(example = "import")]
+    pub kind: String,
+}
+
+/// Response containing every usage of a symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SymbolUsagesResponse {
+    pub usages: Vec<SymbolUsage>,
+}
+
+/// Request to list the files in the workspace.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct ListFilesRequest {
+    /// Only list the files handled by this language's server.
+    /// Defaults to listing the files of every language.
+    #[serde(default)]
+    #[param(example = "python")]
+    pub language: Option<SupportedLanguages>,
+
+    /// Whether to return the size and modification time of each file, as a
+    /// `ListFilesResponse`, rather than only the file paths.
+    /// Defaults to false.
+    #[serde(default)]
+    #[param(example = false)]
+    pub include_metadata: bool,
+
+    /// Whether to return the language of the server owning each file, as `FileLanguage`
+    /// entries, rather than only the file paths. Can't be combined with `include_metadata`.
+    /// Defaults to false.
+    #[serde(default)]
+    #[param(example = false)]
+    pub include_language: bool,
+
+    /// How to sort the files.
+    /// Defaults to sorting by path.
+    #[serde(default)]
+    #[param(example = "path")]
+    pub sort_by: FileSortOrder,
+
+    /// Whether to return the files grouped by the language of the server owning them, as a
+    /// `GroupedFilesResponse`. Can't be combined with `include_metadata` or `include_language`.
+    /// Defaults to false.
+    #[serde(default)]
+    #[param(example = false)]
+    pub grouped: bool,
+}
+
+/// The order of the files listed by `list-files`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileSortOrder {
+    /// By path, lexicographically
+    #[default]
+    Path,
+    /// By file name, ignoring the directories
+    Name,
+    /// Grouped by language, in order of language name, then by path
+    Language,
+    /// By size, smallest first, then by path
+    Size,
+}
+
+/// A workspace file with its size and modification time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FileMetadata {
+    /// The path of the file, relative to the root of the workspace.
+    #[schema(example = "src/main.py")]
+    pub path: String,
+    #[schema(example = 2048)]
+    pub size_bytes: u64,
+    /// The last modification time, in seconds since the Unix epoch.
+    #[schema(example = 1717171717)]
+    pub last_modified: u64,
+}
+
+/// A workspace file with the language of the server owning it.
+///
+/// Files served by several language servers, e.g. C headers, are owned by the server of the
+/// language detected for them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FileLanguage {
+    /// The path of the file, relative to the root of the workspace.
+    #[schema(example = "src/main.py")]
+    pub path: String,
+    pub language: SupportedLanguages,
+}
+
+/// The workspace files owned by a language's server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LanguageFiles {
+    pub language: SupportedLanguages,
+    /// The paths of the files, relative to the root of the workspace.
+    #[schema(example = json!(["src/main.py"]))]
+    pub files: Vec<String>,
+}
+
+/// Response listing the workspace files grouped by language, in order of language name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GroupedFilesResponse {
+    pub groups: Vec<LanguageFiles>,
+}
+
+/// Response listing the workspace files with their metadata, sorted by path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ListFilesResponse {
+    pub files: Vec<FileMetadata>,
+}
+
+/// Request to detect the language of a file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct DetectLanguageRequest {
+    /// Path of the file relative to the workspace root. The file doesn't need to exist.
+    #[param(example = "src/main.rs")]
+    pub file: String,
+}
+
+/// The language whose server handles a file.
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DetectLanguageResponse {
+    /// The detected language, or null if no language server handles the file.
+    #[schema(example = "rust")]
+    pub language: Option<SupportedLanguages>,
+}
+
+/// Request to get the symbols in the workspace.
+#[allow(unused)] // TODO re-implement using textDocument/symbol
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct WorkspaceSymbolsRequest {
+    /// The query to search for.
+    #[schema(example = "User")]
+    pub query: String,
+
+    /// Whether to include the raw response from the langserver in the response.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub include_raw_response: bool,
+}
+
+/// Response to a definition request.
+///
+/// The definition(s) of the symbol, e.g. several for an overloaded function.
+/// Each has the range of the symbol's identifier and the full range of the symbol.
+///
+/// e.g. for the definition of `User` on line 5 of `src/main.py` with the code:
+/// ```
+/// 0: class User:
+/// _________^^^^
+/// 1:     def __init__(self, name, age):
+/// 2:         self.name = name
+/// 3:         self.age = age
+/// 4:
+/// 5: user = User("John", 30)
+/// __________^
+/// ```
+/// The identifier range of the definition will be line 0, characters 6-10, and the full range
+/// lines 0-3.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DefinitionResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The raw response from the langserver.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_definition
+    pub raw_response: Option<Value>,
+    pub definitions: Vec<DefinitionEntry>,
+    /// The source code of symbol definitions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_code_context: Option<Vec<CodeContext>>,
+    /// The identifier that was "clicked-on" to get the definition.
+    pub selected_identifier: Identifier,
+    /// Where the definitions came from.
+    #[serde(default)]
+    pub source: ResultSource,
+}
+
+/// A definition of a symbol.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DefinitionEntry {
+    /// The range of the symbol's identifier, e.g. the name of a function.
+    pub identifier_range: FileRange,
+    /// The full range of the symbol, e.g. a function including its body.
+    ///
+    /// Same as `identifier_range` when neither the langserver nor ast-grep know the symbol's
+    /// extent, e.g. for definitions outside the workspace.
+    pub full_range: FileRange,
+}
+
+/// How the locations in a [`DefinitionResponse`] or [`ReferencesResponse`] were found.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSource {
+    /// Returned by the langserver
+    #[default]
+    Lsp,
+    /// Matched by name against the workspace's ast-grep definitions or identifiers
+    AstGrep,
+}
+
+/// Response to a references request.
+///
+/// Points to the start position of the symbol's identifier.
+///
+/// e.g. for the references of `User` on line 0 character 6 of `src/main.py` with the code:
+/// ```
+/// 0: class User:
+/// 1:     def __init__(self, name, age):
+/// 2:         self.name = name
+/// 3:         self.age = age
+/// 4:
+/// 5: user = User("John", 30)
+/// _________^
+/// 6:
+/// 7: print(user.name)
+/// ```
+/// The references will be `[{"path": "src/main.py", "line": 5, "character": 7}]`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReferencesResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The raw response from the langserver.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references
+    pub raw_response: Option<Value>,
+
+    pub references: Vec<FilePosition>,
+
+    /// The source code around the references.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<CodeContext>>,
+    /// The identifier that was "clicked-on" to get the references.
+    pub selected_identifier: Identifier,
+    /// Where the references came from.
+    #[serde(default)]
+    pub source: ResultSource,
+}
+
+/// Response containing symbols referenced from the requested position
+///
+/// The symbols are categorized into:
+/// - workspace_symbols: References to symbols that were found and have definitions in the workspace
+/// - external_symbols: References to symbols from outside the workspace (built-in functions, external libraries)
+/// - not_found: References where the symbol definition could not be found
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReferencedSymbolsResponse {
+    pub workspace_symbols: Vec<ReferenceWithSymbolDefinitions>,
+    pub external_symbols: Vec<Identifier>,
+    pub not_found: Vec<Identifier>,
+    /// The workspace symbols grouped by definition file, sorted by file path, when requested
+    /// with `group_by_file`. `workspace_symbols` is empty then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_symbols_by_file: Option<Vec<FileGroup>>,
+    /// The number of workspace symbols before `limit` and `offset` were applied.
+    pub total_workspace_symbols: usize,
+    /// The number of external symbols before `limit` and `offset` were applied.
+    pub total_external_symbols: usize,
+    /// The number of symbols not found before `limit` and `offset` were applied.
+    pub total_not_found: usize,
+}
+
+/// Referenced workspace symbols defined in one file.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileGroup {
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+    /// The symbols with a definition in the file, in the order they are referenced.
+    /// A symbol with definitions in several files is listed in each of their groups.
+    pub symbols: Vec<ReferenceWithSymbolDefinitions>,
+}
+
+/// An identifier with the workspace symbols it is defined by.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IdentifierWithDefinition {
+    pub identifier: Identifier,
+    /// The symbols enclosing the identifier's definitions in the workspace. Empty if the
+    /// identifier is defined outside the workspace, e.g. a built-in, or couldn't be resolved.
+    pub definitions: Vec<Symbol>,
+}
+
+/// Response to resolving identifiers to their definitions.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FindAllDefinitionsResponse {
+    /// One result per requested identifier, in the same order.
+    pub results: Vec<IdentifierWithDefinition>,
+}
+
+pub type SymbolResponse = Vec<Symbol>;
+
+impl From<Location> for FilePosition {
+    fn from(location: Location) -> Self {
+        FilePosition::from_lsp_location(location, &get_mount_dir())
+    }
+}
+
+impl From<LocationLink> for FilePosition {
+    fn from(link: LocationLink) -> Self {
+        FilePosition::from_lsp_position(
+            uri_to_relative_path_string(&link.target_uri),
+            link.target_range.start,
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct FindIdentifierRequest {
+    /// The name of the identifier to search for.
+    #[schema(example = "User")]
+    pub name: String,
+    /// The path to the file to search for identifiers.
+    #[serde(alias = "path")]
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+    /// The position hint to search for identifiers. If not provided.
+    pub position: Option<Position>,
+    /// Only return identifiers of this kind.
+    /// Defaults to all kinds.
+    #[serde(default)]
+    pub kind: Option<IdentifierKind>,
+    /// Whether the name must match case-sensitively.
+    /// Defaults to true.
+    #[serde(default = "default_true")]
+    #[schema(example = true)]
+    pub case_sensitive: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct FindIdentifierResponse {
+    pub identifiers: Vec<Identifier>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Range {
+    /// The start position of the range.
+    pub start: Position,
+    /// The end position of the range.
+    pub end: Position,
+}
+
+impl Range {
+    /// Whether the position lies within the range, including both the start and the end.
+    pub fn contains_position(&self, position: &Position) -> bool {
+        self.start <= *position && *position <= self.end
+    }
+
+    /// Whether the two ranges share any text.
+    ///
+    /// Ranges are end-exclusive here, so adjacent ranges where one ends where the other starts
+    /// do not overlap. An empty range overlaps a range that strictly surrounds it.
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Whether the other range lies entirely within this one.
+    pub fn contains_range(&self, other: &Range) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the range covers no text, i.e. does not end after it starts.
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+}
+
+/// Request to format a file in the workspace
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct FormatDocumentRequest {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.py")]
+    pub file_path: String,
+    /// Size of a tab in spaces.
+    /// Defaults to 4.
+    #[serde(default)]
+    #[schema(example = 4)]
+    pub tab_size: Option<u32>,
+    /// Whether to indent with spaces rather than tabs.
+    /// Defaults to true.
+    #[serde(default)]
+    #[schema(example = true)]
+    pub insert_spaces: Option<bool>,
+    /// Whether to write the formatting edits to the file.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub apply: bool,
+}
+
+/// A textual edit to a file in the workspace
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextEditEntry {
+    /// The range of text to replace
+    pub range: FileRange,
+    /// The text to replace the range with
+    #[schema(example = "    ")]
+    pub new_text: String,
+}
+
+/// Response to a formatting request
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FormatResponse {
+    /// The edits needed to format the file. Empty if the file is already formatted.
+    pub edits: Vec<TextEditEntry>,
+    /// Whether the edits were written to the file
+    pub applied: bool,
+}
+
+impl TextEditEntry {
+    pub fn from_lsp(path: &str, edit: lsp_types::TextEdit) -> Self {
+        TextEditEntry {
+            range: FileRange::from_lsp_range(path.to_string(), edit.range),
+            new_text: edit.new_text,
+        }
+    }
+}
+
+/// A set of edits across files in the workspace
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceEdit {
+    /// The edits to make, keyed by file path relative to the workspace root
+    pub changes: BTreeMap<String, Vec<TextEditEntry>>,
+}
+
+impl From<lsp_types::WorkspaceEdit> for WorkspaceEdit {
+    /// Collects the text edits from both `changes` and `document_changes`.
+    /// Resource operations (creating, renaming, or deleting files) are not represented.
+    fn from(edit: lsp_types::WorkspaceEdit) -> Self {
+        let mut changes: BTreeMap<String, Vec<TextEditEntry>> = BTreeMap::new();
+        let mut add_edit = |uri: &lsp_types::Url, edit: lsp_types::TextEdit| {
+            let path = uri_to_relative_path_string(uri);
+            let entry = TextEditEntry::from_lsp(&path, edit);
+            changes.entry(path).or_default().push(entry);
+        };
+
+        for (uri, edits) in edit.changes.unwrap_or_default() {
+            for edit in edits {
+                add_edit(&uri, edit);
+            }
+        }
+
+        let document_edits = match edit.document_changes {
+            Some(lsp_types::DocumentChanges::Edits(edits)) => edits,
+            Some(lsp_types::DocumentChanges::Operations(operations)) => operations
+                .into_iter()
+                .filter_map(|operation| match operation {
+                    lsp_types::DocumentChangeOperation::Edit(edit) => Some(edit),
+                    lsp_types::DocumentChangeOperation::Op(_) => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        for document_edit in document_edits {
+            for edit in document_edit.edits {
+                let edit = match edit {
+                    lsp_types::OneOf::Left(edit) => edit,
+                    lsp_types::OneOf::Right(annotated) => annotated.text_edit,
+                };
+                add_edit(&document_edit.text_document.uri, edit);
+            }
+        }
+
+        WorkspaceEdit { changes }
+    }
+}
+
+impl WorkspaceEdit {
+    /// Converts to an LSP workspace edit, with the paths resolved against `mount_dir`.
+    ///
+    /// Returns the first path that can't be turned into a file URI as the error.
+    pub fn to_lsp_workspace_edit(
+        &self,
+        mount_dir: &Path,
+    ) -> Result<lsp_types::WorkspaceEdit, String> {
+        let mut changes = HashMap::new();
+        for (path, edits) in &self.changes {
+            let uri =
+                lsp_types::Url::from_file_path(mount_dir.join(path)).map_err(|_| path.clone())?;
+            let edits = edits
+                .iter()
+                .map(|edit| lsp_types::TextEdit {
+                    range: edit.range.to_lsp_range(),
+                    new_text: edit.new_text.clone(),
+                })
+                .collect();
+            changes.insert(uri, edits);
+        }
+        Ok(lsp_types::WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        })
+    }
+}
+
+/// Response to applying a workspace edit
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApplyEditResponse {
+    /// The files that were modified, relative to the workspace root
+    #[schema(example = json!(["src/main.py"]))]
+    pub modified_files: Vec<String>,
+}
+
+/// Request to rename a file in the workspace
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RenameFileRequest {
+    /// Current path of the file, relative to the workspace root
+    #[schema(example = "src/graph.py")]
+    pub old_path: String,
+    /// New path of the file, relative to the workspace root. Must not exist yet.
+    #[schema(example = "src/graph2.py")]
+    pub new_path: String,
+}
+
+/// Request to search the workspace files for literal text
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchRequest {
+    /// The text to search for, matched literally.
+    #[schema(example = "heapq")]
+    pub query: String,
+
+    /// Whether the search is case sensitive.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub case_sensitive: bool,
+
+    /// Glob matched against workspace-relative paths; only files that match are searched.
+    /// Defaults to none (all files).
+    #[serde(default)]
+    #[schema(example = "**/*.py")]
+    pub file_pattern: Option<String>,
+
+    /// The maximum number of matches to return. Returns all matches if omitted.
+    #[serde(default)]
+    #[schema(example = 100)]
+    pub max_results: Option<usize>,
+}
+
+/// A line containing a match of a text search
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchMatch {
+    /// The file containing the match, relative to the workspace root
+    #[schema(example = "src/search.py")]
+    pub path: String,
+    /// 0-indexed line of the match
+    #[schema(example = 0)]
+    pub line: u32,
+    /// 0-indexed character of the start of the match, in UTF-16 code units
+    #[schema(example = 7)]
+    pub character: u32,
+    /// The full text of the line, without the line ending
+    #[schema(example = "import heapq")]
+    pub line_text: String,
+}
+
+/// Response to a text search, with matches sorted by file path and position
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchResponse {
+    pub matches: Vec<TextSearchMatch>,
+}
+
+/// Request to get the selection ranges for positions in a file
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct SelectionRangesRequest {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.ts")]
+    pub file_path: String,
+    /// The positions to expand from
+    pub positions: Vec<Position>,
+}
+
+/// A range to select, with the enclosing range to expand to next
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SelectionRange {
+    pub range: FileRange,
+    /// The next larger range containing this one, e.g. statement → block → function
+    #[schema(no_recursion)]
+    pub parent: Option<Box<SelectionRange>>,
+}
+
+impl SelectionRange {
+    pub fn from_lsp(path: &str, selection_range: lsp_types::SelectionRange) -> Self {
+        SelectionRange {
+            range: FileRange::from_lsp_range(path.to_string(), selection_range.range),
+            parent: selection_range
+                .parent
+                .map(|parent| Box::new(SelectionRange::from_lsp(path, *parent))),
+        }
+    }
+}
+
+/// Response containing one selection range per requested position, in request order
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SelectionRangesResponse {
+    pub ranges: Vec<SelectionRange>,
+}
+
+/// Request to get the code actions available for a range in a file
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct CodeActionsRequest {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.ts")]
+    pub file_path: String,
+    /// The range to get code actions for
+    pub range: FileRange,
+    /// Only return code actions of these kinds, e.g. `quickfix` or `refactor.extract`.
+    /// Defaults to all kinds.
+    #[serde(default)]
+    #[schema(example = json!(["quickfix"]))]
+    pub only: Option<Vec<String>>,
+}
+
+/// A change that the language server can make to the code
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CodeAction {
+    /// Human readable description of the action
+    #[schema(example = "Add missing function declaration 'computeTotal'")]
+    pub title: String,
+    /// The kind of the action, e.g. `quickfix`
+    #[schema(example = "quickfix")]
+    pub kind: Option<String>,
+    /// The edits the action makes. Some actions are only available as server-side commands and
+    /// have no edit.
+    pub edit: Option<WorkspaceEdit>,
+}
+
+impl From<lsp_types::CodeActionOrCommand> for CodeAction {
+    fn from(action: lsp_types::CodeActionOrCommand) -> Self {
+        match action {
+            lsp_types::CodeActionOrCommand::Command(command) => CodeAction {
+                title: command.title,
+                kind: None,
+                edit: None,
+            },
+            lsp_types::CodeActionOrCommand::CodeAction(action) => CodeAction {
+                title: action.title,
+                kind: action.kind.map(|kind| kind.as_str().to_string()),
+                edit: action.edit.map(WorkspaceEdit::from),
+            },
+        }
+    }
+}
 
-    /// The identifier position of the symbol to find references within
-    pub identifier_position: FilePosition,
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CodeActionsResponse {
+    pub actions: Vec<CodeAction>,
 }
 
-/// Request to get the symbols in a file.
+/// Request to get the semantic tokens of a file
 #[derive(Deserialize, ToSchema, IntoParams)]
-pub struct FileSymbolsRequest {
-    /// The path to the file to get the symbols for, relative to the root of the workspace.
-    #[schema(example = "src/main.py")]
+pub struct SemanticTokensRequest {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.rs")]
     pub file_path: String,
 }
 
-/// Request to get the symbols in the workspace.
-#[allow(unused)] // TODO re-implement using textDocument/symbol
-#[derive(Deserialize, ToSchema, IntoParams)]
-pub struct WorkspaceSymbolsRequest {
-    /// The query to search for.
-    #[schema(example = "User")]
-    pub query: String,
+/// A token classified by the language server, e.g. a keyword or a function name
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SemanticToken {
+    /// Line of the token (0-indexed)
+    #[schema(example = 3)]
+    pub line: u32,
+    /// Character the token starts at (0-indexed)
+    #[schema(example = 0)]
+    pub character: u32,
+    /// Length of the token in characters
+    #[schema(example = 2)]
+    pub length: u32,
+    /// The type of the token, from the language server's legend
+    #[schema(example = "keyword")]
+    pub token_type: String,
+    /// The modifiers of the token, from the language server's legend
+    #[schema(example = json!(["declaration"]))]
+    pub modifiers: Vec<String>,
+}
 
-    /// Whether to include the raw response from the langserver in the response.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SemanticTokensResponse {
+    pub tokens: Vec<SemanticToken>,
+}
+
+/// Number of symbols of each kind defined in a file
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileSymbolCounts {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.py")]
+    pub path: String,
+    /// Number of symbols defined in the file, keyed by kind
+    #[schema(example = json!({"function": 2, "class": 1}))]
+    pub counts: HashMap<String, usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolCountsResponse {
+    /// Symbol counts for each file in the workspace
+    pub by_file: Vec<FileSymbolCounts>,
+    /// Symbol counts summed across the workspace, keyed by kind
+    #[schema(example = json!({"function": 12, "class": 3}))]
+    pub by_kind: HashMap<String, usize>,
+}
+
+/// Request to write the content of a file in the workspace
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct WriteSourceCodeRequest {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/utils.py")]
+    pub file_path: String,
+    /// The new content of the file
+    #[schema(example = "def greet(name):\n    return f\"Hello, {name}\"\n")]
+    pub content: String,
+    /// Whether to create the file, and any missing parent directories, if it doesn't exist.
     /// Defaults to false.
     #[serde(default)]
-    #[schema(example = false)]
-    pub include_raw_response: bool,
+    #[schema(example = true)]
+    pub create_if_missing: bool,
 }
 
-/// Response to a definition request.
-///
-/// The definition(s) of the symbol.
-/// Points to the start position of the symbol's identifier.
-///
-/// e.g. for the definition of `User` on line 5 of `src/main.py` with the code:
-/// ```
-/// 0: class User:
-/// _________^
-/// 1:     def __init__(self, name, age):
-/// 2:         self.name = name
-/// 3:         self.age = age
-/// 4:
-/// 5: user = User("John", 30)
-/// __________^
-/// ```
-/// The definition(s) will be `[{"path": "src/main.py", "line": 0, "character": 6}]`.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
-pub struct DefinitionResponse {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    /// The raw response from the langserver.
-    ///
-    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_definition
-    pub raw_response: Option<Value>,
-    pub definitions: Vec<FilePosition>,
-    /// The source code of symbol definitions.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub source_code_context: Option<Vec<CodeContext>>,
-    /// The identifier that was "clicked-on" to get the definition.
-    pub selected_identifier: Identifier,
+pub struct GetSymbolResponse {
+    /// The innermost symbol containing the position, if any
+    pub symbol: Option<Symbol>,
 }
 
-/// Response to a references request.
-///
-/// Points to the start position of the symbol's identifier.
-///
-/// e.g. for the references of `User` on line 0 character 6 of `src/main.py` with the code:
-/// ```
-/// 0: class User:
-/// 1:     def __init__(self, name, age):
-/// 2:         self.name = name
-/// 3:         self.age = age
-/// 4:
-/// 5: user = User("John", 30)
-/// _________^
-/// 6:
-/// 7: print(user.name)
-/// ```
-/// The references will be `[{"path": "src/main.py", "line": 5, "character": 7}]`.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
-pub struct ReferencesResponse {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    /// The raw response from the langserver.
-    ///
-    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references
-    pub raw_response: Option<Value>,
+pub struct DefinitionChainResponse {
+    /// The requested position followed by each definition reached from it, ending at the
+    /// original definition
+    pub chain: Vec<FilePosition>,
+}
 
-    pub references: Vec<FilePosition>,
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EnclosingChainResponse {
+    /// The symbols containing the position, from the outermost to the innermost
+    pub chain: Vec<Symbol>,
+}
 
-    /// The source code around the references.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context: Option<Vec<CodeContext>>,
-    /// The identifier that was "clicked-on" to get the references.
-    pub selected_identifier: Identifier,
+/// Code metrics for a single file in the workspace
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileMetrics {
+    /// Path to the file, relative to the workspace root
+    #[schema(example = "src/main.py")]
+    pub path: String,
+    /// Number of lines in the file
+    #[schema(example = 120)]
+    pub total_lines: usize,
+    /// Number of lines containing only whitespace
+    #[schema(example = 15)]
+    pub blank_lines: usize,
+    /// Approximate number of comment lines, i.e. lines starting with `#`, `//`, `*` or `--`
+    #[schema(example = 10)]
+    pub comment_lines: usize,
+    /// Number of symbols defined in the file, not counting local variables
+    #[schema(example = 6)]
+    pub symbol_count: usize,
+    /// Number of symbols defined per 100 lines
+    #[schema(example = 5.0)]
+    pub symbols_per_100_lines: f32,
 }
 
-/// Response containing symbols referenced from the requested position
-///
-/// The symbols are categorized into:
-/// - workspace_symbols: References to symbols that were found and have definitions in the workspace
-/// - external_symbols: References to symbols from outside the workspace (built-in functions, external libraries)
-/// - not_found: References where the symbol definition could not be found
+/// Response containing the code metrics of every file in the workspace
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
-pub struct ReferencedSymbolsResponse {
-    pub workspace_symbols: Vec<ReferenceWithSymbolDefinitions>,
-    pub external_symbols: Vec<Identifier>,
-    pub not_found: Vec<Identifier>,
+pub struct FileMetricsResponse {
+    pub files: Vec<FileMetrics>,
 }
 
-pub type SymbolResponse = Vec<Symbol>;
+/// The symbols affected by changing a symbol
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangeImpactReport {
+    /// The symbols whose code references the changed symbol
+    pub direct_callers: Vec<Symbol>,
+    /// The symbols whose code references a direct caller, but not the changed symbol itself
+    pub transitive_callers: Vec<Symbol>,
+    /// The number of direct and transitive callers
+    #[schema(example = 3)]
+    pub total_impact: usize,
+}
 
-impl From<Location> for FilePosition {
-    fn from(location: Location) -> Self {
-        FilePosition {
-            path: uri_to_relative_path_string(&location.uri),
-            position: Position {
-                line: location.range.start.line,
-                character: location.range.start.character,
-            },
-        }
-    }
+/// A symbol with the symbols calling it, and their callers in turn
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CallTree {
+    pub symbol: Symbol,
+    /// The symbols whose code references this symbol, each with its own callers
+    #[schema(no_recursion)]
+    pub callers: Vec<CallTree>,
 }
 
-impl From<LocationLink> for FilePosition {
-    fn from(link: LocationLink) -> Self {
-        FilePosition {
-            path: uri_to_relative_path_string(&link.target_uri),
-            position: Position {
-                line: link.target_range.start.line,
-                character: link.target_range.start.character,
-            },
-        }
-    }
+/// A symbol with the hover text the language server shows for it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SymbolWithDoc {
+    pub symbol: Symbol,
+    /// The hover text, usually markdown with the signature and doc comment of the symbol.
+    /// `None` if the language server has nothing to show.
+    #[schema(example = "```rust\npub fn new(x: i32, y: i32) -> Self\n```\n\nCreates a point.")]
+    pub documentation: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
-#[serde(rename_all = "camelCase")]
-pub struct FindIdentifierRequest {
-    /// The name of the identifier to search for.
-    #[schema(example = "User")]
-    pub name: String,
-    /// The path to the file to search for identifiers.
-    #[schema(example = "src/main.py")]
-    pub path: String,
-    /// The position hint to search for identifiers. If not provided.
-    pub position: Option<Position>,
+/// A symbol with its source code.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ContextEntry {
+    pub symbol: Symbol,
+    /// The source code of the symbol's full range.
+    #[schema(example = "def log_execution_time(func):\n    ...")]
+    pub source: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
-#[serde(rename_all = "camelCase")]
-pub struct IdentifierResponse {
-    pub identifiers: Vec<Identifier>,
+/// Everything needed to understand a symbol at once.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExplainSymbolResponse {
+    /// The symbol whose identifier is at the requested position.
+    pub symbol: Symbol,
+    /// The source code of the symbol's full range.
+    pub source: String,
+    /// The hover text of the symbol, usually markdown with its signature and doc comment.
+    /// `None` if the language server has nothing to show.
+    pub documentation: Option<String>,
+    /// The number of references to the symbol, excluding its declaration.
+    #[schema(example = 3)]
+    pub reference_count: usize,
+    /// The workspace symbols referenced from the symbol's definition, with their source code.
+    pub dependencies: Vec<ContextEntry>,
 }
 
+/// Response with the documentation of the symbols of a file.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
-pub struct Range {
-    /// The start position of the range.
-    pub start: Position,
-    /// The end position of the range.
-    pub end: Position,
+pub struct SymbolDocsResponse {
+    /// The symbols of the file, local variables excepted, in the order they are defined.
+    pub symbols: Vec<SymbolWithDoc>,
+}
+
+/// A name imported by a file but never used in it
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnusedImport {
+    /// The name the import binds, e.g. `Set` for `from typing import Set`
+    #[schema(example = "Set")]
+    pub name: String,
+    /// The range of the whole import statement
+    pub import_range: FileRange,
+}
+
+/// How strongly one file depends on another
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileCoupling {
+    /// The file defining the symbols, relative to the workspace root
+    #[schema(example = "graph.py")]
+    pub file_a: String,
+    /// The file referencing the symbols, relative to the workspace root
+    #[schema(example = "search.py")]
+    pub file_b: String,
+    /// The number of symbols defined in `file_a` that are referenced in `file_b`
+    #[schema(example = 3)]
+    pub coupling_score: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -424,6 +1913,316 @@ pub struct ReadSourceCodeRequest {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lsp_types::Url;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_error_code_serialization() {
+        for error_code in ErrorCode::iter() {
+            assert_eq!(
+                serde_json::to_value(error_code).unwrap(),
+                error_code.to_string()
+            );
+        }
+        let response = ErrorResponse::new(ErrorCode::FileNotFound, "File not found: main.py");
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            serde_json::json!({
+                "error": "File not found: main.py",
+                "error_code": "FILE_NOT_FOUND",
+            })
+        );
+    }
+
+    fn detect_content(content: &[u8]) -> Option<SupportedLanguages> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script");
+        std::fs::write(&path, content).unwrap();
+        SupportedLanguages::detect_from_file_content(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn test_supported_languages_round_trip() {
+        for language in SupportedLanguages::iter() {
+            let json = serde_json::to_string(&language).unwrap();
+            assert_eq!(
+                serde_json::from_str::<SupportedLanguages>(&json).unwrap(),
+                language
+            );
+            assert_eq!(
+                SupportedLanguages::from_str(&language.to_string()).unwrap(),
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn test_supported_languages_names() {
+        let names: Vec<_> = SupportedLanguages::iter()
+            .map(|language| (language.display_name(), language.lsp_language_id()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Python", "python"),
+                ("TypeScript/JavaScript", "typescript"),
+                ("Rust", "rust"),
+                ("C/C++", "cpp"),
+                ("C#", "csharp"),
+                ("Java", "java"),
+                ("Go", "golang"),
+                ("PHP", "php"),
+                ("Ruby", "ruby"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_supported_languages_file_extensions() {
+        let mut seen = HashSet::new();
+        for language in SupportedLanguages::iter() {
+            let extensions = language.file_extensions();
+            assert!(!extensions.is_empty(), "{}", language.display_name());
+            for extension in extensions {
+                assert!(!extension.starts_with('.'), "{}", extension);
+                // Each extension belongs to a single language
+                assert!(seen.insert(*extension), "{}", extension);
+                let file_path = format!("src/file.{}", extension);
+                assert_eq!(
+                    crate::utils::file_utils::detect_language(&file_path).ok(),
+                    Some(language)
+                );
+            }
+        }
+        assert_eq!(
+            SupportedLanguages::Python.file_extensions(),
+            ["py", "pyx", "pyi"]
+        );
+        assert_eq!(
+            SupportedLanguages::TypeScriptJavaScript.file_extensions(),
+            ["ts", "tsx", "js", "jsx"]
+        );
+    }
+
+    #[test]
+    fn test_lsp_language_id_of_each_extension() {
+        use crate::utils::file_utils::detect_language_string;
+
+        for language in SupportedLanguages::iter() {
+            let id = detect_language_string(&format!("src/file.{}", language.file_extensions()[0]));
+            assert_eq!(id.unwrap(), language.lsp_language_id());
+        }
+        for (file_path, id) in [
+            ("src/app.tsx", "typescriptreact"),
+            ("src/app.js", "javascript"),
+            ("src/app.jsx", "javascriptreact"),
+            ("src/main.c", "c"),
+            ("src/main.h", "c"),
+            ("src/main.hpp", "cpp"),
+        ] {
+            assert_eq!(detect_language_string(file_path).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn test_detect_from_file_content_shebangs() {
+        for shebang in [
+            "#!/usr/bin/env python3\nprint('hi')\n",
+            "#!/usr/bin/python\n",
+            "#!/usr/local/bin/python3.11 -u\n",
+            "\u{feff}#!/usr/bin/env python\n",
+        ] {
+            assert_eq!(
+                detect_content(shebang.as_bytes()),
+                Some(SupportedLanguages::Python),
+                "{:?}",
+                shebang
+            );
+        }
+        assert_eq!(
+            detect_content(b"#!/usr/bin/env -S deno run --allow-net\n"),
+            Some(SupportedLanguages::TypeScriptJavaScript)
+        );
+        assert_eq!(
+            detect_content(b"#!/usr/bin/env ruby\n"),
+            Some(SupportedLanguages::Ruby)
+        );
+        assert_eq!(detect_content(b"#!/bin/bash\necho hi\n"), None);
+    }
+
+    #[test]
+    fn test_detect_from_file_content_markers() {
+        assert_eq!(
+            detect_content(b"// @ts-check\nconst x = 1;\n"),
+            Some(SupportedLanguages::TypeScriptJavaScript)
+        );
+        assert_eq!(
+            detect_content(b"\n'use strict';\nmodule.exports = {};\n"),
+            Some(SupportedLanguages::TypeScriptJavaScript)
+        );
+        assert_eq!(
+            detect_content(b"<?php\necho 'hi';\n"),
+            Some(SupportedLanguages::PHP)
+        );
+        assert_eq!(
+            detect_content(b"# -*- coding: utf-8 -*-\nimport os\n"),
+            Some(SupportedLanguages::Python)
+        );
+    }
+
+    #[test]
+    fn test_detect_from_file_content_unknown() {
+        assert_eq!(detect_content(b"all: build\n\tmake -C src\n"), None);
+        assert_eq!(detect_content(b""), None);
+        // Binary content is never detected, even with a plausible prefix
+        assert_eq!(
+            detect_content(b"#!/usr/bin/env python3\n\x00\x01\x02"),
+            None
+        );
+        assert_eq!(
+            SupportedLanguages::detect_from_file_content("/nonexistent/script"),
+            None
+        );
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range {
+            start: Position {
+                line: start.0,
+                character: start.1,
+            },
+            end: Position {
+                line: end.0,
+                character: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_file_range_lsp_conversions() {
+        let lsp_range = lsp_types::Range::new(
+            lsp_types::Position::new(3, 4),
+            lsp_types::Position::new(5, 0),
+        );
+        let file_range = FileRange::from_lsp_range(String::from("src/main.py"), lsp_range);
+        assert_eq!(
+            file_range,
+            FileRange {
+                path: String::from("src/main.py"),
+                range: range((3, 4), (5, 0)),
+            }
+        );
+        assert_eq!(file_range.to_lsp_range(), lsp_range);
+
+        let mount_dir = Path::new("/mnt/workspace");
+        let location = |uri: &str| Location::new(Url::parse(uri).expect("valid uri"), lsp_range);
+        assert_eq!(
+            FileRange::from_lsp_location(location("file:///mnt/workspace/src/main.py"), mount_dir),
+            file_range
+        );
+        assert_eq!(
+            FileRange::from_lsp_location(location("file:///mnt/workspace/main.py"), mount_dir).path,
+            "main.py"
+        );
+        // Paths outside the mount dir stay absolute
+        assert_eq!(
+            FileRange::from_lsp_location(location("file:///usr/lib/os.py"), mount_dir).path,
+            "/usr/lib/os.py"
+        );
+    }
+
+    #[test]
+    fn test_file_position_lsp_conversions() {
+        let lsp_position = lsp_types::Position::new(7, 2);
+        let file_position =
+            FilePosition::from_lsp_position(String::from("src/graph.py"), lsp_position);
+        assert_eq!(
+            file_position,
+            FilePosition {
+                path: String::from("src/graph.py"),
+                position: Position {
+                    line: 7,
+                    character: 2,
+                },
+            }
+        );
+        assert_eq!(file_position.to_lsp_position(), lsp_position);
+
+        let location = Location::new(
+            Url::parse("file:///mnt/workspace/src/graph.py").expect("valid uri"),
+            lsp_types::Range::new(lsp_position, lsp_types::Position::new(7, 12)),
+        );
+        assert_eq!(
+            FilePosition::from_lsp_location(location, Path::new("/mnt/workspace")),
+            file_position
+        );
+    }
+
+    #[test]
+    fn test_range_contains_position() {
+        let multi_line = range((10, 5), (12, 10));
+        let position = |line, character| Position { line, character };
+
+        assert!(multi_line.contains_position(&position(10, 5)));
+        assert!(multi_line.contains_position(&position(10, 80)));
+        assert!(multi_line.contains_position(&position(11, 0)));
+        assert!(multi_line.contains_position(&position(12, 0)));
+        assert!(multi_line.contains_position(&position(12, 10)));
+        assert!(!multi_line.contains_position(&position(10, 4)));
+        assert!(!multi_line.contains_position(&position(12, 11)));
+        assert!(!multi_line.contains_position(&position(9, 20)));
+        assert!(!multi_line.contains_position(&position(13, 0)));
+
+        let empty = range((3, 4), (3, 4));
+        assert!(empty.contains_position(&position(3, 4)));
+        assert!(!empty.contains_position(&position(3, 5)));
+    }
+
+    #[test]
+    fn test_range_overlaps() {
+        let outer = range((10, 0), (20, 0));
+
+        assert!(outer.overlaps(&range((5, 0), (10, 1))));
+        assert!(outer.overlaps(&range((19, 5), (25, 0))));
+        assert!(outer.overlaps(&range((12, 0), (13, 0))));
+        assert!(outer.overlaps(&range((0, 0), (30, 0))));
+        assert!(outer.overlaps(&outer));
+
+        // Adjacent ranges share only a boundary
+        assert!(!outer.overlaps(&range((5, 0), (10, 0))));
+        assert!(!outer.overlaps(&range((20, 0), (25, 0))));
+        assert!(!range((5, 0), (10, 0)).overlaps(&outer));
+        assert!(!outer.overlaps(&range((21, 0), (22, 0))));
+
+        // An empty range overlaps a range strictly around it, but not one it touches
+        assert!(outer.overlaps(&range((15, 0), (15, 0))));
+        assert!(range((15, 0), (15, 0)).overlaps(&outer));
+        assert!(!outer.overlaps(&range((10, 0), (10, 0))));
+        assert!(!outer.overlaps(&range((20, 0), (20, 0))));
+    }
+
+    #[test]
+    fn test_range_contains_range() {
+        let outer = range((10, 5), (20, 0));
+
+        assert!(outer.contains_range(&outer));
+        assert!(outer.contains_range(&range((10, 5), (11, 0))));
+        assert!(outer.contains_range(&range((19, 0), (20, 0))));
+        assert!(outer.contains_range(&range((15, 3), (15, 3))));
+        assert!(!outer.contains_range(&range((10, 4), (11, 0))));
+        assert!(!outer.contains_range(&range((19, 0), (20, 1))));
+        assert!(!outer.contains_range(&range((0, 0), (30, 0))));
+        assert!(!range((15, 3), (15, 3)).contains_range(&outer));
+    }
+
+    #[test]
+    fn test_range_is_empty() {
+        assert!(range((3, 4), (3, 4)).is_empty());
+        assert!(range((3, 4), (3, 2)).is_empty());
+        assert!(range((4, 0), (3, 9)).is_empty());
+        assert!(!range((3, 4), (3, 5)).is_empty());
+        assert!(!range((3, 4), (4, 0)).is_empty());
+    }
 
     #[test]
     fn test_contains_multi_line_range() {
@@ -627,4 +2426,62 @@ mod tests {
             "position after zero-width range should not be contained"
         );
     }
+
+    #[test]
+    fn test_identifier_kind_serialization() {
+        let cases = [
+            (IdentifierKind::FunctionCall, "function-call"),
+            (IdentifierKind::Decorator, "decorator"),
+            (IdentifierKind::ClassInstantiation, "class-instantiation"),
+            (IdentifierKind::AttributeUsage, "attribute-usage"),
+            (IdentifierKind::ComponentRender, "component-render"),
+            (IdentifierKind::AllReferences, "all-references"),
+            (
+                IdentifierKind::Other(String::from("macro-call")),
+                "macro-call",
+            ),
+        ];
+        for (kind, rule_id) in cases {
+            assert_eq!(serde_json::to_value(&kind).unwrap(), Value::from(rule_id));
+            assert_eq!(
+                serde_json::from_value::<IdentifierKind>(Value::from(rule_id)).unwrap(),
+                kind
+            );
+            assert_eq!(IdentifierKind::from(rule_id), kind);
+        }
+    }
+
+    #[test]
+    fn test_identifier_source_text_serialization() {
+        let json = serde_json::json!({
+            "name": "method_name",
+            "file_range": {
+                "path": "main.py",
+                "range": {
+                    "start": {"line": 3, "character": 4},
+                    "end": {"line": 3, "character": 20}
+                }
+            },
+            "kind": "attribute-usage",
+            "source_text": "self.method_name"
+        });
+        let identifier: Identifier = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(identifier.source_text.as_deref(), Some("self.method_name"));
+        assert_eq!(serde_json::to_value(&identifier).unwrap(), json);
+
+        // Identifiers serialized before the field was added still deserialize
+        let mut json = json;
+        json.as_object_mut().unwrap().remove("source_text");
+        let identifier: Identifier = serde_json::from_value(json).unwrap();
+        assert_eq!(identifier.source_text, None);
+    }
+
+    #[test]
+    fn test_find_identifier_request_defaults() {
+        let request: FindIdentifierRequest =
+            serde_json::from_str(r#"{"name": "graph", "path": "main.py"}"#).unwrap();
+        assert_eq!(request.file_path, "main.py");
+        assert!(request.case_sensitive);
+        assert_eq!(request.kind, None);
+    }
 }
@@ -1,25 +1,81 @@
+use serde::Deserialize;
+use std::collections::HashSet;
 use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
 use tokio::process::Command;
 
-const SYMBOL_CONFIG_PATH: &str = "/usr/src/ast_grep/symbol/config.yml";
-const IDENTIFIER_CONFIG_PATH: &str = "/usr/src/ast_grep/identifier/config.yml";
-const REFERENCE_CONFIG_PATH: &str = "/usr/src/ast_grep/reference/config.yml";
+use crate::api_types::SupportedLanguages;
+
+/// Directory holding the `symbol`, `identifier`, `reference`, `comment`, `test` and `import` rule
+/// sets in the docker image.
+pub const DEFAULT_AST_GREP_CONFIG_DIR: &str = "/usr/src/ast_grep";
+
+const SYMBOL_CONFIG_PATH: &str = "symbol/config.yml";
+const IDENTIFIER_CONFIG_PATH: &str = "identifier/config.yml";
+const REFERENCE_CONFIG_PATH: &str = "reference/config.yml";
+const COMMENT_CONFIG_PATH: &str = "comment/config.yml";
+const TEST_CONFIG_PATH: &str = "test/config.yml";
+const IMPORT_CONFIG_PATH: &str = "import/config.yml";
 
 use super::types::AstGrepMatch;
 
-pub struct AstGrepClient;
+/// Runs the ast-grep rule sets of a config directory.
+pub struct AstGrepClient {
+    config_dir: PathBuf,
+    /// The languages the rules of `config_dir` are written for.
+    languages: HashSet<SupportedLanguages>,
+}
+
+impl Default for AstGrepClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_AST_GREP_CONFIG_DIR)
+    }
+}
 
 impl AstGrepClient {
+    /// Creates a client that loads its rule sets, e.g. `symbol/config.yml`, from `config_dir`.
+    pub fn new(config_dir: impl AsRef<Path>) -> Self {
+        let config_dir = config_dir.as_ref().to_path_buf();
+        let languages = rule_languages(&config_dir);
+        Self {
+            config_dir,
+            languages,
+        }
+    }
+
+    /// Whether the rules of this client's config directory are written for `language`.
+    pub fn handles(&self, language: SupportedLanguages) -> bool {
+        self.languages.contains(&language)
+    }
+
     pub async fn get_symbol_match_from_position(
         &self,
         file_name: &str,
         identifier_position: &lsp_types::Position,
     ) -> Result<AstGrepMatch, Box<dyn std::error::Error>> {
+        match self
+            .find_symbol_match_at_position(file_name, identifier_position)
+            .await?
+        {
+            Some(matched_symbol) => Ok(matched_symbol),
+            None => Err(Box::new(Error::new(
+                ErrorKind::NotFound,
+                "No symbol found for position",
+            ))),
+        }
+    }
+
+    /// Finds the symbol whose identifier starts exactly at `identifier_position`, if any.
+    pub async fn find_symbol_match_at_position(
+        &self,
+        file_name: &str,
+        identifier_position: &lsp_types::Position,
+    ) -> Result<Option<AstGrepMatch>, Box<dyn std::error::Error>> {
         // Get all symbols in the file
         let file_symbols = self.scan_file(SYMBOL_CONFIG_PATH, file_name).await?;
 
         // Find the symbol that matches our identifier position
-        let symbol_result = file_symbols.into_iter().find(|ast_symbol_match| {
+        Ok(file_symbols.into_iter().find(|ast_symbol_match| {
             ast_symbol_match.meta_variables.single.name.range.start.line == identifier_position.line
                 && ast_symbol_match
                     .meta_variables
@@ -29,14 +85,7 @@ impl AstGrepClient {
                     .start
                     .column
                     == identifier_position.character
-        });
-        match symbol_result {
-            Some(matched_symbol) => Ok(matched_symbol),
-            None => Err(Box::new(Error::new(
-                ErrorKind::NotFound,
-                "No symbol found for position",
-            ))),
-        }
+        }))
     }
 
     pub async fn get_file_symbols(
@@ -53,6 +102,32 @@ impl AstGrepClient {
         self.scan_file(IDENTIFIER_CONFIG_PATH, file_name).await
     }
 
+    /// Gets the comments marking work to do, e.g. `# TODO: ...`, `// FIXME` or `/* HACK */`.
+    pub async fn get_file_todo_comments(
+        &self,
+        file_name: &str,
+    ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
+        self.scan_file(COMMENT_CONFIG_PATH, file_name).await
+    }
+
+    /// Gets the tests marked as such by the test framework, e.g. Rust functions with a
+    /// `#[test]` attribute, Java methods annotated with `@Test` or `it()` calls.
+    pub async fn get_file_test_symbols(
+        &self,
+        file_name: &str,
+    ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
+        self.scan_file(TEST_CONFIG_PATH, file_name).await
+    }
+
+    /// Gets the names bound by the import statements, e.g. `b` for `from a import b`, with the
+    /// whole statement as context.
+    pub async fn get_file_imports(
+        &self,
+        file_name: &str,
+    ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
+        self.scan_file(IMPORT_CONFIG_PATH, file_name).await
+    }
+
     pub async fn get_symbol_and_references(
         &self,
         file_name: &str,
@@ -68,69 +143,153 @@ impl AstGrepClient {
         Ok((symbol_match, references))
     }
 
+    /// Gets the references made by a symbol's code.
+    ///
+    /// Without `full_scan`, only the targeted reference rules are used and only the matches within
+    /// the symbol's range are kept. With `full_scan`, every identifier occurrence in the file is
+    /// kept, also from the permissive `all-references` rule (e.g. type hints and chained
+    /// attribute access) and outside the symbol's range. A full scan therefore returns a superset
+    /// of the references found without one.
     pub async fn get_references_contained_in_symbol_match(
         &self,
         file_name: &str,
         symbol_match: &AstGrepMatch,
         full_scan: bool,
     ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
-        // Get all references
         let matches = self.scan_file(REFERENCE_CONFIG_PATH, file_name).await?;
-
-        // Filter matches to those within the symbol's range
-        // And if not full_scan, exclude matches with rule_id "non-function"
-        let contained_references = matches
-            .into_iter()
-            .filter(|m| {
-                let contained = symbol_match.contains(m);
-                let all_ref = m.rule_id == "all-references";
-
-                // If we're doing a full scan, we want to use the more permissive "all-references"
-                // rule, whereas if we're not doing a full scan, we just want to use the targeted
-                // rules
-                contained && ((full_scan && all_ref) || (!full_scan && !all_ref))
-            })
-            .collect();
-
-        Ok(contained_references)
+        Ok(filter_references(matches, symbol_match, full_scan))
     }
 
+    /// Scans a file with the rule set at `config_path` of the config directory.
     async fn scan_file(
         &self,
         config_path: &str,
         file_name: &str,
     ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
-        let command_result = Command::new("ast-grep")
-            .arg("scan")
-            .arg("--config")
-            .arg(config_path)
-            .arg("--json")
-            .arg(file_name)
-            .output()
-            .await?;
+        let mut symbols =
+            scan_file_with_config(self.config_dir.join(config_path), file_name).await?;
+        symbols.sort_by_key(|s| s.get_identifier_range().start.line);
+        Ok(symbols)
+    }
+}
 
-        if !command_result.status.success() {
-            let error = String::from_utf8_lossy(&command_result.stderr);
-            return Err(format!("sg command failed: {}", error).into());
+/// The `language` of an ast-grep rule; the rest of the rule doesn't matter here.
+#[derive(Deserialize)]
+struct RuleLanguage {
+    language: Option<String>,
+}
+
+/// The languages of the rules in a config directory, read from the `language` of every rule
+/// file in it. Files that aren't rules, e.g. the `config.yml` of each rule set, have none.
+fn rule_languages(config_dir: &Path) -> HashSet<SupportedLanguages> {
+    let pattern = config_dir.join("**/*.yml");
+    let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+        return HashSet::new();
+    };
+    let mut languages = HashSet::new();
+    for content in paths
+        .flatten()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+    {
+        // A rule file may hold several rules, separated by `---`
+        for document in serde_yaml::Deserializer::from_str(&content) {
+            if let Some(language) = RuleLanguage::deserialize(document)
+                .ok()
+                .and_then(|rule| rule.language)
+                .and_then(|language| supported_language(&language))
+            {
+                languages.insert(language);
+            }
         }
+    }
+    languages
+}
 
-        let output = String::from_utf8(command_result.stdout)?;
+/// The language whose server handles the files of an ast-grep language, e.g. `tsx`.
+fn supported_language(ast_grep_language: &str) -> Option<SupportedLanguages> {
+    match ast_grep_language.to_lowercase().as_str() {
+        "python" | "py" => Some(SupportedLanguages::Python),
+        "typescript" | "ts" | "tsx" | "javascript" | "js" | "jsx" => {
+            Some(SupportedLanguages::TypeScriptJavaScript)
+        }
+        "rust" | "rs" => Some(SupportedLanguages::Rust),
+        "c" | "cpp" | "c++" => Some(SupportedLanguages::CPP),
+        "csharp" | "cs" | "c#" => Some(SupportedLanguages::CSharp),
+        "java" => Some(SupportedLanguages::Java),
+        "go" | "golang" => Some(SupportedLanguages::Golang),
+        "php" => Some(SupportedLanguages::PHP),
+        "ruby" | "rb" => Some(SupportedLanguages::Ruby),
+        _ => None,
+    }
+}
 
-        let mut symbols: Vec<AstGrepMatch> =
-            serde_json::from_str(&output).map_err(|e| format!("Failed to parse JSON: {}", e))?;
-        symbols = symbols.into_iter().collect();
-        symbols.sort_by_key(|s| s.get_identifier_range().start.line);
-        Ok(symbols)
+async fn scan_file_with_config(
+    config: PathBuf,
+    file_name: &str,
+) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
+    let command_result = Command::new("ast-grep")
+        .arg("scan")
+        .arg("--config")
+        .arg(config)
+        .arg("--json")
+        .arg(file_name)
+        .output()
+        .await?;
+
+    if !command_result.status.success() {
+        let error = String::from_utf8_lossy(&command_result.stderr);
+        return Err(format!("sg command failed: {}", error).into());
     }
+
+    let output = String::from_utf8(command_result.stdout)?;
+    serde_json::from_str(&output).map_err(|e| format!("Failed to parse JSON: {}", e).into())
+}
+
+/// Keeps the reference matches a symbol makes, see
+/// [`AstGrepClient::get_references_contained_in_symbol_match`].
+///
+/// Identifiers matched by both a targeted rule and `all-references` are kept once, from the
+/// targeted rule.
+fn filter_references(
+    matches: Vec<AstGrepMatch>,
+    symbol_match: &AstGrepMatch,
+    full_scan: bool,
+) -> Vec<AstGrepMatch> {
+    if !full_scan {
+        return matches
+            .into_iter()
+            .filter(|m| m.rule_id != "all-references" && symbol_match.contains(m))
+            .collect();
+    }
+
+    let (targeted, permissive): (Vec<_>, Vec<_>) = matches
+        .into_iter()
+        .partition(|m| m.rule_id != "all-references");
+    let mut seen = HashSet::new();
+    let mut references: Vec<AstGrepMatch> = targeted
+        .into_iter()
+        .chain(permissive)
+        .filter(|m| {
+            let start = m.get_identifier_range().start;
+            seen.insert((start.line, start.column))
+        })
+        .collect();
+    references.sort_by_key(|m| {
+        let start = m.get_identifier_range().start;
+        (start.line, start.column)
+    });
+    references
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::ast_grep::types::MetaVariable;
+
     #[tokio::test]
     async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
-        let client = AstGrepClient {};
+        let client = AstGrepClient::default();
 
         let path = "/mnt/lsproxy_root/sample_project/python/graph.py";
         let position = lsp_types::Position {
@@ -214,7 +373,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_contained_references() -> Result<(), Box<dyn std::error::Error>> {
-        let client = AstGrepClient {};
+        let client = AstGrepClient::default();
 
         let path = "/mnt/lsproxy_root/sample_project/python/main.py";
         let position = lsp_types::Position {
@@ -261,4 +420,130 @@ mod tests {
         assert_eq!(match_positions, expected);
         Ok(())
     }
+
+    /// Writes a `symbol` rule set with a single function rule for `language`.
+    fn write_symbol_config(
+        config_dir: &Path,
+        rule_id: &str,
+        language: &str,
+        function_kind: &str,
+    ) -> std::io::Result<()> {
+        let rules_dir = config_dir.join("symbol/rules");
+        std::fs::create_dir_all(&rules_dir)?;
+        std::fs::write(
+            config_dir.join(SYMBOL_CONFIG_PATH),
+            "ruleDirs:\n  - rules\n",
+        )?;
+        std::fs::write(
+            rules_dir.join("function.yml"),
+            format!(
+                "id: {}\nlanguage: {}\nrule:\n  kind: identifier\n  pattern: $NAME\n  \
+                 inside:\n    kind: {}\n    pattern: $CONTEXT\n",
+                rule_id, language, function_kind
+            ),
+        )
+    }
+
+    #[test]
+    fn test_rule_languages() -> Result<(), Box<dyn std::error::Error>> {
+        let python_config = tempfile::tempdir()?;
+        write_symbol_config(
+            python_config.path(),
+            "python-function",
+            "python",
+            "function_definition",
+        )?;
+        let typescript_config = tempfile::tempdir()?;
+        write_symbol_config(
+            typescript_config.path(),
+            "typescript-function",
+            "tsx",
+            "function_declaration",
+        )?;
+        // Several rules in one file
+        std::fs::write(
+            typescript_config.path().join("symbol/rules/class.yml"),
+            "id: class\nlanguage: javascript\nrule:\n  kind: identifier\n---\n\
+             id: rust-struct\nlanguage: rust\nrule:\n  kind: type_identifier\n",
+        )?;
+
+        let client = AstGrepClient::new(python_config.path());
+        assert!(client.handles(SupportedLanguages::Python));
+        assert!(!client.handles(SupportedLanguages::TypeScriptJavaScript));
+
+        let client = AstGrepClient::new(typescript_config.path());
+        assert!(client.handles(SupportedLanguages::TypeScriptJavaScript));
+        assert!(client.handles(SupportedLanguages::Rust));
+        assert!(!client.handles(SupportedLanguages::Python));
+
+        // A missing directory has no rules at all
+        let client = AstGrepClient::new(python_config.path().join("missing"));
+        assert!(!client.handles(SupportedLanguages::Python));
+        Ok(())
+    }
+
+    fn reference_match(rule_id: &str, line: u32, start: u32, end: u32) -> AstGrepMatch {
+        let range = serde_json::json!({
+            "byteOffset": {"start": 0, "end": 0},
+            "start": {"line": line, "column": start},
+            "end": {"line": line, "column": end},
+        });
+        serde_json::from_value(serde_json::json!({
+            "text": "name",
+            "range": range,
+            "file": "main.py",
+            "lines": "",
+            "charCount": {"leading": 0, "trailing": 0},
+            "language": "Python",
+            "metaVariables": {
+                "single": {"NAME": {"text": "name", "range": range}},
+                "multi": {"secondary": null},
+            },
+            "ruleId": rule_id,
+            "labels": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_full_scan_is_superset() {
+        let mut symbol = reference_match("function", 1, 0, 8);
+        symbol.meta_variables.single.context = Some(MetaVariable {
+            text: String::new(),
+            range: serde_json::from_value(serde_json::json!({
+                "byteOffset": {"start": 0, "end": 0},
+                "start": {"line": 1, "column": 0},
+                "end": {"line": 3, "column": 0},
+            }))
+            .unwrap(),
+        });
+        let matches = vec![
+            reference_match("function-call", 2, 4, 8),
+            reference_match("all-references", 2, 4, 8),
+            reference_match("all-references", 2, 10, 14),
+            reference_match("function-call", 5, 0, 4),
+        ];
+        let positions = |references: Vec<AstGrepMatch>| -> Vec<(String, u32, u32)> {
+            references
+                .into_iter()
+                .map(|m| {
+                    let start = m.get_identifier_range().start;
+                    (m.rule_id, start.line, start.column)
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            positions(filter_references(matches.clone(), &symbol, false)),
+            vec![(String::from("function-call"), 2, 4)]
+        );
+        assert_eq!(
+            positions(filter_references(matches, &symbol, true)),
+            vec![
+                (String::from("function-call"), 2, 4),
+                (String::from("all-references"), 2, 10),
+                (String::from("function-call"), 5, 0),
+            ]
+        );
+    }
 }
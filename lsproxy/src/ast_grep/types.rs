@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    api_types::{FilePosition, FileRange, Identifier, Position, Range, Symbol},
+    api_types::{FilePosition, FileRange, Identifier, IdentifierKind, Position, Range, Symbol},
     utils::file_utils::absolute_path_to_relative_path_string,
 };
 
@@ -42,14 +43,26 @@ impl AstGrepMatch {
         self.meta_variables.single.name.range.clone()
     }
 
+    /// Clamps the columns of every range in the match to the given line lengths.
+    ///
+    /// ast-grep counts the `\r` of a CRLF line ending as part of the line, so a range ending on
+    /// such a line can point past the end of the line once the content is normalized to LF.
+    pub fn clamp_to_line_lengths(&mut self, line_lengths: &HashMap<u32, u32>) {
+        self.range.clamp_to_line_lengths(line_lengths);
+        self.meta_variables
+            .single
+            .name
+            .range
+            .clamp_to_line_lengths(line_lengths);
+        if let Some(context) = self.meta_variables.single.context.as_mut() {
+            context.range.clamp_to_line_lengths(line_lengths);
+        }
+    }
+
     pub fn contains(&self, other: &AstGrepMatch) -> bool {
         self.file == other.file
-            && self.get_context_range().start.line <= other.get_context_range().start.line
-            && self.get_context_range().end.line >= other.get_context_range().end.line
-            && (self.get_context_range().start.line != other.get_context_range().start.line
-                || self.get_context_range().start.column <= other.get_context_range().start.column)
-            && (self.get_context_range().end.line != other.get_context_range().end.line
-                || self.get_context_range().end.column >= other.get_context_range().end.column)
+            && Range::from(self.get_context_range())
+                .contains_range(&Range::from(other.get_context_range()))
     }
 }
 
@@ -61,6 +74,16 @@ pub struct AstGrepRange {
     pub end: AstGrepPosition,
 }
 
+impl AstGrepRange {
+    fn clamp_to_line_lengths(&mut self, line_lengths: &HashMap<u32, u32>) {
+        for position in [&mut self.start, &mut self.end] {
+            if let Some(&length) = line_lengths.get(&position.line) {
+                position.column = position.column.min(length);
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ByteOffset {
@@ -117,6 +140,21 @@ pub struct Label {
     pub range: AstGrepRange,
 }
 
+impl From<AstGrepRange> for Range {
+    fn from(range: AstGrepRange) -> Self {
+        Range {
+            start: Position {
+                line: range.start.line,
+                character: range.start.column,
+            },
+            end: Position {
+                line: range.end.line,
+                character: range.end.column,
+            },
+        }
+    }
+}
+
 impl From<&AstGrepMatch> for lsp_types::Position {
     fn from(ast_match: &AstGrepMatch) -> Self {
         Self {
@@ -164,12 +202,14 @@ impl From<AstGrepMatch> for Identifier {
         let match_range = ast_match.get_context_range();
         let kind = match ast_match.rule_id.as_str() {
             "all-identifiers" => None,
-            _ => Some(ast_match.rule_id),
+            rule_id => Some(IdentifierKind::from(rule_id)),
         };
 
         Identifier {
             name: ast_match.meta_variables.single.name.text.clone(),
             kind,
+            source_text: Some(ast_match.get_source_code()),
+            context_source: None,
             file_range: FileRange {
                 path: path.clone(),
                 range: Range {
@@ -0,0 +1,127 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{AllReferencesInFileRequest, AllReferencesInFileResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Find the references to every symbol defined in a file
+///
+/// Bulk version of `find-references` for indexing: looks up the references of each symbol that
+/// `definitions-in-file` returns and groups them by symbol name. Declarations are included, and
+/// symbols sharing a name, such as methods of different classes, share an entry. References
+/// outside the workspace files are left out.
+///
+/// The symbols are looked up one after the other, so files with many symbols can take a while.
+#[utoipa::path(
+    get,
+    path = "/workspace/all-references-in-file",
+    tag = "workspace",
+    params(AllReferencesInFileRequest),
+    responses(
+        (status = 200, description = "References retrieved successfully", body = AllReferencesInFileResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn all_references_in_file(
+    data: Data<AppState>,
+    info: Query<AllReferencesInFileRequest>,
+) -> HttpResponse {
+    info!(
+        "Received all references request for file: {}",
+        info.file_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .get_all_references_in_file(&info.file_path)
+        .await
+    {
+        Ok(references) => HttpResponse::Ok().json(AllReferencesInFileResponse {
+            references: references.into_iter().collect(),
+        }),
+        Err(e) => {
+            error!("Failed to find references in file: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FileRange, Position, Range, SupportedLanguages};
+    use crate::initialize_app_state;
+    use crate::test_utils::{mocks::MockFile, python_sample_path, TestContext};
+
+    fn request(file_path: &str) -> Query<AllReferencesInFileRequest> {
+        Query(AllReferencesInFileRequest {
+            file_path: file_path.to_string(),
+        })
+    }
+
+    fn file_range(path: &str, start: (u32, u32), end: (u32, u32)) -> FileRange {
+        FileRange {
+            path: path.to_string(),
+            range: Range {
+                start: Position {
+                    line: start.0,
+                    character: start.1,
+                },
+                end: Position {
+                    line: end.0,
+                    character: end.1,
+                },
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_python_all_references_in_file() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = all_references_in_file(state, request("graph.py")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: AllReferencesInFileResponse = serde_json::from_slice(&bytes)?;
+
+        // The `AStarGraph` declaration and its uses in main.py
+        let graph_references = &response.references["AStarGraph"];
+        assert!(graph_references.contains(&file_range("graph.py", (12, 6), (12, 16))));
+        assert!(graph_references.iter().any(|r| r.path == "main.py"));
+
+        for references in response.references.values() {
+            assert!(!references.is_empty());
+            assert!(references
+                .windows(2)
+                .all(|pair| (&pair[0].path, &pair[0].range.start)
+                    < (&pair[1].path, &pair[1].range.start)));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_references_in_missing_file() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.py", "")];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let response = all_references_in_file(state, request("missing.py")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info, warn};
+
+use crate::api_types::{AllSymbolsRequest, Symbol};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::parse_kinds_param;
+use crate::AppState;
+
+/// The most symbols returned by a single all-symbols request.
+const MAX_WORKSPACE_SYMBOLS: usize = 10_000;
+
+/// Get the symbols defined across the whole workspace
+///
+/// Returns the symbols of every file in the workspace, sorted by file and then by position,
+/// optionally filtered to the comma-separated kinds in `kind`. Unknown kinds return 400 with the
+/// list of valid kinds.
+///
+/// At most 10,000 symbols are returned. If there are more, the rest are dropped and the response
+/// carries a `Warning` header with the total count.
+#[utoipa::path(
+    get,
+    path = "/workspace/all-symbols",
+    tag = "workspace",
+    params(AllSymbolsRequest),
+    responses(
+        (status = 200, description = "Symbols retrieved successfully", body = Vec<Symbol>),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn all_symbols(data: Data<AppState>, info: Query<AllSymbolsRequest>) -> HttpResponse {
+    info!("Received all symbols request, kinds: {:?}", info.kind);
+
+    let kinds = match parse_kinds_param(info.kind.as_deref()) {
+        Ok(kinds) => kinds,
+        Err(response) => return response,
+    };
+    let kind_filter = kinds.map(|kinds| kinds.iter().map(ToString::to_string).collect());
+
+    match data.manager.definitions_in_workspace(kind_filter).await {
+        Ok(symbols) => symbols_response(symbols, MAX_WORKSPACE_SYMBOLS),
+        Err(e) => {
+            error!("Failed to get workspace symbols: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+/// Responds with at most `limit` symbols, warning about the rest in a `Warning` header.
+fn symbols_response(mut symbols: Vec<Symbol>, limit: usize) -> HttpResponse {
+    if symbols.len() <= limit {
+        return HttpResponse::Ok().json(symbols);
+    }
+    let total = symbols.len();
+    warn!("Truncating {} workspace symbols to {}", total, limit);
+    symbols.truncate(limit);
+    HttpResponse::Ok()
+        .insert_header((
+            "Warning",
+            format!("299 - \"Truncated to {} of {} symbols\"", limit, total),
+        ))
+        .json(symbols)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{ErrorResponse, FilePosition, FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    fn request(kind: Option<&str>) -> Query<AllSymbolsRequest> {
+        Query(AllSymbolsRequest {
+            kind: kind.map(String::from),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_python_all_classes() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = all_symbols(state, request(Some("class"))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("Warning").is_none());
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        let classes: Vec<(&str, &str)> = symbols
+            .iter()
+            .map(|symbol| (symbol.file_range.path.as_str(), symbol.name.as_str()))
+            .collect();
+        assert_eq!(
+            classes,
+            vec![
+                ("graph.py", "GraphBase"),
+                ("graph.py", "CostStrategy"),
+                ("graph.py", "AStarGraph"),
+            ]
+        );
+        assert!(symbols.iter().all(|symbol| symbol.kind == "class"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_all_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = all_symbols(state.clone(), request(None)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        let files: Vec<&str> = symbols
+            .iter()
+            .map(|symbol| symbol.file_range.path.as_str())
+            .collect();
+        for file in ["decorators.py", "graph.py", "main.py", "search.py"] {
+            assert!(files.contains(&file), "no symbols from {}", file);
+        }
+        assert!(files.is_sorted());
+        assert!(symbols.iter().all(|symbol| symbol.kind != "local-variable"));
+
+        let response = all_symbols(state, request(Some("class,klass"))).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&bytes)?;
+        assert!(error.error.starts_with("Unknown symbol kind 'klass'"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_symbols_response_truncates() {
+        let symbol = |line| {
+            let position = Position { line, character: 4 };
+            Symbol {
+                name: format!("symbol_{}", line),
+                kind: String::from("function"),
+                identifier_position: FilePosition {
+                    path: String::from("main.py"),
+                    position: position.clone(),
+                },
+                file_range: FileRange {
+                    path: String::from("main.py"),
+                    range: Range {
+                        start: position.clone(),
+                        end: position,
+                    },
+                },
+            }
+        };
+        let symbols: Vec<Symbol> = (0..5).map(symbol).collect();
+
+        let response = symbols_response(symbols.clone(), 5);
+        assert!(response.headers().get("Warning").is_none());
+
+        let response = symbols_response(symbols, 3);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Warning").unwrap(),
+            "299 - \"Truncated to 3 of 5 symbols\""
+        );
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let truncated: Vec<Symbol> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(truncated.len(), 3);
+        assert_eq!(truncated[2].name, "symbol_2");
+    }
+}
@@ -0,0 +1,256 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{get_mount_dir, ApplyEditResponse, ErrorCode, ErrorResponse, WorkspaceEdit};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Apply a set of edits to files in the workspace
+///
+/// Takes edits keyed by file path, in the same shape as the `edit` of a code action, and writes
+/// them to the files. The language servers are notified of the new content so that they're
+/// re-indexed immediately.
+///
+/// All files must already be part of the workspace, and the `range.path` of each edit must match
+/// the file it's listed under. Nothing is written if any file is invalid or if edits to a file
+/// overlap.
+#[utoipa::path(
+    post,
+    path = "/workspace/apply-edit",
+    tag = "workspace",
+    request_body = WorkspaceEdit,
+    responses(
+        (status = 200, description = "Edits applied successfully", body = ApplyEditResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn apply_edit(data: Data<AppState>, info: Json<WorkspaceEdit>) -> HttpResponse {
+    info!("Applying edits to {} files", info.changes.len());
+
+    for (path, edits) in &info.changes {
+        if let Err(response) = validate_file_in_workspace(path, &data.manager).await {
+            return response;
+        }
+        if let Some(edit) = edits.iter().find(|edit| &edit.range.path != path) {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::BadRequest,
+                format!(
+                    "Edit for {} is listed under a different file: {}",
+                    edit.range.path, path
+                ),
+            ));
+        }
+    }
+
+    let edit = match info.to_lsp_workspace_edit(&get_mount_dir()) {
+        Ok(edit) => edit,
+        Err(path) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::InvalidPath,
+                format!("Invalid file path: {}", path),
+            ))
+        }
+    };
+
+    match data.manager.apply_workspace_edit(&edit).await {
+        Ok(modified_files) => HttpResponse::Ok().json(ApplyEditResponse { modified_files }),
+        Err(e) => {
+            error!("Failed to apply edits: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{
+        set_thread_local_mount_dir, FileRange, Position, Range, SupportedLanguages, TextEditEntry,
+    };
+    use crate::initialize_app_state;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    fn edit(path: &str, start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEditEntry {
+        TextEditEntry {
+            range: FileRange {
+                path: path.to_string(),
+                range: Range {
+                    start: Position {
+                        line: start.0,
+                        character: start.1,
+                    },
+                    end: Position {
+                        line: end.0,
+                        character: end.1,
+                    },
+                },
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    fn mock_files() -> Vec<MockFile> {
+        vec![
+            MockFile::new("main.py", "def foo():\n    return 1\n\n\nfoo()\n"),
+            MockFile::new("other.py", "from main import foo\nfoo()\n"),
+        ]
+    }
+
+    fn workspace() -> Result<tempfile::TempDir, std::io::Error> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("main.py"),
+            "def foo():\n    return 1\n\n\nfoo()\n",
+        )?;
+        std::fs::write(dir.path().join("other.py"), "from main import foo\nfoo()\n")?;
+        Ok(dir)
+    }
+
+    #[tokio::test]
+    async fn test_python_apply_edit() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = workspace()?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = apply_edit(
+            state.clone(),
+            Json(WorkspaceEdit {
+                changes: BTreeMap::from([
+                    (
+                        String::from("main.py"),
+                        vec![
+                            edit("main.py", (0, 4), (0, 7), "bar"),
+                            edit("main.py", (4, 0), (4, 3), "bar"),
+                        ],
+                    ),
+                    (
+                        String::from("other.py"),
+                        vec![
+                            edit("other.py", (0, 17), (0, 20), "bar"),
+                            edit("other.py", (1, 0), (1, 3), "bar"),
+                        ],
+                    ),
+                ]),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: ApplyEditResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.modified_files, vec!["main.py", "other.py"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("main.py"))?,
+            "def bar():\n    return 1\n\n\nbar()\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("other.py"))?,
+            "from main import bar\nbar()\n"
+        );
+
+        // The language server sees the new content
+        let symbols = state
+            .manager
+            .definitions_in_file_ast_grep("main.py")
+            .await?;
+        assert!(symbols
+            .iter()
+            .any(|s| s.meta_variables.single.name.text == "bar"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_edit_rejects_path_traversal() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("workspace/main.py", "print('hello')\n"),
+            MockFile::new("escaped.py", "print('hello')\n"),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        // Mount the nested workspace, so that `escaped.py` is just outside it
+        let dir = context.workspace_path().to_path_buf();
+        set_thread_local_mount_dir(dir.join("workspace"));
+        let state = context.app_state();
+
+        let response = apply_edit(
+            state,
+            Json(WorkspaceEdit {
+                changes: BTreeMap::from([(
+                    String::from("../escaped.py"),
+                    vec![edit("../escaped.py", (0, 0), (0, 5), "exit")],
+                )]),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("escaped.py"))?,
+            "print('hello')\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_edit_overlapping_edits() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, mock_files()).await?;
+        let state = context.app_state();
+
+        let response = apply_edit(
+            state,
+            Json(WorkspaceEdit {
+                changes: BTreeMap::from([
+                    (
+                        String::from("main.py"),
+                        vec![edit("main.py", (0, 4), (0, 7), "bar")],
+                    ),
+                    (
+                        String::from("other.py"),
+                        vec![
+                            edit("other.py", (0, 0), (0, 9), "import"),
+                            edit("other.py", (0, 5), (0, 20), "x"),
+                        ],
+                    ),
+                ]),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            std::fs::read_to_string(context.workspace_path().join("main.py"))?,
+            "def foo():\n    return 1\n\n\nfoo()\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_edit_mismatched_path() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, mock_files()).await?;
+        let state = context.app_state();
+
+        let response = apply_edit(
+            state,
+            Json(WorkspaceEdit {
+                changes: BTreeMap::from([(
+                    String::from("main.py"),
+                    vec![edit("other.py", (0, 0), (0, 4), "import")],
+                )]),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            std::fs::read_to_string(context.workspace_path().join("main.py"))?,
+            "def foo():\n    return 1\n\n\nfoo()\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FilePosition, ReferenceCount};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Count the references to several symbols
+///
+/// Bulk version of `reference-count`: returns the number of references, excluding the
+/// declaration, for each position in the same order as requested. Fails if any position
+/// can't be counted.
+#[utoipa::path(
+    post,
+    path = "/symbol/bulk-reference-count",
+    tag = "symbol",
+    request_body = Vec<FilePosition>,
+    responses(
+        (status = 200, description = "Reference counts retrieved successfully", body = Vec<ReferenceCount>),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn bulk_reference_count(
+    data: Data<AppState>,
+    info: Json<Vec<FilePosition>>,
+) -> HttpResponse {
+    info!(
+        "Received bulk reference count request for {} positions",
+        info.len()
+    );
+
+    let mut counts = Vec::with_capacity(info.len());
+    for position in info.into_inner() {
+        if let Err(response) = validate_file_in_workspace(&position.path, &data.manager).await {
+            return response;
+        }
+        match data
+            .manager
+            .get_symbol_references_count(&position.path, position.position.clone().into())
+            .await
+        {
+            Ok(count) => counts.push(ReferenceCount { position, count }),
+            Err(e) => {
+                error!("Failed to count references: {}", e);
+                return e.into_http_response();
+            }
+        }
+    }
+    HttpResponse::Ok().json(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_bulk_reference_count() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The `AStarGraph` declaration and its import in main.py refer to the same class
+        let positions = vec![
+            FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            },
+            FilePosition {
+                path: String::from("main.py"),
+                position: Position {
+                    line: 1,
+                    character: 18,
+                },
+            },
+        ];
+        let response = bulk_reference_count(state.clone(), Json(positions.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let counts: Vec<ReferenceCount> = serde_json::from_slice(&bytes)?;
+        assert_eq!(
+            counts,
+            positions
+                .into_iter()
+                .map(|position| ReferenceCount { position, count: 6 })
+                .collect::<Vec<_>>()
+        );
+
+        // An empty request returns an empty list
+        let response = bulk_reference_count(state, Json(vec![])).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let counts: Vec<ReferenceCount> = serde_json::from_slice(&bytes)?;
+        assert!(counts.is_empty());
+        Ok(())
+    }
+}
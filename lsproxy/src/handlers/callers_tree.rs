@@ -0,0 +1,156 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{CallTree, CallersTreeRequest, ErrorCode, ErrorResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Maximum number of caller levels in a tree.
+const MAX_DEPTH: u32 = 10;
+
+/// Build the tree of callers reaching a symbol
+///
+/// The input position should point to the identifier of the symbol, e.g. a function name.
+/// Returns the symbol with the symbols whose code references it, each with its own callers, for
+/// `max_depth` levels, e.g. to trace every path reaching a function that writes to the database.
+///
+/// e.g. for `heuristic` with a `max_depth` of 2:
+/// ```text
+/// heuristic
+/// ├── initialize_search
+/// │   └── a_star_search
+/// └── a_star_search
+///     └── main
+/// ```
+///
+/// Call cycles are cut where a caller is already on the path from the root. References outside
+/// any symbol, such as module-level imports, aren't callers. A position that isn't a symbol's
+/// identifier is a bad request.
+#[utoipa::path(
+    post,
+    path = "/symbol/callers-tree",
+    tag = "symbol",
+    request_body = CallersTreeRequest,
+    responses(
+        (status = 200, description = "Callers tree built successfully", body = CallTree),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn callers_tree(data: Data<AppState>, info: Json<CallersTreeRequest>) -> HttpResponse {
+    info!(
+        "Received callers tree request for file: {}, line: {}, character: {}, max depth: {}",
+        info.position.path,
+        info.position.position.line,
+        info.position.position.character,
+        info.max_depth
+    );
+
+    if info.max_depth > MAX_DEPTH {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            format!("max_depth must be at most {}", MAX_DEPTH),
+        ));
+    }
+
+    if let Err(response) = validate_file_in_workspace(&info.position.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .find_symbol_callers_recursive(
+            &info.position.path,
+            info.position.to_lsp_position(),
+            info.max_depth,
+        )
+        .await
+    {
+        Ok(tree) => HttpResponse::Ok().json(tree),
+        Err(e) => {
+            error!("Failed to build callers tree: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FilePosition, Position};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    fn request(line: u32, character: u32, max_depth: u32) -> Json<CallersTreeRequest> {
+        Json(CallersTreeRequest {
+            position: FilePosition {
+                path: String::from("graph.py"),
+                position: Position { line, character },
+            },
+            max_depth,
+        })
+    }
+
+    /// The tree as `path:name` lines, indented by depth.
+    fn render(tree: &CallTree, depth: usize, lines: &mut Vec<String>) {
+        lines.push(format!(
+            "{}{}:{}",
+            "  ".repeat(depth),
+            tree.symbol.identifier_position.path,
+            tree.symbol.name
+        ));
+        for caller in &tree.callers {
+            render(caller, depth + 1, lines);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_python_callers_tree() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `AStarGraph.heuristic`, two levels deep
+        let response = callers_tree(state.clone(), request(68, 8, 2)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let tree: CallTree = serde_json::from_slice(&bytes)?;
+
+        let mut lines = Vec::new();
+        render(&tree, 0, &mut lines);
+        assert_eq!(
+            lines,
+            [
+                "graph.py:heuristic",
+                "  search.py:initialize_search",
+                "    search.py:a_star_search",
+                "  search.py:a_star_search",
+                "    main.py:main",
+            ]
+        );
+
+        // Capped at the root
+        let response = callers_tree(state.clone(), request(68, 8, 0)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let tree: CallTree = serde_json::from_slice(&bytes)?;
+        assert_eq!(tree.symbol.name, "heuristic");
+        assert!(tree.callers.is_empty());
+
+        let response = callers_tree(state.clone(), request(68, 8, MAX_DEPTH + 1)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A blank line between the imports and the first class
+        let response = callers_tree(state, request(3, 0, 1)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
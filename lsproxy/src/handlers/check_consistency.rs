@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+use tokio::sync::Mutex;
+
+use crate::api_types::ConsistencyReport;
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::etag::LIST_FILES_ETAG_KEY;
+use crate::lsp::manager::{LspManagerError, Manager};
+use crate::AppState;
+
+/// Check the listed workspace files against the files on disk
+///
+/// Files deleted or created since they were last listed are normally picked up by the file
+/// watcher after a short delay; this reconciles them right away. Stale files are dropped from
+/// the workspace and closed in their language server, and files missing from the listing are
+/// added to it.
+///
+/// Runs every 5 minutes on its own when the `AUTO_CONSISTENCY_CHECK=true` environment variable
+/// is set.
+#[utoipa::path(
+    get,
+    path = "/admin/check-consistency",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Consistency checked successfully", body = ConsistencyReport),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn check_consistency(data: Data<AppState>) -> HttpResponse {
+    info!("Received consistency check request");
+
+    match run_consistency_check(&data.manager, &data.etags).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            error!("Failed to check workspace consistency: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+/// Checks the workspace consistency, dropping the file list ETags if any file was reconciled.
+pub(crate) async fn run_consistency_check(
+    manager: &Manager,
+    etags: &Mutex<HashMap<String, String>>,
+) -> Result<ConsistencyReport, LspManagerError> {
+    let report = manager.check_workspace_consistency().await?;
+    if !report.stale_files.is_empty() || !report.missing_from_lsp.is_empty() {
+        info!(
+            "Reconciled {} stale and {} missing workspace files",
+            report.stale_files.len(),
+            report.missing_from_lsp.len()
+        );
+        etags
+            .lock()
+            .await
+            .retain(|key, _| !key.starts_with(LIST_FILES_ETAG_KEY));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::SupportedLanguages;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    async fn check(state: &Data<AppState>) -> ConsistencyReport {
+        let response = check_consistency(state.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_check_consistency_after_deletion() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("graph.py", "class AStarGraph:\n    pass\n"),
+            MockFile::new("main.py", "print('hello')\n"),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+        assert_eq!(state.manager.list_files().await?, ["graph.py", "main.py"]);
+
+        // Changed before the file watcher reports it
+        let workspace = context.workspace_path();
+        std::fs::remove_file(workspace.join("graph.py"))?;
+        std::fs::write(workspace.join("search.py"), "def search():\n    pass\n")?;
+        assert_eq!(state.manager.list_files().await?, ["graph.py", "main.py"]);
+
+        let report = check(&state).await;
+        assert_eq!(report.stale_files, ["graph.py"]);
+        assert_eq!(report.missing_from_lsp, ["search.py"]);
+        assert_eq!(state.manager.list_files().await?, ["main.py", "search.py"]);
+
+        // Nothing left to reconcile
+        let report = check(&state).await;
+        assert!(report.stale_files.is_empty());
+        assert!(report.missing_from_lsp.is_empty());
+        Ok(())
+    }
+}
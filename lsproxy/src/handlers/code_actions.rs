@@ -0,0 +1,159 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{
+    CodeAction, CodeActionsRequest, CodeActionsResponse, ErrorCode, ErrorResponse,
+};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the code actions available for a range in a file
+///
+/// Returns the quick fixes and refactorings the language server offers for the range, such as
+/// adding a missing import or declaring an undefined function. Quick fixes are based on the
+/// diagnostics the language server reports for the range.
+///
+/// Actions are not applied; the edits each action would make are returned in `edit`.
+#[utoipa::path(
+    post,
+    path = "/symbol/code-actions",
+    tag = "symbol",
+    request_body = CodeActionsRequest,
+    responses(
+        (status = 200, description = "Code actions retrieved successfully", body = CodeActionsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn code_actions(data: Data<AppState>, info: Json<CodeActionsRequest>) -> HttpResponse {
+    info!(
+        "Received code actions request for file: {}, lines {}-{}",
+        info.file_path, info.range.range.start.line, info.range.range.end.line
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    if info.range.path != info.file_path {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            format!(
+                "Range path {} does not match file path {}",
+                info.range.path, info.file_path
+            ),
+        ));
+    }
+
+    match data
+        .manager
+        .code_actions(
+            &info.file_path,
+            info.range.clone().into(),
+            info.only.clone(),
+        )
+        .await
+    {
+        Ok(actions) => HttpResponse::Ok().json(CodeActionsResponse {
+            actions: actions.into_iter().map(CodeAction::from).collect(),
+        }),
+        Err(e) => {
+            error!("Failed to get code actions: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test]
+    async fn test_typescript_add_declaration() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("main.ts"),
+            "const total: number = computeTotal(1, 2);\nconsole.log(total);\n",
+        )?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = code_actions(
+            state,
+            Json(CodeActionsRequest {
+                file_path: String::from("main.ts"),
+                range: FileRange {
+                    path: String::from("main.ts"),
+                    range: Range {
+                        start: Position {
+                            line: 0,
+                            character: 22,
+                        },
+                        end: Position {
+                            line: 0,
+                            character: 34,
+                        },
+                    },
+                },
+                only: Some(vec![String::from("quickfix")]),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let code_actions_response: CodeActionsResponse = serde_json::from_slice(&bytes)?;
+
+        let declaration = code_actions_response
+            .actions
+            .iter()
+            .find(|action| action.title.contains("declaration"))
+            .ok_or("No add declaration action returned")?;
+        assert_eq!(declaration.kind.as_deref(), Some("quickfix"));
+        let edit = declaration.edit.as_ref().ok_or("Action has no edit")?;
+        let edits = edit.changes.get("main.ts").ok_or("No edits to main.ts")?;
+        assert!(edits
+            .iter()
+            .any(|edit| edit.new_text.contains("function computeTotal")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_range_path() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&crate::test_utils::python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = code_actions(
+            state,
+            Json(CodeActionsRequest {
+                file_path: String::from("main.py"),
+                range: FileRange {
+                    path: String::from("graph.py"),
+                    range: Range {
+                        start: Position {
+                            line: 0,
+                            character: 0,
+                        },
+                        end: Position {
+                            line: 0,
+                            character: 0,
+                        },
+                    },
+                },
+                only: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
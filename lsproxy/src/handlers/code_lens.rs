@@ -0,0 +1,90 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{CodeLensRequest, CodeLensResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the code lenses of a file
+///
+/// Returns the annotations the language server attaches to code, such as "▶ Run" above a
+/// `main` function or test, or reference counts above definitions.
+///
+/// Currently supported for Rust and Java.
+#[utoipa::path(
+    get,
+    path = "/symbol/code-lens",
+    tag = "symbol",
+    params(CodeLensRequest),
+    responses(
+        (status = 200, description = "Code lenses retrieved successfully", body = CodeLensResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Code lenses not supported for this language")
+    )
+)]
+pub async fn code_lens(data: Data<AppState>, info: Query<CodeLensRequest>) -> HttpResponse {
+    info!("Received code lens request for file: {}", info.file_path);
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.code_lenses(&info.file_path).await {
+        Ok(lenses) => HttpResponse::Ok().json(CodeLensResponse { lenses }),
+        Err(e) => {
+            error!("Failed to get code lenses: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{rust_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_rust_main_run_code_lens() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&rust_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = code_lens(
+            state,
+            Query(CodeLensRequest {
+                file_path: String::from("src/main.rs"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let lens_response: CodeLensResponse = serde_json::from_slice(&bytes)?;
+
+        // `fn main()` is on line 10
+        let main_lenses: Vec<_> = lens_response
+            .lenses
+            .iter()
+            .filter(|lens| lens.range.range.start.line == 10)
+            .collect();
+        assert!(
+            main_lenses
+                .iter()
+                .any(|lens| lens.title.contains("Run") || lens.title.contains("Test")),
+            "no run lens on main: {:?}",
+            lens_response.lenses
+        );
+        assert!(main_lenses
+            .iter()
+            .all(|lens| lens.range.path == "src/main.rs"));
+        Ok(())
+    }
+}
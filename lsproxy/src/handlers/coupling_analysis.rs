@@ -0,0 +1,69 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::FileCoupling;
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Measure how strongly the workspace files depend on each other
+///
+/// For each pair of files, counts the symbols defined in `file_a` that are referenced in
+/// `file_b`. Highly coupled files are candidates for refactoring.
+///
+/// Returns at most the 100 most coupled pairs, highest score first. Every symbol in the
+/// workspace is looked up, so this can take a while for large workspaces.
+#[utoipa::path(
+    get,
+    path = "/workspace/coupling-analysis",
+    tag = "workspace",
+    responses(
+        (status = 200, description = "Coupling analyzed successfully", body = Vec<FileCoupling>),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn coupling_analysis(data: Data<AppState>) -> HttpResponse {
+    info!("Received coupling analysis request");
+
+    match data.manager.analyze_symbol_coupling().await {
+        Ok(couplings) => HttpResponse::Ok().json(couplings),
+        Err(e) => {
+            error!("Failed to analyze coupling: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_coupling_analysis() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = coupling_analysis(state).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let couplings: Vec<FileCoupling> = serde_json::from_slice(&bytes)?;
+        assert!(couplings
+            .windows(2)
+            .all(|pair| pair[0].coupling_score >= pair[1].coupling_score));
+
+        // search.py uses the graph class along with its heuristic, neighbours and cost methods
+        assert_eq!(couplings[0].file_a, "graph.py");
+        assert_eq!(couplings[0].file_b, "search.py");
+        assert!(couplings[0].coupling_score >= 3);
+        Ok(())
+    }
+}
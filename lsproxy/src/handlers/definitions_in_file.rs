@@ -1,8 +1,13 @@
-use actix_web::web::{Data, Query};
+use actix_web::http::header::IfNoneMatch;
+use actix_web::web::{Data, Header, Query};
 use actix_web::HttpResponse;
 use log::info;
 
-use crate::api_types::{ErrorResponse, FileSymbolsRequest, Symbol};
+use crate::api_types::{ErrorResponse, FileSymbolsRequest, Symbol, SymbolKind};
+use crate::handlers::etag::{
+    cached_not_modified, definitions_in_file_etag_key, etag_json_response,
+};
+use crate::handlers::utils::{parse_kinds_param, validate_file_in_workspace};
 use crate::AppState;
 
 /// Get symbols in a specific file (uses ast-grep)
@@ -21,6 +26,14 @@ use crate::AppState;
 /// 2:         self.name = name
 /// 3:         self.age = age
 /// ```
+///
+/// Set `kinds` to a comma-separated list of symbol kinds, e.g. `function,class`, to only get those
+/// kinds of symbols. Unknown kinds are rejected with a 400 listing the valid ones.
+///
+/// Set `include_comments` to also get the comments marking work to do, e.g. `# TODO: ...`, as
+/// symbols of kind `todo-comment` named after the comment's text.
+///
+/// Unfiltered responses carry an `ETag`; send it back as `If-None-Match` to get a 304 when the file is unchanged.
 #[utoipa::path(
     get,
     path = "/symbol/definitions-in-file",
@@ -28,6 +41,7 @@ use crate::AppState;
     params(FileSymbolsRequest),
     responses(
         (status = 200, description = "Symbols retrieved successfully", body = Vec<Symbol>),
+        (status = 304, description = "Symbols unchanged since the given ETag"),
         (status = 400, description = "Bad request"),
         (status = 500, description = "Internal server error")
     )
@@ -35,28 +49,76 @@ use crate::AppState;
 pub async fn definitions_in_file(
     data: Data<AppState>,
     info: Query<FileSymbolsRequest>,
+    if_none_match: Option<Header<IfNoneMatch>>,
 ) -> HttpResponse {
     info!(
         "Received definitions in file request for file: {}",
         info.file_path
     );
 
-    match data
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    let kinds = match parse_kinds_param(info.kinds.as_deref()) {
+        Ok(kinds) => kinds,
+        Err(response) => return response,
+    };
+
+    // Filtered responses and those with comments aren't cached, since the cache holds one hash
+    // per file
+    let cacheable = kinds.is_none() && !info.include_comments;
+    let etag_key = definitions_in_file_etag_key(&info.file_path);
+    if cacheable {
+        if let Some(response) = cached_not_modified(&data, &etag_key, &if_none_match).await {
+            return response;
+        }
+    }
+
+    let symbols = match data
         .manager
-        .definitions_in_file_ast_grep(&info.file_path)
+        .definitions_in_file_ast_grep_cached(&info.file_path)
         .await
     {
+        Ok(symbols) if info.include_comments => {
+            match data.manager.todo_comments_in_file(&info.file_path).await {
+                Ok(comments) => {
+                    let mut symbols: Vec<_> = symbols.into_iter().chain(comments).collect();
+                    symbols.sort_by_key(|s| {
+                        let start = s.get_identifier_range().start;
+                        (start.line, start.column)
+                    });
+                    Ok(symbols)
+                }
+                Err(e) => Err(e),
+            }
+        }
+        result => result,
+    };
+
+    match symbols {
         Ok(symbols) => {
             let symbol_response: Vec<Symbol> = symbols
                 .into_iter()
                 .filter(|s| s.rule_id != "local-variable")
+                .filter(|s| {
+                    kinds.as_ref().is_none_or(|kinds| {
+                        s.rule_id
+                            .parse::<SymbolKind>()
+                            .is_ok_and(|kind| kinds.contains(&kind))
+                    })
+                })
                 .map(Symbol::from)
                 .collect();
-            HttpResponse::Ok().json(symbol_response)
+            if !cacheable {
+                return HttpResponse::Ok().json(symbol_response);
+            }
+            etag_json_response(&data, &etag_key, &if_none_match, &symbol_response).await
         }
-        Err(e) => HttpResponse::BadRequest().json(ErrorResponse {
-            error: format!("Couldn't get symbols: {}", e),
-        }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse::new(
+            e.error_code(),
+            format!("Couldn't get symbols: {}", e),
+        )),
     }
 }
 
@@ -66,9 +128,9 @@ mod test {
 
     use actix_web::http::StatusCode;
 
-    use crate::api_types::{FilePosition, FileRange, Position, Range, Symbol};
+    use crate::api_types::{FilePosition, FileRange, Position, Range, SupportedLanguages, Symbol};
     use crate::initialize_app_state;
-    use crate::test_utils::{python_sample_path, TestContext};
+    use crate::test_utils::{mocks::MockFile, python_sample_path, TestContext};
 
     #[tokio::test]
     async fn test_python_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
@@ -77,9 +139,11 @@ mod test {
 
         let mock_request = Query(FileSymbolsRequest {
             file_path: String::from("main.py"),
+            kinds: None,
+            include_comments: false,
         });
 
-        let response = definitions_in_file(state, mock_request).await;
+        let response = definitions_in_file(state, mock_request, None).await;
 
         assert_eq!(response.status(), StatusCode::OK);
         assert_eq!(
@@ -146,4 +210,181 @@ mod test {
         assert_eq!(expected, file_symbols_response);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_python_file_symbols_kinds_filter() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let request = |kinds: &str| {
+            Query(FileSymbolsRequest {
+                file_path: String::from("graph.py"),
+                kinds: Some(String::from(kinds)),
+                include_comments: false,
+            })
+        };
+
+        let response = definitions_in_file(state.clone(), request("class"), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        assert!(!symbols.is_empty());
+        assert!(symbols.iter().all(|symbol| symbol.kind == "class"));
+        assert!(symbols.iter().any(|symbol| symbol.name == "AStarGraph"));
+
+        for kinds in ["klass", "class,klass"] {
+            let response = definitions_in_file(state.clone(), request(kinds), None).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let bytes = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let error: ErrorResponse = serde_json::from_slice(&bytes)?;
+            assert!(error.error.starts_with("Unknown symbol kind 'klass'"));
+            assert!(error.error.contains("class, constant, enum"));
+        }
+        Ok(())
+    }
+
+    fn if_none_match(etag: &str) -> Option<Header<IfNoneMatch>> {
+        let tag = etag.parse().expect("valid entity tag");
+        Some(Header(IfNoneMatch::Items(vec![tag])))
+    }
+
+    #[tokio::test]
+    async fn test_python_file_symbols_not_modified() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let request = || {
+            Query(FileSymbolsRequest {
+                file_path: String::from("main.py"),
+                kinds: None,
+                include_comments: false,
+            })
+        };
+
+        let response = definitions_in_file(state.clone(), request(), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()?
+            .to_string();
+
+        let response = definitions_in_file(state, request(), if_none_match(&etag)).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get("etag").unwrap().to_str()?, etag);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert!(bytes.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_file_symbols_etag_changes_on_edit(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("main.py"), "def first():\n    pass\n")?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let request = || {
+            Query(FileSymbolsRequest {
+                file_path: String::from("main.py"),
+                kinds: None,
+                include_comments: false,
+            })
+        };
+
+        let response = definitions_in_file(state.clone(), request(), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()?
+            .to_string();
+
+        std::fs::write(
+            dir.path().join("main.py"),
+            "def first():\n    pass\n\n\ndef second():\n    pass\n",
+        )?;
+        // Wait for the debounced watch event to invalidate the stored hash
+        tokio::time::sleep(std::time::Duration::from_secs(4)).await;
+
+        let response = definitions_in_file(state, request(), if_none_match(&etag)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let new_etag = response
+            .headers()
+            .get("etag")
+            .unwrap()
+            .to_str()?
+            .to_string();
+        assert_ne!(etag, new_etag);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        assert!(symbols.iter().any(|s| s.name == "second"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_symbols_with_comments() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new(
+            "main.py",
+            "# TODO: handle empty graphs\ndef main():\n    # A regular comment\n    pass  # FIXME\n",
+        )];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+        let request = |include_comments: bool| {
+            Query(FileSymbolsRequest {
+                file_path: String::from("main.py"),
+                kinds: None,
+                include_comments,
+            })
+        };
+
+        let response = definitions_in_file(state.clone(), request(true), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        let names: Vec<(&str, &str)> = symbols
+            .iter()
+            .map(|symbol| (symbol.kind.as_str(), symbol.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("todo-comment", "# TODO: handle empty graphs"),
+                ("function", "main"),
+                ("todo-comment", "# FIXME"),
+            ]
+        );
+        assert_eq!(
+            symbols[0].identifier_position,
+            FilePosition {
+                path: String::from("main.py"),
+                position: Position {
+                    line: 0,
+                    character: 0,
+                },
+            }
+        );
+
+        let response = definitions_in_file(state, request(false), None).await;
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_slice(&bytes)?;
+        assert!(symbols.iter().all(|symbol| symbol.kind != "todo-comment"));
+        Ok(())
+    }
 }
@@ -0,0 +1,140 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FileRange, Symbol, SymbolResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get symbols defined within a range of a file (uses ast-grep)
+///
+/// Returns the symbols (functions, classes, variables, etc.) whose definitions overlap the given
+/// range, including symbols that only partially overlap it.
+///
+/// As with `definitions-in-file`, only the variables defined at the file level are included.
+///
+/// e.g. for lines 1-2 of `src/main.py`, both `User` and `__init__` are returned:
+/// ```text
+/// 0: class User:
+/// 1:     def __init__(self, name, age):
+/// 2:         self.name = name
+/// 3:         self.age = age
+/// ```
+#[utoipa::path(
+    post,
+    path = "/symbol/definitions-in-range",
+    tag = "symbol",
+    request_body = FileRange,
+    responses(
+        (status = 200, description = "Symbols retrieved successfully", body = Vec<Symbol>),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn definitions_in_range(data: Data<AppState>, info: Json<FileRange>) -> HttpResponse {
+    info!(
+        "Received definitions in range request for file: {}, lines {}-{}",
+        info.path, info.range.start.line, info.range.end.line
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .definitions_in_range(&info.path, info.clone().into())
+        .await
+    {
+        Ok(symbols) => {
+            let symbol_response: SymbolResponse = symbols
+                .into_iter()
+                .filter(|s| s.rule_id != "local-variable")
+                .map(Symbol::from)
+                .collect();
+            HttpResponse::Ok().json(symbol_response)
+        }
+        Err(e) => {
+            error!("Failed to get definitions in range: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_class_body() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The body of `AStarGraph`, from its first method to the end of the file
+        let response = definitions_in_range(
+            state,
+            Json(FileRange {
+                path: String::from("graph.py"),
+                range: Range {
+                    start: Position {
+                        line: 13,
+                        character: 4,
+                    },
+                    end: Position {
+                        line: 88,
+                        character: 16,
+                    },
+                },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let symbols: SymbolResponse = serde_json::from_slice(&bytes)?;
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+
+        for method in [
+            "__init__",
+            "barriers",
+            "_barrier_cost",
+            "_distance_cost",
+            "_combined_cost",
+            "move_cost",
+            "heuristic",
+            "get_vertex_neighbours",
+        ] {
+            assert!(
+                names.contains(&method),
+                "{} missing from {:?}",
+                method,
+                names
+            );
+        }
+        // The class itself partially overlaps the range
+        assert!(names.contains(&"AStarGraph"));
+        for outside in [
+            "GraphBase",
+            "CostStrategy",
+            "BARRIER",
+            "DISTANCE",
+            "COMBINED",
+        ] {
+            assert!(
+                !names.contains(&outside),
+                "{} unexpectedly in {:?}",
+                outside,
+                names
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::info;
+
+use crate::api_types::{DetectLanguageRequest, DetectLanguageResponse};
+use crate::AppState;
+
+/// Detect the language of a file
+///
+/// Returns the language whose server handles the file. Workspace files belong to the server
+/// listing them; other files are detected from their extension or, for files without a known
+/// extension, from their content, e.g. a shebang. Returns null for files no language server
+/// handles.
+///
+/// The file doesn't need to exist, so this can be used to check which server a new file would
+/// be served by.
+#[utoipa::path(
+    get,
+    path = "/workspace/detect-language",
+    tag = "workspace",
+    params(DetectLanguageRequest),
+    responses(
+        (status = 200, description = "Language detected successfully", body = DetectLanguageResponse),
+        (status = 400, description = "Bad request")
+    )
+)]
+pub async fn detect_language(
+    data: Data<AppState>,
+    info: Query<DetectLanguageRequest>,
+) -> HttpResponse {
+    info!("Received detect language request for file: {}", info.file);
+
+    HttpResponse::Ok().json(DetectLanguageResponse {
+        language: data.manager.get_language_for_file(&info.file).await,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::SupportedLanguages;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    #[tokio::test]
+    async fn test_detect_language() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.py", "")];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let cases = [
+            ("main.py", Some(SupportedLanguages::Python)),
+            ("src/lib.rs", Some(SupportedLanguages::Rust)),
+            ("notes.xyz", None),
+        ];
+        for (file, expected) in cases {
+            let response = detect_language(
+                state.clone(),
+                Query(DetectLanguageRequest {
+                    file: String::from(file),
+                }),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let bytes = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let value: serde_json::Value = serde_json::from_slice(&bytes)?;
+            assert_eq!(
+                value["language"],
+                serde_json::to_value(expected)?,
+                "{}",
+                file
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{DocumentLinksRequest, DocumentLinksResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the links in a file
+///
+/// Returns the links embedded in a file, such as URLs in doc comments (e.g. JSDoc `@see`
+/// tags), with the range they appear at.
+///
+/// Currently supported for Python and TypeScript/JavaScript.
+#[utoipa::path(
+    get,
+    path = "/symbol/document-links",
+    tag = "symbol",
+    params(DocumentLinksRequest),
+    responses(
+        (status = 200, description = "Document links retrieved successfully", body = DocumentLinksResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Document links not supported for this language")
+    )
+)]
+pub async fn document_links(
+    data: Data<AppState>,
+    info: Query<DocumentLinksRequest>,
+) -> HttpResponse {
+    info!(
+        "Received document links request for file: {}",
+        info.file_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.document_links(&info.file_path).await {
+        Ok(links) => HttpResponse::Ok().json(DocumentLinksResponse { links }),
+        Err(e) => {
+            error!("Failed to get document links: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{DocumentLink, FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test]
+    async fn test_typescript_jsdoc_see_links() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("tsconfig.json"), "{}\n")?;
+        std::fs::write(
+            dir.path().join("distance.ts"),
+            "/**\n * Manhattan distance between two grid cells.\n * @see https://en.wikipedia.org/wiki/Taxicab_geometry\n */\nexport function manhattan(x: number, y: number): number {\n    return Math.abs(x) + Math.abs(y);\n}\n",
+        )?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = document_links(
+            state,
+            Query(DocumentLinksRequest {
+                file_path: String::from("distance.ts"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let links_response: DocumentLinksResponse = serde_json::from_slice(&bytes)?;
+
+        assert_eq!(
+            links_response.links,
+            vec![DocumentLink {
+                range: FileRange {
+                    path: String::from("distance.ts"),
+                    range: Range {
+                        start: Position {
+                            line: 2,
+                            character: 8,
+                        },
+                        end: Position {
+                            line: 2,
+                            character: 54,
+                        },
+                    },
+                },
+                target: String::from("https://en.wikipedia.org/wiki/Taxicab_geometry"),
+            }]
+        );
+        Ok(())
+    }
+}
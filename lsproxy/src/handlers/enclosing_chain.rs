@@ -0,0 +1,139 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{EnclosingChainResponse, FilePosition};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the chain of symbols enclosing a position (uses ast-grep)
+///
+/// Returns every symbol whose definition contains the given position, from the outermost to the
+/// innermost, e.g. the class, then the method, then the nested function. The chain is empty if
+/// the position is outside every symbol.
+///
+/// e.g. for line 3, character 12 of `src/scheduler.py`:
+/// ```text
+/// 0: class Scheduler:
+/// 1:     def run(self, jobs):
+/// 2:         def on_done(job):
+/// 3:             self.finished.append(job)
+/// ```
+/// the chain is `Scheduler` (lines 0-3), then `run` (lines 1-3), then `on_done` (lines 2-3).
+#[utoipa::path(
+    post,
+    path = "/symbol/enclosing-chain",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Enclosing chain retrieved successfully", body = EnclosingChainResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn enclosing_chain(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received enclosing chain request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .get_symbol_enclosing_chain(&info.path, &info.position.clone().into())
+        .await
+    {
+        Ok(chain) => HttpResponse::Ok().json(EnclosingChainResponse { chain }),
+        Err(e) => {
+            error!("Failed to get enclosing chain: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    async fn chain_at(
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = enclosing_chain(
+            state,
+            Json(FilePosition {
+                path: String::from(path),
+                position: Position { line, character },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let chain_response: EnclosingChainResponse = serde_json::from_slice(&bytes)?;
+        Ok(chain_response
+            .chain
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind))
+            .collect())
+    }
+
+    fn named(symbols: &[(&str, &str)]) -> Vec<(String, String)> {
+        symbols
+            .iter()
+            .map(|(name, kind)| (String::from(*name), String::from(*kind)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_python_enclosing_chain() -> Result<(), Box<dyn std::error::Error>> {
+        // Inside the body of `AStarGraph.__init__`
+        let chain = chain_at("graph.py", 14, 8).await?;
+        assert_eq!(
+            chain,
+            named(&[("AStarGraph", "class"), ("__init__", "function")])
+        );
+
+        // Inside `reconstruct_path`, nested in `a_star_search`
+        let chain = chain_at("search.py", 23, 8).await?;
+        assert_eq!(
+            chain,
+            named(&[
+                ("a_star_search", "function"),
+                ("reconstruct_path", "function")
+            ])
+        );
+
+        // The imports aren't part of any symbol
+        let chain = chain_at("graph.py", 0, 0).await?;
+        assert!(chain.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_enclosing_chain_in_lambda() -> Result<(), Box<dyn std::error::Error>> {
+        // Inside `lambda pos: F[pos]`, which is assigned to `current` in `a_star_search`
+        let chain = chain_at("search.py", 34, 53).await?;
+        assert_eq!(
+            chain,
+            named(&[("a_star_search", "function"), ("current", "local-variable")])
+        );
+        Ok(())
+    }
+}
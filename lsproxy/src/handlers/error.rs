@@ -9,24 +9,133 @@ pub trait IntoHttpResponse {
 impl IntoHttpResponse for LspManagerError {
     fn into_http_response(self) -> HttpResponse {
         log::error!("LSP error: {}", self);
-        match self {
-            Self::FileNotFound(path) => HttpResponse::BadRequest().json(ErrorResponse {
-                error: format!("File not found: {}", path),
-            }),
-            Self::LspClientNotFound(lang) => {
-                HttpResponse::InternalServerError().json(ErrorResponse {
-                    error: format!("LSP client not found for {:?}", lang),
-                })
+        let error_code = self.error_code();
+        let (mut response, error) = match self {
+            Self::FileNotFound(path) => (
+                HttpResponse::BadRequest(),
+                format!("File not found: {}", path),
+            ),
+            Self::LspClientNotFound(lang) => (
+                HttpResponse::InternalServerError(),
+                format!("LSP client not found for {}", lang.display_name()),
+            ),
+            Self::InternalError(msg) => (
+                HttpResponse::InternalServerError(),
+                format!("Internal error: {}", msg),
+            ),
+            Self::UnsupportedFileType(path) => (
+                HttpResponse::BadRequest(),
+                format!("Unsupported file type: {}", path),
+            ),
+            Self::NotImplemented(msg) => (
+                HttpResponse::NotImplemented(),
+                format!("Not implemented: {}", msg),
+            ),
+            Self::InvalidPath(path) => (
+                HttpResponse::BadRequest(),
+                format!("Invalid path: {}", path),
+            ),
+            Self::InvalidEdit(msg) => {
+                (HttpResponse::BadRequest(), format!("Invalid edit: {}", msg))
             }
-            Self::InternalError(msg) => HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Internal error: {}", msg),
-            }),
-            Self::UnsupportedFileType(path) => HttpResponse::BadRequest().json(ErrorResponse {
-                error: format!("Unsupported file type: {}", path),
-            }),
-            Self::NotImplemented(msg) => HttpResponse::NotImplemented().json(ErrorResponse {
-                error: format!("Not implemented: {}", msg),
-            }),
+            Self::InitializationTimeout(lang) => (
+                HttpResponse::ServiceUnavailable(),
+                format!(
+                    "LSP client for {} timed out during initialization",
+                    lang.display_name()
+                ),
+            ),
+            Self::SymbolNotFound(location) => (
+                HttpResponse::BadRequest(),
+                format!("No symbol found at {}", location),
+            ),
+        };
+        response.json(ErrorResponse::new(error_code, error))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{ErrorCode, SupportedLanguages};
+
+    async fn error_response(error: LspManagerError) -> (StatusCode, ErrorResponse) {
+        let response = error.into_http_response();
+        let status = response.status();
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let cases = [
+            (
+                LspManagerError::FileNotFound(String::from("main.py")),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::FileNotFound,
+                "File not found: main.py",
+            ),
+            (
+                LspManagerError::LspClientNotFound(SupportedLanguages::Python),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::LspClientNotFound,
+                "LSP client not found for Python",
+            ),
+            (
+                LspManagerError::InternalError(String::from("boom")),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::InternalError,
+                "Internal error: boom",
+            ),
+            (
+                LspManagerError::UnsupportedFileType(String::from("README.md")),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::UnsupportedFileType,
+                "Unsupported file type: README.md",
+            ),
+            (
+                LspManagerError::NotImplemented(String::from("hover")),
+                StatusCode::NOT_IMPLEMENTED,
+                ErrorCode::NotImplemented,
+                "Not implemented: hover",
+            ),
+            (
+                LspManagerError::InvalidPath(String::from("../main.py")),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidPath,
+                "Invalid path: ../main.py",
+            ),
+            (
+                LspManagerError::InvalidEdit(String::from("overlapping edits")),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidEdit,
+                "Invalid edit: overlapping edits",
+            ),
+            (
+                LspManagerError::InitializationTimeout(SupportedLanguages::Rust),
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorCode::Timeout,
+                "LSP client for Rust timed out during initialization",
+            ),
+            (
+                LspManagerError::SymbolNotFound(String::from("main.py:3:4")),
+                StatusCode::BAD_REQUEST,
+                ErrorCode::SymbolNotFound,
+                "No symbol found at main.py:3:4",
+            ),
+        ];
+        for (error, status, error_code, message) in cases {
+            assert_eq!(error.error_code(), error_code);
+            let (response_status, response) = error_response(error).await;
+            assert_eq!(response_status, status);
+            assert_eq!(response.error_code, error_code);
+            assert_eq!(response.error, message);
+            assert_eq!(response.request_id, None);
         }
     }
 }
@@ -0,0 +1,77 @@
+use actix_web::http::header::{EntityTag, IfNoneMatch, ETAG};
+use actix_web::web::Header;
+use actix_web::HttpResponse;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::AppState;
+
+/// Cache key for `/workspace/list-files` responses; also the prefix for per-language keys.
+pub(crate) const LIST_FILES_ETAG_KEY: &str = "list-files";
+
+/// Cache key for `/symbol/definitions-in-file` responses of the given file.
+pub(crate) fn definitions_in_file_etag_key(file_path: &str) -> String {
+    format!("definitions-in-file:{}", file_path)
+}
+
+fn matches(if_none_match: &Option<Header<IfNoneMatch>>, etag: &EntityTag) -> bool {
+    match if_none_match.as_ref().map(|h| &h.0) {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        None => false,
+    }
+}
+
+fn not_modified(etag: EntityTag) -> HttpResponse {
+    HttpResponse::NotModified()
+        .insert_header((ETAG, etag.to_string()))
+        .finish()
+}
+
+/// Returns 304 if the client already holds the last hash computed for `key`.
+///
+/// The stored hash is dropped whenever a watched file changes, so a hit here is always fresh.
+pub(crate) async fn cached_not_modified(
+    data: &AppState,
+    key: &str,
+    if_none_match: &Option<Header<IfNoneMatch>>,
+) -> Option<HttpResponse> {
+    if if_none_match.is_none() {
+        return None;
+    }
+    let hash = data.etags.lock().await.get(key).cloned()?;
+    let etag = EntityTag::new_strong(hash);
+    matches(if_none_match, &etag).then(|| not_modified(etag))
+}
+
+/// Serializes `body`, stores its SHA-256 hash under `key` and responds with an `ETag`.
+///
+/// Responds with 304 and no body if the hash matches the request's `If-None-Match`.
+pub(crate) async fn etag_json_response<T: Serialize>(
+    data: &AppState,
+    key: &str,
+    if_none_match: &Option<Header<IfNoneMatch>>,
+    body: &T,
+) -> HttpResponse {
+    let bytes = match serde_json::to_vec(body) {
+        Ok(bytes) => bytes,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let hash: String = Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    data.etags
+        .lock()
+        .await
+        .insert(key.to_string(), hash.clone());
+
+    let etag = EntityTag::new_strong(hash);
+    if matches(if_none_match, &etag) {
+        return not_modified(etag);
+    }
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((ETAG, etag.to_string()))
+        .body(bytes)
+}
@@ -0,0 +1,318 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info, warn};
+
+use crate::api_types::{
+    ContextEntry, ErrorCode, ErrorResponse, ExplainSymbolResponse, FilePosition, FileRange,
+    ReferenceWithSymbolDefinitions, Symbol,
+};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::find_referenced_symbols::categorize_referenced_symbols;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::lsp::manager::{LspManagerError, Manager};
+use crate::utils::workspace_documents::max_source_code_bytes;
+use crate::AppState;
+
+/// Explain the symbol at a position in one request (uses ast-grep)
+///
+/// Gathers what is usually fetched with several requests to understand a symbol: the symbol
+/// whose identifier is at the position, its source code, its documentation from the language
+/// server's hover, how many times it is referenced, and the source code of the workspace symbols
+/// it references, e.g. the decorator and class a function uses.
+///
+/// Returns 400 if no symbol is defined at the position. Documentation and dependencies that can't
+/// be retrieved are left out rather than failing the request.
+#[utoipa::path(
+    post,
+    path = "/symbol/explain-symbol",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Symbol explained successfully", body = ExplainSymbolResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn explain_symbol(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received explain symbol request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    let symbol = match data
+        .manager
+        .get_symbol_from_position(&info.path, &info.position.clone().into())
+        .await
+    {
+        Ok(Some(symbol)) => symbol,
+        Ok(None) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::BadRequest,
+                format!(
+                    "No symbol found at {}:{}:{}",
+                    info.path, info.position.line, info.position.character
+                ),
+            ));
+        }
+        Err(e) => {
+            error!("Failed to get symbol from position: {}", e);
+            return e.into_http_response();
+        }
+    };
+
+    match explain(&data.manager, symbol).await {
+        Ok(explanation) => HttpResponse::Ok().json(explanation),
+        Err(e) => {
+            error!("Failed to explain symbol: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+async fn explain(
+    manager: &Manager,
+    symbol: Symbol,
+) -> Result<ExplainSymbolResponse, LspManagerError> {
+    let identifier_position = symbol.identifier_position.clone();
+    let position = identifier_position.position.clone().into();
+
+    let source = read_symbol_source(manager, &symbol.file_range).await?;
+    let documentation = match manager.get_hover(&identifier_position.path, position).await {
+        Ok(documentation) => documentation,
+        Err(e) => {
+            warn!("Failed to get documentation for {}: {}", symbol.name, e);
+            None
+        }
+    };
+    let reference_count = manager
+        .get_symbol_references_count(&identifier_position.path, position)
+        .await?;
+
+    let workspace_symbols = match referenced_workspace_symbols(manager, &identifier_position).await
+    {
+        Ok(workspace_symbols) => workspace_symbols,
+        Err(e) => {
+            warn!(
+                "Failed to get referenced symbols for {}: {}",
+                symbol.name, e
+            );
+            Vec::new()
+        }
+    };
+
+    // A symbol referenced several times is only included once
+    let mut dependencies: Vec<ContextEntry> = Vec::new();
+    for definition in workspace_symbols
+        .into_iter()
+        .flat_map(|symbol| symbol.definitions)
+    {
+        if definition.file_range == symbol.file_range
+            || dependencies
+                .iter()
+                .any(|entry| entry.symbol.file_range == definition.file_range)
+        {
+            continue;
+        }
+        let source = read_symbol_source(manager, &definition.file_range).await?;
+        dependencies.push(ContextEntry {
+            symbol: definition,
+            source,
+        });
+    }
+
+    Ok(ExplainSymbolResponse {
+        symbol,
+        source,
+        documentation,
+        reference_count,
+        dependencies,
+    })
+}
+
+async fn referenced_workspace_symbols(
+    manager: &Manager,
+    identifier_position: &FilePosition,
+) -> Result<Vec<ReferenceWithSymbolDefinitions>, LspManagerError> {
+    let referenced_symbols = manager
+        .find_referenced_symbols(
+            &identifier_position.path,
+            identifier_position.position.clone().into(),
+            false,
+        )
+        .await?;
+    let (workspace_symbols, _, _) =
+        categorize_referenced_symbols(manager, referenced_symbols).await?;
+    Ok(workspace_symbols)
+}
+
+async fn read_symbol_source(
+    manager: &Manager,
+    file_range: &FileRange,
+) -> Result<String, LspManagerError> {
+    manager
+        .read_source_code(
+            &file_range.path,
+            Some(file_range.range.clone().into()),
+            Some(max_source_code_bytes()),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use tokio::time::{sleep, Duration};
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, rust_sample_path, TestContext};
+
+    async fn explain_at(
+        state: Data<AppState>,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<ExplainSymbolResponse, Box<dyn std::error::Error>> {
+        let response = explain_symbol(
+            state,
+            Json(FilePosition {
+                path: String::from(path),
+                position: Position { line, character },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK, "{:?}", response);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn dependency_names(explanation: &ExplainSymbolResponse) -> Vec<&str> {
+        explanation
+            .dependencies
+            .iter()
+            .map(|entry| entry.symbol.name.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_python_explain_symbol() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+        sleep(Duration::from_secs(5)).await;
+
+        // `initialize_search` in search.py
+        let explanation = explain_at(state, "search.py", 5, 4).await?;
+        assert_eq!(explanation.symbol.name, "initialize_search");
+        assert_eq!(explanation.symbol.kind, "function");
+        assert!(
+            explanation
+                .source
+                .contains("def initialize_search(start, end, graph: AStarGraph):"),
+            "{}",
+            explanation.source
+        );
+        assert!(explanation
+            .source
+            .ends_with("return G, F, closed_vertices, open_vertices, came_from"));
+        let documentation = explanation
+            .documentation
+            .as_deref()
+            .ok_or("Missing documentation")?;
+        assert!(
+            documentation.contains("initialize_search"),
+            "{}",
+            documentation
+        );
+        // Called from a_star_search
+        assert_eq!(explanation.reference_count, 1);
+
+        // The decorator, the graph class and its heuristic, each once
+        let names = dependency_names(&explanation);
+        for name in ["log_execution_time", "AStarGraph", "heuristic"] {
+            assert_eq!(
+                names.iter().filter(|n| **n == name).count(),
+                1,
+                "{:?}",
+                names
+            );
+        }
+        let decorator = explanation
+            .dependencies
+            .iter()
+            .find(|entry| entry.symbol.name == "log_execution_time")
+            .ok_or("Missing decorator")?;
+        assert_eq!(decorator.symbol.file_range.path, "decorators.py");
+        assert!(decorator.source.starts_with("def log_execution_time"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rust_explain_symbol() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&rust_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+        sleep(Duration::from_secs(5)).await;
+
+        // `add` in the `Add` implementation of src/point.rs builds a `Point`
+        let explanation = explain_at(state, "src/point.rs", 15, 7).await?;
+        assert_eq!(explanation.symbol.name, "add");
+        assert!(
+            explanation
+                .source
+                .starts_with("fn add(self, other: Self) -> Self {"),
+            "{}",
+            explanation.source
+        );
+        let documentation = explanation
+            .documentation
+            .as_deref()
+            .ok_or("Missing documentation")?;
+        assert!(
+            documentation.contains("fn add(self, other: Self) -> Self"),
+            "{}",
+            documentation
+        );
+
+        let point = explanation
+            .dependencies
+            .iter()
+            .find(|entry| entry.symbol.name == "Point")
+            .ok_or("Missing Point")?;
+        assert_eq!(point.symbol.file_range.path, "src/point.rs");
+        assert!(
+            point.source.contains("pub struct Point"),
+            "{}",
+            point.source
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_explain_symbol_without_symbol() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The import at the top of search.py is outside every symbol
+        let response = explain_symbol(
+            state,
+            Json(FilePosition {
+                path: String::from("search.py"),
+                position: Position {
+                    line: 0,
+                    character: 0,
+                },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Export the workspace dependency graph in Graphviz DOT format
+///
+/// Builds the graph between the workspace files and formats it as `digraph workspace { ... }`,
+/// ready to be rendered with e.g. `dot -Tsvg`. An edge `"a" -> "b";` means `b` uses symbols
+/// defined in `a`. Edges that are part of a dependency cycle are drawn with `[color=red]`.
+///
+/// Every symbol in the workspace is looked up, so this can take a while for large workspaces.
+#[utoipa::path(
+    get,
+    path = "/workspace/export-graph-dot",
+    tag = "workspace",
+    responses(
+        (status = 200, description = "Graph exported successfully", body = String, content_type = "text/plain"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn export_graph_dot(data: Data<AppState>) -> HttpResponse {
+    info!("Received export graph dot request");
+
+    match data.manager.import_graph().await {
+        Ok(edges) => HttpResponse::Ok()
+            .content_type("text/plain")
+            .body(to_dot(&edges)),
+        Err(e) => {
+            error!("Failed to build import graph: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+/// Formats the edges as a DOT digraph, coloring the edges that close a cycle red.
+fn to_dot(edges: &[(String, String)]) -> String {
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (from, to) in edges {
+        successors.entry(from).or_default().push(to);
+    }
+
+    let mut dot = String::from("digraph workspace {\n");
+    for (from, to) in edges {
+        let attributes = if reaches(&successors, to, from) {
+            " [color=red]"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    {:?} -> {:?}{};\n",
+            from.as_str(),
+            to.as_str(),
+            attributes
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Whether `target` can be reached from `start` by following the edges.
+fn reaches(successors: &HashMap<&str, Vec<&str>>, start: &str, target: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        if node == target {
+            return true;
+        }
+        if visited.insert(node) {
+            stack.extend(successors.get(node).into_iter().flatten());
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    fn edge(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn test_to_dot_marks_cycles() {
+        let edges = vec![
+            edge("a.py", "b.py"),
+            edge("b.py", "c.py"),
+            edge("c.py", "a.py"),
+            edge("c.py", "d.py"),
+        ];
+        assert_eq!(
+            to_dot(&edges),
+            "digraph workspace {\n    \
+             \"a.py\" -> \"b.py\" [color=red];\n    \
+             \"b.py\" -> \"c.py\" [color=red];\n    \
+             \"c.py\" -> \"a.py\" [color=red];\n    \
+             \"c.py\" -> \"d.py\";\n\
+             }\n"
+        );
+        assert_eq!(to_dot(&[]), "digraph workspace {\n}\n");
+    }
+
+    #[tokio::test]
+    async fn test_python_export_graph_dot() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = export_graph_dot(state).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert_eq!(content_type, "text/plain");
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let dot = String::from_utf8(bytes.to_vec())?;
+        assert!(dot.starts_with("digraph workspace {\n"));
+        assert!(dot.ends_with("}\n"));
+        // search.py uses the graph class defined in graph.py
+        assert!(dot.contains("\"graph.py\" -> \"search.py\""));
+        Ok(())
+    }
+}
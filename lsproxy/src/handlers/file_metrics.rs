@@ -0,0 +1,100 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::FileMetricsResponse;
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Get code metrics for every file in the workspace
+///
+/// Returns the number of total, blank and comment lines of each file, along with the number of
+/// symbols it defines and the symbol density per 100 lines. Local variables aren't counted as
+/// symbols, like in the file's symbol list.
+///
+/// Comment lines are approximated as lines starting with `#`, `//`, `*` or `--`.
+/// Metrics are cached for 60 seconds per file.
+#[utoipa::path(
+    get,
+    path = "/workspace/file-metrics",
+    tag = "workspace",
+    responses(
+        (status = 200, description = "File metrics retrieved successfully", body = FileMetricsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn file_metrics(data: Data<AppState>) -> HttpResponse {
+    info!("Received file metrics request");
+
+    match data.manager.get_workspace_file_metrics().await {
+        Ok(files) => HttpResponse::Ok().json(FileMetricsResponse { files }),
+        Err(e) => {
+            error!("Failed to get file metrics: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::FileMetrics;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_file_metrics() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = file_metrics(state).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let file_metrics_response: FileMetricsResponse = serde_json::from_slice(&bytes).unwrap();
+
+        let paths: Vec<&str> = file_metrics_response
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "__init__.py",
+                "decorators.py",
+                "graph.py",
+                "main.py",
+                "search.py"
+            ]
+        );
+
+        let main = file_metrics_response
+            .files
+            .iter()
+            .find(|f| f.path == "main.py")
+            .unwrap();
+        assert_eq!(
+            main,
+            &FileMetrics {
+                path: String::from("main.py"),
+                total_lines: 23,
+                blank_lines: 3,
+                comment_lines: 0,
+                symbol_count: 2,
+                symbols_per_100_lines: 2.0 * 100.0 / 23.0,
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{
+    get_mount_dir, FindAllDefinitionsRequest, FindAllDefinitionsResponse, IdentifierWithDefinition,
+};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::find_referenced_symbols::definition_positions;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Resolve identifiers to the symbols they are defined by
+///
+/// Looks up the definition of each identifier at the start of its range, and returns the
+/// workspace symbols enclosing those definitions, in the same order as requested. This is the
+/// second step of `find-referenced-symbols`, e.g. to resolve the references it returned
+/// separately.
+///
+/// e.g. for the identifier `User` in `user = User()`, this would return the `User` class in
+/// `models.py`. The definitions of identifiers defined outside the workspace, e.g. `print`, are
+/// empty.
+#[utoipa::path(
+    post,
+    path = "/symbol/find-all-definitions",
+    tag = "symbol",
+    request_body = FindAllDefinitionsRequest,
+    responses(
+        (status = 200, description = "Definitions retrieved successfully", body = FindAllDefinitionsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn find_all_definitions(
+    data: Data<AppState>,
+    info: Json<FindAllDefinitionsRequest>,
+) -> HttpResponse {
+    info!(
+        "Received find all definitions request for {} identifiers",
+        info.identifiers.len()
+    );
+
+    let files = match data.manager.list_files().await {
+        Ok(files) => files,
+        Err(e) => {
+            error!("Failed to list workspace files: {}", e);
+            return e.into_http_response();
+        }
+    };
+    let mount_dir = get_mount_dir();
+
+    let mut results = Vec::with_capacity(info.identifiers.len());
+    for identifier in info.into_inner().identifiers {
+        if let Err(response) =
+            validate_file_in_workspace(&identifier.file_range.path, &data.manager).await
+        {
+            return response;
+        }
+        let definition_response = match data
+            .manager
+            .find_definition(
+                &identifier.file_range.path,
+                identifier.file_range.range.start.clone().into(),
+            )
+            .await
+        {
+            Ok(definition_response) => definition_response,
+            Err(e) => {
+                error!("Failed to find definition: {}", e);
+                return e.into_http_response();
+            }
+        };
+
+        let mut definitions = Vec::new();
+        for definition in definition_positions(definition_response, &mount_dir)
+            .into_iter()
+            .filter(|definition| files.contains(&definition.path))
+        {
+            match data
+                .manager
+                .get_symbol_from_position(&definition.path, &definition.position.into())
+                .await
+            {
+                Ok(Some(symbol)) => definitions.push(symbol),
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to get symbol from position: {}", e);
+                    return e.into_http_response();
+                }
+            }
+        }
+        results.push(IdentifierWithDefinition {
+            identifier,
+            definitions,
+        });
+    }
+
+    HttpResponse::Ok().json(FindAllDefinitionsResponse { results })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FilePosition, FileRange, Identifier, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    fn identifier(path: &str, name: &str, line: u32, character: u32) -> Identifier {
+        Identifier {
+            name: String::from(name),
+            file_range: FileRange {
+                path: String::from(path),
+                range: Range {
+                    start: Position { line, character },
+                    end: Position {
+                        line,
+                        character: character + name.len() as u32,
+                    },
+                },
+            },
+            kind: None,
+            source_text: None,
+            context_source: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_python_find_all_definitions() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let identifiers = vec![
+            identifier("main.py", "AStarGraph", 15, 12),
+            identifier("main.py", "print", 17, 4),
+        ];
+        let response = find_all_definitions(
+            state,
+            Json(FindAllDefinitionsRequest {
+                identifiers: identifiers.clone(),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: FindAllDefinitionsResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(response.results.len(), 2);
+
+        // `AStarGraph` is the class defined in graph.py
+        let result = &response.results[0];
+        assert_eq!(result.identifier, identifiers[0]);
+        assert_eq!(
+            result
+                .definitions
+                .iter()
+                .map(|symbol| (
+                    symbol.name.as_str(),
+                    symbol.kind.as_str(),
+                    &symbol.identifier_position
+                ))
+                .collect::<Vec<_>>(),
+            vec![(
+                "AStarGraph",
+                "class",
+                &FilePosition {
+                    path: String::from("graph.py"),
+                    position: Position {
+                        line: 12,
+                        character: 6,
+                    },
+                }
+            )]
+        );
+
+        // `print` is a built-in
+        let result = &response.results[1];
+        assert_eq!(result.identifier, identifiers[1]);
+        assert!(result.definitions.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_all_definitions_outside_workspace() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = find_all_definitions(
+            state,
+            Json(FindAllDefinitionsRequest {
+                identifiers: vec![identifier("missing.py", "User", 0, 0)],
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
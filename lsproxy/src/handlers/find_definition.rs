@@ -1,15 +1,21 @@
-use crate::api_types::{CodeContext, ErrorResponse, FileRange, Position, Range};
+use crate::api_types::{
+    get_mount_dir, CodeContext, ErrorCode, ErrorResponse, FileRange, Identifier, Range,
+    ResultSource,
+};
 use crate::handlers::error::IntoHttpResponse;
 use crate::handlers::utils;
+use crate::handlers::utils::validate_file_in_workspace;
 use crate::lsp::manager::{LspManagerError, Manager};
-use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::utils::file_utils::{detect_language, uri_to_relative_path_string};
 use actix_web::web::{Data, Json};
 use actix_web::HttpResponse;
 use log::{error, info, warn};
 
-use crate::api_types::{DefinitionResponse, GetDefinitionRequest};
+use crate::api_types::{DefinitionEntry, DefinitionResponse, GetDefinitionRequest};
 use crate::AppState;
-use lsp_types::{GotoDefinitionResponse, Location, Position as LspPosition, Range as LspRange};
+use lsp_types::{
+    GotoDefinitionResponse, Location, Position as LspPosition, Range as LspRange, Url,
+};
 /// Get the definition of a symbol at a specific position in a file
 ///
 /// Returns the location of the definition for the symbol at the given position.
@@ -29,6 +35,11 @@ use lsp_types::{GotoDefinitionResponse, Location, Position as LspPosition, Range
 /// 5: user = User("John", 30)
 /// input_____^^^^
 /// ```
+///
+/// With `fallback_to_ast_grep`, if the langserver finds no definition in the workspace, e.g. for
+/// dynamically dispatched calls, the workspace's ast-grep definitions with the same name are
+/// searched instead, preferring ones in the same file and skipping local variables. The `source`
+/// field of the response tells which of the two the definitions came from.
 #[utoipa::path(
     post,
     path = "/symbol/find-definition",
@@ -49,13 +60,18 @@ pub async fn find_definition(
         info.position.path, info.position.position.line, info.position.position.character
     );
 
+    if let Err(response) = validate_file_in_workspace(&info.position.path, &data.manager).await {
+        return response;
+    }
+
     let file_identifiers = match data.manager.get_file_identifiers(&info.position.path).await {
         Ok(identifiers) => identifiers,
         Err(e) => {
             error!("Failed to get file identifiers: {:?}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to get file identifiers: {}", e),
-            });
+            return HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get file identifiers: {}", e),
+            ));
         }
     };
     let identifier =
@@ -63,13 +79,14 @@ pub async fn find_definition(
             Ok(identifier) => identifier,
             Err(e) => {
                 error!("Failed to find definition from position: {:?}", e);
-                return HttpResponse::BadRequest().json(ErrorResponse {
-                    error: format!("Failed to find definition from position: {}", e),
-                });
+                return HttpResponse::BadRequest().json(ErrorResponse::new(
+                    ErrorCode::IdentifierNotFound,
+                    format!("Failed to find definition from position: {}", e),
+                ));
             }
         };
 
-    let definitions = match data
+    let lsp_definitions = match data
         .manager
         .find_definition(
             &info.position.path,
@@ -86,6 +103,26 @@ pub async fn find_definition(
         }
     };
 
+    let fallback_definition =
+        if info.fallback_to_ast_grep && !has_workspace_definition(&lsp_definitions) {
+            match find_definition_by_name(&data.manager, &identifier).await {
+                Ok(definition) => definition,
+                Err(e) => {
+                    error!("Failed to find ast-grep definitions: {}", e);
+                    return e.into_http_response();
+                }
+            }
+        } else {
+            None
+        };
+    let (definitions, source) = match fallback_definition {
+        Some(location) => (
+            GotoDefinitionResponse::Scalar(location),
+            ResultSource::AstGrep,
+        ),
+        None => (lsp_definitions.clone(), ResultSource::Lsp),
+    };
+
     let source_code_context = if info.include_source_code {
         match fetch_definition_source_code(&data.manager, &definitions).await {
             Ok(context) => Some(context),
@@ -98,24 +135,145 @@ pub async fn find_definition(
         None
     };
 
+    let definition_entries = match definition_entries(&data.manager, &definitions).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to resolve definition ranges: {}", e);
+            return e.into_http_response();
+        }
+    };
+
     HttpResponse::Ok().json(DefinitionResponse {
         raw_response: if info.include_raw_response {
-            Some(serde_json::to_value(&definitions).unwrap())
+            Some(serde_json::to_value(&lsp_definitions).unwrap())
         } else {
             None
         },
-        definitions: match &definitions {
-            GotoDefinitionResponse::Scalar(location) => vec![location.clone().into()],
-            GotoDefinitionResponse::Array(locations) => {
-                locations.iter().map(|l| l.clone().into()).collect()
-            }
-            GotoDefinitionResponse::Link(links) => links.iter().map(|l| l.clone().into()).collect(),
-        },
+        definitions: definition_entries,
         source_code_context,
         selected_identifier: identifier,
+        source,
     })
 }
 
+/// Converts the definitions to entries with both the identifier range and the full range.
+///
+/// Location links carry both. Plain locations only point at the identifier, so the full range is
+/// taken from the ast-grep symbol whose identifier starts there, for files in the workspace.
+async fn definition_entries(
+    manager: &Manager,
+    definitions: &GotoDefinitionResponse,
+) -> Result<Vec<DefinitionEntry>, LspManagerError> {
+    let locations = match definitions {
+        GotoDefinitionResponse::Scalar(location) => vec![location.clone()],
+        GotoDefinitionResponse::Array(locations) => locations.clone(),
+        GotoDefinitionResponse::Link(links) => {
+            return Ok(links
+                .iter()
+                .map(|link| {
+                    let path = uri_to_relative_path_string(&link.target_uri);
+                    DefinitionEntry {
+                        identifier_range: FileRange::from_lsp_range(
+                            path.clone(),
+                            link.target_selection_range,
+                        ),
+                        full_range: FileRange::from_lsp_range(path, link.target_range),
+                    }
+                })
+                .collect())
+        }
+    };
+
+    let files = manager.list_files().await?;
+    let mut entries = Vec::new();
+    for location in locations {
+        let path = uri_to_relative_path_string(&location.uri);
+        let identifier_range = FileRange::from_lsp_range(path.clone(), location.range);
+        let symbol = if files.contains(&path) {
+            manager
+                .definitions_in_file_ast_grep_cached(&path)
+                .await?
+                .into_iter()
+                .find(|symbol| {
+                    let start = symbol.get_identifier_range().start;
+                    start.line == location.range.start.line
+                        && start.column == location.range.start.character
+                })
+        } else {
+            None
+        };
+        let full_range = match symbol {
+            Some(symbol) => FileRange {
+                path,
+                range: Range::from(symbol.get_context_range()),
+            },
+            None => identifier_range.clone(),
+        };
+        entries.push(DefinitionEntry {
+            identifier_range,
+            full_range,
+        });
+    }
+    Ok(entries)
+}
+
+/// Whether any of the definitions is a location inside the workspace.
+fn has_workspace_definition(definitions: &GotoDefinitionResponse) -> bool {
+    let mount_dir = get_mount_dir();
+    let in_workspace = |uri: &Url| {
+        uri.to_file_path()
+            .is_ok_and(|path| path.starts_with(&mount_dir))
+    };
+    match definitions {
+        GotoDefinitionResponse::Scalar(location) => in_workspace(&location.uri),
+        GotoDefinitionResponse::Array(locations) => {
+            locations.iter().any(|location| in_workspace(&location.uri))
+        }
+        GotoDefinitionResponse::Link(links) => {
+            links.iter().any(|link| in_workspace(&link.target_uri))
+        }
+    }
+}
+
+/// Finds the ast-grep definition in the workspace with the same name as the identifier, in a file
+/// of the same language, preferring one in the identifier's own file. Local variables are skipped,
+/// since one inside an unrelated function isn't the identifier's definition.
+///
+/// Returns the location of the definition's identifier.
+async fn find_definition_by_name(
+    manager: &Manager,
+    identifier: &Identifier,
+) -> Result<Option<Location>, LspManagerError> {
+    let identifier_path = &identifier.file_range.path;
+    let language = detect_language(identifier_path)?;
+    let mut file_paths: Vec<String> = manager
+        .list_files()
+        .await?
+        .into_iter()
+        .filter(|path| detect_language(path).is_ok_and(|lang| lang == language))
+        .collect();
+    // Search the identifier's own file first
+    file_paths.sort_by_key(|path| path != identifier_path);
+
+    for file_path in file_paths {
+        let definition = manager
+            .definitions_in_file_ast_grep_cached(&file_path)
+            .await?
+            .into_iter()
+            .find(|symbol| {
+                symbol.rule_id != "local-variable"
+                    && symbol.meta_variables.single.name.text == identifier.name
+            });
+        if let Some(definition) = definition {
+            let uri = Url::from_file_path(get_mount_dir().join(&file_path))
+                .map_err(|_| LspManagerError::InvalidPath(file_path.clone()))?;
+            let range = Range::from(definition.get_identifier_range());
+            return Ok(Some(Location::new(uri, range.into())));
+        }
+    }
+    Ok(None)
+}
+
 async fn fetch_definition_source_code(
     manager: &Manager,
     definitions_response: &GotoDefinitionResponse,
@@ -126,11 +284,19 @@ async fn fetch_definition_source_code(
         GotoDefinitionResponse::Array(definitions) => definitions,
         GotoDefinitionResponse::Link(links) => &links
             .iter()
-            .map(|link| Location::new(link.target_uri.clone(), link.target_range))
+            .map(|link| Location::new(link.target_uri.clone(), link.target_selection_range))
             .collect::<Vec<Location>>(),
     };
 
     for definition in definitions {
+        // Files whose language can't be detected, e.g. C++ standard headers, can't be read
+        if manager
+            .detect_language_for_location(&definition.uri)
+            .is_none()
+        {
+            warn!("Skipping source code of definition: {:?}", definition);
+            continue;
+        }
         let relative_path = uri_to_relative_path_string(&definition.uri);
         let file_symbols = manager.definitions_in_file_ast_grep(&relative_path).await?;
         let symbol = file_symbols.iter().find(|s| {
@@ -142,16 +308,7 @@ async fn fetch_definition_source_code(
             Some(ast_grep_match) => CodeContext {
                 range: FileRange {
                     path: relative_path,
-                    range: Range {
-                        start: Position {
-                            line: ast_grep_match.get_context_range().start.line,
-                            character: ast_grep_match.get_context_range().start.column,
-                        },
-                        end: Position {
-                            line: ast_grep_match.get_context_range().end.line,
-                            character: ast_grep_match.get_context_range().end.column,
-                        },
-                    },
+                    range: Range::from(ast_grep_match.get_context_range()),
                 },
                 source_code: ast_grep_match.get_source_code(),
             },
@@ -169,22 +326,10 @@ async fn fetch_definition_source_code(
                     },
                 };
                 let source_code = manager
-                    .read_source_code(&relative_path, Some(range))
+                    .read_source_code(&relative_path, Some(range), None)
                     .await?;
                 CodeContext {
-                    range: FileRange {
-                        path: relative_path,
-                        range: Range {
-                            start: Position {
-                                line: definition.range.start.line.saturating_sub(3),
-                                character: 0,
-                            },
-                            end: Position {
-                                line: definition.range.end.line + 3,
-                                character: 0,
-                            },
-                        },
-                    },
+                    range: FileRange::from_lsp_range(relative_path, range),
                     source_code,
                 }
             }
@@ -201,9 +346,9 @@ mod test {
 
     use actix_web::http::StatusCode;
 
-    use crate::api_types::{FilePosition, Identifier, Position};
+    use crate::api_types::{FilePosition, Identifier, Position, SupportedLanguages};
     use crate::initialize_app_state;
-    use crate::test_utils::{python_sample_path, TestContext};
+    use crate::test_utils::{mocks::MockFile, python_sample_path, TestContext};
 
     #[tokio::test]
     async fn test_python_definition() -> Result<(), Box<dyn std::error::Error>> {
@@ -220,16 +365,12 @@ mod test {
             },
             include_source_code: true,
             include_raw_response: false,
+            fallback_to_ast_grep: false,
         });
 
         let response = find_definition(state, mock_request).await;
 
-        assert_eq!(
-            response.status(),
-            StatusCode::OK,
-            "{}",
-            format!("{:?}", response.body())
-        );
+        assert_eq!(response.status(), StatusCode::OK, "{:?}", response.body());
         assert_eq!(
             response.headers().get("content-type").unwrap(),
             "application/json"
@@ -241,11 +382,32 @@ mod test {
 
         let expected_response = DefinitionResponse {
             raw_response: None,
-            definitions: vec![FilePosition {
-                path: String::from("graph.py"),
-                position: Position {
-                    line: 12,
-                    character: 6,
+            definitions: vec![DefinitionEntry {
+                identifier_range: FileRange {
+                    path: String::from("graph.py"),
+                    range: Range {
+                        start: Position {
+                            line: 12,
+                            character: 6,
+                        },
+                        end: Position {
+                            line: 12,
+                            character: 16,
+                        },
+                    },
+                },
+                full_range: FileRange {
+                    path: String::from("graph.py"),
+                    range: Range {
+                        start: Position {
+                            line: 12,
+                            character: 0,
+                        },
+                        end: Position {
+                            line: 88,
+                            character: 16,
+                        },
+                    },
                 },
             }],
             source_code_context: Some(vec![CodeContext {
@@ -280,7 +442,10 @@ mod test {
                         },
                     },
                 },
+                source_text: Some(String::from("AStarGraph")),
+                context_source: None,
             },
+            source: ResultSource::Lsp,
         };
 
         assert_eq!(definition_response, expected_response);
@@ -302,6 +467,7 @@ mod test {
             },
             include_source_code: false,
             include_raw_response: false,
+            fallback_to_ast_grep: false,
         });
 
         let response = find_definition(state, mock_request).await;
@@ -318,8 +484,191 @@ mod test {
 
         assert_eq!(
             error_response.error,
-            "Failed to find definition from position: No identifier found at position. Closest matches: [Identifier { name: \"plt\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 28 }, end: Position { line: 0, character: 31 } } }, kind: None }, Identifier { name: \"pyplot\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 18 }, end: Position { line: 0, character: 24 } } }, kind: None }, Identifier { name: \"matplotlib\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 7 }, end: Position { line: 0, character: 17 } } }, kind: None }]"
+            "Failed to find definition from position: No identifier found at position. Closest matches: [Identifier { name: \"plt\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 28 }, end: Position { line: 0, character: 31 } } }, kind: None, source_text: Some(\"plt\"), context_source: None }, Identifier { name: \"pyplot\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 18 }, end: Position { line: 0, character: 24 } } }, kind: None, source_text: Some(\"pyplot\"), context_source: None }, Identifier { name: \"matplotlib\", file_range: FileRange { path: \"main.py\", range: Range { start: Position { line: 0, character: 7 }, end: Position { line: 0, character: 17 } } }, kind: None, source_text: Some(\"matplotlib\"), context_source: None }]"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_has_workspace_definition() {
+        crate::api_types::set_thread_local_mount_dir("/mnt/workspace");
+        let location = |path: &str| {
+            Location::new(
+                Url::from_file_path(path).unwrap(),
+                LspRange::new(LspPosition::new(0, 0), LspPosition::new(0, 4)),
+            )
+        };
+        let empty = GotoDefinitionResponse::Array(vec![]);
+        let external = GotoDefinitionResponse::Scalar(location("/usr/lib/python3/typing.py"));
+        let mixed = GotoDefinitionResponse::Array(vec![
+            location("/usr/lib/python3/typing.py"),
+            location("/mnt/workspace/graph.py"),
+        ]);
+        let results = [
+            has_workspace_definition(&empty),
+            has_workspace_definition(&external),
+            has_workspace_definition(&mixed),
+        ];
+        crate::api_types::unset_thread_local_mount_dir();
+
+        assert_eq!(results, [false, false, true]);
+    }
+
+    #[tokio::test]
+    async fn test_python_find_definition_by_name() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `heuristic` in `start: graph.heuristic(start, end)`
+        let identifier = Identifier {
+            name: String::from("heuristic"),
+            kind: None,
+            file_range: FileRange {
+                path: String::from("search.py"),
+                range: Range {
+                    start: Position {
+                        line: 8,
+                        character: 21,
+                    },
+                    end: Position {
+                        line: 8,
+                        character: 30,
+                    },
+                },
+            },
+            source_text: None,
+            context_source: None,
+        };
+        let location = find_definition_by_name(&state.manager, &identifier)
+            .await?
+            .ok_or("No definition found")?;
+
+        assert_eq!(uri_to_relative_path_string(&location.uri), "graph.py");
+        assert_eq!(
+            location.range,
+            LspRange::new(LspPosition::new(68, 8), LspPosition::new(68, 17))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_definition_with_fallback() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The langserver resolves `AStarGraph`, so the fallback isn't used
+        let mock_request = Json(GetDefinitionRequest {
+            position: FilePosition {
+                path: String::from("main.py"),
+                position: Position {
+                    line: 1,
+                    character: 18,
+                },
+            },
+            include_source_code: false,
+            include_raw_response: false,
+            fallback_to_ast_grep: true,
+        });
+        let response = find_definition(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let definition_response: DefinitionResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(definition_response.source, ResultSource::Lsp);
+        assert_eq!(definition_response.definitions.len(), 1);
+        assert_eq!(
+            definition_response.definitions[0].identifier_range.path,
+            "graph.py"
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_definition_entries_from_links() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new(
+            "shapes.py",
+            "def area(square):\n    pass\n\ndef area(width, height):\n    pass\n",
+        )];
+        let context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let manager = context.manager.as_ref().unwrap();
+        let uri = Url::from_file_path(context.workspace_path().join("shapes.py")).unwrap();
+        let link = |line: u32| lsp_types::LocationLink {
+            origin_selection_range: None,
+            target_uri: uri.clone(),
+            target_range: LspRange::new(LspPosition::new(line, 0), LspPosition::new(line + 1, 8)),
+            target_selection_range: LspRange::new(
+                LspPosition::new(line, 4),
+                LspPosition::new(line, 8),
+            ),
+        };
+
+        let entries = definition_entries(
+            manager,
+            &GotoDefinitionResponse::Link(vec![link(0), link(3)]),
+        )
+        .await?;
+        assert_eq!(
+            entries,
+            vec![
+                DefinitionEntry {
+                    identifier_range: FileRange::from_lsp_range(
+                        String::from("shapes.py"),
+                        LspRange::new(LspPosition::new(0, 4), LspPosition::new(0, 8)),
+                    ),
+                    full_range: FileRange::from_lsp_range(
+                        String::from("shapes.py"),
+                        LspRange::new(LspPosition::new(0, 0), LspPosition::new(1, 8)),
+                    ),
+                },
+                DefinitionEntry {
+                    identifier_range: FileRange::from_lsp_range(
+                        String::from("shapes.py"),
+                        LspRange::new(LspPosition::new(3, 4), LspPosition::new(3, 8)),
+                    ),
+                    full_range: FileRange::from_lsp_range(
+                        String::from("shapes.py"),
+                        LspRange::new(LspPosition::new(3, 0), LspPosition::new(4, 8)),
+                    ),
+                },
+            ]
+        );
+
+        // Locations outside the workspace keep the identifier range as the full range
+        let outside = Location::new(
+            Url::from_file_path("/usr/lib/python3/typing.py").unwrap(),
+            LspRange::new(LspPosition::new(5, 4), LspPosition::new(5, 8)),
+        );
+        let entries = definition_entries(manager, &GotoDefinitionResponse::Scalar(outside)).await?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].full_range, entries[0].identifier_range);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_definition_source_code_without_extension(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.cpp", "#include <vector>\n")];
+        let context = TestContext::setup_mock(SupportedLanguages::CPP, files).await?;
+        let manager = context.manager.as_ref().unwrap();
+
+        // A standard header outside the workspace, skipped rather than failing the request
+        let include_dir = tempfile::tempdir()?;
+        let header = include_dir.path().join("vector");
+        std::fs::write(
+            &header,
+            "namespace std {\n\ntemplate <class T>\nclass vector {};\n}\n",
+        )?;
+        let definition = Location::new(
+            Url::from_file_path(&header).unwrap(),
+            LspRange::new(LspPosition::new(3, 6), LspPosition::new(3, 12)),
+        );
+
+        let contexts =
+            fetch_definition_source_code(manager, &GotoDefinitionResponse::Scalar(definition))
+                .await?;
+        assert!(contexts.is_empty());
+        Ok(())
+    }
 }
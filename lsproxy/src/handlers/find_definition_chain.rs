@@ -0,0 +1,199 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{DefinitionChainRequest, DefinitionChainResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Default maximum number of definitions followed for a chain.
+const DEFAULT_MAX_HOPS: u32 = 10;
+
+/// Follow the definition of a symbol hop by hop to its original definition
+///
+/// Each position of the chain is the definition of the previous one, starting at the requested
+/// position. This follows re-exports and aliases that a single `find-definition` stops at, e.g.
+/// from a type imported through an `index.ts` barrel file to the module declaring it.
+///
+/// The chain ends when a definition resolves to itself or to a location already in the chain,
+/// when it is outside the workspace, or after `max_hops` definitions.
+#[utoipa::path(
+    post,
+    path = "/symbol/find-definition-chain",
+    tag = "symbol",
+    request_body = DefinitionChainRequest,
+    responses(
+        (status = 200, description = "Definition chain retrieved successfully", body = DefinitionChainResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn find_definition_chain(
+    data: Data<AppState>,
+    info: Json<DefinitionChainRequest>,
+) -> HttpResponse {
+    info!(
+        "Received definition chain request for file: {}, line: {}, character: {}",
+        info.position.path, info.position.position.line, info.position.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.position.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .find_definition_chain(
+            &info.position.path,
+            info.position.position.clone().into(),
+            info.max_hops.unwrap_or(DEFAULT_MAX_HOPS),
+        )
+        .await
+    {
+        Ok(chain) => HttpResponse::Ok().json(DefinitionChainResponse { chain }),
+        Err(e) => {
+            error!("Failed to find definition chain: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::Position;
+
+    use crate::api_types::{FilePosition, SupportedLanguages};
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    fn position(path: &str, line: u32, character: u32) -> FilePosition {
+        FilePosition {
+            path: String::from(path),
+            position: crate::api_types::Position { line, character },
+        }
+    }
+
+    /// `Shape` is imported from a barrel file re-exporting it from `aliases.ts`, where it is an
+    /// alias of `Polygon`, itself an alias of the `Outline` interface.
+    async fn setup() -> Result<TestContext, Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new(
+                "src/main.ts",
+                "import { Shape } from './index';\n\nconst square: Shape = { points: [] };\n",
+            ),
+            MockFile::new("src/index.ts", "export { Shape } from './aliases';\n"),
+            MockFile::new(
+                "src/aliases.ts",
+                "import { Outline } from './outline';\n\n\
+                 export type Polygon = Outline;\nexport type Shape = Polygon;\n",
+            ),
+            MockFile::new(
+                "src/outline.ts",
+                "export interface Outline {\n  points: number[];\n}\n",
+            ),
+        ];
+        let context = TestContext::setup_mock_with(
+            SupportedLanguages::TypeScriptJavaScript,
+            files,
+            |client| {
+                let hop = |file_path: &str, line: u32, character: u32| {
+                    vec![client.location(
+                        file_path,
+                        Position::new(line, character),
+                        Position::new(line, character + 5),
+                    )]
+                };
+                let (index, shape, polygon, outline) = (
+                    hop("src/index.ts", 0, 9),
+                    hop("src/aliases.ts", 3, 12),
+                    hop("src/aliases.ts", 2, 12),
+                    hop("src/outline.ts", 0, 17),
+                );
+                client
+                    .with_definition("src/main.ts", Position::new(2, 14), index)
+                    .with_definition("src/index.ts", Position::new(0, 9), shape)
+                    .with_definition("src/aliases.ts", Position::new(3, 12), polygon)
+                    .with_definition("src/aliases.ts", Position::new(2, 12), outline.clone())
+                    .with_definition("src/outline.ts", Position::new(0, 17), outline)
+            },
+        )
+        .await?;
+        Ok(context)
+    }
+
+    async fn chain(
+        state: &Data<AppState>,
+        start: FilePosition,
+        max_hops: Option<u32>,
+    ) -> Result<Vec<FilePosition>, Box<dyn std::error::Error>> {
+        let response = find_definition_chain(
+            state.clone(),
+            Json(DefinitionChainRequest {
+                position: start,
+                max_hops,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: DefinitionChainResponse = serde_json::from_slice(&bytes)?;
+        Ok(response.chain)
+    }
+
+    #[tokio::test]
+    async fn test_typescript_definition_chain() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = setup().await?;
+        let state = context.app_state();
+
+        // Through the re-export and both aliases, ending at the interface defining itself
+        assert_eq!(
+            chain(&state, position("src/main.ts", 2, 14), None).await?,
+            vec![
+                position("src/main.ts", 2, 14),
+                position("src/index.ts", 0, 9),
+                position("src/aliases.ts", 3, 12),
+                position("src/aliases.ts", 2, 12),
+                position("src/outline.ts", 0, 17),
+            ]
+        );
+
+        // Stops early
+        assert_eq!(
+            chain(&state, position("src/main.ts", 2, 14), Some(2)).await?,
+            vec![
+                position("src/main.ts", 2, 14),
+                position("src/index.ts", 0, 9),
+                position("src/aliases.ts", 3, 12),
+            ]
+        );
+
+        // Nothing to follow
+        assert_eq!(
+            chain(&state, position("src/main.ts", 0, 0), None).await?,
+            vec![position("src/main.ts", 0, 0)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_definition_chain_outside_workspace() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = setup().await?;
+
+        let response = find_definition_chain(
+            context.app_state(),
+            Json(DefinitionChainRequest {
+                position: position("src/missing.ts", 0, 0),
+                max_hops: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
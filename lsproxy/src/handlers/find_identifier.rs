@@ -5,7 +5,7 @@ use actix_web::{
 
 use crate::{
     api_types::{
-        ErrorResponse, FilePosition, FindIdentifierRequest, Identifier, IdentifierResponse,
+        ErrorResponse, FilePosition, FindIdentifierRequest, FindIdentifierResponse, Identifier,
     },
     handlers::utils::{self, PositionError},
     AppState,
@@ -27,13 +27,16 @@ use log::{error, info};
 /// When a position is provided, it searches for an exact match at that location.
 /// If no exact match exists, returns the 3 identifiers closest to the position
 /// based on line and character distance, prioritizing lines.
+///
+/// Matches can be narrowed to a `kind` and the name can be matched case-insensitively.
+/// Results are sorted by position and each range is only returned once.
 #[utoipa::path(
     post,
     path = "/symbol/find-identifier",
     tag = "symbol",
     request_body = FindIdentifierRequest,
     responses(
-        (status = 200, description = "Identifier retrieved successfully", body = IdentifierResponse),
+        (status = 200, description = "Identifier retrieved successfully", body = FindIdentifierResponse),
         (status = 400, description = "Bad request"),
         (status = 500, description = "Internal server error")
     )
@@ -44,26 +47,49 @@ pub async fn find_identifier(
 ) -> HttpResponse {
     info!(
         "Received identifier request for file: {}, name: {}, position: {:?}",
-        info.path, info.name, info.position
+        info.file_path, info.name, info.position
     );
-    let file_identifiers = match data.manager.get_file_identifiers(&info.path).await {
+
+    if let Err(response) = utils::validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    let file_identifiers = match data.manager.get_file_identifiers(&info.file_path).await {
         Ok(identifiers) => identifiers,
         Err(e) => {
             error!("Failed to get file identifiers: {:?}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to get file identifiers: {}", e),
-            });
+            return HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get file identifiers: {}", e),
+            ));
         }
     };
 
-    // filter identifiers by name
-    let name_matched_identifiers: Vec<Identifier> = file_identifiers
+    // filter identifiers by name and kind
+    let mut name_matched_identifiers: Vec<Identifier> = file_identifiers
         .into_iter()
-        .filter(|id| id.name == info.name)
+        .filter(|id| {
+            if info.case_sensitive {
+                id.name == info.name
+            } else {
+                id.name.to_lowercase() == info.name.to_lowercase()
+            }
+        })
+        .filter(|id| info.kind.is_none() || id.kind == info.kind)
         .collect();
+    name_matched_identifiers.sort_by_key(|id| {
+        let range = &id.file_range.range;
+        (
+            range.start.line,
+            range.start.character,
+            range.end.line,
+            range.end.character,
+        )
+    });
+    name_matched_identifiers.dedup_by(|a, b| a.file_range == b.file_range);
 
     if name_matched_identifiers.is_empty() {
-        return HttpResponse::Ok().json(IdentifierResponse {
+        return HttpResponse::Ok().json(FindIdentifierResponse {
             identifiers: vec![],
         });
     }
@@ -72,24 +98,24 @@ pub async fn find_identifier(
         match utils::find_identifier_at_position(
             name_matched_identifiers.clone(),
             &FilePosition {
-                path: info.path.clone(),
+                path: info.file_path.clone(),
                 position: position.clone(),
             },
         )
         .await
         {
-            Ok(identifier) => HttpResponse::Ok().json(IdentifierResponse {
+            Ok(identifier) => HttpResponse::Ok().json(FindIdentifierResponse {
                 identifiers: vec![identifier],
             }),
             Err(PositionError::IdentifierNotFound { closest }) => {
                 // Not an error case, just closest matches
-                HttpResponse::Ok().json(IdentifierResponse {
+                HttpResponse::Ok().json(FindIdentifierResponse {
                     identifiers: closest,
                 })
             }
         }
     } else {
-        HttpResponse::Ok().json(IdentifierResponse {
+        HttpResponse::Ok().json(FindIdentifierResponse {
             identifiers: name_matched_identifiers,
         })
     }
@@ -110,9 +136,11 @@ mod test {
 
         // Test finding all occurrences of 'graph' without position
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("main.py"),
+            file_path: String::from("main.py"),
             name: String::from("graph"),
             position: None,
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
@@ -120,7 +148,7 @@ mod test {
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let identifier_response: IdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
 
         // Should find at least two occurrences: declaration and usage
         assert!(identifier_response.identifiers.len() >= 2);
@@ -131,6 +159,72 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_python_find_all_call_sites() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(FindIdentifierRequest {
+            file_path: String::from("graph.py"),
+            name: String::from("APPEND"),
+            position: None,
+            kind: None,
+            case_sensitive: false,
+        });
+
+        let response = find_identifier(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+
+        let positions: Vec<(String, u32, u32)> = identifier_response
+            .identifiers
+            .into_iter()
+            .map(|id| {
+                (
+                    id.name,
+                    id.file_range.range.start.line,
+                    id.file_range.range.start.character,
+                )
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (String::from("append"), 15, 23),
+                (String::from("append"), 87, 18),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_find_identifier_case_sensitive() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(FindIdentifierRequest {
+            file_path: String::from("graph.py"),
+            name: String::from("APPEND"),
+            position: None,
+            kind: None,
+            case_sensitive: true,
+        });
+
+        let response = find_identifier(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+
+        assert!(identifier_response.identifiers.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_python_find_exact_identifier() -> Result<(), Box<dyn std::error::Error>> {
         let _context = TestContext::setup(&python_sample_path(), false).await?;
@@ -138,12 +232,14 @@ mod test {
 
         // Test finding exact occurrence of 'AStarGraph' at its definition
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("graph.py"),
+            file_path: String::from("graph.py"),
             name: String::from("AStarGraph"),
             position: Some(Position {
                 line: 12,
                 character: 6,
             }),
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
@@ -151,7 +247,7 @@ mod test {
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let identifier_response: IdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
 
         assert_eq!(identifier_response.identifiers.len(), 1);
         assert_eq!(identifier_response.identifiers[0].name, "AStarGraph");
@@ -173,9 +269,11 @@ mod test {
 
         // Test finding all occurrences of 'path' in PathfinderDisplay.tsx
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("src/PathfinderDisplay.tsx"),
+            file_path: String::from("src/PathfinderDisplay.tsx"),
             name: String::from("path"),
             position: None,
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
@@ -183,7 +281,7 @@ mod test {
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let identifier_response: IdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
 
         // Should find multiple occurrences in state and JSX
         assert!(identifier_response.identifiers.len() >= 2);
@@ -201,12 +299,14 @@ mod test {
 
         // Test finding closest matches for 'maze' near but not exactly at a position
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("src/PathfinderDisplay.tsx"),
+            file_path: String::from("src/PathfinderDisplay.tsx"),
             name: String::from("maze"),
             position: Some(Position {
                 line: 25, // Near maze usage but not exact
                 character: 10,
             }),
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
@@ -214,7 +314,7 @@ mod test {
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let identifier_response: IdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
 
         // Should return up to 3 closest matches
         assert!(identifier_response.identifiers.len() <= 3);
@@ -231,21 +331,24 @@ mod test {
         let state = initialize_app_state().await?;
 
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("nonexistent.py"),
+            file_path: String::from("nonexistent.py"),
             name: String::from("identifier"),
             position: None,
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
-        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
         let error_response: ErrorResponse = serde_json::from_slice(&bytes).unwrap();
 
-        assert!(error_response
-            .error
-            .contains("Failed to get file identifiers"));
+        assert_eq!(
+            error_response.error,
+            "File not found in workspace: nonexistent.py"
+        );
         Ok(())
     }
 
@@ -255,9 +358,11 @@ mod test {
         let state = initialize_app_state().await?;
 
         let mock_request = Json(FindIdentifierRequest {
-            path: String::from("main.py"),
+            file_path: String::from("main.py"),
             name: String::from("nonexistent_identifier"),
             position: None,
+            kind: None,
+            case_sensitive: true,
         });
 
         let response = find_identifier(state, mock_request).await;
@@ -265,7 +370,7 @@ mod test {
 
         let body = response.into_body();
         let bytes = actix_web::body::to_bytes(body).await.unwrap();
-        let identifier_response: IdentifierResponse = serde_json::from_slice(&bytes).unwrap();
+        let identifier_response: FindIdentifierResponse = serde_json::from_slice(&bytes).unwrap();
 
         assert_eq!(identifier_response.identifiers.len(), 0);
         Ok(())
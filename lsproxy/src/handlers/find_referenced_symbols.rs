@@ -1,13 +1,31 @@
 use crate::api_types::{
-    ErrorResponse, FilePosition, GetReferencedSymbolsRequest, Identifier, Position,
+    get_mount_dir, ErrorResponse, FileGroup, FilePosition, GetReferencedSymbolsRequest, Identifier,
     ReferenceWithSymbolDefinitions, ReferencedSymbolsResponse,
 };
-use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::ast_grep::types::AstGrepMatch;
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::lsp::manager::{LspManagerError, Manager};
 use crate::AppState;
 use actix_web::web::{Data, Json};
 use actix_web::HttpResponse;
 use log::{error, info};
-use lsp_types::{GotoDefinitionResponse, Position as LspPosition};
+use lsp_types::{GotoDefinitionResponse, Location, Position as LspPosition};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+
+/// Lines of source context around each reference when none are requested.
+const DEFAULT_CONTEXT_LINES: u32 = 2;
+
+/// Total source context attached to the references of a response, in bytes.
+const MAX_SOURCE_CONTEXT_BYTES: usize = 256 * 1024;
+
+/// Workspace symbols, external symbols and symbols that couldn't be found.
+pub(crate) type CategorizedReferencedSymbols = (
+    Vec<ReferenceWithSymbolDefinitions>,
+    Vec<Identifier>,
+    Vec<Identifier>,
+);
 
 /// Find all symbols that are referenced from a given symbol's definition
 ///
@@ -30,6 +48,15 @@ use lsp_types::{GotoDefinitionResponse, Position as LspPosition};
 ///     User (with definition from models.py)
 ///   ]
 /// - External symbols: print (Python built-in)
+///
+/// Set `group_by_file` to group the workspace symbols by the file they are defined in, to see
+/// which files the symbol depends on most.
+///
+/// Set `include_source_context` to attach the lines around each reference, e.g. to see how a
+/// function is called without reading the whole file.
+///
+/// Set `exclude_test_files` to leave out the workspace symbols defined in test files, e.g. test
+/// helpers and fixtures.
 #[utoipa::path(
     post,
     path = "/symbol/find-referenced-symbols",
@@ -52,6 +79,12 @@ pub async fn find_referenced_symbols(
         info.identifier_position.position.character
     );
 
+    if let Err(response) =
+        validate_file_in_workspace(&info.identifier_position.path, &data.manager).await
+    {
+        return response;
+    }
+
     let referenecd_ast_symbols = match data
         .manager
         .find_referenced_symbols(
@@ -67,102 +100,233 @@ pub async fn find_referenced_symbols(
         Ok(ast_symbols) => ast_symbols,
         Err(e) => {
             error!("Failed to get referenced symbols: {:?}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to get referenced symbols: {}", e),
-            });
+            return HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get referenced symbols: {}", e),
+            ));
         }
     };
 
-    let unwrapped_definition_responses: Vec<(Identifier, Vec<FilePosition>)> =
-        referenecd_ast_symbols
-            .into_iter()
-            .map(|(ast_grep_result, definition_response)| {
-                let definitions = match definition_response {
-                    GotoDefinitionResponse::Scalar(location) => vec![FilePosition {
-                        path: uri_to_relative_path_string(&location.uri),
-                        position: Position {
-                            line: location.range.start.line,
-                            character: location.range.start.character,
-                        },
-                    }],
-                    GotoDefinitionResponse::Array(locations) => locations
-                        .into_iter()
-                        .map(|location| FilePosition {
-                            path: uri_to_relative_path_string(&location.uri),
-                            position: Position {
-                                line: location.range.start.line,
-                                character: location.range.start.character,
-                            },
-                        })
-                        .collect(),
-                    GotoDefinitionResponse::Link(links) => links
-                        .into_iter()
-                        .map(|link| FilePosition {
-                            path: uri_to_relative_path_string(&link.target_uri),
-                            position: Position {
-                                line: link.target_range.start.line,
-                                character: link.target_range.start.character,
-                            },
-                        })
-                        .collect(),
-                };
-                (Identifier::from(ast_grep_result), definitions)
-            })
-            .collect();
+    let (mut workspace_symbols, mut external_symbols, not_found) =
+        match categorize_referenced_symbols(&data.manager, referenecd_ast_symbols).await {
+            Ok(categorized) => categorized,
+            Err(e) => {
+                error!("Failed to categorize referenced symbols: {}", e);
+                return e.into_http_response();
+            }
+        };
 
-    // First get the workspace files
-    let files = match data.manager.list_files().await {
-        Ok(files) => files,
-        Err(e) => {
-            error!("Failed to list workspace files: {:?}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to list workspace files: {}", e),
-            });
+    if info.exclude_test_files {
+        workspace_symbols.retain_mut(|symbol| {
+            symbol
+                .definitions
+                .retain(|definition| !data.manager.is_test_file(&definition.file_range.path));
+            !symbol.definitions.is_empty()
+        });
+    }
+
+    if info.unique_symbols_only {
+        let mut seen_workspace_symbols = Vec::new();
+        workspace_symbols.retain(|symbol| {
+            let key = (
+                symbol.reference.name.clone(),
+                symbol
+                    .definitions
+                    .iter()
+                    .map(|definition| definition.file_range.clone())
+                    .collect::<Vec<_>>(),
+            );
+            if seen_workspace_symbols.contains(&key) {
+                false
+            } else {
+                seen_workspace_symbols.push(key);
+                true
+            }
+        });
+        let mut seen_external_symbols = HashSet::new();
+        external_symbols.retain(|symbol| seen_external_symbols.insert(symbol.name.clone()));
+    }
+
+    let total_workspace_symbols = workspace_symbols.len();
+    let total_external_symbols = external_symbols.len();
+    let total_not_found = not_found.len();
+    let offset = info.offset.unwrap_or(0);
+    let mut workspace_symbols = paginate(workspace_symbols, offset, info.limit);
+    let mut external_symbols = paginate(external_symbols, offset, info.limit);
+    let mut not_found = paginate(not_found, offset, info.limit);
+
+    if info.include_source_context {
+        let context_lines = info.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES);
+        let references = workspace_symbols
+            .iter_mut()
+            .map(|symbol| &mut symbol.reference)
+            .chain(external_symbols.iter_mut())
+            .chain(not_found.iter_mut());
+        if let Err(e) = attach_source_context(&data.manager, references, context_lines).await {
+            error!("Failed to read source context: {}", e);
+            return e.into_http_response();
         }
+    }
+
+    let workspace_symbols_by_file = if info.group_by_file {
+        Some(group_by_definition_file(std::mem::take(
+            &mut workspace_symbols,
+        )))
+    } else {
+        None
     };
 
+    // Return the sorted response
+    HttpResponse::Ok().json(ReferencedSymbolsResponse {
+        workspace_symbols,
+        external_symbols,
+        not_found,
+        workspace_symbols_by_file,
+        total_workspace_symbols,
+        total_external_symbols,
+        total_not_found,
+    })
+}
+
+/// Attaches the lines around each reference to it, `context_lines` before and after its line,
+/// until [`MAX_SOURCE_CONTEXT_BYTES`] of context have been attached.
+async fn attach_source_context(
+    manager: &Manager,
+    references: impl Iterator<Item = &mut Identifier>,
+    context_lines: u32,
+) -> Result<(), LspManagerError> {
+    let mut total_bytes = 0;
+    for reference in references {
+        if total_bytes >= MAX_SOURCE_CONTEXT_BYTES {
+            break;
+        }
+        let line = reference.file_range.range.start.line;
+        let range = lsp_types::Range::new(
+            LspPosition::new(line.saturating_sub(context_lines), 0),
+            LspPosition::new(line.saturating_add(context_lines), u32::MAX),
+        );
+        let source = manager
+            .read_source_code(&reference.file_range.path, Some(range), None)
+            .await?;
+        total_bytes += source.len();
+        reference.context_source = Some(source);
+    }
+    Ok(())
+}
+
+/// Skips the first `offset` items and keeps at most `limit` of the rest.
+fn paginate<T>(items: Vec<T>, offset: usize, limit: Option<usize>) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Groups workspace symbols by the files their definitions are in, sorted by file path.
+fn group_by_definition_file(
+    workspace_symbols: Vec<ReferenceWithSymbolDefinitions>,
+) -> Vec<FileGroup> {
+    let mut groups: BTreeMap<String, Vec<ReferenceWithSymbolDefinitions>> = BTreeMap::new();
+    for symbol in workspace_symbols {
+        let definition_files: BTreeSet<&String> = symbol
+            .definitions
+            .iter()
+            .map(|definition| &definition.file_range.path)
+            .collect();
+        for file_path in definition_files {
+            groups
+                .entry(file_path.clone())
+                .or_default()
+                .push(symbol.clone());
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(file_path, symbols)| FileGroup { file_path, symbols })
+        .collect()
+}
+
+/// The locations of the definitions in a go to definition response.
+fn definition_locations(definition_response: GotoDefinitionResponse) -> Vec<Location> {
+    match definition_response {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| Location::new(link.target_uri, link.target_range))
+            .collect(),
+    }
+}
+
+/// The positions of the definitions in a go to definition response.
+pub(crate) fn definition_positions(
+    definition_response: GotoDefinitionResponse,
+    mount_dir: &Path,
+) -> Vec<FilePosition> {
+    definition_locations(definition_response)
+        .into_iter()
+        .map(|location| FilePosition::from_lsp_location(location, mount_dir))
+        .collect()
+}
+
+/// Splits referenced symbols into workspace symbols with their definitions, external symbols and
+/// symbols whose definition couldn't be found, each sorted by location.
+pub(crate) async fn categorize_referenced_symbols(
+    manager: &Manager,
+    referenced_symbols: Vec<(AstGrepMatch, GotoDefinitionResponse)>,
+) -> Result<CategorizedReferencedSymbols, LspManagerError> {
+    // First get the workspace files
+    let files = manager.list_files().await?;
+
     // Then categorize the definitions
-    let mut workspace_symbols = Vec::new();
+    let mut workspace_symbols: Vec<ReferenceWithSymbolDefinitions> = Vec::new();
     let mut external_symbols = Vec::new();
     let mut not_found = Vec::new();
 
-    for (identifier, definitions) in unwrapped_definition_responses {
+    let mount_dir = get_mount_dir();
+    for (ast_grep_result, definition_response) in referenced_symbols {
+        let identifier = Identifier::from(ast_grep_result);
+        let definitions = definition_locations(definition_response);
         if definitions.is_empty() {
             not_found.push(identifier);
-        } else {
-            // Check if any definition is in workspace files
-            let has_internal_definition = definitions.iter().any(|def| files.contains(&def.path));
-            if has_internal_definition {
-                let mut symbols_with_definitions = Vec::new();
-                for def in definitions.iter().filter(|def| files.contains(&def.path)) {
-                    if let Ok(symbol) = data
-                        .manager
-                        .get_symbol_from_position(
-                            &def.path,
-                            &lsp_types::Position {
-                                line: def.position.line,
-                                character: def.position.character,
-                            },
-                        )
-                        .await
-                    {
-                        symbols_with_definitions.push(symbol);
-                    }
-                }
-                // Only add to workspace_symbols if we found at least one symbol
-                if !symbols_with_definitions.is_empty() {
-                    workspace_symbols.push(ReferenceWithSymbolDefinitions {
-                        reference: identifier.clone(),
-                        definitions: symbols_with_definitions,
-                    });
-                } else {
-                    // If no symbols were found, add to not_found
-                    not_found.push(identifier.clone());
-                }
-            } else {
-                external_symbols.push(identifier.clone());
+            continue;
+        }
+
+        // Definitions outside the workspace, e.g. in the standard library, are external
+        let workspace_definitions: Vec<FilePosition> = definitions
+            .into_iter()
+            .filter(|location| {
+                manager
+                    .detect_language_for_location(&location.uri)
+                    .is_some()
+            })
+            .map(|location| FilePosition::from_lsp_location(location, &mount_dir))
+            .filter(|definition| files.contains(&definition.path))
+            .collect();
+        if workspace_definitions.is_empty() {
+            external_symbols.push(identifier);
+            continue;
+        }
+
+        let mut symbols_with_definitions = Vec::new();
+        for definition in workspace_definitions {
+            if let Ok(Some(symbol)) = manager
+                .get_symbol_from_position(&definition.path, &definition.to_lsp_position())
+                .await
+            {
+                symbols_with_definitions.push(symbol);
             }
         }
+        // Only add to workspace_symbols if we found at least one symbol
+        if symbols_with_definitions.is_empty() {
+            not_found.push(identifier);
+        } else {
+            workspace_symbols.push(ReferenceWithSymbolDefinitions {
+                reference: identifier,
+                definitions: symbols_with_definitions,
+            });
+        }
     }
 
     // Sort workspace_symbols by reference location
@@ -212,12 +376,7 @@ pub async fn find_referenced_symbols(
         }
     });
 
-    // Return the sorted response
-    HttpResponse::Ok().json(ReferencedSymbolsResponse {
-        workspace_symbols,
-        external_symbols,
-        not_found,
-    })
+    Ok((workspace_symbols, external_symbols, not_found))
 }
 
 #[cfg(test)]
@@ -227,9 +386,190 @@ mod test {
     use actix_web::http::StatusCode;
     use tokio::time::{sleep, Duration};
 
-    use crate::api_types::{FilePosition, FileRange, Position, Range, Symbol};
+    use crate::api_types::{
+        FilePosition, FileRange, IdentifierKind, Position, Range, SupportedLanguages, Symbol,
+    };
     use crate::initialize_app_state;
-    use crate::test_utils::{csharp_sample_path, python_sample_path, TestContext};
+    use crate::test_utils::{csharp_sample_path, mocks::MockFile, python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_csharp_unique_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&csharp_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencedSymbolsRequest {
+            identifier_position: FilePosition {
+                path: String::from("AStar.cs"),
+                position: Position {
+                    line: 2,
+                    character: 17,
+                },
+            },
+            full_scan: false,
+            unique_symbols_only: true,
+            group_by_file: false,
+            limit: None,
+            offset: None,
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
+        });
+
+        sleep(Duration::from_secs(5)).await;
+
+        let response = find_referenced_symbols(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await?;
+        let referenced_symbols_response: ReferencedSymbolsResponse =
+            serde_json::from_slice(&bytes)?;
+
+        // AddNeighborsToOpenList is called on lines 28 and 38, only the first call is kept
+        let add_neighbors: Vec<_> = referenced_symbols_response
+            .workspace_symbols
+            .iter()
+            .filter(|symbol| symbol.reference.name == "AddNeighborsToOpenList")
+            .collect();
+        assert_eq!(add_neighbors.len(), 1);
+        assert_eq!(add_neighbors[0].reference.file_range.range.start.line, 28);
+
+        let mut external_names: Vec<_> = referenced_symbols_response
+            .external_symbols
+            .iter()
+            .map(|symbol| symbol.name.clone())
+            .collect();
+        let external_count = external_names.len();
+        external_names.sort();
+        external_names.dedup();
+        assert_eq!(external_names.len(), external_count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_definition_file() {
+        let range = |line| Range {
+            start: Position { line, character: 0 },
+            end: Position { line, character: 4 },
+        };
+        let symbol = |name: &str, path: &str| Symbol {
+            name: String::from(name),
+            kind: String::from("function"),
+            identifier_position: FilePosition {
+                path: String::from(path),
+                position: Position {
+                    line: 0,
+                    character: 0,
+                },
+            },
+            file_range: FileRange {
+                path: String::from(path),
+                range: range(0),
+            },
+        };
+        let reference = |name: &str, line, definitions| ReferenceWithSymbolDefinitions {
+            reference: Identifier {
+                name: String::from(name),
+                kind: None,
+                file_range: FileRange {
+                    path: String::from("main.py"),
+                    range: range(line),
+                },
+                source_text: None,
+                context_source: None,
+            },
+            definitions,
+        };
+        let graph = reference("AStarGraph", 1, vec![symbol("AStarGraph", "graph.py")]);
+        let search = reference(
+            "a_star_search",
+            2,
+            vec![symbol("a_star_search", "search.py")],
+        );
+        let neighbours = reference(
+            "get_vertex_neighbours",
+            3,
+            vec![
+                symbol("get_vertex_neighbours", "search.py"),
+                symbol("get_vertex_neighbours", "graph.py"),
+                symbol("get_vertex_neighbours", "graph.py"),
+            ],
+        );
+
+        let groups =
+            group_by_definition_file(vec![graph.clone(), search.clone(), neighbours.clone()]);
+        assert_eq!(
+            groups,
+            vec![
+                FileGroup {
+                    file_path: String::from("graph.py"),
+                    symbols: vec![graph, neighbours.clone()],
+                },
+                FileGroup {
+                    file_path: String::from("search.py"),
+                    symbols: vec![search, neighbours],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csharp_grouped_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&csharp_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencedSymbolsRequest {
+            identifier_position: FilePosition {
+                path: String::from("AStar.cs"),
+                position: Position {
+                    line: 2,
+                    character: 17,
+                },
+            },
+            full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: true,
+            limit: None,
+            offset: None,
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
+        });
+
+        sleep(Duration::from_secs(5)).await;
+
+        let response = find_referenced_symbols(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await?;
+        let referenced_symbols_response: ReferencedSymbolsResponse =
+            serde_json::from_slice(&bytes)?;
+
+        // Every method referenced from `AStar` is defined in AStar.cs
+        assert!(referenced_symbols_response.workspace_symbols.is_empty());
+        let groups = referenced_symbols_response
+            .workspace_symbols_by_file
+            .ok_or("Missing grouped workspace symbols")?;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].file_path, "AStar.cs");
+        let names: Vec<_> = groups[0]
+            .symbols
+            .iter()
+            .map(|symbol| symbol.reference.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "AddNeighborsToOpenList",
+                "AddNeighborsToOpenList",
+                "Distance",
+                "FindNeighborInList",
+                "FindNeighborInList",
+            ]
+        );
+        Ok(())
+    }
 
     #[tokio::test]
     async fn test_csharp_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
@@ -245,6 +585,13 @@ mod test {
                 },
             },
             full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: false,
+            limit: None,
+            offset: None,
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
         });
 
         sleep(Duration::from_secs(5)).await;
@@ -287,7 +634,9 @@ mod test {
                                 },
                             },
                         },
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
+                        source_text: Some(String::from("AddNeighborsToOpenList")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("AddNeighborsToOpenList"),
@@ -330,7 +679,9 @@ mod test {
                                 },
                             },
                         },
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
+                        source_text: Some(String::from("AddNeighborsToOpenList")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("AddNeighborsToOpenList"),
@@ -373,7 +724,9 @@ mod test {
                                 },
                             },
                         },
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
+                        source_text: Some(String::from("Distance")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("Distance"),
@@ -416,7 +769,9 @@ mod test {
                                 },
                             },
                         },
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
+                        source_text: Some(String::from("FindNeighborInList")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("FindNeighborInList"),
@@ -459,7 +814,9 @@ mod test {
                                 },
                             },
                         },
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
+                        source_text: Some(String::from("FindNeighborInList")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("FindNeighborInList"),
@@ -503,7 +860,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Add")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Any"),
@@ -520,7 +879,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Any")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("RemoveAt"),
@@ -537,7 +898,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("RemoveAt")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Add"),
@@ -554,7 +917,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Add")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Insert"),
@@ -571,7 +936,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Insert")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Insert"),
@@ -588,7 +955,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Insert")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Add"),
@@ -605,7 +974,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Add")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Sort"),
@@ -622,7 +993,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Sort")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Sqrt"),
@@ -639,7 +1012,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Sqrt")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Pow"),
@@ -656,7 +1031,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Pow")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Pow"),
@@ -673,7 +1050,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Pow")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Any"),
@@ -690,7 +1069,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
+                    source_text: Some(String::from("Any")),
+                    context_source: None,
                 },
             ],
             not_found: vec![
@@ -709,7 +1090,9 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("class-instantiation")),
+                    kind: Some(IdentifierKind::ClassInstantiation),
+                    source_text: Some(String::from("Node")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("Node"),
@@ -726,9 +1109,15 @@ mod test {
                             },
                         },
                     },
-                    kind: Some(String::from("class-instantiation")),
+                    kind: Some(IdentifierKind::ClassInstantiation),
+                    source_text: Some(String::from("Node")),
+                    context_source: None,
                 },
             ],
+            workspace_symbols_by_file: None,
+            total_workspace_symbols: 5,
+            total_external_symbols: 12,
+            total_not_found: 2,
         };
 
         // Sort definitions for each reference before comparing
@@ -780,6 +1169,13 @@ mod test {
                 },
             },
             full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: false,
+            limit: None,
+            offset: None,
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
         });
 
         sleep(Duration::from_secs(5)).await;
@@ -809,7 +1205,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("log_execution_time"),
-                        kind: Some(String::from("decorator")),
+                        kind: Some(IdentifierKind::Decorator),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -823,6 +1219,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("log_execution_time")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("log_execution_time"),
@@ -852,7 +1250,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("initialize_search"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -866,6 +1264,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("initialize_search")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("initialize_search"),
@@ -895,7 +1295,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("reconstruct_path"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -909,6 +1309,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("reconstruct_path")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("reconstruct_path"),
@@ -938,7 +1340,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("get_vertex_neighbours"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -952,6 +1354,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("get_vertex_neighbours")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("get_vertex_neighbours"),
@@ -981,7 +1385,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("move_cost"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -995,6 +1399,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("move_cost")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("move_cost"),
@@ -1024,7 +1430,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("heuristic"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("search.py"),
                             range: Range {
@@ -1038,6 +1444,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("heuristic")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("heuristic"),
@@ -1068,7 +1476,7 @@ mod test {
             external_symbols: vec![
                 Identifier {
                     name: String::from("append"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1082,10 +1490,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("append")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("append"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1099,10 +1509,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("append")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("min"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1116,10 +1528,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("min")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("remove"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1133,10 +1547,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("remove")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("add"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1150,10 +1566,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("add")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("add"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1167,10 +1585,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("add")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("get"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1184,10 +1604,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("get")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("float"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1201,10 +1623,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("float")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("RuntimeError"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("search.py"),
                         range: Range {
@@ -1218,9 +1642,15 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("RuntimeError")),
+                    context_source: None,
                 },
             ],
             not_found: vec![],
+            workspace_symbols_by_file: None,
+            total_workspace_symbols: 6,
+            total_external_symbols: 9,
+            total_not_found: 0,
         };
 
         // Sort definitions for each reference before comparing
@@ -1272,6 +1702,13 @@ mod test {
                 },
             },
             full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: false,
+            limit: None,
+            offset: None,
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
         });
 
         sleep(Duration::from_secs(5)).await;
@@ -1301,7 +1738,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("_barrier_cost"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1315,6 +1752,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("_barrier_cost")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("_barrier_cost"),
@@ -1344,7 +1783,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("_distance_cost"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1358,6 +1797,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("_distance_cost")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("_distance_cost"),
@@ -1387,7 +1828,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("cost_function"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1401,6 +1842,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("cost_function")),
+                        context_source: None,
                     },
                     definitions: vec![
                         Symbol {
@@ -1480,7 +1923,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("log_execution_time"),
-                        kind: Some(String::from("decorator")),
+                        kind: Some(IdentifierKind::Decorator),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1494,6 +1937,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("log_execution_time")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("log_execution_time"),
@@ -1523,7 +1968,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("log_execution_time"),
-                        kind: Some(String::from("decorator")),
+                        kind: Some(IdentifierKind::Decorator),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1537,6 +1982,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("log_execution_time")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("log_execution_time"),
@@ -1566,7 +2013,7 @@ mod test {
                 ReferenceWithSymbolDefinitions {
                     reference: Identifier {
                         name: String::from("move_cost"),
-                        kind: Some(String::from("function-call")),
+                        kind: Some(IdentifierKind::FunctionCall),
                         file_range: FileRange {
                             path: String::from("graph.py"),
                             range: Range {
@@ -1580,6 +2027,8 @@ mod test {
                                 },
                             },
                         },
+                        source_text: Some(String::from("move_cost")),
+                        context_source: None,
                     },
                     definitions: vec![Symbol {
                         name: String::from("move_cost"),
@@ -1610,7 +2059,7 @@ mod test {
             external_symbols: vec![
                 Identifier {
                     name: String::from("append"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1624,10 +2073,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("append")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("property"),
-                    kind: Some(String::from("decorator")),
+                    kind: Some(IdentifierKind::Decorator),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1641,10 +2092,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("property")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("abs"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1658,10 +2111,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("abs")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("abs"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1675,10 +2130,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("abs")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("ValueError"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1692,10 +2149,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("ValueError")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("abs"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1709,10 +2168,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("abs")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("abs"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1726,10 +2187,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("abs")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("min"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1743,10 +2206,12 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("min")),
+                    context_source: None,
                 },
                 Identifier {
                     name: String::from("append"),
-                    kind: Some(String::from("function-call")),
+                    kind: Some(IdentifierKind::FunctionCall),
                     file_range: FileRange {
                         path: String::from("graph.py"),
                         range: Range {
@@ -1760,9 +2225,15 @@ mod test {
                             },
                         },
                     },
+                    source_text: Some(String::from("append")),
+                    context_source: None,
                 },
             ],
             not_found: vec![],
+            workspace_symbols_by_file: None,
+            total_workspace_symbols: 6,
+            total_external_symbols: 9,
+            total_not_found: 0,
         };
 
         // Sort definitions for each reference before comparing
@@ -1799,4 +2270,208 @@ mod test {
         assert_eq!(sorted_response, sorted_expected);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_python_paginated_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencedSymbolsRequest {
+            identifier_position: FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            },
+            full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: false,
+            limit: Some(2),
+            offset: Some(0),
+            include_source_context: false,
+            context_lines: None,
+            exclude_test_files: false,
+        });
+
+        sleep(Duration::from_secs(5)).await;
+
+        let response = find_referenced_symbols(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body()).await?;
+        let referenced_symbols_response: ReferencedSymbolsResponse =
+            serde_json::from_slice(&bytes)?;
+        assert_eq!(referenced_symbols_response.workspace_symbols.len(), 2);
+        assert_eq!(referenced_symbols_response.external_symbols.len(), 2);
+        assert!(referenced_symbols_response.not_found.is_empty());
+        assert_eq!(referenced_symbols_response.total_workspace_symbols, 6);
+        assert_eq!(referenced_symbols_response.total_external_symbols, 9);
+        assert_eq!(referenced_symbols_response.total_not_found, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_referenced_symbols_with_source_context(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencedSymbolsRequest {
+            identifier_position: FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            },
+            full_scan: false,
+            unique_symbols_only: false,
+            group_by_file: false,
+            limit: None,
+            offset: None,
+            include_source_context: true,
+            context_lines: Some(1),
+            exclude_test_files: false,
+        });
+
+        sleep(Duration::from_secs(5)).await;
+
+        let response = find_referenced_symbols(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body()).await?;
+        let referenced_symbols_response: ReferencedSymbolsResponse =
+            serde_json::from_slice(&bytes)?;
+        let references: Vec<&Identifier> = referenced_symbols_response
+            .workspace_symbols
+            .iter()
+            .map(|symbol| &symbol.reference)
+            .chain(&referenced_symbols_response.external_symbols)
+            .collect();
+        assert!(!references.is_empty());
+        for reference in references {
+            let context_source = reference.context_source.as_deref().unwrap_or_default();
+            assert!(context_source.contains(&reference.name));
+            assert!(context_source.lines().count() <= 3);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_attach_source_context() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.py", "a = 1\nb = a\nc = b\nd = c\n")];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let identifier = |line| Identifier {
+            name: String::from("x"),
+            file_range: FileRange {
+                path: String::from("main.py"),
+                range: Range {
+                    start: Position { line, character: 0 },
+                    end: Position { line, character: 1 },
+                },
+            },
+            kind: None,
+            source_text: None,
+            context_source: None,
+        };
+        let mut references = [identifier(0), identifier(2), identifier(3)];
+        attach_source_context(&state.manager, references.iter_mut(), 1).await?;
+
+        let context_sources: Vec<_> = references
+            .iter()
+            .map(|reference| reference.context_source.as_deref())
+            .collect();
+        assert_eq!(
+            context_sources,
+            vec![
+                Some("a = 1\nb = a"),
+                Some("b = a\nc = b\nd = c"),
+                Some("c = b\nd = c")
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_referenced_symbols_exclude_test_files(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new(
+                "main.py",
+                "from models import User\nfrom test_utils import log_calls\n\n\n\
+                 @log_calls\ndef process():\n    return User()\n",
+            ),
+            MockFile::new("models.py", "class User:\n    pass\n"),
+            MockFile::new("test_utils.py", "def log_calls(func):\n    return func\n"),
+        ];
+        let mut context =
+            TestContext::setup_mock_with(SupportedLanguages::Python, files, |client| {
+                let decorator = client.location(
+                    "test_utils.py",
+                    LspPosition::new(0, 4),
+                    LspPosition::new(0, 13),
+                );
+                let class =
+                    client.location("models.py", LspPosition::new(0, 6), LspPosition::new(0, 10));
+                client
+                    .with_definition("main.py", LspPosition::new(4, 1), vec![decorator])
+                    .with_definition("main.py", LspPosition::new(6, 11), vec![class])
+            })
+            .await?;
+        let state = context.app_state();
+
+        let workspace_symbols = |exclude_test_files| {
+            let state = state.clone();
+            async move {
+                let response = find_referenced_symbols(
+                    state,
+                    Json(GetReferencedSymbolsRequest {
+                        identifier_position: FilePosition {
+                            path: String::from("main.py"),
+                            position: Position {
+                                line: 5,
+                                character: 4,
+                            },
+                        },
+                        full_scan: false,
+                        unique_symbols_only: false,
+                        group_by_file: false,
+                        limit: None,
+                        offset: None,
+                        include_source_context: false,
+                        context_lines: None,
+                        exclude_test_files,
+                    }),
+                )
+                .await;
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = actix_web::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap();
+                let response: ReferencedSymbolsResponse = serde_json::from_slice(&bytes).unwrap();
+                response
+                    .workspace_symbols
+                    .into_iter()
+                    .map(|symbol| symbol.reference.name)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(workspace_symbols(false).await, ["log_calls", "User"]);
+        // The decorator is defined in a test helper module
+        assert_eq!(workspace_symbols(true).await, ["User"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_paginate() {
+        let items = vec![1, 2, 3, 4, 5];
+        assert_eq!(paginate(items.clone(), 0, Some(2)), vec![1, 2]);
+        assert_eq!(paginate(items.clone(), 3, Some(5)), vec![4, 5]);
+        assert_eq!(paginate(items.clone(), 1, None), vec![2, 3, 4, 5]);
+        assert!(paginate(items, 10, Some(2)).is_empty());
+    }
 }
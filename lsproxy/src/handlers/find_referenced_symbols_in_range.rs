@@ -0,0 +1,155 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{GetReferencedSymbolsInRangeRequest, ReferencedSymbolsResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::find_referenced_symbols::categorize_referenced_symbols;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Find all symbols that are referenced from within a range of a file
+///
+/// Like `find-referenced-symbols`, but for every symbol defined in the range rather than a
+/// single one, e.g. a block of methods. Only references inside the range are returned, each
+/// once, categorized the same way:
+/// - Workspace symbols (with their definitions)
+/// - External symbols (built-in functions like 'len', 'print' or from external libraries)
+/// - Symbols that couldn't be found
+#[utoipa::path(
+    post,
+    path = "/symbol/find-referenced-symbols-in-range",
+    tag = "symbol",
+    request_body = GetReferencedSymbolsInRangeRequest,
+    responses(
+        (status = 200, description = "Referenced symbols retrieved successfully", body = ReferencedSymbolsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Referenced symbols not supported for this language")
+    )
+)]
+pub async fn find_referenced_symbols_in_range(
+    data: Data<AppState>,
+    info: Json<GetReferencedSymbolsInRangeRequest>,
+) -> HttpResponse {
+    info!(
+        "Received referenced symbols in range request for file: {}, lines {}-{}",
+        info.file_range.path, info.file_range.range.start.line, info.file_range.range.end.line
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_range.path, &data.manager).await {
+        return response;
+    }
+
+    let referenced_symbols = match data
+        .manager
+        .find_referenced_symbols_in_range(
+            &info.file_range.path,
+            info.file_range.clone().into(),
+            info.full_scan,
+        )
+        .await
+    {
+        Ok(referenced_symbols) => referenced_symbols,
+        Err(e) => {
+            error!("Failed to get referenced symbols in range: {}", e);
+            return e.into_http_response();
+        }
+    };
+
+    match categorize_referenced_symbols(&data.manager, referenced_symbols).await {
+        Ok((workspace_symbols, external_symbols, not_found)) => {
+            HttpResponse::Ok().json(ReferencedSymbolsResponse {
+                total_workspace_symbols: workspace_symbols.len(),
+                total_external_symbols: external_symbols.len(),
+                total_not_found: not_found.len(),
+                workspace_symbols,
+                external_symbols,
+                not_found,
+                workspace_symbols_by_file: None,
+            })
+        }
+        Err(e) => {
+            error!("Failed to categorize referenced symbols: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use std::collections::HashSet;
+
+    use crate::api_types::{FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_referenced_symbols_in_range() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `_barrier_cost`, `_distance_cost` and `_combined_cost` in graph.py
+        let range = Range {
+            start: Position {
+                line: 26,
+                character: 0,
+            },
+            end: Position {
+                line: 42,
+                character: 0,
+            },
+        };
+        let response = find_referenced_symbols_in_range(
+            state,
+            Json(GetReferencedSymbolsInRangeRequest {
+                file_range: FileRange {
+                    path: String::from("graph.py"),
+                    range: range.clone(),
+                },
+                full_scan: false,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let referenced: ReferencedSymbolsResponse = serde_json::from_slice(&bytes)?;
+
+        // References from every method in the range are merged
+        let workspace_names: HashSet<&str> = referenced
+            .workspace_symbols
+            .iter()
+            .map(|symbol| symbol.reference.name.as_str())
+            .collect();
+        for name in ["barriers", "_barrier_cost", "_distance_cost"] {
+            assert!(workspace_names.contains(name), "{} missing", name);
+        }
+        assert!(referenced
+            .external_symbols
+            .iter()
+            .any(|symbol| symbol.name == "abs"));
+
+        // Each reference is inside the range and only listed once
+        let references: Vec<_> = referenced
+            .workspace_symbols
+            .iter()
+            .map(|symbol| &symbol.reference)
+            .chain(referenced.external_symbols.iter())
+            .chain(referenced.not_found.iter())
+            .collect();
+        let mut seen = HashSet::new();
+        for reference in references {
+            let start = &reference.file_range.range.start;
+            assert_eq!(reference.file_range.path, "graph.py");
+            assert!(start.line >= range.start.line && start.line < range.end.line);
+            assert!(seen.insert((start.line, start.character, reference.name.clone())));
+        }
+        Ok(())
+    }
+}
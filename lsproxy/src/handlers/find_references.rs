@@ -1,16 +1,16 @@
 use actix_web::web::{Data, Json};
 use actix_web::HttpResponse;
-use log::{error, info};
-use lsp_types::{Location, Position as LspPosition};
+use log::{error, info, warn};
+use lsp_types::{Location, Position as LspPosition, Url};
 
 use crate::api_types::{
-    CodeContext, ErrorResponse, FilePosition, FileRange, GetReferencesRequest, Position, Range,
-    ReferencesResponse,
+    get_mount_dir, CodeContext, ErrorCode, ErrorResponse, FilePosition, FileRange,
+    GetReferencesRequest, Identifier, ReferencesResponse, ResultSource,
 };
 use crate::handlers::error::IntoHttpResponse;
 use crate::handlers::utils;
 use crate::lsp::manager::{LspManagerError, Manager};
-use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::utils::file_utils::{detect_language, uri_to_relative_path_string};
 use crate::AppState;
 
 /// Find all references to a symbol
@@ -32,6 +32,12 @@ use crate::AppState;
 ///  5: user = User("John", 30)
 ///  output____^
 /// ```
+///
+/// Set `file_pattern_filter` to only keep references in files matching a glob, e.g. test files.
+///
+/// With `fallback_to_ast_grep`, if the langserver finds no references, e.g. across modules of
+/// dynamically typed code, every identifier in the workspace with the same name is returned
+/// instead. The `source` field of the response tells which of the two the references came from.
 #[utoipa::path(
     post,
     path = "/symbol/find-references",
@@ -54,6 +60,27 @@ pub async fn find_references(
         info.identifier_position.position.character
     );
 
+    if let Err(response) =
+        utils::validate_file_in_workspace(&info.identifier_position.path, &data.manager).await
+    {
+        return response;
+    }
+
+    let file_pattern = match info
+        .file_pattern_filter
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+    {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::BadRequest,
+                format!("Invalid file pattern filter: {}", e),
+            ));
+        }
+    };
+
     let file_identifiers = match data
         .manager
         .get_file_identifiers(&info.identifier_position.path)
@@ -62,9 +89,10 @@ pub async fn find_references(
         Ok(identifiers) => identifiers,
         Err(e) => {
             error!("Failed to get file identifiers: {:?}", e);
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to get file identifiers: {}", e),
-            });
+            return HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get file identifiers: {}", e),
+            ));
         }
     };
 
@@ -74,14 +102,41 @@ pub async fn find_references(
             Ok(identifier) => identifier,
             Err(e) => {
                 error!("Failed to find references from position: {:?}", e);
-                return HttpResponse::BadRequest().json(ErrorResponse {
-                    error: format!("Failed to find references from position: {}", e),
-                });
+                return HttpResponse::BadRequest().json(ErrorResponse::new(
+                    ErrorCode::IdentifierNotFound,
+                    format!("Failed to find references from position: {}", e),
+                ));
             }
         };
 
-    let references_result =
-        find_and_filter_references(&data.manager, &info.identifier_position).await;
+    let references_result = find_and_filter_references(
+        &data.manager,
+        &info.identifier_position,
+        info.include_declaration,
+        file_pattern.as_ref(),
+    )
+    .await;
+    let (references_result, source) = match references_result {
+        Ok(references) if references.is_empty() && info.fallback_to_ast_grep => {
+            match find_references_by_name(
+                &data.manager,
+                &selected_identifier,
+                info.include_declaration,
+                file_pattern.as_ref(),
+            )
+            .await
+            {
+                Ok(fallback) if !fallback.is_empty() => (Ok(fallback), ResultSource::AstGrep),
+                Ok(_) => (Ok(references), ResultSource::Lsp),
+                // The langserver's answer still stands
+                Err(e) => {
+                    warn!("Failed to find ast-grep references: {}", e);
+                    (Ok(references), ResultSource::Lsp)
+                }
+            }
+        }
+        result => (result, ResultSource::Lsp),
+    };
     let code_contexts_result = get_code_contexts(
         &data.manager,
         &references_result,
@@ -107,25 +162,21 @@ pub async fn find_references(
                 raw_response,
                 references: references
                     .into_iter()
-                    .map(|loc| FilePosition {
-                        path: uri_to_relative_path_string(&loc.uri),
-                        position: Position {
-                            line: loc.range.start.line,
-                            character: loc.range.start.character,
-                        },
-                    })
+                    .map(|loc| FilePosition::from_lsp_location(loc, &get_mount_dir()))
                     .collect(),
                 context: code_contexts,
                 selected_identifier,
+                source,
             };
             HttpResponse::Ok().json(response)
         }
         (Err(e), _) => handle_lsp_error(e),
         (_, Err(e)) => {
             error!("Failed to fetch code context: {}", e);
-            HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to fetch code context: {}", e),
-            })
+            HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to fetch code context: {}", e),
+            ))
         }
     }
 }
@@ -133,6 +184,8 @@ pub async fn find_references(
 async fn find_and_filter_references(
     manager: &Manager,
     position: &FilePosition,
+    include_declaration: bool,
+    file_pattern: Option<&glob::Pattern>,
 ) -> Result<Vec<Location>, LspManagerError> {
     let references = manager
         .find_references(
@@ -141,6 +194,7 @@ async fn find_and_filter_references(
                 line: position.position.line,
                 character: position.position.character,
             },
+            include_declaration,
         )
         .await?;
 
@@ -149,7 +203,7 @@ async fn find_and_filter_references(
         .into_iter()
         .filter(|reference| {
             let path = uri_to_relative_path_string(&reference.uri);
-            files.contains(&path)
+            files.contains(&path) && file_pattern.is_none_or(|pattern| pattern.matches(&path))
         })
         .collect();
 
@@ -165,6 +219,66 @@ async fn find_and_filter_references(
     Ok(filtered_refs)
 }
 
+/// Finds the identifiers in the workspace with the same name as the selected one, in files of the
+/// same language, sorted like the langserver's references.
+///
+/// Without `include_declaration`, identifiers of ast-grep definitions are left out.
+async fn find_references_by_name(
+    manager: &Manager,
+    identifier: &Identifier,
+    include_declaration: bool,
+    file_pattern: Option<&glob::Pattern>,
+) -> Result<Vec<Location>, LspManagerError> {
+    let language = detect_language(&identifier.file_range.path)?;
+    let mut references = Vec::new();
+    for file_path in manager.list_files().await? {
+        if !detect_language(&file_path).is_ok_and(|lang| lang == language)
+            || !file_pattern.is_none_or(|pattern| pattern.matches(&file_path))
+        {
+            continue;
+        }
+        let matches: Vec<Identifier> = manager
+            .get_file_identifiers(&file_path)
+            .await?
+            .into_iter()
+            .filter(|candidate| candidate.name == identifier.name)
+            .collect();
+        if matches.is_empty() {
+            continue;
+        }
+        let declarations: Vec<(u32, u32)> = if include_declaration {
+            Vec::new()
+        } else {
+            manager
+                .definitions_in_file_ast_grep_cached(&file_path)
+                .await?
+                .iter()
+                .map(|symbol| {
+                    let start = symbol.get_identifier_range().start;
+                    (start.line, start.column)
+                })
+                .collect()
+        };
+        let uri = Url::from_file_path(get_mount_dir().join(&file_path))
+            .map_err(|_| LspManagerError::InvalidPath(file_path.clone()))?;
+        for candidate in matches {
+            let start = &candidate.file_range.range.start;
+            if declarations.contains(&(start.line, start.character)) {
+                continue;
+            }
+            references.push(Location::new(uri.clone(), candidate.file_range.into()));
+        }
+    }
+
+    references.sort_by(|a, b| {
+        a.uri
+            .to_string()
+            .cmp(&b.uri.to_string())
+            .then(a.range.start.line.cmp(&b.range.start.line))
+    });
+    Ok(references)
+}
+
 async fn get_code_contexts(
     manager: &Manager,
     references_result: &Result<Vec<Location>, LspManagerError>,
@@ -200,25 +314,20 @@ async fn fetch_code_context(
             },
         };
         match manager
-            .read_source_code(&uri_to_relative_path_string(&reference.uri), Some(range))
+            .read_source_code(
+                &uri_to_relative_path_string(&reference.uri),
+                Some(range),
+                None,
+            )
             .await
         {
             Ok(source_code) => {
                 code_contexts.push(CodeContext {
                     source_code,
-                    range: FileRange {
-                        path: uri_to_relative_path_string(&reference.uri),
-                        range: Range {
-                            start: Position {
-                                line: range.start.line,
-                                character: 0,
-                            },
-                            end: Position {
-                                line: range.end.line,
-                                character: 0,
-                            },
-                        },
-                    },
+                    range: FileRange::from_lsp_location(
+                        Location::new(reference.uri.clone(), range),
+                        &get_mount_dir(),
+                    ),
                 });
             }
             Err(e) => return Err(e),
@@ -234,7 +343,7 @@ mod test {
     use actix_web::http::StatusCode;
     use tokio::time::{sleep, Duration};
 
-    use crate::api_types::{FilePosition, Identifier, Position};
+    use crate::api_types::{FilePosition, Identifier, Position, Range};
     use crate::initialize_app_state;
     use crate::test_utils::{python_sample_path, ruby_sample_path, rust_sample_path, TestContext};
 
@@ -251,8 +360,11 @@ mod test {
                     character: 6,
                 },
             },
+            include_declaration: true,
             include_code_context_lines: None,
             include_raw_response: false,
+            file_pattern_filter: None,
+            fallback_to_ast_grep: false,
         });
 
         let response = find_references(state, mock_request).await;
@@ -340,13 +452,62 @@ mod test {
                         },
                     },
                 },
+                source_text: Some(String::from("AStarGraph")),
+                context_source: None,
             },
+            source: ResultSource::Lsp,
         };
 
         assert_eq!(reference_response, expected_response);
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_python_references_include_declaration() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let declaration = FilePosition {
+            path: String::from("graph.py"),
+            position: Position {
+                line: 12,
+                character: 6,
+            },
+        };
+        for include_declaration in [false, true] {
+            let mock_request = Json(GetReferencesRequest {
+                identifier_position: declaration.clone(),
+                include_declaration,
+                include_code_context_lines: None,
+                include_raw_response: false,
+                file_pattern_filter: None,
+                fallback_to_ast_grep: false,
+            });
+
+            let response = find_references(state.clone(), mock_request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response.into_body();
+            let bytes = actix_web::body::to_bytes(body).await?;
+            let reference_response: ReferencesResponse = serde_json::from_slice(&bytes)?;
+
+            assert_eq!(
+                reference_response.references.contains(&declaration),
+                include_declaration
+            );
+            // The usages are returned either way
+            assert!(reference_response.references.contains(&FilePosition {
+                path: String::from("main.py"),
+                position: Position {
+                    line: 1,
+                    character: 18,
+                },
+            }));
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rust_references() -> Result<(), Box<dyn std::error::Error>> {
         let _context = TestContext::setup(&rust_sample_path(), false).await?;
@@ -360,8 +521,11 @@ mod test {
                     character: 11,
                 },
             },
+            include_declaration: true,
             include_code_context_lines: None,
             include_raw_response: false,
+            file_pattern_filter: None,
+            fallback_to_ast_grep: false,
         });
 
         sleep(Duration::from_secs(5)).await;
@@ -450,6 +614,7 @@ mod test {
             ],
             context: None,
             selected_identifier: reference_response.selected_identifier.clone(), // We can't predict this value
+            source: ResultSource::Lsp,
         };
 
         assert_eq!(expected_response, reference_response);
@@ -469,8 +634,11 @@ mod test {
                     character: 8,
                 },
             },
+            include_declaration: true,
             include_code_context_lines: None,
             include_raw_response: false,
+            file_pattern_filter: None,
+            fallback_to_ast_grep: false,
         });
 
         let response = find_references(state, mock_request).await;
@@ -550,7 +718,10 @@ mod test {
                     },
                 },
                 kind: None,
+                source_text: Some(String::from("log_time")),
+                context_source: None,
             },
+            source: ResultSource::Lsp,
         };
 
         assert_eq!(reference_response, expected_response);
@@ -570,8 +741,11 @@ mod test {
                     character: 0,
                 },
             },
+            include_declaration: true,
             include_code_context_lines: None,
             include_raw_response: false,
+            file_pattern_filter: None,
+            fallback_to_ast_grep: false,
         });
 
         let response = find_references(state, mock_request).await;
@@ -582,8 +756,140 @@ mod test {
         let error_response: ErrorResponse = serde_json::from_slice(&bytes)?;
         assert_eq!(
             error_response.error,
-            "Failed to find references from position: No identifier found at position. Closest matches: [Identifier { name: \"n\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 88, character: 15 }, end: Position { line: 88, character: 16 } } }, kind: None }, Identifier { name: \"n\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 87, character: 16 }, end: Position { line: 87, character: 17 } } }, kind: None }, Identifier { name: \"append\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 87, character: 18 }, end: Position { line: 87, character: 24 } } }, kind: None }]"        );
+            "Failed to find references from position: No identifier found at position. Closest matches: [Identifier { name: \"n\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 88, character: 15 }, end: Position { line: 88, character: 16 } } }, kind: None, source_text: Some(\"n\"), context_source: None }, Identifier { name: \"n\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 87, character: 16 }, end: Position { line: 87, character: 17 } } }, kind: None, source_text: Some(\"n\"), context_source: None }, Identifier { name: \"append\", file_range: FileRange { path: \"graph.py\", range: Range { start: Position { line: 87, character: 18 }, end: Position { line: 87, character: 24 } } }, kind: None, source_text: Some(\"append\"), context_source: None }]"        );
+
+        Ok(())
+    }
 
+    #[tokio::test]
+    async fn test_python_references_test_files_only() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir(dir.path().join("tests"))?;
+        std::fs::write(
+            dir.path().join("calc.py"),
+            "def add(a, b):\n    return a + b\n",
+        )?;
+        std::fs::write(
+            dir.path().join("app.py"),
+            "from calc import add\n\nprint(add(1, 2))\n",
+        )?;
+        std::fs::write(
+            dir.path().join("tests/test_calc.py"),
+            "from calc import add\n\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+        )?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencesRequest {
+            identifier_position: FilePosition {
+                path: String::from("calc.py"),
+                position: Position {
+                    line: 0,
+                    character: 4,
+                },
+            },
+            include_declaration: true,
+            include_code_context_lines: None,
+            include_raw_response: false,
+            file_pattern_filter: Some(String::from("**/test_*.py")),
+            fallback_to_ast_grep: false,
+        });
+
+        let response = find_references(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body()).await?;
+        let reference_response: ReferencesResponse = serde_json::from_slice(&bytes)?;
+
+        assert!(!reference_response.references.is_empty());
+        assert!(reference_response
+            .references
+            .iter()
+            .all(|reference| reference.path == "tests/test_calc.py"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file_pattern_filter() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(GetReferencesRequest {
+            identifier_position: FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            },
+            include_declaration: true,
+            include_code_context_lines: None,
+            include_raw_response: false,
+            file_pattern_filter: Some(String::from("[")),
+            fallback_to_ast_grep: false,
+        });
+
+        let response = find_references(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_find_references_by_name() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `heuristic` in `def heuristic(self, start, goal):`
+        let identifier = Identifier {
+            name: String::from("heuristic"),
+            kind: None,
+            file_range: FileRange {
+                path: String::from("graph.py"),
+                range: Range {
+                    start: Position {
+                        line: 68,
+                        character: 8,
+                    },
+                    end: Position {
+                        line: 68,
+                        character: 17,
+                    },
+                },
+            },
+            source_text: None,
+            context_source: None,
+        };
+        let positions = |references: Vec<Location>| {
+            references
+                .into_iter()
+                .map(|reference| {
+                    (
+                        uri_to_relative_path_string(&reference.uri),
+                        reference.range.start.line,
+                        reference.range.start.character,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let references = find_references_by_name(&state.manager, &identifier, true, None).await?;
+        assert_eq!(
+            positions(references),
+            vec![
+                (String::from("graph.py"), 68, 8),
+                (String::from("search.py"), 8, 21),
+                (String::from("search.py"), 54, 48),
+            ]
+        );
+
+        let references = find_references_by_name(&state.manager, &identifier, false, None).await?;
+        assert_eq!(
+            positions(references),
+            vec![
+                (String::from("search.py"), 8, 21),
+                (String::from("search.py"), 54, 48),
+            ]
+        );
         Ok(())
     }
 }
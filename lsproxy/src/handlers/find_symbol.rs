@@ -0,0 +1,162 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorCode, ErrorResponse, FindSymbolRequest, SymbolResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Find symbols by their exact name
+///
+/// A faster alternative to searching all the symbols of the workspace when the name is known.
+/// The language servers' workspace symbol search is tried first, falling back to searching
+/// every file with ast-grep when it finds nothing.
+///
+/// e.g. `name=AStarGraph&kind=class` returns the definition of the `AStarGraph` class.
+#[utoipa::path(
+    get,
+    path = "/workspace/find-symbol",
+    tag = "workspace",
+    params(FindSymbolRequest),
+    responses(
+        (status = 200, description = "Symbols retrieved successfully", body = SymbolResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn find_symbol(data: Data<AppState>, info: Query<FindSymbolRequest>) -> HttpResponse {
+    info!(
+        "Received find symbol request for name: {}, kind: {:?}",
+        info.name, info.kind
+    );
+
+    if info.name.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            String::from("Name must not be empty"),
+        ));
+    }
+
+    match data
+        .manager
+        .find_symbol_by_name(&info.name, info.kind.as_deref())
+        .await
+    {
+        Ok(symbols) => HttpResponse::Ok().json(symbols),
+        Err(e) => {
+            error!("Failed to find symbol: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::{Position as LspPosition, SymbolKind};
+
+    use crate::api_types::{FilePosition, FileRange, Position, Range, SupportedLanguages, Symbol};
+    use crate::initialize_app_state;
+    use crate::test_utils::{mocks::MockFile, python_sample_path, TestContext};
+
+    fn request(name: &str, kind: Option<&str>) -> Query<FindSymbolRequest> {
+        Query(FindSymbolRequest {
+            name: name.to_string(),
+            kind: kind.map(String::from),
+        })
+    }
+
+    async fn find(
+        data: Data<AppState>,
+        request: Query<FindSymbolRequest>,
+    ) -> Result<SymbolResponse, Box<dyn std::error::Error>> {
+        let response = find_symbol(data, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    #[tokio::test]
+    async fn test_python_find_symbol() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let symbols = find(state.clone(), request("AStarGraph", Some("class"))).await?;
+        assert_eq!(
+            symbols,
+            vec![Symbol {
+                name: String::from("AStarGraph"),
+                kind: String::from("class"),
+                identifier_position: FilePosition {
+                    path: String::from("graph.py"),
+                    position: Position {
+                        line: 12,
+                        character: 6,
+                    },
+                },
+                file_range: FileRange {
+                    path: String::from("graph.py"),
+                    range: Range {
+                        start: Position {
+                            line: 12,
+                            character: 0,
+                        },
+                        end: Position {
+                            line: 88,
+                            character: 16,
+                        },
+                    },
+                },
+            }]
+        );
+
+        // Partial names don't match
+        assert!(find(state.clone(), request("AStar", None))
+            .await?
+            .is_empty());
+        assert!(find(state, request("AStarGraph", Some("function")))
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_symbol_from_workspace_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new(
+            "graph.py",
+            "class Graph:\n    def neighbors(self):\n        pass\n\n\ndef Graph2():\n    pass\n",
+        )];
+        let context = TestContext::setup_mock_with(SupportedLanguages::Python, files, |client| {
+            let location =
+                client.location("graph.py", LspPosition::new(0, 6), LspPosition::new(0, 11));
+            let other =
+                client.location("graph.py", LspPosition::new(5, 4), LspPosition::new(5, 10));
+            client
+                .with_workspace_symbol("Graph", SymbolKind::CLASS, location)
+                .with_workspace_symbol("Graph2", SymbolKind::FUNCTION, other)
+        })
+        .await?;
+        let manager = context.manager.as_ref().unwrap();
+
+        let symbols = manager.find_symbol_by_name("Graph", None).await?;
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].kind, "class");
+        assert_eq!(
+            symbols[0].identifier_position.position,
+            Position {
+                line: 0,
+                character: 6
+            }
+        );
+
+        // Falls back to ast-grep for symbols the server doesn't report
+        let symbols = manager.find_symbol_by_name("neighbors", None).await?;
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].identifier_position.position.line, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info, warn};
+use lsp_types::Position as LspPosition;
+
+use crate::api_types::{FilePosition, SymbolUsage, SymbolUsagesResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::utils::text_edits::{position_to_offset, word_range_at};
+use crate::AppState;
+
+/// Find every usage of a symbol, classified by kind
+///
+/// The input position should point to the identifier of the symbol. Returns the declaration and
+/// every reference to it, each tagged with how the symbol is used there:
+/// - `definition`: where the symbol is defined
+/// - `import`: an import statement, e.g. `from graph import AStarGraph`
+/// - `call`: a call, e.g. `AStarGraph()`
+/// - `type-annotation`: a type annotation, e.g. `graph: AStarGraph`
+/// - `reference`: any other use
+///
+/// Classification is based on the text around each usage, so it is a best effort.
+#[utoipa::path(
+    post,
+    path = "/symbol/find-symbol-usages",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Symbol usages retrieved successfully", body = SymbolUsagesResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn find_symbol_usages(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received symbol usages request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    let references = match data
+        .manager
+        .find_references_as_file_ranges(&info.path, info.position.clone().into(), true)
+        .await
+    {
+        Ok(references) => references,
+        Err(e) => {
+            error!("Failed to find references: {}", e);
+            return e.into_http_response();
+        }
+    };
+
+    // Definitions and source text are fetched once per file
+    let mut definition_sites: HashMap<String, HashSet<(u32, u32)>> = HashMap::new();
+    let mut sources: HashMap<String, Option<String>> = HashMap::new();
+    let mut usages = Vec::with_capacity(references.len());
+    for location in references {
+        let path = location.path.clone();
+        if !definition_sites.contains_key(&path) {
+            let sites = match data
+                .manager
+                .definitions_in_file_ast_grep_cached(&path)
+                .await
+            {
+                Ok(symbols) => symbols
+                    .iter()
+                    .map(|symbol| {
+                        let start = symbol.get_identifier_range().start;
+                        (start.line, start.column)
+                    })
+                    .collect(),
+                Err(e) => {
+                    warn!("Failed to get definitions in {}: {}", path, e);
+                    HashSet::new()
+                }
+            };
+            definition_sites.insert(path.clone(), sites);
+        }
+        if !sources.contains_key(&path) {
+            let source = match data.manager.read_source_code(&path, None, None).await {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    warn!("Failed to read {}: {}", path, e);
+                    None
+                }
+            };
+            sources.insert(path.clone(), source);
+        }
+
+        let start = location.to_lsp_range().start;
+        let kind = if definition_sites[&path].contains(&(start.line, start.character)) {
+            "definition"
+        } else {
+            sources[&path]
+                .as_deref()
+                .map_or("reference", |source| classify_usage(source, start))
+        };
+        usages.push(SymbolUsage {
+            location,
+            kind: kind.to_string(),
+        });
+    }
+
+    HttpResponse::Ok().json(SymbolUsagesResponse { usages })
+}
+
+/// Classifies the usage of the identifier at `position` from the surrounding text.
+fn classify_usage(content: &str, position: LspPosition) -> &'static str {
+    let offset = position_to_offset(content, position);
+    let line_start = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |index| offset + index);
+    let line = content[line_start..line_end].trim_start();
+
+    let is_import = ["import ", "from ", "use ", "#include"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || line.contains("require(")
+        || (line.starts_with("export ") && line.contains(" from "));
+    if is_import {
+        return "import";
+    }
+
+    let identifier_end = word_range_at(content, position)
+        .map_or(offset, |range| position_to_offset(content, range.end));
+    if content[identifier_end..line_end]
+        .trim_start()
+        .starts_with('(')
+    {
+        return "call";
+    }
+
+    let before = content[line_start..offset].trim_end();
+    if before.ends_with("->") || (before.ends_with(':') && !before.ends_with("::")) {
+        return "type-annotation";
+    }
+    "reference"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[test]
+    fn test_classify_usage() {
+        let cases = [
+            ("from graph import AStarGraph", 18, "import"),
+            ("import { AStar } from './astar';", 9, "import"),
+            ("use crate::astar::AStar;", 18, "import"),
+            ("    graph = AStarGraph()", 12, "call"),
+            ("    graph = AStarGraph ()", 12, "call"),
+            (
+                "def plot_path(path, graph: AStarGraph) -> None:",
+                27,
+                "type-annotation",
+            ),
+            ("def search() -> AStarGraph:", 16, "type-annotation"),
+            ("    let x = astar::AStar::new();", 19, "reference"),
+            ("    graphs = [AStarGraph]", 14, "reference"),
+            ("export const graph = new AStarGraph", 25, "reference"),
+        ];
+        for (line, character, expected) in cases {
+            assert_eq!(
+                classify_usage(line, LspPosition::new(0, character)),
+                expected,
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_python_symbol_usages() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The `AStarGraph` class declaration
+        let response = find_symbol_usages(
+            state,
+            Json(FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: SymbolUsagesResponse = serde_json::from_slice(&bytes)?;
+        let usage_kind = |path: &str, line: u32| {
+            response
+                .usages
+                .iter()
+                .find(|usage| {
+                    usage.location.path == path && usage.location.range.start.line == line
+                })
+                .map(|usage| usage.kind.as_str())
+        };
+
+        assert_eq!(response.usages.len(), 7);
+        assert_eq!(usage_kind("graph.py", 12), Some("definition"));
+        assert_eq!(usage_kind("search.py", 1), Some("import"));
+        assert_eq!(usage_kind("search.py", 5), Some("type-annotation"));
+        assert_eq!(usage_kind("main.py", 1), Some("import"));
+        assert_eq!(usage_kind("main.py", 15), Some("call"));
+        Ok(())
+    }
+}
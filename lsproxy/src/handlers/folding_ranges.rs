@@ -0,0 +1,97 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FoldingRangesRequest, FoldingRangesResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the folding ranges of a file
+///
+/// Returns the foldable regions of a file (class and function bodies, import blocks, comments,
+/// etc.) as whole-line ranges, sorted by start line.
+///
+/// Currently supported for Python, TypeScript/JavaScript, and Rust.
+#[utoipa::path(
+    get,
+    path = "/symbol/folding-ranges",
+    tag = "symbol",
+    params(FoldingRangesRequest),
+    responses(
+        (status = 200, description = "Folding ranges retrieved successfully", body = FoldingRangesResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Folding ranges not supported for this language")
+    )
+)]
+pub async fn folding_ranges(
+    data: Data<AppState>,
+    info: Query<FoldingRangesRequest>,
+) -> HttpResponse {
+    info!(
+        "Received folding ranges request for file: {}",
+        info.file_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.folding_ranges(&info.file_path).await {
+        Ok(ranges) => HttpResponse::Ok().json(FoldingRangesResponse { ranges }),
+        Err(e) => {
+            error!("Failed to get folding ranges: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::FoldingRange;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_folding_ranges() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = folding_ranges(
+            state,
+            Query(FoldingRangesRequest {
+                file_path: String::from("graph.py"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let folding_response: FoldingRangesResponse = serde_json::from_slice(&bytes)?;
+
+        // The AStarGraph class body
+        assert!(folding_response.ranges.contains(&FoldingRange {
+            start_line: 12,
+            end_line: 88,
+            kind: None,
+        }));
+        assert!(folding_response
+            .ranges
+            .windows(2)
+            .all(|pair| pair[0].start_line <= pair[1].start_line));
+        Ok(())
+    }
+
+    #[test]
+    fn test_folding_ranges_query_alias() {
+        let request: Query<FoldingRangesRequest> = Query::from_query("file=src/astar.rs").unwrap();
+        assert_eq!(request.file_path, "src/astar.rs");
+    }
+}
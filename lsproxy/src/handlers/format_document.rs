@@ -0,0 +1,146 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FormatDocumentRequest, FormatResponse, TextEditEntry};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Format a file in the workspace
+///
+/// Returns the edits the language server would make to format the file.
+/// An empty list of edits means the file is already formatted.
+///
+/// When `apply` is true, the edits are also written to the file.
+///
+/// Currently supported for Python (using ruff), TypeScript/JavaScript, and Rust.
+#[utoipa::path(
+    post,
+    path = "/workspace/format-document",
+    tag = "workspace",
+    request_body = FormatDocumentRequest,
+    responses(
+        (status = 200, description = "Document formatted successfully", body = FormatResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Formatting not implemented for this language")
+    )
+)]
+pub async fn format_document(
+    data: Data<AppState>,
+    info: Json<FormatDocumentRequest>,
+) -> HttpResponse {
+    info!(
+        "Received format request for file: {}, apply: {}",
+        info.file_path, info.apply
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    let edits = match data
+        .manager
+        .format_document(
+            &info.file_path,
+            info.tab_size.unwrap_or(4),
+            info.insert_spaces.unwrap_or(true),
+        )
+        .await
+    {
+        Ok(edits) => edits,
+        Err(e) => {
+            error!("Failed to format document: {}", e);
+            return e.into_http_response();
+        }
+    };
+
+    if info.apply && !edits.is_empty() {
+        if let Err(e) = data.manager.apply_text_edits(&info.file_path, &edits).await {
+            error!("Failed to apply formatting edits: {}", e);
+            return e.into_http_response();
+        }
+    }
+
+    HttpResponse::Ok().json(FormatResponse {
+        edits: edits
+            .into_iter()
+            .map(|edit| TextEditEntry::from_lsp(&info.file_path, edit))
+            .collect(),
+        applied: info.apply,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{rust_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_format_document() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("unformatted.py");
+        std::fs::write(&file_path, "def greet( name ):\n  return 'hi '+name\n")?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = format_document(
+            state,
+            Json(FormatDocumentRequest {
+                file_path: String::from("unformatted.py"),
+                tab_size: None,
+                insert_spaces: None,
+                apply: true,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let format_response: FormatResponse = serde_json::from_slice(&bytes)?;
+        assert!(format_response.applied);
+        assert!(!format_response.edits.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(&file_path)?,
+            "def greet(name):\n    return \"hi \" + name\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rust_format_document_not_applied() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&rust_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+        let original = std::fs::read_to_string(format!("{}/src/point.rs", rust_sample_path()))?;
+
+        let response = format_document(
+            state,
+            Json(FormatDocumentRequest {
+                file_path: String::from("src/point.rs"),
+                tab_size: None,
+                insert_spaces: None,
+                apply: false,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let format_response: FormatResponse = serde_json::from_slice(&bytes)?;
+        assert!(!format_response.applied);
+        // The sample has trailing whitespace on the blank line inside `impl Add`
+        assert!(!format_response.edits.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(format!("{}/src/point.rs", rust_sample_path()))?,
+            original
+        );
+        Ok(())
+    }
+}
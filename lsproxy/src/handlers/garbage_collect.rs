@@ -0,0 +1,89 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::GarbageCollectResponse;
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Clear all caches
+///
+/// Drops the cached file contents, definitions, metrics and response ETags to free memory in
+/// long-running sessions, and asks the language servers to re-index the workspace files.
+/// Subsequent requests recompute their results from scratch. Requests in flight still reading a
+/// cache finish before it is cleared.
+#[utoipa::path(
+    post,
+    path = "/admin/gc",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Caches cleared successfully", body = GarbageCollectResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn garbage_collect(data: Data<AppState>) -> HttpResponse {
+    info!("Received garbage collection request");
+
+    let cleared_etags = std::mem::take(&mut *data.etags.lock().await).len();
+    match data.manager.clear_all_caches().await {
+        Ok(cleared_entries) => HttpResponse::Ok().json(GarbageCollectResponse {
+            cleared_entries: cleared_entries + cleared_etags,
+        }),
+        Err(e) => {
+            error!("Failed to clear caches: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use actix_web::web::Query;
+
+    use crate::api_types::FileSymbolsRequest;
+    use crate::handlers::definitions_in_file;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_garbage_collect() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let definitions = || async {
+            let response = definitions_in_file(
+                state.clone(),
+                Query(FileSymbolsRequest {
+                    file_path: String::from("graph.py"),
+                    kinds: None,
+                    include_comments: false,
+                }),
+                None,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap()
+        };
+        let before = definitions().await;
+
+        let response = garbage_collect(state.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let gc: GarbageCollectResponse = serde_json::from_slice(&bytes)?;
+        // At least the listed workspace files and the ETag of the definitions response
+        assert!(gc.cleared_entries > 1);
+        assert!(state.etags.lock().await.is_empty());
+
+        // Recomputed from scratch, with the same result
+        assert_eq!(definitions().await, before);
+        assert!(!state.etags.lock().await.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FilePosition, GetSymbolResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the symbol that contains a position (uses ast-grep)
+///
+/// Returns the innermost symbol (function, class, variable, etc.) whose definition contains the
+/// given position, or null if the position is outside every symbol.
+///
+/// e.g. for line 2, character 15 of `src/graph.py`, the `neighbors` method is returned rather
+/// than the `Graph` class around it:
+/// ```text
+/// 0: class Graph:
+/// 1:     def neighbors(self, node):
+/// 2:         return self.edges.get(node, [])
+/// ```
+#[utoipa::path(
+    post,
+    path = "/symbol/get-symbol-from-position",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Symbol retrieved successfully", body = GetSymbolResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_symbol_from_position(
+    data: Data<AppState>,
+    info: Json<FilePosition>,
+) -> HttpResponse {
+    info!(
+        "Received symbol from position request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .get_innermost_symbol_at_position(&info.path, &info.position.clone().into())
+        .await
+    {
+        Ok(symbol) => HttpResponse::Ok().json(GetSymbolResponse { symbol }),
+        Err(e) => {
+            error!("Failed to get symbol from position: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{
+        csharp_sample_path, java_sample_path, python_sample_path, rust_sample_path,
+        typescript_sample_path, TestContext,
+    };
+
+    async fn symbol_at(
+        sample_path: &str,
+        path: &str,
+        line: u32,
+        character: u32,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(sample_path, false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = get_symbol_from_position(
+            state,
+            Json(FilePosition {
+                path: String::from(path),
+                position: Position { line, character },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let symbol_response: GetSymbolResponse = serde_json::from_slice(&bytes)?;
+        Ok(symbol_response.symbol.map(|s| (s.name, s.kind)))
+    }
+
+    fn named(name: &str, kind: &str) -> Option<(String, String)> {
+        Some((String::from(name), String::from(kind)))
+    }
+
+    #[tokio::test]
+    async fn test_python_symbol_from_position() -> Result<(), Box<dyn std::error::Error>> {
+        // Inside the body of `AStarGraph.__init__`
+        let symbol = symbol_at(&python_sample_path(), "graph.py", 14, 8).await?;
+        assert_eq!(symbol, named("__init__", "function"));
+
+        // The imports aren't part of any symbol
+        let symbol = symbol_at(&python_sample_path(), "graph.py", 0, 0).await?;
+        assert_eq!(symbol, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_typescript_symbol_from_position() -> Result<(), Box<dyn std::error::Error>> {
+        // The closing line of the `Node` constructor
+        let symbol = symbol_at(&typescript_sample_path(), "src/node.ts", 7, 5).await?;
+        assert_eq!(symbol, named("constructor", "method"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rust_symbol_from_position() -> Result<(), Box<dyn std::error::Error>> {
+        // Inside `Point::new`, which is itself inside `impl Point`
+        let symbol = symbol_at(&rust_sample_path(), "src/point.rs", 8, 8).await?;
+        assert_eq!(symbol, named("new", "function"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_java_symbol_from_position() -> Result<(), Box<dyn std::error::Error>> {
+        // Inside `Node.compareTo`
+        let symbol = symbol_at(&java_sample_path(), "Node.java", 18, 10).await?;
+        assert_eq!(symbol, named("compareTo", "method"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csharp_symbol_from_position() -> Result<(), Box<dyn std::error::Error>> {
+        // On the first `for` of `AddNeighborsToOpenList`
+        let symbol = symbol_at(&csharp_sample_path(), "AStar.cs", 53, 12).await?;
+        assert_eq!(symbol, named("AddNeighborsToOpenList", "method"));
+        Ok(())
+    }
+}
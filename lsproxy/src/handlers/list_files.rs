@@ -1,29 +1,205 @@
-use actix_web::web::Data;
+use actix_web::http::header::IfNoneMatch;
+use actix_web::web::{Data, Header, Query};
 use actix_web::HttpResponse;
 use log::error;
 
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::api_types::{
+    get_mount_dir, ErrorCode, ErrorResponse, FileLanguage, FileSortOrder, GroupedFilesResponse,
+    LanguageFiles, ListFilesRequest, ListFilesResponse,
+};
 use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::etag::{cached_not_modified, etag_json_response, LIST_FILES_ETAG_KEY};
+use crate::utils::file_utils::detect_language;
 use crate::AppState;
 
 /// Get a list of all files in the workspace
 ///
 /// Returns an array of file paths for all files in the current workspace.
 ///
+/// When `language` is provided, only the files handled by that language's server are returned.
+///
+/// Set `include_metadata` to get a `ListFilesResponse` instead, with the size and last
+/// modification time of each file, e.g. to skip reading files that haven't changed. Set
+/// `include_language` to get `FileLanguage` entries instead, with the language of the server
+/// owning each file; files served by several servers, e.g. C headers, are only listed once.
+/// Set `grouped` to get a `GroupedFilesResponse` instead, with the files of each language in a
+/// group, e.g. all Python files before all TypeScript files.
+///
+/// Files are sorted by path unless `sort_by` is set to `name` (the file name, ignoring
+/// directories), `language` (grouped by language) or `size` (smallest first).
+///
 /// This is a convenience endpoint that does not use the underlying Language Servers directly, but it does apply the same filtering.
+///
+/// Responses carry an `ETag`; send it back as `If-None-Match` to get a 304 when nothing changed.
 #[utoipa::path(
     get,
     path = "/workspace/list-files",
     tag = "workspace",
+    params(ListFilesRequest),
     responses(
-        (status = 200, description = "Workspace files retrieved successfully", body = Vec<String>),
+        (status = 200, description = "Workspace files retrieved successfully, as a `ListFilesResponse` with `include_metadata`, `FileLanguage` entries with `include_language` or a `GroupedFilesResponse` with `grouped`", body = Vec<String>),
+        (status = 304, description = "Workspace files unchanged since the given ETag"),
         (status = 400, description = "Bad request"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub async fn list_files(data: Data<AppState>) -> HttpResponse {
-    let files = data.manager.list_files().await;
+pub async fn list_files(
+    data: Data<AppState>,
+    info: Query<ListFilesRequest>,
+    if_none_match: Option<Header<IfNoneMatch>>,
+) -> HttpResponse {
+    if info.include_metadata && info.include_language {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            String::from("include_metadata and include_language can't be combined"),
+        ));
+    }
+    if info.grouped && (info.include_metadata || info.include_language) {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            String::from("grouped can't be combined with include_metadata or include_language"),
+        ));
+    }
+
+    let mut etag_key = match info.language {
+        Some(language) => format!("{}:{}", LIST_FILES_ETAG_KEY, language),
+        None => LIST_FILES_ETAG_KEY.to_string(),
+    };
+    if info.include_metadata {
+        etag_key.push_str(":metadata");
+    }
+    if info.include_language {
+        etag_key.push_str(":language");
+    }
+    if info.grouped {
+        etag_key.push_str(":grouped");
+    }
+    if info.sort_by != FileSortOrder::Path {
+        etag_key.push_str(&format!(":{:?}", info.sort_by));
+    }
+    if let Some(response) = cached_not_modified(&data, &etag_key, &if_none_match).await {
+        return response;
+    }
+
+    if info.grouped {
+        let groups = match info.language {
+            Some(language) => data
+                .manager
+                .list_files_by_language(language)
+                .await
+                .map(|files| HashMap::from([(language, files)])),
+            None => data.manager.list_files_grouped().await,
+        };
+        return match groups {
+            Ok(groups) => {
+                let mount_dir = get_mount_dir();
+                let mut groups: Vec<LanguageFiles> = groups
+                    .into_iter()
+                    .map(|(language, mut files)| {
+                        sort_files(&mut files, info.sort_by, String::as_str, |file| {
+                            std::fs::metadata(mount_dir.join(file))
+                                .map_or(0, |metadata| metadata.len())
+                        });
+                        LanguageFiles { language, files }
+                    })
+                    .collect();
+                groups.sort_by_key(|group| group.language.to_string());
+                etag_json_response(
+                    &data,
+                    &etag_key,
+                    &if_none_match,
+                    &GroupedFilesResponse { groups },
+                )
+                .await
+            }
+            Err(e) => {
+                error!("Failed to get workspace files: {}", e);
+                e.into_http_response()
+            }
+        };
+    }
+
+    if info.include_metadata {
+        let files = match info.language {
+            Some(language) => {
+                data.manager
+                    .list_files_by_language_with_metadata(language)
+                    .await
+            }
+            None => data.manager.list_files_with_metadata().await,
+        };
+        return match files {
+            Ok(mut files) => {
+                sort_files(
+                    &mut files,
+                    info.sort_by,
+                    |file| &file.path,
+                    |file| file.size_bytes,
+                );
+                etag_json_response(
+                    &data,
+                    &etag_key,
+                    &if_none_match,
+                    &ListFilesResponse { files },
+                )
+                .await
+            }
+            Err(e) => {
+                error!("Failed to get workspace files: {}", e);
+                e.into_http_response()
+            }
+        };
+    }
+
+    if info.include_language {
+        let files = match info.language {
+            Some(language) => data
+                .manager
+                .list_files_by_language(language)
+                .await
+                .map(|files| files.into_iter().map(|path| (path, language)).collect()),
+            None => data.manager.list_files_with_owner().await,
+        };
+        return match files {
+            Ok(files) => {
+                let mut files: Vec<FileLanguage> = files
+                    .into_iter()
+                    .map(|(path, language)| FileLanguage { path, language })
+                    .collect();
+                let mount_dir = get_mount_dir();
+                sort_files(
+                    &mut files,
+                    info.sort_by,
+                    |file| &file.path,
+                    |file| {
+                        std::fs::metadata(mount_dir.join(&file.path))
+                            .map_or(0, |metadata| metadata.len())
+                    },
+                );
+                etag_json_response(&data, &etag_key, &if_none_match, &files).await
+            }
+            Err(e) => {
+                error!("Failed to get workspace files: {}", e);
+                e.into_http_response()
+            }
+        };
+    }
+
+    let files = match info.language {
+        Some(language) => data.manager.list_files_by_language(language).await,
+        None => data.manager.list_files().await,
+    };
     match files {
-        Ok(files) => HttpResponse::Ok().json(files),
+        Ok(mut files) => {
+            let mount_dir = get_mount_dir();
+            sort_files(&mut files, info.sort_by, String::as_str, |file| {
+                std::fs::metadata(mount_dir.join(file)).map_or(0, |metadata| metadata.len())
+            });
+            etag_json_response(&data, &etag_key, &if_none_match, &files).await
+        }
         Err(e) => {
             error!("Failed to get workspace files: {}", e);
             e.into_http_response()
@@ -31,21 +207,75 @@ pub async fn list_files(data: Data<AppState>) -> HttpResponse {
     }
 }
 
+/// Sorts files listed by path into `sort_by` order, breaking ties by path.
+fn sort_files<T>(
+    files: &mut [T],
+    sort_by: FileSortOrder,
+    path: impl Fn(&T) -> &str,
+    size: impl Fn(&T) -> u64,
+) {
+    match sort_by {
+        FileSortOrder::Path => {}
+        FileSortOrder::Name => files.sort_by_cached_key(|file| {
+            let path = path(file);
+            let name = Path::new(path).file_name().unwrap_or_default();
+            (name.to_string_lossy().into_owned(), path.to_string())
+        }),
+        // Files of no known language come last
+        FileSortOrder::Language => files.sort_by_cached_key(|file| {
+            let path = path(file);
+            let language = detect_language(path)
+                .ok()
+                .map(|language| language.to_string());
+            (language.is_none(), language, path.to_string())
+        }),
+        FileSortOrder::Size => {
+            files.sort_by_cached_key(|file| (size(file), path(file).to_string()))
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     use actix_web::http::StatusCode;
 
+    use crate::api_types::SupportedLanguages;
     use crate::initialize_app_state;
-    use crate::test_utils::{python_sample_path, TestContext};
+    use crate::test_utils::{
+        cpp_sample_path, csharp_sample_path, go_sample_path, java_sample_path, js_sample_path,
+        mocks::MockFile, php_sample_path, python_sample_path, ruby_sample_path, rust_sample_path,
+        typescript_sample_path, TestContext,
+    };
 
     #[tokio::test]
     async fn test_python_workspace_files() -> Result<(), Box<dyn std::error::Error>> {
-        let _context = TestContext::setup(&python_sample_path(), false).await?;
-        let state = initialize_app_state().await?;
+        let files = [
+            "__init__.py",
+            "decorators.py",
+            "graph.py",
+            "main.py",
+            "search.py",
+        ]
+        .into_iter()
+        .map(|path| MockFile::new(path, ""))
+        .collect();
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
 
-        let response = list_files(state).await;
+        let response = list_files(
+            state,
+            Query(ListFilesRequest {
+                language: None,
+                include_metadata: false,
+                include_language: false,
+                sort_by: FileSortOrder::Path,
+                grouped: false,
+            }),
+            None,
+        )
+        .await;
 
         assert_eq!(response.status(), StatusCode::OK);
         assert_eq!(
@@ -70,4 +300,386 @@ mod test {
         assert_eq!(workspace_files_response, expected);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_python_workspace_files_with_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("__init__.py", ""),
+            MockFile::new(
+                "decorators.py",
+                "def log_execution_time(func):\n    return func\n",
+            ),
+            MockFile::new("graph.py", "class AStarGraph:\n    pass\n"),
+            MockFile::new("main.py", "from graph import AStarGraph\n"),
+            MockFile::new("search.py", "def a_star_search():\n    pass\n"),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let response = list_files(
+            state,
+            Query(ListFilesRequest {
+                language: None,
+                include_metadata: true,
+                include_language: false,
+                sort_by: FileSortOrder::Path,
+                grouped: false,
+            }),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: ListFilesResponse = serde_json::from_slice(&bytes)?;
+        let paths: Vec<&str> = response
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "__init__.py",
+                "decorators.py",
+                "graph.py",
+                "main.py",
+                "search.py"
+            ]
+        );
+        for file in &response.files {
+            let metadata = std::fs::metadata(context.workspace_path().join(&file.path))?;
+            // The package marker is the only empty file
+            if file.path != "__init__.py" {
+                assert!(file.size_bytes > 0, "{} is empty", file.path);
+            }
+            assert_eq!(file.size_bytes, metadata.len());
+            assert!(file.last_modified > 0);
+        }
+        Ok(())
+    }
+
+    async fn sorted_files(
+        state: &Data<AppState>,
+        sort_by: FileSortOrder,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let response = list_files(
+            state.clone(),
+            Query(ListFilesRequest {
+                language: None,
+                include_metadata: false,
+                include_language: false,
+                sort_by,
+                grouped: false,
+            }),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    #[tokio::test]
+    async fn test_workspace_files_sorted() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("src/search.py", "def a_star_search():\n    pass\n"),
+            MockFile::new("src/graph.ts", "export class Graph {}\n"),
+            MockFile::new("tests/test_graph.py", "def test_graph():\n    pass\n"),
+            MockFile::new("main.py", ""),
+            MockFile::new("app.ts", "import { Graph } from './src/graph';\n"),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        context
+            .add_mock_client(SupportedLanguages::TypeScriptJavaScript)
+            .await?;
+        let state = context.app_state();
+
+        assert_eq!(
+            sorted_files(&state, FileSortOrder::Path).await?,
+            [
+                "app.ts",
+                "main.py",
+                "src/graph.ts",
+                "src/search.py",
+                "tests/test_graph.py"
+            ]
+        );
+        // The directories don't matter
+        assert_eq!(
+            sorted_files(&state, FileSortOrder::Name).await?,
+            [
+                "app.ts",
+                "src/graph.ts",
+                "main.py",
+                "src/search.py",
+                "tests/test_graph.py"
+            ]
+        );
+        // Python files before TypeScript ones
+        assert_eq!(
+            sorted_files(&state, FileSortOrder::Language).await?,
+            [
+                "main.py",
+                "src/search.py",
+                "tests/test_graph.py",
+                "app.ts",
+                "src/graph.ts"
+            ]
+        );
+        assert_eq!(
+            sorted_files(&state, FileSortOrder::Size).await?,
+            [
+                "main.py",
+                "src/graph.ts",
+                "tests/test_graph.py",
+                "src/search.py",
+                "app.ts"
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_workspace_files_with_language() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("graph.py", ""),
+            MockFile::new("src/graph.ts", ""),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        context
+            .add_mock_client(SupportedLanguages::TypeScriptJavaScript)
+            .await?;
+        let state = context.app_state();
+
+        let request = |include_metadata| {
+            Query(ListFilesRequest {
+                language: None,
+                include_metadata,
+                include_language: true,
+                sort_by: FileSortOrder::Path,
+                grouped: false,
+            })
+        };
+        let response = list_files(state.clone(), request(false), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let files: Vec<FileLanguage> = serde_json::from_slice(&bytes)?;
+        assert_eq!(
+            files,
+            [
+                FileLanguage {
+                    path: String::from("graph.py"),
+                    language: SupportedLanguages::Python,
+                },
+                FileLanguage {
+                    path: String::from("src/graph.ts"),
+                    language: SupportedLanguages::TypeScriptJavaScript,
+                },
+            ]
+        );
+
+        let response = list_files(state, request(true), None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_workspace_files_grouped() -> Result<(), Box<dyn std::error::Error>> {
+        use actix_web::{test, web, App};
+
+        let files = vec![
+            MockFile::new("src/search.py", ""),
+            MockFile::new("src/graph.ts", ""),
+            MockFile::new("main.py", ""),
+            MockFile::new("app.ts", ""),
+            MockFile::new("web/index.js", ""),
+        ];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        context
+            .add_mock_client(SupportedLanguages::TypeScriptJavaScript)
+            .await?;
+        let state = context.app_state();
+
+        let mut groups = state.manager.list_files_grouped().await?;
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups.remove(&SupportedLanguages::Python),
+            Some(vec![String::from("main.py"), String::from("src/search.py")])
+        );
+        assert_eq!(
+            groups.remove(&SupportedLanguages::TypeScriptJavaScript),
+            Some(vec![
+                String::from("app.ts"),
+                String::from("src/graph.ts"),
+                String::from("web/index.js")
+            ])
+        );
+
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .route("/workspace/list-files", web::get().to(list_files)),
+        )
+        .await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let response: GroupedFilesResponse =
+            test::call_and_read_body_json(&app, get("/workspace/list-files?grouped=true")).await;
+        assert_eq!(
+            response.groups,
+            [
+                LanguageFiles {
+                    language: SupportedLanguages::Python,
+                    files: vec![String::from("main.py"), String::from("src/search.py")],
+                },
+                LanguageFiles {
+                    language: SupportedLanguages::TypeScriptJavaScript,
+                    files: vec![
+                        String::from("app.ts"),
+                        String::from("src/graph.ts"),
+                        String::from("web/index.js")
+                    ],
+                },
+            ]
+        );
+
+        // A single group for a single language
+        let response: GroupedFilesResponse = test::call_and_read_body_json(
+            &app,
+            get("/workspace/list-files?grouped=true&language=typescript_javascript"),
+        )
+        .await;
+        assert_eq!(response.groups.len(), 1);
+        assert_eq!(
+            response.groups[0].language,
+            SupportedLanguages::TypeScriptJavaScript
+        );
+
+        // Not grouped by default
+        let files: Vec<String> =
+            test::call_and_read_body_json(&app, get("/workspace/list-files")).await;
+        assert_eq!(
+            files,
+            [
+                "app.ts",
+                "main.py",
+                "src/graph.ts",
+                "src/search.py",
+                "web/index.js"
+            ]
+        );
+
+        let response = test::call_service(
+            &app,
+            get("/workspace/list-files?grouped=true&include_language=true"),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_workspace_files_by_language() -> Result<(), Box<dyn std::error::Error>> {
+        for (sample_path, language) in [
+            (python_sample_path(), SupportedLanguages::Python),
+            (js_sample_path(), SupportedLanguages::TypeScriptJavaScript),
+            (
+                typescript_sample_path(),
+                SupportedLanguages::TypeScriptJavaScript,
+            ),
+            (rust_sample_path(), SupportedLanguages::Rust),
+            (cpp_sample_path(), SupportedLanguages::CPP),
+            (csharp_sample_path(), SupportedLanguages::CSharp),
+            (java_sample_path(), SupportedLanguages::Java),
+            (go_sample_path(), SupportedLanguages::Golang),
+            (php_sample_path(), SupportedLanguages::PHP),
+            (ruby_sample_path(), SupportedLanguages::Ruby),
+        ] {
+            let _context = TestContext::setup(&sample_path, false).await?;
+            let state = initialize_app_state().await?;
+
+            let response = list_files(
+                state,
+                Query(ListFilesRequest {
+                    language: Some(language),
+                    include_metadata: false,
+                    include_language: false,
+                    sort_by: FileSortOrder::Path,
+                    grouped: false,
+                }),
+                None,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response.into_body();
+            let bytes = actix_web::body::to_bytes(body).await.unwrap();
+            let files: Vec<String> = serde_json::from_slice(&bytes).unwrap();
+
+            assert!(!files.is_empty(), "no {} files listed", language);
+            for file in files {
+                assert_eq!(
+                    detect_language(&file).ok(),
+                    Some(language),
+                    "{} listed for {}",
+                    file,
+                    language
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_files_gzip_compression() -> Result<(), Box<dyn std::error::Error>> {
+        use actix_web::middleware::Compress;
+        use actix_web::{test, web, App};
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let app = test::init_service(
+            App::new()
+                .wrap(Compress::default())
+                .app_data(state)
+                .route("/workspace/list-files", web::get().to(list_files)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/workspace/list-files")
+            .insert_header(("Accept-Encoding", "gzip"))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("content-encoding").unwrap(), "gzip");
+
+        let compressed = test::read_body(response).await;
+        let mut decompressed = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed)?;
+        let mut files: Vec<String> = serde_json::from_str(&decompressed)?;
+        files.sort();
+
+        assert_eq!(
+            files,
+            [
+                "__init__.py",
+                "decorators.py",
+                "graph.py",
+                "main.py",
+                "search.py"
+            ]
+        );
+        Ok(())
+    }
 }
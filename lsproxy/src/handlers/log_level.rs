@@ -0,0 +1,127 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+use tracing_subscriber::EnvFilter;
+
+use crate::api_types::{ErrorCode, ErrorResponse, LogLevelRequest};
+use crate::AppState;
+
+/// Change the log level
+///
+/// Replaces the log filter, which is initially read from `RUST_LOG`, without restarting the
+/// server. The filter uses the same syntax, e.g. `lsproxy::lsp::client=debug,info` logs the
+/// language server client at debug level and everything else at info level.
+#[utoipa::path(
+    post,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 204, description = "Log filter changed successfully"),
+        (status = 400, description = "Invalid log filter"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "The log filter can't be changed in this server")
+    )
+)]
+pub async fn set_log_level(data: Data<AppState>, info: Json<LogLevelRequest>) -> HttpResponse {
+    info!("Received log level request with filter: {}", info.filter);
+
+    let Some(log_filter) = &data.log_filter else {
+        return HttpResponse::NotImplemented().json(ErrorResponse::new(
+            ErrorCode::NotImplemented,
+            String::from("The log filter can't be changed in this server"),
+        ));
+    };
+    let filter = match EnvFilter::try_new(&info.filter) {
+        Ok(filter) => filter,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::BadRequest,
+                format!("Invalid log filter '{}': {}", info.filter, e),
+            ));
+        }
+    };
+    match log_filter.reload(filter) {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            error!("Failed to change the log filter: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse::new(
+                ErrorCode::InternalError,
+                format!("Failed to change the log filter: {}", e),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use actix_web::http::StatusCode;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::reload;
+
+    use crate::lsp::manager::{Manager, ManagerConfig};
+    use crate::test_utils::LogBuffer;
+
+    #[tokio::test]
+    async fn test_set_log_level() -> Result<(), Box<dyn std::error::Error>> {
+        let (filter_layer, log_filter) = reload::Layer::new(EnvFilter::new("info"));
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::registry().with(filter_layer).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir()?;
+        let manager = Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        let state = Data::new(AppState {
+            manager: Arc::new(manager),
+            etags: Default::default(),
+            log_filter: Some(log_filter),
+        });
+        let set_filter = |filter: &str| {
+            set_log_level(
+                state.clone(),
+                Json(LogLevelRequest {
+                    filter: String::from(filter),
+                }),
+            )
+        };
+
+        let response = set_filter("error").await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        tracing::debug!("hidden at error level");
+        assert!(!logs.contains("hidden at error level"));
+
+        let response = set_filter("debug").await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        tracing::debug!("shown at debug level");
+        assert!(logs.contains("shown at debug level"));
+
+        let response = set_filter("lsproxy=verbose").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        tracing::debug!("still shown after an invalid filter");
+        assert!(logs.contains("still shown after an invalid filter"));
+
+        let state = Data::new(AppState {
+            manager: Arc::clone(&state.manager),
+            etags: Default::default(),
+            log_filter: None,
+        });
+        let response = set_log_level(
+            state,
+            Json(LogLevelRequest {
+                filter: String::from("debug"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+        Ok(())
+    }
+}
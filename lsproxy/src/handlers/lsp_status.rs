@@ -0,0 +1,53 @@
+use crate::api_types::LspStatusResponse;
+use crate::AppState;
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+
+/// Get the state of the running language servers
+///
+/// Returns the process ID, uptime and restart count of each language server client.
+#[utoipa::path(
+    get,
+    path = "/admin/lsp-status",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Language server status retrieved successfully", body = LspStatusResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn lsp_status(data: Data<AppState>) -> HttpResponse {
+    let clients = data.manager.lsp_status().await;
+    HttpResponse::Ok().json(LspStatusResponse { clients })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_lsp_status() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = lsp_status(state).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let status: LspStatusResponse = serde_json::from_slice(&bytes)?;
+
+        assert_eq!(status.clients.len(), 1);
+        let client = &status.clients[0];
+        assert_eq!(client.language, "python");
+        assert_eq!(client.status, "running");
+        assert!(client.pid.is_some());
+        assert_eq!(client.restart_count, 0);
+        Ok(())
+    }
+}
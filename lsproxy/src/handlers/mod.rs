@@ -1,15 +1,70 @@
+mod all_references_in_file;
+mod all_symbols;
+mod apply_edit;
+mod bulk_reference_count;
+mod callers_tree;
+mod check_consistency;
+mod code_actions;
+mod code_lens;
+mod coupling_analysis;
 mod definitions_in_file;
+mod definitions_in_range;
+mod detect_language;
+mod document_links;
+mod enclosing_chain;
 mod error;
+mod etag;
+mod explain_symbol;
+mod export_graph_dot;
+mod file_metrics;
+mod find_all_definitions;
 mod find_definition;
+mod find_definition_chain;
 mod find_identifier;
 mod find_referenced_symbols;
+mod find_referenced_symbols_in_range;
 mod find_references;
+mod find_symbol;
+mod find_symbol_usages;
+mod folding_ranges;
+mod format_document;
+mod garbage_collect;
+mod get_symbol_from_position;
 mod health;
 mod list_files;
+mod log_level;
+mod lsp_status;
+mod open_file;
+mod prepare_rename;
 mod read_source_code;
+mod reference_count;
+mod rename_file;
+mod rename_symbol;
+mod restart_lsp;
+mod search_text;
+mod selection_ranges;
+mod semantic_tokens;
+mod set_lsp_trace;
+mod symbol_change_impact;
+mod symbol_counts;
+mod symbol_docs;
+mod test_symbols;
+mod unused_imports;
+mod write_source_code;
 
 mod utils;
+pub(crate) use self::etag::{definitions_in_file_etag_key, LIST_FILES_ETAG_KEY};
 pub use self::{
-    definitions_in_file::*, find_definition::*, find_identifier::*, find_referenced_symbols::*,
-    find_references::*, health::*, list_files::*, read_source_code::*,
+    all_references_in_file::*, all_symbols::*, apply_edit::*, bulk_reference_count::*,
+    callers_tree::*, check_consistency::*, code_actions::*, code_lens::*, coupling_analysis::*,
+    definitions_in_file::*, definitions_in_range::*, detect_language::*, document_links::*,
+    enclosing_chain::*, explain_symbol::*, export_graph_dot::*, file_metrics::*,
+    find_all_definitions::*, find_definition::*, find_definition_chain::*, find_identifier::*,
+    find_referenced_symbols::*, find_referenced_symbols_in_range::*, find_references::*,
+    find_symbol::*, find_symbol_usages::*, folding_ranges::*, format_document::*,
+    garbage_collect::*, get_symbol_from_position::*, health::*, list_files::*, log_level::*,
+    lsp_status::*, open_file::*, prepare_rename::*, read_source_code::*, reference_count::*,
+    rename_file::*, rename_symbol::*, restart_lsp::*, search_text::*, selection_ranges::*,
+    semantic_tokens::*, set_lsp_trace::*, symbol_change_impact::*, symbol_counts::*,
+    symbol_docs::*, test_symbols::*, unused_imports::*, write_source_code::*,
 };
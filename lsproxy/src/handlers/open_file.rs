@@ -0,0 +1,91 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::OpenFileRequest;
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Open a file in its language server
+///
+/// Sends a `textDocument/didOpen` notification with the file's current content, unless the file
+/// is already open. Some language servers, such as Java's JDT LS, only give accurate results for
+/// open files. Java files are opened automatically before looking up definitions.
+#[utoipa::path(
+    put,
+    path = "/workspace/open-file",
+    tag = "workspace",
+    request_body = OpenFileRequest,
+    responses(
+        (status = 200, description = "File opened successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn open_file(data: Data<AppState>, info: Json<OpenFileRequest>) -> HttpResponse {
+    info!("Received open file request for file: {}", info.file_path);
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.open_file(&info.file_path).await {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(e) => {
+            error!("Failed to open file: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::{GotoDefinitionResponse, Position};
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{java_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_java_open_file_then_find_definition() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&java_sample_path(), true).await?;
+        let state = initialize_app_state().await?;
+
+        let response = open_file(
+            state.clone(),
+            Json(OpenFileRequest {
+                file_path: String::from("Main.java"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Opening again is a no-op
+        let response = open_file(
+            state.clone(),
+            Json(OpenFileRequest {
+                file_path: String::from("Main.java"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // `findPathTo` in `List<Node> path = as.findPathTo(7, 7);`
+        let definition = state
+            .manager
+            .find_definition("Main.java", Position::new(19, 29))
+            .await?;
+        let locations = match definition {
+            GotoDefinitionResponse::Scalar(location) => vec![location],
+            GotoDefinitionResponse::Array(locations) => locations,
+            GotoDefinitionResponse::Link(_) => return Err("Unexpected location links".into()),
+        };
+        assert_eq!(locations.len(), 1);
+        assert!(locations[0].uri.path().ends_with("/AStar.java"));
+        assert_eq!(locations[0].range.start, Position::new(39, 22));
+        Ok(())
+    }
+}
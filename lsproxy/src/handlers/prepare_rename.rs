@@ -0,0 +1,144 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorCode, ErrorResponse, FilePosition, PrepareRenameResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Check whether the symbol at a position can be renamed
+///
+/// Returns the range of the text that a rename would replace and the current name, which can be
+/// used as the default for the new name. Returns 400 if the language server indicates that
+/// nothing can be renamed at the position, e.g. on a keyword or in whitespace.
+///
+/// Currently supported for Rust, TypeScript/JavaScript and Python.
+#[utoipa::path(
+    post,
+    path = "/symbol/prepare-rename",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Symbol can be renamed", body = PrepareRenameResponse),
+        (status = 400, description = "Symbol cannot be renamed at this position"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Prepare rename not supported for this language")
+    )
+)]
+pub async fn prepare_rename(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received prepare rename request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .prepare_rename(&info.path, info.position.clone().into())
+        .await
+    {
+        Ok(Some(response)) => HttpResponse::Ok().json(response),
+        Ok(None) => HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            format!(
+                "Cannot rename at {}:{}:{}",
+                info.path, info.position.line, info.position.character
+            ),
+        )),
+        Err(e) => {
+            error!("Failed to prepare rename: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, typescript_sample_path, TestContext};
+
+    fn file_position(path: &str, line: u32, character: u32) -> Json<FilePosition> {
+        Json(FilePosition {
+            path: String::from(path),
+            position: Position { line, character },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_python_prepare_rename_imported_name() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `AStarGraph` in `from graph import AStarGraph`
+        let response = prepare_rename(state, file_position("main.py", 1, 20)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let prepare_response: PrepareRenameResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(
+            prepare_response,
+            PrepareRenameResponse {
+                range: FileRange {
+                    path: String::from("main.py"),
+                    range: Range {
+                        start: Position {
+                            line: 1,
+                            character: 18,
+                        },
+                        end: Position {
+                            line: 1,
+                            character: 28,
+                        },
+                    },
+                },
+                placeholder: String::from("AStarGraph"),
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_prepare_rename_keyword() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `from` in `from graph import AStarGraph`
+        let response = prepare_rename(state, file_position("main.py", 1, 2)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_typescript_prepare_rename() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&typescript_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `AStar` in `import { AStar } from './astar';`
+        let response = prepare_rename(state.clone(), file_position("src/main.ts", 0, 10)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let prepare_response: PrepareRenameResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(prepare_response.placeholder, "AStar");
+        assert_eq!(prepare_response.range.path, "src/main.ts");
+        assert_eq!(prepare_response.range.range.start.line, 0);
+
+        // `import` keyword
+        let response = prepare_rename(state, file_position("src/main.ts", 0, 2)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -2,10 +2,12 @@ use crate::api_types::{ErrorResponse, ReadSourceCodeRequest};
 use actix_web::web::{Data, Json};
 use actix_web::HttpResponse;
 use log::{error, info};
-use lsp_types::{Position as LspPosition, Range as LspRange};
+use lsp_types::Range as LspRange;
 use serde::Serialize;
 use utoipa::ToSchema;
 
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::utils::workspace_documents::max_source_code_bytes;
 use crate::AppState;
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -15,7 +17,9 @@ pub struct ReadSourceCodeResponse {
 
 /// Read source code from a file in the workspace
 ///
-/// Returns the contents of the specified file.
+/// Returns the contents of the specified file. Files larger than `MAX_SOURCE_CODE_BYTES` (5 MB by
+/// default) are cut off at that size and end with a `// ... truncated` marker. A range is read
+/// from the whole file, and only the returned code is cut off at that size.
 #[utoipa::path(
     post,
     path = "/workspace/read-source-code",
@@ -33,26 +37,24 @@ pub async fn read_source_code(
 ) -> HttpResponse {
     info!("Reading source code from file: {}", info.path);
 
-    let lsp_range = info.range.as_ref().map(|range| {
-        LspRange::new(
-            LspPosition {
-                line: range.start.line,
-                character: range.start.character,
-            },
-            LspPosition {
-                line: range.end.line,
-                character: range.end.character,
-            },
-        )
-    });
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    let lsp_range = info.range.clone().map(LspRange::from);
 
-    match data.manager.read_source_code(&info.path, lsp_range).await {
+    match data
+        .manager
+        .read_source_code(&info.path, lsp_range, Some(max_source_code_bytes()))
+        .await
+    {
         Ok(source_code) => HttpResponse::Ok().json(ReadSourceCodeResponse { source_code }),
         Err(e) => {
             error!("Failed to read source code: {:?}", e);
-            HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Failed to read source code: {}", e),
-            })
+            HttpResponse::InternalServerError().json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to read source code: {}", e),
+            ))
         }
     }
 }
@@ -0,0 +1,85 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{FilePosition, ReferenceCountResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Count the references to a symbol
+///
+/// Returns how many times the symbol at the given position is referenced across the workspace,
+/// excluding its declaration. This is cheaper to transfer than `find-references` when only
+/// the usage count matters, e.g. for ranking symbols.
+#[utoipa::path(
+    post,
+    path = "/symbol/reference-count",
+    tag = "symbol",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Reference count retrieved successfully", body = ReferenceCountResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn reference_count(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received reference count request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .get_symbol_references_count(&info.path, info.position.clone().into())
+        .await
+    {
+        Ok(count) => HttpResponse::Ok().json(ReferenceCountResponse { count }),
+        Err(e) => {
+            error!("Failed to count references: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_reference_count() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The `AStarGraph` class declaration
+        let response = reference_count(
+            state,
+            Json(FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 12,
+                    character: 6,
+                },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let count_response: ReferenceCountResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(count_response, ReferenceCountResponse { count: 6 });
+        Ok(())
+    }
+}
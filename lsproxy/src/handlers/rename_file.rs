@@ -0,0 +1,140 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ApplyEditResponse, RenameFileRequest};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::{validate_file_in_workspace, validate_file_path};
+use crate::AppState;
+
+/// Rename a file in the workspace
+///
+/// Before the file is moved, its language server is asked for the edits the rename requires,
+/// e.g. updating the imports of the file, and those edits are applied. Returns the files the
+/// edits modified.
+///
+/// Servers that don't support `workspace/willRenameFiles` make no edits, so only the file is
+/// moved. The new path must not exist yet.
+#[utoipa::path(
+    post,
+    path = "/workspace/rename-file",
+    tag = "workspace",
+    request_body = RenameFileRequest,
+    responses(
+        (status = 200, description = "File renamed successfully", body = ApplyEditResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn rename_file(data: Data<AppState>, info: Json<RenameFileRequest>) -> HttpResponse {
+    info!(
+        "Received rename file request from {} to {}",
+        info.old_path, info.new_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.old_path, &data.manager).await {
+        return response;
+    }
+    if let Err(response) = validate_file_path(&info.new_path) {
+        return response;
+    }
+
+    match data
+        .manager
+        .rename_file(&info.old_path, &info.new_path)
+        .await
+    {
+        Ok(modified_files) => HttpResponse::Ok().json(ApplyEditResponse { modified_files }),
+        Err(e) => {
+            error!("Failed to rename file: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::{Position, Range, TextEdit, WorkspaceEdit};
+
+    use crate::api_types::SupportedLanguages;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    fn files() -> Vec<MockFile> {
+        vec![
+            MockFile::new("graph.py", "class AStarGraph:\n    pass\n"),
+            MockFile::new("main.py", "from graph import AStarGraph\n\nAStarGraph()\n"),
+        ]
+    }
+
+    fn request(old_path: &str, new_path: &str) -> Json<RenameFileRequest> {
+        Json(RenameFileRequest {
+            old_path: String::from(old_path),
+            new_path: String::from(new_path),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_python_rename_file_updates_imports() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context =
+            TestContext::setup_mock_with(SupportedLanguages::Python, files(), |client| {
+                // The edit a language server computes for renaming graph.py to graph2.py
+                let import = client.location("main.py", Position::new(0, 5), Position::new(0, 10));
+                client.with_will_rename_files_edit(WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        import.uri,
+                        vec![TextEdit {
+                            range: Range::new(import.range.start, import.range.end),
+                            new_text: String::from("graph2"),
+                        }],
+                    )])),
+                    ..Default::default()
+                })
+            })
+            .await?;
+        let state = context.app_state();
+
+        let response = rename_file(state.clone(), request("graph.py", "graph2.py")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: ApplyEditResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(response.modified_files, vec![String::from("main.py")]);
+
+        let workspace = context.workspace_path();
+        assert!(!workspace.join("graph.py").exists());
+        assert!(workspace.join("graph2.py").exists());
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("main.py"))?,
+            "from graph2 import AStarGraph\n\nAStarGraph()\n"
+        );
+        assert_eq!(
+            state.manager.list_files().await?,
+            vec![String::from("graph2.py"), String::from("main.py")]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_file_invalid_request() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files()).await?;
+        let state = context.app_state();
+
+        let response = rename_file(state.clone(), request("missing.py", "graph2.py")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = rename_file(state.clone(), request("graph.py", "../graph.py")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The new path already exists
+        let response = rename_file(state, request("graph.py", "main.py")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(context.workspace_path().join("graph.py").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{
+    ErrorCode, ErrorResponse, RenameSymbolQuery, RenameSymbolRequest, RenameSymbolResponse,
+};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Rename the symbol at a position across the workspace
+///
+/// Returns the edits the language server computes for the rename. With the default
+/// `dry_run=true` nothing is modified, so the edits can be reviewed first; only
+/// `dry_run=false` applies them and returns the modified files. Use `prepare-rename` beforehand
+/// to check that the symbol can be renamed and to get its current name.
+///
+/// The new name must be a valid identifier of the file's language, e.g. not a keyword. Returns
+/// 400 if it isn't, or if nothing can be renamed at the position.
+///
+/// Currently supported for Rust, TypeScript/JavaScript and Python.
+#[utoipa::path(
+    post,
+    path = "/symbol/rename",
+    tag = "symbol",
+    params(RenameSymbolQuery),
+    request_body = RenameSymbolRequest,
+    responses(
+        (status = 200, description = "Rename computed, and applied unless dry_run", body = RenameSymbolResponse),
+        (status = 400, description = "Invalid new name, or symbol cannot be renamed at this position"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Rename not supported for this language")
+    )
+)]
+pub async fn rename_symbol(
+    data: Data<AppState>,
+    query: Query<RenameSymbolQuery>,
+    info: Json<RenameSymbolRequest>,
+) -> HttpResponse {
+    let position = &info.identifier_position;
+    info!(
+        "Received rename request to {} for file: {}, line: {}, character: {}, dry run: {}",
+        info.new_name,
+        position.path,
+        position.position.line,
+        position.position.character,
+        query.dry_run
+    );
+
+    if let Err(response) = validate_file_in_workspace(&position.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .rename_symbol(
+            &position.path,
+            position.position.clone().into(),
+            &info.new_name,
+            query.dry_run,
+        )
+        .await
+    {
+        Ok(Some(response)) => HttpResponse::Ok().json(response),
+        Ok(None) => HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            format!(
+                "Cannot rename at {}:{}:{}",
+                position.path, position.position.line, position.position.character
+            ),
+        )),
+        Err(e) => {
+            error!("Failed to rename symbol: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::{Position, Range, TextEdit, WorkspaceEdit};
+
+    use crate::api_types::{FilePosition, SupportedLanguages};
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    const GRAPH: &str = "class AStarGraph:\n    pass\n";
+    const MAIN: &str = "from graph import AStarGraph\n\nAStarGraph()\n";
+
+    async fn setup() -> Result<TestContext, Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("graph.py", GRAPH),
+            MockFile::new("main.py", MAIN),
+        ];
+        let context = TestContext::setup_mock_with(SupportedLanguages::Python, files, |client| {
+            // The edit a language server computes for renaming `AStarGraph` to `PathGraph`
+            let edit = |file_path: &str, ranges: &[(u32, u32)]| {
+                let uri = client
+                    .location(file_path, Position::new(0, 0), Position::new(0, 0))
+                    .uri;
+                let edits = ranges
+                    .iter()
+                    .map(|&(line, character)| TextEdit {
+                        range: Range::new(
+                            Position::new(line, character),
+                            Position::new(line, character + 10),
+                        ),
+                        new_text: String::from("PathGraph"),
+                    })
+                    .collect();
+                (uri, edits)
+            };
+            let rename = WorkspaceEdit {
+                changes: Some(HashMap::from([
+                    edit("graph.py", &[(0, 6)]),
+                    edit("main.py", &[(0, 18), (2, 0)]),
+                ])),
+                ..Default::default()
+            };
+            client.with_rename("graph.py", Position::new(0, 6), rename)
+        })
+        .await?;
+        Ok(context)
+    }
+
+    fn request(new_name: &str, line: u32, character: u32) -> Json<RenameSymbolRequest> {
+        Json(RenameSymbolRequest {
+            identifier_position: FilePosition {
+                path: String::from("graph.py"),
+                position: crate::api_types::Position { line, character },
+            },
+            new_name: String::from(new_name),
+        })
+    }
+
+    async fn rename_response(response: HttpResponse) -> RenameSymbolResponse {
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rename_symbol_dry_run() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = setup().await?;
+
+        let response = rename_symbol(
+            context.app_state(),
+            Query(RenameSymbolQuery { dry_run: true }),
+            request("PathGraph", 0, 6),
+        )
+        .await;
+        let response = rename_response(response).await;
+        assert!(response.modified_files.is_empty());
+        assert_eq!(
+            response.edit.changes.keys().collect::<Vec<_>>(),
+            ["graph.py", "main.py"]
+        );
+        assert_eq!(response.edit.changes["main.py"].len(), 2);
+
+        // Nothing was written
+        let workspace = context.workspace_path();
+        assert_eq!(std::fs::read_to_string(workspace.join("graph.py"))?, GRAPH);
+        assert_eq!(std::fs::read_to_string(workspace.join("main.py"))?, MAIN);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_symbol_applies_edits() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = setup().await?;
+
+        let response = rename_symbol(
+            context.app_state(),
+            Query(RenameSymbolQuery { dry_run: false }),
+            request("PathGraph", 0, 6),
+        )
+        .await;
+        let response = rename_response(response).await;
+        assert_eq!(response.modified_files, ["graph.py", "main.py"]);
+
+        let workspace = context.workspace_path();
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("graph.py"))?,
+            "class PathGraph:\n    pass\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("main.py"))?,
+            "from graph import PathGraph\n\nPathGraph()\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_symbol_invalid_request() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = setup().await?;
+        let state = context.app_state();
+
+        // Not a Python identifier
+        for new_name in ["class", "2d", "path-graph", ""] {
+            let response = rename_symbol(
+                state.clone(),
+                Query(RenameSymbolQuery { dry_run: false }),
+                request(new_name, 0, 6),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", new_name);
+        }
+
+        // Nothing to rename
+        let response = rename_symbol(
+            state,
+            Query(RenameSymbolQuery { dry_run: true }),
+            request("PathGraph", 1, 4),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let workspace = context.workspace_path();
+        assert_eq!(std::fs::read_to_string(workspace.join("graph.py"))?, GRAPH);
+        Ok(())
+    }
+}
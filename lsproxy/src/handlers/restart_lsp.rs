@@ -0,0 +1,114 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorCode, ErrorResponse, RestartLspRequest};
+use crate::handlers::error::IntoHttpResponse;
+use crate::lsp::manager::LspManagerError;
+use crate::AppState;
+
+/// Restart a language server
+///
+/// Starts a new language server for the given language and stops the old one once the new one
+/// is initialized, e.g. to recover from a stale index or high memory usage. Requests for that
+/// language keep being served by the old server until then. If the new server fails to
+/// initialize, the old one keeps running.
+#[utoipa::path(
+    delete,
+    path = "/admin/restart-lsp",
+    tag = "admin",
+    params(RestartLspRequest),
+    responses(
+        (status = 204, description = "Language server restarted successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Language server is not running"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server did not initialize in time")
+    )
+)]
+pub async fn restart_lsp(data: Data<AppState>, info: Query<RestartLspRequest>) -> HttpResponse {
+    info!(
+        "Received restart request for the {} language server",
+        info.language.display_name()
+    );
+
+    match data.manager.restart_language_server(info.language).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(LspManagerError::LspClientNotFound(language)) => {
+            HttpResponse::NotFound().json(ErrorResponse::new(
+                ErrorCode::LspClientNotFound,
+                format!("No {} language server is running", language.display_name()),
+            ))
+        }
+        Err(e) => {
+            error!(
+                "Failed to restart the {} language server: {}",
+                info.language.display_name(),
+                e
+            );
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FileSymbolsRequest, SupportedLanguages};
+    use crate::handlers::definitions_in_file;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_restart_lsp() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+        let pid = state.manager.lsp_status().await[0].pid;
+
+        let response = restart_lsp(
+            state.clone(),
+            Query(RestartLspRequest {
+                language: SupportedLanguages::Python,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let status = &state.manager.lsp_status().await[0];
+        assert_eq!(status.restart_count, 1);
+        assert_ne!(status.pid, pid);
+
+        // The new server answers requests
+        let response = definitions_in_file(
+            state,
+            Query(FileSymbolsRequest {
+                file_path: String::from("graph.py"),
+                kinds: None,
+                include_comments: false,
+            }),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restart_lsp_not_running() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = restart_lsp(
+            state,
+            Query(RestartLspRequest {
+                language: SupportedLanguages::Java,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+}
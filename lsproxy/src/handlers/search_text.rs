@@ -0,0 +1,225 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorCode, ErrorResponse, TextSearchRequest, TextSearchResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Search the workspace files for literal text
+///
+/// A plain text search for when the language servers miss something, e.g. names built
+/// dynamically or mentioned in strings and comments. The query is matched literally, not as a
+/// regular expression, and matches are returned sorted by file path and position.
+///
+/// Only files the language servers handle are searched. Narrow them down with `file_pattern`,
+/// e.g. `**/*.py`.
+#[utoipa::path(
+    post,
+    path = "/workspace/search-text",
+    tag = "workspace",
+    request_body = TextSearchRequest,
+    responses(
+        (status = 200, description = "Text searched successfully", body = TextSearchResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn search_text(data: Data<AppState>, info: Json<TextSearchRequest>) -> HttpResponse {
+    info!("Received text search request for: {}", info.query);
+
+    if info.query.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::BadRequest,
+            String::from("Query must not be empty"),
+        ));
+    }
+
+    let file_pattern = match info
+        .file_pattern
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+    {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::BadRequest,
+                format!("Invalid file pattern: {}", e),
+            ));
+        }
+    };
+
+    match data
+        .manager
+        .search_text(
+            &info.query,
+            info.case_sensitive,
+            file_pattern.as_ref(),
+            info.max_results,
+        )
+        .await
+    {
+        Ok(matches) => HttpResponse::Ok().json(TextSearchResponse { matches }),
+        Err(e) => {
+            error!("Failed to search text: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{SupportedLanguages, TextSearchMatch};
+    use crate::initialize_app_state;
+    use crate::test_utils::{mocks::MockFile, python_sample_path, TestContext};
+
+    fn request(query: &str, case_sensitive: bool) -> TextSearchRequest {
+        TextSearchRequest {
+            query: query.to_string(),
+            case_sensitive,
+            file_pattern: None,
+            max_results: None,
+        }
+    }
+
+    async fn search(
+        data: Data<AppState>,
+        request: TextSearchRequest,
+    ) -> Result<TextSearchResponse, Box<dyn std::error::Error>> {
+        let response = search_text(data, Json(request)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn mock_files() -> Vec<MockFile> {
+        vec![
+            MockFile::new(
+                "search.py",
+                "import heapq\n\nheapq.heappush(queue, HEAPQ)\n",
+            ),
+            MockFile::new("pkg/util.py", "# no heapq here, only Heapq\n"),
+            MockFile::new("README.md", "heapq\n"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_search_text_heapq() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, mock_files()).await?;
+        let state = context.app_state();
+
+        let response = search(state.clone(), request("heapq", true)).await?;
+        let found: Vec<_> = response
+            .matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.line, m.character))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("pkg/util.py", 0, 5),
+                ("search.py", 0, 7),
+                ("search.py", 2, 0)
+            ]
+        );
+        assert_eq!(
+            response.matches[2],
+            TextSearchMatch {
+                path: String::from("search.py"),
+                line: 2,
+                character: 0,
+                line_text: String::from("heapq.heappush(queue, HEAPQ)"),
+            }
+        );
+
+        let response = search(state.clone(), request("heapq", false)).await?;
+        assert_eq!(response.matches.len(), 5);
+
+        let response = search(
+            state,
+            TextSearchRequest {
+                file_pattern: Some(String::from("s*.py")),
+                max_results: Some(2),
+                ..request("HEAPQ", false)
+            },
+        )
+        .await?;
+        let found: Vec<_> = response
+            .matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.line, m.character))
+            .collect();
+        assert_eq!(found, vec![("search.py", 0, 7), ("search.py", 2, 0)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_text_skips_unreadable_files() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, mock_files()).await?;
+        let state = context.app_state();
+        // Still listed, but gone from disk
+        std::fs::remove_file(context.workspace_path().join("pkg/util.py"))?;
+
+        let response = search(state, request("heapq", true)).await?;
+        let found: Vec<_> = response
+            .matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.line))
+            .collect();
+        assert_eq!(found, vec![("search.py", 0), ("search.py", 2)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_text_invalid_request() -> Result<(), Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, mock_files()).await?;
+        let state = context.app_state();
+
+        let response = search_text(state.clone(), Json(request("", false))).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = search_text(
+            state,
+            Json(TextSearchRequest {
+                file_pattern: Some(String::from("[")),
+                ..request("heapq", false)
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_search_text() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = search(state, request("import AStarGraph", true)).await?;
+        assert_eq!(
+            response.matches,
+            vec![
+                TextSearchMatch {
+                    path: String::from("main.py"),
+                    line: 1,
+                    character: 11,
+                    line_text: String::from("from graph import AStarGraph"),
+                },
+                TextSearchMatch {
+                    path: String::from("search.py"),
+                    line: 1,
+                    character: 11,
+                    line_text: String::from("from graph import AStarGraph"),
+                },
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+use lsp_types::Position as LspPosition;
+
+use crate::api_types::{SelectionRangesRequest, SelectionRangesResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the selection ranges for positions in a file
+///
+/// For each position, returns the innermost syntactic range containing it along with a chain
+/// of `parent` ranges that each enclose the previous one, e.g. word → expression → statement →
+/// block → function. This is what editors use for "expand selection".
+///
+/// Currently supported for TypeScript/JavaScript and Rust.
+#[utoipa::path(
+    post,
+    path = "/symbol/selection-ranges",
+    tag = "symbol",
+    request_body = SelectionRangesRequest,
+    responses(
+        (status = 200, description = "Selection ranges retrieved successfully", body = SelectionRangesResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Selection ranges not supported for this language")
+    )
+)]
+pub async fn selection_ranges(
+    data: Data<AppState>,
+    info: Json<SelectionRangesRequest>,
+) -> HttpResponse {
+    info!(
+        "Received selection ranges request for file: {}, {} positions",
+        info.file_path,
+        info.positions.len()
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    let positions = info
+        .positions
+        .iter()
+        .map(|position| LspPosition {
+            line: position.line,
+            character: position.character,
+        })
+        .collect();
+
+    match data
+        .manager
+        .selection_ranges(&info.file_path, positions)
+        .await
+    {
+        Ok(ranges) => HttpResponse::Ok().json(SelectionRangesResponse { ranges }),
+        Err(e) => {
+            error!("Failed to get selection ranges: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FilePosition, Position};
+    use crate::initialize_app_state;
+    use crate::test_utils::{typescript_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_typescript_selection_ranges() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&typescript_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The `g` in `this.g + this.h`
+        let position = Position {
+            line: 10,
+            character: 28,
+        };
+        let response = selection_ranges(
+            state,
+            Json(SelectionRangesRequest {
+                file_path: String::from("src/node.ts"),
+                positions: vec![position.clone()],
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let selection_response: SelectionRangesResponse = serde_json::from_slice(&bytes)?;
+        assert_eq!(selection_response.ranges.len(), 1);
+
+        let mut current = &selection_response.ranges[0];
+        assert_eq!(current.range.path, "src/node.ts");
+        assert!(current.range.contains(FilePosition {
+            path: String::from("src/node.ts"),
+            position,
+        }));
+
+        // Each parent encloses its child, ending at a range that spans the whole class
+        let mut depth = 0;
+        while let Some(parent) = current.parent.as_deref() {
+            let key = |p: &Position| (p.line, p.character);
+            assert!(key(&parent.range.range.start) <= key(&current.range.range.start));
+            assert!(key(&parent.range.range.end) >= key(&current.range.range.end));
+            current = parent;
+            depth += 1;
+        }
+        assert!(depth > 2);
+        assert_eq!(current.range.range.start.line, 0);
+        assert!(current.range.range.end.line >= 14);
+        Ok(())
+    }
+}
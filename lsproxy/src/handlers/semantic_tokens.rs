@@ -0,0 +1,49 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{SemanticTokensRequest, SemanticTokensResponse};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the semantic tokens of a file
+///
+/// Returns every token the language server classifies in the file, such as keywords, functions,
+/// and variables, with absolute positions. Token types and modifiers are named using the
+/// language server's legend.
+///
+/// Currently supported for Rust and TypeScript/JavaScript.
+#[utoipa::path(
+    post,
+    path = "/symbol/semantic-tokens",
+    tag = "symbol",
+    request_body = SemanticTokensRequest,
+    responses(
+        (status = 200, description = "Semantic tokens retrieved successfully", body = SemanticTokensResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Semantic tokens not supported for this language")
+    )
+)]
+pub async fn semantic_tokens(
+    data: Data<AppState>,
+    info: Json<SemanticTokensRequest>,
+) -> HttpResponse {
+    info!(
+        "Received semantic tokens request for file: {}",
+        info.file_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.semantic_tokens(&info.file_path).await {
+        Ok(tokens) => HttpResponse::Ok().json(SemanticTokensResponse { tokens }),
+        Err(e) => {
+            error!("Failed to get semantic tokens: {}", e);
+            e.into_http_response()
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorCode, ErrorResponse, SetLspTraceRequest};
+use crate::handlers::error::IntoHttpResponse;
+use crate::lsp::manager::LspManagerError;
+use crate::AppState;
+
+/// Trace a language server's protocol messages
+///
+/// Sends `$/setTrace` to the language server for the given language. With `messages` or
+/// `verbose`, the server reports the messages it handles, which are logged at debug level with
+/// the `lsp_trace` target, e.g. visible with the `lsp_trace=debug` log filter. `off` stops the
+/// trace.
+///
+/// Servers that don't support tracing ignore the request.
+#[utoipa::path(
+    post,
+    path = "/admin/set-lsp-trace",
+    tag = "admin",
+    request_body = SetLspTraceRequest,
+    responses(
+        (status = 204, description = "Trace level set successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "Language server is not running"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn set_lsp_trace(data: Data<AppState>, info: Json<SetLspTraceRequest>) -> HttpResponse {
+    info!(
+        "Received trace request for the {} language server: {:?}",
+        info.language, info.value
+    );
+
+    match data
+        .manager
+        .set_trace(info.language, info.value.into())
+        .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(LspManagerError::LspClientNotFound(language)) => {
+            HttpResponse::NotFound().json(ErrorResponse::new(
+                ErrorCode::LspClientNotFound,
+                format!("No {} language server is running", language),
+            ))
+        }
+        Err(e) => {
+            error!(
+                "Failed to set the trace level of the {} language server: {}",
+                info.language, e
+            );
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use tokio::time::{sleep, Duration};
+
+    use crate::api_types::{
+        FilePosition, GetDefinitionRequest, LspTraceValue, Position, SupportedLanguages,
+    };
+    use crate::handlers::find_definition;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, LogBuffer, TestContext};
+
+    #[tokio::test]
+    async fn test_python_verbose_trace() -> Result<(), Box<dyn std::error::Error>> {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = set_lsp_trace(
+            state.clone(),
+            Json(SetLspTraceRequest {
+                language: SupportedLanguages::Python,
+                value: LspTraceValue::Verbose,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        // Any request makes the server trace the messages it handles
+        let response = find_definition(
+            state.clone(),
+            Json(GetDefinitionRequest {
+                position: FilePosition {
+                    path: String::from("main.py"),
+                    position: Position {
+                        line: 1,
+                        character: 18,
+                    },
+                },
+                include_raw_response: false,
+                include_source_code: false,
+                fallback_to_ast_grep: false,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        sleep(Duration::from_millis(500)).await;
+        assert!(logs.contains("lsp_trace:"));
+
+        let response = set_lsp_trace(
+            state,
+            Json(SetLspTraceRequest {
+                language: SupportedLanguages::Rust,
+                value: LspTraceValue::Off,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ChangeImpactReport, FilePosition};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Estimate the blast radius of changing a symbol
+///
+/// The input position should point to the identifier of the symbol, e.g. a function name.
+/// Returns the symbols whose code references it directly, and the symbols referencing those in
+/// turn, two levels deep in total.
+///
+/// e.g. for `heuristic` in:
+/// ```python
+/// def initialize_search(start, end, graph):
+///     return graph.heuristic(start, end)   # direct caller
+///
+/// def a_star_search(start, end, graph):
+///     G, F = initialize_search(start, end, graph)   # transitive caller
+/// ```
+///
+/// References outside any symbol, such as module-level imports, aren't counted.
+#[utoipa::path(
+    post,
+    path = "/workspace/symbol-change-impact",
+    tag = "workspace",
+    request_body = FilePosition,
+    responses(
+        (status = 200, description = "Change impact estimated successfully", body = ChangeImpactReport),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn symbol_change_impact(data: Data<AppState>, info: Json<FilePosition>) -> HttpResponse {
+    info!(
+        "Received change impact request for file: {}, line: {}, character: {}",
+        info.path, info.position.line, info.position.character
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .symbol_change_impact(&info.path, info.to_lsp_position())
+        .await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            error!("Failed to estimate change impact: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::Position;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_symbol_change_impact() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `AStarGraph.heuristic`
+        let response = symbol_change_impact(
+            state,
+            Json(FilePosition {
+                path: String::from("graph.py"),
+                position: Position {
+                    line: 68,
+                    character: 8,
+                },
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let report: ChangeImpactReport = serde_json::from_slice(&bytes)?;
+        let names = |symbols: &[crate::api_types::Symbol]| -> Vec<(String, String)> {
+            symbols
+                .iter()
+                .map(|s| (s.identifier_position.path.clone(), s.name.clone()))
+                .collect()
+        };
+
+        assert_eq!(
+            names(&report.direct_callers),
+            vec![
+                (String::from("search.py"), String::from("initialize_search")),
+                (String::from("search.py"), String::from("a_star_search")),
+            ]
+        );
+        // `a_star_search` also calls `initialize_search`, but is already a direct caller
+        assert_eq!(
+            names(&report.transitive_callers),
+            vec![(String::from("main.py"), String::from("main"))]
+        );
+        assert_eq!(report.total_impact, 3);
+        Ok(())
+    }
+}
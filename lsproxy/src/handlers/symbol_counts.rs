@@ -0,0 +1,71 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::SymbolCountsResponse;
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Get the number of symbols defined in the workspace, by file and by kind
+///
+/// Returns the symbol counts of each file keyed by symbol kind (e.g. `function`, `class`), along
+/// with the totals for each kind across the whole workspace. Local variables aren't counted.
+///
+/// Counts are cached for 120 seconds, or until a file in the workspace changes.
+#[utoipa::path(
+    get,
+    path = "/workspace/symbol-counts",
+    tag = "workspace",
+    responses(
+        (status = 200, description = "Symbol counts retrieved successfully", body = SymbolCountsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn symbol_counts(data: Data<AppState>) -> HttpResponse {
+    info!("Received symbol counts request");
+
+    match data.manager.get_symbol_counts().await {
+        Ok(counts) => HttpResponse::Ok().json(counts),
+        Err(e) => {
+            error!("Failed to get symbol counts: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_symbol_counts() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = symbol_counts(state).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let counts: SymbolCountsResponse = serde_json::from_slice(&bytes)?;
+
+        assert!(counts.by_kind.get("function").copied().unwrap_or(0) >= 5);
+        assert!(!counts.by_kind.contains_key("local-variable"));
+        assert_eq!(counts.by_file.len(), 5);
+        for (kind, total) in &counts.by_kind {
+            let summed: usize = counts
+                .by_file
+                .iter()
+                .map(|file| file.counts.get(kind).copied().unwrap_or(0))
+                .sum();
+            assert_eq!(summed, *total, "by_kind total mismatch for {}", kind);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{SymbolDocsRequest, SymbolDocsResponse, SymbolWithDoc};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Get the documentation of every symbol in a file (uses ast-grep)
+///
+/// Finds the symbols defined in the file and asks the language server for the hover text of
+/// each, e.g. to generate API documentation. The hover text is usually markdown with the
+/// signature and doc comment of the symbol, e.g. `pub fn new(x: i32, y: i32) -> Self` followed
+/// by the doc comment of `Point::new`.
+///
+/// The symbols are looked up one after the other, so files with many symbols can take a while.
+#[utoipa::path(
+    get,
+    path = "/workspace/symbol-docs",
+    tag = "workspace",
+    params(SymbolDocsRequest),
+    responses(
+        (status = 200, description = "Symbol documentation retrieved successfully", body = SymbolDocsResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn symbol_docs(data: Data<AppState>, info: Query<SymbolDocsRequest>) -> HttpResponse {
+    info!("Received symbol docs request for file: {}", info.file_path);
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data
+        .manager
+        .get_hover_for_all_symbols(&info.file_path)
+        .await
+    {
+        Ok(hovers) => HttpResponse::Ok().json(SymbolDocsResponse {
+            symbols: hovers
+                .into_iter()
+                .map(|(symbol, documentation)| SymbolWithDoc {
+                    symbol,
+                    documentation,
+                })
+                .collect(),
+        }),
+        Err(e) => {
+            error!("Failed to get symbol docs: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{rust_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_rust_symbol_docs() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&rust_sample_path(), true).await?;
+        let state = initialize_app_state().await?;
+
+        let response = symbol_docs(
+            state,
+            Query(SymbolDocsRequest {
+                file_path: String::from("src/point.rs"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: SymbolDocsResponse = serde_json::from_slice(&bytes)?;
+        let documentation = |name: &str, kind: &str| {
+            response
+                .symbols
+                .iter()
+                .find(|doc| doc.symbol.name == name && doc.symbol.kind == kind)
+                .and_then(|doc| doc.documentation.clone())
+                .ok_or(format!("Missing documentation for {}", name))
+        };
+
+        // rust-analyzer shows the definition of each symbol
+        let point = documentation("Point", "struct")?;
+        assert!(point.contains("pub struct Point"), "{}", point);
+        let new = documentation("new", "function")?;
+        assert!(
+            new.contains("pub fn new(x: i32, y: i32) -> Self"),
+            "{}",
+            new
+        );
+
+        // The symbols are in the order they are defined
+        let lines: Vec<u32> = response
+            .symbols
+            .iter()
+            .map(|doc| doc.symbol.identifier_position.position.line)
+            .collect();
+        assert!(lines.windows(2).all(|pair| pair[0] <= pair[1]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_symbol_docs_file_not_found() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&rust_sample_path(), true).await?;
+        let state = initialize_app_state().await?;
+
+        let response = symbol_docs(
+            state,
+            Query(SymbolDocsRequest {
+                file_path: String::from("src/missing.rs"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
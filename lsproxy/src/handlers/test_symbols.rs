@@ -0,0 +1,135 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::SymbolResponse;
+use crate::handlers::error::IntoHttpResponse;
+use crate::AppState;
+
+/// Find the tests in the workspace (uses ast-grep)
+///
+/// Returns the tests marked by their framework, with the kind of the match:
+/// - `test-function`: pytest `test*` functions and Rust `#[test]` functions
+/// - `test-method`: Java methods annotated with `@Test`
+/// - `test-case`: TypeScript/JavaScript `it()` and `test()` calls, named by their description
+///
+/// Symbols following the naming conventions of their language are included too, with their
+/// usual kind, e.g. pytest `Test*` classes, JUnit `*Test` classes and Go `Test*` functions.
+#[utoipa::path(
+    get,
+    path = "/workspace/test-symbols",
+    tag = "workspace",
+    responses(
+        (status = 200, description = "Test symbols retrieved successfully", body = SymbolResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn test_symbols(data: Data<AppState>) -> HttpResponse {
+    info!("Received test symbols request");
+
+    match data.manager.find_test_symbols().await {
+        Ok(symbols) => HttpResponse::Ok().json(symbols),
+        Err(e) => {
+            error!("Failed to find test symbols: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::SupportedLanguages;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    async fn find_tests(
+        language: SupportedLanguages,
+        files: Vec<MockFile>,
+    ) -> Result<Vec<(String, String, String)>, Box<dyn std::error::Error>> {
+        let mut context = TestContext::setup_mock(language, files).await?;
+        let response = test_symbols(context.app_state()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let symbols: SymbolResponse = serde_json::from_slice(&bytes)?;
+        Ok(symbols
+            .into_iter()
+            .map(|symbol| (symbol.identifier_position.path, symbol.name, symbol.kind))
+            .collect())
+    }
+
+    fn test(path: &str, name: &str, kind: &str) -> (String, String, String) {
+        (path.to_string(), name.to_string(), kind.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_rust_test_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new(
+            "src/point.rs",
+            "pub fn distance(a: i32, b: i32) -> i32 {\n    (a - b).abs()\n}\n\n\
+             #[cfg(test)]\n\
+             mod tests {\n    use super::*;\n\n    \
+             fn helper() -> i32 {\n        1\n    }\n\n    \
+             #[test]\n    fn test_distance() {\n        assert_eq!(distance(1, helper()), 0);\n    }\n\n    \
+             #[tokio::test]\n    #[should_panic]\n    async fn async_distance() {}\n}\n",
+        )];
+        let tests = find_tests(SupportedLanguages::Rust, files).await?;
+        assert_eq!(
+            tests,
+            vec![
+                test("src/point.rs", "test_distance", "test-function"),
+                test("src/point.rs", "async_distance", "test-function"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_test_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![
+            MockFile::new("graph.py", "def test_helper_in_source():\n    pass\n"),
+            MockFile::new(
+                "tests/test_graph.py",
+                "import pytest\n\n\
+                 class TestGraph:\n    def test_empty(self):\n        pass\n\n\
+                 @pytest.mark.slow\ndef test_large():\n    pass\n\n\
+                 def build():\n    pass\n",
+            ),
+        ];
+        let tests = find_tests(SupportedLanguages::Python, files).await?;
+        assert_eq!(
+            tests,
+            vec![
+                test("graph.py", "test_helper_in_source", "test-function"),
+                test("tests/test_graph.py", "TestGraph", "class"),
+                test("tests/test_graph.py", "test_empty", "test-function"),
+                test("tests/test_graph.py", "test_large", "test-function"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_typescript_test_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new(
+            "src/graph.test.ts",
+            "describe('graph', () => {\n  it('finds a path', () => {});\n  \
+             test.skip(\"handles cycles\", () => {});\n  helper('not a test');\n});\n",
+        )];
+        let tests = find_tests(SupportedLanguages::TypeScriptJavaScript, files).await?;
+        assert_eq!(
+            tests,
+            vec![
+                test("src/graph.test.ts", "finds a path", "test-case"),
+                test("src/graph.test.ts", "handles cycles", "test-case"),
+            ]
+        );
+        Ok(())
+    }
+}
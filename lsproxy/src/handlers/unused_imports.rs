@@ -0,0 +1,128 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{UnusedImport, UnusedImportsRequest};
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::validate_file_in_workspace;
+use crate::AppState;
+
+/// Find the imports of a file that are never used (uses ast-grep)
+///
+/// Each imported name is resolved with the language server, and flagged when the file doesn't
+/// reference it beyond the import itself. e.g. `Set` is unused in:
+/// ```python
+/// from typing import List, Set
+///
+/// def first(items: List[int]) -> int:
+///     return items[0]
+/// ```
+///
+/// Only Python files are supported at the moment.
+#[utoipa::path(
+    get,
+    path = "/workspace/unused-imports",
+    tag = "workspace",
+    params(UnusedImportsRequest),
+    responses(
+        (status = 200, description = "Unused imports retrieved successfully", body = Vec<UnusedImport>),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 501, description = "Unused imports not supported for this language")
+    )
+)]
+pub async fn unused_imports(
+    data: Data<AppState>,
+    info: Query<UnusedImportsRequest>,
+) -> HttpResponse {
+    info!(
+        "Received unused imports request for file: {}",
+        info.file_path
+    );
+
+    if let Err(response) = validate_file_in_workspace(&info.file_path, &data.manager).await {
+        return response;
+    }
+
+    match data.manager.detect_unused_imports(&info.file_path).await {
+        Ok(unused_imports) => HttpResponse::Ok().json(unused_imports),
+        Err(e) => {
+            error!("Failed to detect unused imports: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::api_types::{FileRange, Position, Range};
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_unused_imports() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = unused_imports(
+            state.clone(),
+            Query(UnusedImportsRequest {
+                file_path: String::from("search.py"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let unused: Vec<UnusedImport> = serde_json::from_slice(&bytes)?;
+        // `from typing import Dict, Set, Tuple, List` only uses `Dict` and `List`
+        let import_range = FileRange {
+            path: String::from("search.py"),
+            range: Range {
+                start: Position {
+                    line: 0,
+                    character: 0,
+                },
+                end: Position {
+                    line: 0,
+                    character: 41,
+                },
+            },
+        };
+        assert_eq!(
+            unused,
+            vec![
+                UnusedImport {
+                    name: String::from("Set"),
+                    import_range: import_range.clone(),
+                },
+                UnusedImport {
+                    name: String::from("Tuple"),
+                    import_range,
+                },
+            ]
+        );
+
+        // Every import of main.py is used
+        let response = unused_imports(
+            state,
+            Query(UnusedImportsRequest {
+                file_path: String::from("main.py"),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let unused: Vec<UnusedImport> = serde_json::from_slice(&bytes)?;
+        assert!(unused.is_empty());
+        Ok(())
+    }
+}
@@ -1,4 +1,11 @@
-use crate::api_types::{FilePosition, Identifier};
+use actix_web::HttpResponse;
+
+use crate::api_types::{
+    get_mount_dir, ErrorCode, ErrorResponse, FilePosition, Identifier, SymbolKind, UnknownKind,
+};
+use crate::handlers::error::IntoHttpResponse;
+use crate::lsp::manager::Manager;
+use crate::utils::file_utils::sanitize_file_path;
 
 #[derive(Debug)]
 pub enum PositionError {
@@ -21,7 +28,7 @@ impl std::fmt::Display for PositionError {
 
 impl std::error::Error for PositionError {}
 
-pub(crate) async fn find_identifier_at_position<'a>(
+pub(crate) async fn find_identifier_at_position(
     identifiers: Vec<Identifier>,
     position: &FilePosition,
 ) -> Result<Identifier, PositionError> {
@@ -64,3 +71,283 @@ pub(crate) async fn find_identifier_at_position<'a>(
 
     Err(PositionError::IdentifierNotFound { closest })
 }
+
+/// Checks that `path` stays inside the workspace, rejecting path traversal attempts with a 400.
+#[allow(clippy::result_large_err)]
+pub(crate) fn validate_file_path(path: &str) -> Result<(), HttpResponse> {
+    sanitize_file_path(path, &get_mount_dir())
+        .map(|_| ())
+        .map_err(|e| {
+            HttpResponse::BadRequest().json(ErrorResponse::new(
+                ErrorCode::InvalidPath,
+                format!("Invalid file path: {}", e),
+            ))
+        })
+}
+
+/// Checks that `path` is one of the workspace files, so that requests for unknown files fail
+/// with a 400 before reaching the language servers.
+pub(crate) async fn validate_file_in_workspace(
+    path: &str,
+    manager: &Manager,
+) -> Result<(), HttpResponse> {
+    validate_file_path(path)?;
+    let files = manager
+        .list_files()
+        .await
+        .map_err(|e| e.into_http_response())?;
+    if files.iter().any(|file| file == path) {
+        Ok(())
+    } else {
+        Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+            ErrorCode::FileNotFound,
+            format!("File not found in workspace: {}", path),
+        )))
+    }
+}
+
+/// Parses a comma-separated list of symbol kinds, ignoring empty entries.
+pub(crate) fn parse_kinds(kinds: &str) -> Result<Vec<SymbolKind>, UnknownKind> {
+    kinds
+        .split(',')
+        .map(str::trim)
+        .filter(|kind| !kind.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Parses an optional `kinds` query parameter, returning a 400 response listing the valid kinds if
+/// any of them is unknown.
+#[allow(clippy::result_large_err)]
+pub(crate) fn parse_kinds_param(
+    kinds: Option<&str>,
+) -> Result<Option<Vec<SymbolKind>>, HttpResponse> {
+    kinds.map(parse_kinds).transpose().map_err(|e| {
+        HttpResponse::BadRequest().json(ErrorResponse::new(ErrorCode::BadRequest, e.to_string()))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use actix_web::web::{Json, Query};
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use crate::handlers::*;
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    const MISSING_FILE: &str = "missing.py";
+
+    #[test]
+    fn test_parse_kinds() {
+        assert_eq!(
+            parse_kinds("function, class,,local-variable"),
+            Ok(vec![
+                SymbolKind::Function,
+                SymbolKind::Class,
+                SymbolKind::LocalVariable
+            ])
+        );
+        assert_eq!(parse_kinds(""), Ok(vec![]));
+        assert_eq!(
+            parse_kinds("function,funtion"),
+            Err(UnknownKind(String::from("funtion")))
+        );
+        assert_eq!(
+            parse_kinds("Function"),
+            Err(UnknownKind(String::from("Function")))
+        );
+    }
+
+    fn request<T: DeserializeOwned>(value: serde_json::Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_handlers_reject_files_outside_workspace() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let file = json!({ "file_path": MISSING_FILE });
+        let position = json!({ "path": MISSING_FILE, "position": { "line": 0, "character": 0 } });
+        let range = json!({
+            "path": MISSING_FILE,
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 1, "character": 0 }
+            }
+        });
+
+        let responses = vec![
+            (
+                "code_actions",
+                code_actions(
+                    state.clone(),
+                    Json(request(
+                        json!({ "file_path": MISSING_FILE, "range": range }),
+                    )),
+                )
+                .await,
+            ),
+            (
+                "code_lens",
+                code_lens(state.clone(), Query(request(file.clone()))).await,
+            ),
+            (
+                "definitions_in_file",
+                definitions_in_file(state.clone(), Query(request(file.clone())), None).await,
+            ),
+            (
+                "definitions_in_range",
+                definitions_in_range(state.clone(), Json(request(range.clone()))).await,
+            ),
+            (
+                "document_links",
+                document_links(state.clone(), Query(request(file.clone()))).await,
+            ),
+            (
+                "find_definition",
+                find_definition(
+                    state.clone(),
+                    Json(request(json!({ "position": position }))),
+                )
+                .await,
+            ),
+            (
+                "find_identifier",
+                find_identifier(
+                    state.clone(),
+                    Json(request(
+                        json!({ "file_path": MISSING_FILE, "name": "main" }),
+                    )),
+                )
+                .await,
+            ),
+            (
+                "find_referenced_symbols",
+                find_referenced_symbols(
+                    state.clone(),
+                    Json(request(json!({ "identifier_position": position }))),
+                )
+                .await,
+            ),
+            (
+                "find_references",
+                find_references(
+                    state.clone(),
+                    Json(request(json!({ "identifier_position": position }))),
+                )
+                .await,
+            ),
+            (
+                "folding_ranges",
+                folding_ranges(state.clone(), Query(request(file.clone()))).await,
+            ),
+            (
+                "format_document",
+                format_document(state.clone(), Json(request(file.clone()))).await,
+            ),
+            (
+                "get_symbol_from_position",
+                get_symbol_from_position(state.clone(), Json(request(position.clone()))).await,
+            ),
+            (
+                "open_file",
+                open_file(state.clone(), Json(request(file.clone()))).await,
+            ),
+            (
+                "prepare_rename",
+                prepare_rename(state.clone(), Json(request(position.clone()))).await,
+            ),
+            (
+                "read_source_code",
+                read_source_code(
+                    state.clone(),
+                    Json(request(json!({ "path": MISSING_FILE }))),
+                )
+                .await,
+            ),
+            (
+                "selection_ranges",
+                selection_ranges(
+                    state.clone(),
+                    Json(request(
+                        json!({ "file_path": MISSING_FILE, "positions": [] }),
+                    )),
+                )
+                .await,
+            ),
+            (
+                "semantic_tokens",
+                semantic_tokens(state.clone(), Json(request(file.clone()))).await,
+            ),
+            (
+                "write_source_code",
+                write_source_code(
+                    state.clone(),
+                    Json(request(json!({ "file_path": MISSING_FILE, "content": "" }))),
+                )
+                .await,
+            ),
+        ];
+
+        for (handler, response) in responses {
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", handler);
+            let bytes = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            let error_response: ErrorResponse = serde_json::from_slice(&bytes)?;
+            assert_eq!(
+                error_response.error,
+                format!("File not found in workspace: {}", MISSING_FILE),
+                "{}",
+                handler
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_handlers_reject_path_traversal() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        for payload in [
+            "../../../../etc/passwd",
+            "..%2F..%2F..%2F..%2Fetc%2Fpasswd",
+            "main.py\0../../etc/passwd",
+        ] {
+            let responses = [
+                read_source_code(state.clone(), Json(request(json!({ "path": payload })))).await,
+                write_source_code(
+                    state.clone(),
+                    Json(request(json!({
+                        "file_path": payload,
+                        "content": "",
+                        "create_if_missing": true
+                    }))),
+                )
+                .await,
+            ];
+            for response in responses {
+                assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{:?}", payload);
+                let bytes = actix_web::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap();
+                let error_response: ErrorResponse = serde_json::from_slice(&bytes)?;
+                assert!(
+                    error_response.error.starts_with("Invalid file path: "),
+                    "{:?}: {}",
+                    payload,
+                    error_response.error
+                );
+            }
+        }
+        Ok(())
+    }
+}
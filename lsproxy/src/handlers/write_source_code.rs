@@ -0,0 +1,158 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::WriteSourceCodeRequest;
+use crate::handlers::error::IntoHttpResponse;
+use crate::handlers::utils::{validate_file_in_workspace, validate_file_path};
+use crate::AppState;
+
+/// Write source code to a file in the workspace
+///
+/// Replaces the contents of the specified file, creating it first when `create_if_missing` is set.
+///
+/// The language server is notified of the new content so that it's re-indexed immediately.
+/// Paths must be relative to the workspace root and must not contain `..`.
+#[utoipa::path(
+    post,
+    path = "/workspace/write-source-code",
+    tag = "workspace",
+    request_body = WriteSourceCodeRequest,
+    responses(
+        (status = 204, description = "Source code written successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn write_source_code(
+    data: Data<AppState>,
+    info: Json<WriteSourceCodeRequest>,
+) -> HttpResponse {
+    info!(
+        "Writing source code to file: {}, create if missing: {}",
+        info.file_path, info.create_if_missing
+    );
+
+    // New files can't be in the workspace yet, so only their path is checked
+    let validation = if info.create_if_missing {
+        validate_file_path(&info.file_path)
+    } else {
+        validate_file_in_workspace(&info.file_path, &data.manager).await
+    };
+    if let Err(response) = validation {
+        return response;
+    }
+
+    match data
+        .manager
+        .write_source_code(&info.file_path, &info.content, info.create_if_missing)
+        .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            error!("Failed to write source code: {}", e);
+            e.into_http_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use lsp_types::{GotoDefinitionResponse, Position};
+
+    use crate::api_types::SupportedLanguages;
+    use crate::initialize_app_state;
+    use crate::test_utils::{mocks::MockFile, TestContext};
+
+    #[tokio::test]
+    async fn test_python_write_new_file() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("main.py"), "print('hello')\n")?;
+        let _context = TestContext::setup(dir.path().to_str().unwrap(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let response = write_source_code(
+            state.clone(),
+            Json(WriteSourceCodeRequest {
+                file_path: String::from("utils/helpers.py"),
+                content: String::from(
+                    "def greet(name):\n    return \"hi \" + name\n\n\ngreet(\"lsproxy\")\n",
+                ),
+                create_if_missing: true,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert!(state
+            .manager
+            .list_files()
+            .await?
+            .contains(&String::from("utils/helpers.py")));
+        let symbols = state
+            .manager
+            .definitions_in_file_ast_grep("utils/helpers.py")
+            .await?;
+        assert!(symbols
+            .iter()
+            .any(|s| s.meta_variables.single.name.text == "greet"));
+
+        // The language server resolves the call to the new definition
+        let definition = state
+            .manager
+            .find_definition("utils/helpers.py", Position::new(4, 0))
+            .await?;
+        let locations = match definition {
+            GotoDefinitionResponse::Scalar(location) => vec![location],
+            GotoDefinitionResponse::Array(locations) => locations,
+            GotoDefinitionResponse::Link(_) => vec![],
+        };
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(0, 4));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_missing_file_without_create() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.py", "print('hello')\n")];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let response = write_source_code(
+            state,
+            Json(WriteSourceCodeRequest {
+                file_path: String::from("missing.py"),
+                content: String::new(),
+                create_if_missing: false,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(!context.workspace_path().join("missing.py").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_rejects_path_traversal() -> Result<(), Box<dyn std::error::Error>> {
+        let files = vec![MockFile::new("main.py", "print('hello')\n")];
+        let mut context = TestContext::setup_mock(SupportedLanguages::Python, files).await?;
+        let state = context.app_state();
+
+        let response = write_source_code(
+            state,
+            Json(WriteSourceCodeRequest {
+                file_path: String::from("../escaped.py"),
+                content: String::from("print('escaped')\n"),
+                create_if_missing: true,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let parent = context.workspace_path().parent().unwrap();
+        assert!(!parent.join("escaped.py").exists());
+        Ok(())
+    }
+}
@@ -1,18 +1,25 @@
 use actix_cors::Cors;
 mod middleware;
 use actix_web::{
-    web::{get, post, resource, scope, Data},
+    middleware::{Compress, Condition},
+    web::{delete, get, post, put, resource, scope, Data},
     App, HttpServer,
 };
-use api_types::{FindIdentifierRequest, IdentifierResponse};
+use api_types::{FindIdentifierRequest, FindIdentifierResponse};
 use handlers::{find_identifier, read_source_code};
 use log::{error, info, warn};
-use middleware::{validate_jwt_config, JwtMiddleware};
+use middleware::{
+    disconnect_timeout, is_compression_enabled, json_config, max_body_size, payload_config,
+    request_timeout, validate_jwt_config, JwtMiddleware, RequestIdMiddleware,
+};
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -22,17 +29,49 @@ mod handlers;
 mod lsp;
 mod utils;
 
+/// Exported for the `read_source_code_lines` benchmark only.
+#[doc(hidden)]
+pub use crate::utils::file_utils::read_file_lines;
+
 use crate::api_types::{
-    get_mount_dir, set_global_mount_dir, CodeContext, DefinitionResponse, ErrorResponse,
-    FilePosition, FileRange, FileSymbolsRequest, GetDefinitionRequest, GetReferencedSymbolsRequest,
-    GetReferencesRequest, HealthResponse, Position, ReferenceWithSymbolDefinitions,
-    ReferencedSymbolsResponse, ReferencesResponse, SupportedLanguages, Symbol, SymbolResponse,
+    get_mount_dir, set_global_mount_dir, AllReferencesInFileRequest, AllReferencesInFileResponse,
+    AllSymbolsRequest, ApplyEditResponse, CallTree, CallersTreeRequest, ChangeImpactReport,
+    CodeAction, CodeActionsRequest, CodeActionsResponse, CodeContext, CodeLens, CodeLensRequest,
+    CodeLensResponse, ConsistencyReport, ContextEntry, DefinitionChainRequest,
+    DefinitionChainResponse, DefinitionEntry, DefinitionResponse, DetectLanguageRequest,
+    DetectLanguageResponse, DocumentLink, DocumentLinksRequest, DocumentLinksResponse,
+    EnclosingChainResponse, ErrorResponse, ExplainSymbolResponse, FileCoupling, FileGroup,
+    FileLanguage, FileMetadata, FileMetrics, FileMetricsResponse, FilePosition, FileRange,
+    FileSortOrder, FileSymbolCounts, FileSymbolsRequest, FindAllDefinitionsRequest,
+    FindAllDefinitionsResponse, FindSymbolRequest, FoldingRange, FoldingRangesRequest,
+    FoldingRangesResponse, FormatDocumentRequest, FormatResponse, GarbageCollectResponse,
+    GetDefinitionRequest, GetReferencedSymbolsInRangeRequest, GetReferencedSymbolsRequest,
+    GetReferencesRequest, GetSymbolResponse, GroupedFilesResponse, HealthResponse, Identifier,
+    IdentifierWithDefinition, LanguageFiles, ListFilesRequest, ListFilesResponse, LogLevelRequest,
+    LspClientStatus, LspStatusResponse, LspTraceValue, OpenFileRequest, Position,
+    PrepareRenameResponse, ReferenceCount, ReferenceCountResponse, ReferenceWithSymbolDefinitions,
+    ReferencedSymbolsResponse, ReferencesResponse, RenameFileRequest, RenameSymbolQuery,
+    RenameSymbolRequest, RenameSymbolResponse, RestartLspRequest, ResultSource, SelectionRange,
+    SelectionRangesRequest, SelectionRangesResponse, SemanticToken, SemanticTokensRequest,
+    SemanticTokensResponse, SetLspTraceRequest, SupportedLanguages, Symbol, SymbolCountsResponse,
+    SymbolDocsRequest, SymbolDocsResponse, SymbolResponse, SymbolUsage, SymbolUsagesResponse,
+    SymbolWithDoc, TextEditEntry, TextSearchMatch, TextSearchRequest, TextSearchResponse,
+    UnusedImport, UnusedImportsRequest, WorkspaceEdit, WriteSourceCodeRequest,
 };
 use crate::handlers::{
-    definitions_in_file, find_definition, find_referenced_symbols, find_references, health_check,
-    list_files,
+    all_references_in_file, all_symbols, apply_edit, bulk_reference_count, callers_tree,
+    check_consistency, code_actions, code_lens, coupling_analysis, definitions_in_file,
+    definitions_in_range, detect_language, document_links, enclosing_chain, explain_symbol,
+    export_graph_dot, file_metrics, find_all_definitions, find_definition, find_definition_chain,
+    find_referenced_symbols, find_referenced_symbols_in_range, find_references, find_symbol,
+    find_symbol_usages, folding_ranges, format_document, garbage_collect, get_symbol_from_position,
+    health_check, list_files, lsp_status, open_file, prepare_rename, reference_count, rename_file,
+    rename_symbol, restart_lsp, search_text, selection_ranges, semantic_tokens, set_log_level,
+    set_lsp_trace, symbol_change_impact, symbol_counts, symbol_docs, test_symbols, unused_imports,
+    write_source_code,
 };
-use crate::lsp::manager::Manager;
+use crate::lsp::manager::{Manager, ManagerConfig};
+use crate::utils::file_utils::relative_path_string;
 // use crate::utils::doc_utils::make_code_sample;
 
 pub fn check_mount_dir() -> std::io::Result<()> {
@@ -56,11 +95,20 @@ pub fn check_mount_dir() -> std::io::Result<()> {
     components(
         schemas(
             FileSymbolsRequest,
+            ListFilesRequest,
+            ListFilesResponse,
+            FileMetadata,
+            FileSortOrder,
+            FileLanguage,
+            GroupedFilesResponse,
+            LanguageFiles,
             GetDefinitionRequest,
             GetReferencesRequest,
             GetReferencedSymbolsRequest,
             SupportedLanguages,
             DefinitionResponse,
+            DefinitionEntry,
+            ResultSource,
             ReferencesResponse,
             ReferencedSymbolsResponse,
             SymbolResponse,
@@ -71,13 +119,90 @@ pub fn check_mount_dir() -> std::io::Result<()> {
             ErrorResponse,
             CodeContext,
             FileRange,
+            Identifier,
             HealthResponse,
             FindIdentifierRequest,
-            IdentifierResponse,
+            FindIdentifierResponse,
+            FileGroup,
+            FileMetrics,
+            FileMetricsResponse,
+            FormatDocumentRequest,
+            TextEditEntry,
+            FormatResponse,
+            CodeActionsRequest,
+            CodeAction,
+            CodeActionsResponse,
+            WorkspaceEdit,
+            SemanticTokensRequest,
+            SemanticToken,
+            SemanticTokensResponse,
+            FileSymbolCounts,
+            SymbolCountsResponse,
+            WriteSourceCodeRequest,
+            GetSymbolResponse,
+            LspClientStatus,
+            LspStatusResponse,
+            FoldingRangesRequest,
+            FoldingRange,
+            FoldingRangesResponse,
+            SelectionRangesRequest,
+            SelectionRange,
+            SelectionRangesResponse,
+            DocumentLinksRequest,
+            DocumentLink,
+            DocumentLinksResponse,
+            CodeLensRequest,
+            CodeLens,
+            CodeLensResponse,
+            PrepareRenameResponse,
+            ReferenceCountResponse,
+            ReferenceCount,
+            GetReferencedSymbolsInRangeRequest,
+            SymbolUsage,
+            SymbolUsagesResponse,
+            OpenFileRequest,
+            RestartLspRequest,
+            GarbageCollectResponse,
+            LogLevelRequest,
+            DetectLanguageRequest,
+            DetectLanguageResponse,
+            AllSymbolsRequest,
+            SetLspTraceRequest,
+            LspTraceValue,
+            ApplyEditResponse,
+            AllReferencesInFileRequest,
+            AllReferencesInFileResponse,
+            TextSearchRequest,
+            TextSearchMatch,
+            TextSearchResponse,
+            FindSymbolRequest,
+            FileCoupling,
+            ChangeImpactReport,
+            EnclosingChainResponse,
+            RenameFileRequest,
+            UnusedImport,
+            UnusedImportsRequest,
+            FindAllDefinitionsRequest,
+            FindAllDefinitionsResponse,
+            IdentifierWithDefinition,
+            SymbolDocsRequest,
+            SymbolDocsResponse,
+            SymbolWithDoc,
+            RenameSymbolRequest,
+            RenameSymbolQuery,
+            RenameSymbolResponse,
+            DefinitionChainRequest,
+            DefinitionChainResponse,
+            ConsistencyReport,
+            ExplainSymbolResponse,
+            ContextEntry,
+            CallersTreeRequest,
+            CallTree,
         )
     ),
     paths(
         crate::handlers::definitions_in_file,
+        crate::handlers::definitions_in_range,
         crate::handlers::find_definition,
         crate::handlers::find_references,
         crate::handlers::health_check,
@@ -85,6 +210,48 @@ pub fn check_mount_dir() -> std::io::Result<()> {
         crate::handlers::read_source_code,
         crate::handlers::find_referenced_symbols,
         crate::handlers::find_identifier,
+        crate::handlers::file_metrics,
+        crate::handlers::format_document,
+        crate::handlers::code_actions,
+        crate::handlers::semantic_tokens,
+        crate::handlers::symbol_counts,
+        crate::handlers::write_source_code,
+        crate::handlers::get_symbol_from_position,
+        crate::handlers::lsp_status,
+        crate::handlers::folding_ranges,
+        crate::handlers::selection_ranges,
+        crate::handlers::document_links,
+        crate::handlers::code_lens,
+        crate::handlers::prepare_rename,
+        crate::handlers::reference_count,
+        crate::handlers::bulk_reference_count,
+        crate::handlers::find_referenced_symbols_in_range,
+        crate::handlers::find_symbol_usages,
+        crate::handlers::open_file,
+        crate::handlers::restart_lsp,
+        crate::handlers::garbage_collect,
+        crate::handlers::set_log_level,
+        crate::handlers::detect_language,
+        crate::handlers::all_symbols,
+        crate::handlers::set_lsp_trace,
+        crate::handlers::apply_edit,
+        crate::handlers::all_references_in_file,
+        crate::handlers::search_text,
+        crate::handlers::find_symbol,
+        crate::handlers::coupling_analysis,
+        crate::handlers::symbol_change_impact,
+        crate::handlers::export_graph_dot,
+        crate::handlers::enclosing_chain,
+        crate::handlers::rename_file,
+        crate::handlers::test_symbols,
+        crate::handlers::unused_imports,
+        crate::handlers::find_all_definitions,
+        crate::handlers::symbol_docs,
+        crate::handlers::rename_symbol,
+        crate::handlers::find_definition_chain,
+        crate::handlers::check_consistency,
+        crate::handlers::explain_symbol,
+        crate::handlers::callers_tree,
     ),
     tags(
         (name = "lsproxy-api", description = "LSP Proxy API")
@@ -95,16 +262,33 @@ pub fn check_mount_dir() -> std::io::Result<()> {
 )]
 pub struct ApiDoc;
 
+/// How often the workspace consistency check runs when `AUTO_CONSISTENCY_CHECK=true` is set.
+const CONSISTENCY_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
 pub struct AppState {
     manager: Arc<Manager>,
+    /// Last response hash per cached endpoint key, used for `ETag` / `If-None-Match`.
+    etags: Arc<Mutex<HashMap<String, String>>>,
+    /// Replaces the log filter at runtime, if the subscriber was set up with a reloadable one.
+    log_filter: Option<LogFilterHandle>,
 }
 
+/// Handle to the reloadable log filter of the tracing subscriber.
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
 pub async fn initialize_app_state() -> Result<Data<AppState>, Box<dyn std::error::Error>> {
-    initialize_app_state_with_mount_dir(None).await
+    initialize_app_state_with_mount_dir(None, None, None).await
 }
 
+/// Initializes the application state.
+///
+/// `ast_grep_config` overrides the ast-grep rules directories, separated with `:`; when `None`,
+/// the `AST_GREP_CONFIG` environment variable is used, falling back to `/usr/src/ast_grep`.
+/// `log_filter` lets the log level be changed through the API.
 pub async fn initialize_app_state_with_mount_dir(
     mount_dir_override: Option<&str>,
+    ast_grep_config: Option<&str>,
+    log_filter: Option<LogFilterHandle>,
 ) -> Result<Data<AppState>, Box<dyn std::error::Error>> {
     if let Some(global_mount_dir) = mount_dir_override {
         set_global_mount_dir(global_mount_dir);
@@ -123,11 +307,51 @@ pub async fn initialize_app_state_with_mount_dir(
     let mount_dir = mount_dir_path.to_string_lossy();
 
     // Create and initialize manager before wrapping in Arc
-    let mut manager = Manager::new(&mount_dir).await?;
+    let config = ManagerConfig::resolve(ast_grep_config);
+    info!(
+        "Using ast-grep config directories: {:?}",
+        config.ast_grep_config_dirs
+    );
+    let mut manager = Manager::new(&mount_dir, config).await?;
     manager.start_langservers(&mount_dir).await?;
     let manager = Arc::new(manager);
 
-    Ok(Data::new(AppState { manager }))
+    let etags: Arc<Mutex<HashMap<String, String>>> = Arc::new(Mutex::new(HashMap::new()));
+    let etags_clone = Arc::clone(&etags);
+    let mut watch_events_rx = manager.subscribe_watch_events();
+    tokio::spawn(async move {
+        while let Ok(event) = watch_events_rx.recv().await {
+            let definitions_key = handlers::definitions_in_file_etag_key(&relative_path_string(
+                &event.path,
+                &mount_dir_path,
+            ));
+            etags_clone.lock().await.retain(|key, _| {
+                !key.starts_with(handlers::LIST_FILES_ETAG_KEY) && *key != definitions_key
+            });
+        }
+    });
+
+    if std::env::var("AUTO_CONSISTENCY_CHECK").is_ok_and(|value| value.trim() == "true") {
+        let manager = Arc::clone(&manager);
+        let etags = Arc::clone(&etags);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CONSISTENCY_CHECK_INTERVAL);
+            // The first tick completes immediately, right after the files were listed
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = handlers::run_consistency_check(&manager, &etags).await {
+                    warn!("Scheduled workspace consistency check failed: {}", e);
+                }
+            }
+        });
+    }
+
+    Ok(Data::new(AppState {
+        manager,
+        etags,
+        log_filter,
+    }))
 }
 
 // Helper enum for cleaner matching
@@ -135,6 +359,8 @@ pub async fn initialize_app_state_with_mount_dir(
 enum Method {
     Get,
     Post,
+    Put,
+    Delete,
 }
 
 pub async fn run_server(app_state: Data<AppState>) -> std::io::Result<()> {
@@ -192,6 +418,19 @@ pub async fn run_server_with_port_and_host(
         }
     };
 
+    let max_body_size = max_body_size();
+    info!("Maximum request body size: {} bytes", max_body_size);
+    let request_timeout = request_timeout();
+    let disconnect_timeout = disconnect_timeout();
+    info!(
+        "Client request timeout: {:?}, disconnect timeout: {:?}",
+        request_timeout, disconnect_timeout
+    );
+    let compression_enabled = is_compression_enabled();
+    if compression_enabled {
+        info!("Response compression enabled");
+    }
+
     HttpServer::new(move || {
         let mut api_scope = scope(format!("/{}", server_path).as_str());
 
@@ -201,6 +440,10 @@ pub async fn run_server_with_port_and_host(
                 Some(Method::Get)
             } else if path_item.post.is_some() {
                 Some(Method::Post)
+            } else if path_item.put.is_some() {
+                Some(Method::Put)
+            } else if path_item.delete.is_some() {
+                Some(Method::Delete)
             } else {
                 None
             };
@@ -214,14 +457,100 @@ pub async fn run_server_with_port_and_host(
                     api_scope.service(resource(path).route(post().to(find_referenced_symbols))),
                 ("/symbol/find-identifier", Some(Method::Post)) =>
                     api_scope.service(resource(path).route(post().to(find_identifier))),
+                ("/symbol/code-actions", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(code_actions))),
+                ("/symbol/definitions-in-range", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(definitions_in_range))),
+                ("/symbol/get-symbol-from-position", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(get_symbol_from_position))),
+                ("/symbol/semantic-tokens", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(semantic_tokens))),
                 ("/symbol/definitions-in-file", Some(Method::Get)) =>
                     api_scope.service(resource(path).route(get().to(definitions_in_file))),
                 ("/workspace/list-files", Some(Method::Get)) =>
                     api_scope.service(resource(path).route(get().to(list_files))),
+                ("/workspace/file-metrics", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(file_metrics))),
+                ("/workspace/format-document", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(format_document))),
+                ("/workspace/symbol-counts", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(symbol_counts))),
                 ("/workspace/read-source-code", Some(Method::Post)) =>
                     api_scope.service(resource(path).route(post().to(read_source_code))),
+                ("/workspace/write-source-code", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(write_source_code))),
                 ("/system/health", Some(Method::Get)) =>
                     api_scope.service(resource(path).route(get().to(health_check))),
+                ("/admin/lsp-status", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(lsp_status))),
+                ("/symbol/folding-ranges", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(folding_ranges))),
+                ("/symbol/selection-ranges", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(selection_ranges))),
+                ("/symbol/document-links", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(document_links))),
+                ("/symbol/code-lens", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(code_lens))),
+                ("/symbol/prepare-rename", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(prepare_rename))),
+                ("/symbol/reference-count", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(reference_count))),
+                ("/symbol/bulk-reference-count", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(bulk_reference_count))),
+                ("/symbol/find-referenced-symbols-in-range", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(find_referenced_symbols_in_range))),
+                ("/symbol/find-symbol-usages", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(find_symbol_usages))),
+                ("/workspace/open-file", Some(Method::Put)) =>
+                    api_scope.service(resource(path).route(put().to(open_file))),
+                ("/admin/restart-lsp", Some(Method::Delete)) =>
+                    api_scope.service(resource(path).route(delete().to(restart_lsp))),
+                ("/admin/gc", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(garbage_collect))),
+                ("/admin/log-level", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(set_log_level))),
+                ("/workspace/detect-language", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(detect_language))),
+                ("/workspace/all-symbols", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(all_symbols))),
+                ("/admin/set-lsp-trace", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(set_lsp_trace))),
+                ("/workspace/apply-edit", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(apply_edit))),
+                ("/workspace/all-references-in-file", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(all_references_in_file))),
+                ("/workspace/search-text", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(search_text))),
+                ("/workspace/find-symbol", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(find_symbol))),
+                ("/workspace/coupling-analysis", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(coupling_analysis))),
+                ("/workspace/symbol-change-impact", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(symbol_change_impact))),
+                ("/workspace/export-graph-dot", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(export_graph_dot))),
+                ("/symbol/enclosing-chain", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(enclosing_chain))),
+                ("/workspace/rename-file", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(rename_file))),
+                ("/workspace/test-symbols", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(test_symbols))),
+                ("/workspace/unused-imports", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(unused_imports))),
+                ("/symbol/find-all-definitions", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(find_all_definitions))),
+                ("/workspace/symbol-docs", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(symbol_docs))),
+                ("/symbol/rename", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(rename_symbol))),
+                ("/symbol/find-definition-chain", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(find_definition_chain))),
+                ("/admin/check-consistency", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(check_consistency))),
+                ("/symbol/explain-symbol", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(explain_symbol))),
+                ("/symbol/callers-tree", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(callers_tree))),
                 (p, m) => panic!(
                     "Invalid path configuration for {}: {:?}. Ensure the OpenAPI spec matches your handlers.",
                     p,
@@ -231,8 +560,13 @@ pub async fn run_server_with_port_and_host(
         }
 
         App::new()
+            // Innermost so error bodies are amended before compression
+            .wrap(RequestIdMiddleware)
+            .wrap(Condition::new(compression_enabled, Compress::default()))
             .wrap(Cors::permissive())
             .app_data(app_state.clone())
+            .app_data(json_config(max_body_size))
+            .app_data(payload_config(max_body_size))
             .configure(|cfg| {
                 if middleware::is_auth_enabled() {
                     cfg.service(api_scope.wrap(JwtMiddleware));
@@ -245,6 +579,8 @@ pub async fn run_server_with_port_and_host(
                     .url("/api-docs/openapi.json", openapi.clone())
             )
     })
+    .client_request_timeout(request_timeout)
+    .client_disconnect_timeout(disconnect_timeout)
     .bind(format!("{}:{}", host, port))?
     .run()
     .await
@@ -258,7 +594,8 @@ pub async fn run_server_with_port_and_host(
 //     return response.json()
 // "#;
 
-pub fn write_openapi_to_file(file_path: &PathBuf) -> std::io::Result<()> {
+/// The OpenAPI spec as written to files, with the bearer auth security scheme.
+fn openapi_spec() -> utoipa::openapi::OpenApi {
     // We use a clone since we're just adding the docs and writing it to the file. We don't need
     // this for runtime
     let mut openapi = ApiDoc::openapi().clone();
@@ -290,14 +627,28 @@ pub fn write_openapi_to_file(file_path: &PathBuf) -> std::io::Result<()> {
             ),
         );
     }
+    openapi
+}
+
+pub fn write_openapi_to_file(file_path: &PathBuf) -> std::io::Result<()> {
     let openapi_json =
-        serde_json::to_string_pretty(&openapi).expect("Failed to serialize OpenAPI to JSON");
+        serde_json::to_string_pretty(&openapi_spec()).expect("Failed to serialize OpenAPI to JSON");
     let mut file = File::create(file_path)?;
     file.write_all(openapi_json.as_bytes())?;
     info!("OpenAPI spec written to: {}", file_path.display());
     Ok(())
 }
 
+/// Writes the OpenAPI spec as YAML, e.g. for API gateways that prefer it over JSON.
+pub fn write_openapi_to_file_yaml(file_path: &PathBuf) -> std::io::Result<()> {
+    let openapi_yaml =
+        serde_yaml::to_string(&openapi_spec()).expect("Failed to serialize OpenAPI to YAML");
+    let mut file = File::create(file_path)?;
+    file.write_all(openapi_yaml.as_bytes())?;
+    info!("OpenAPI spec written to: {}", file_path.display());
+    Ok(())
+}
+
 #[cfg(test)]
 mod test_utils;
 
@@ -312,68 +663,31 @@ mod test {
     use std::sync::mpsc;
     use std::thread;
     use std::time::Duration;
-    use tempfile::TempDir;
-
-    fn simple_diff(text1: &str, text2: &str) -> String {
-        let lines1: Vec<&str> = text1.lines().collect();
-        let lines2: Vec<&str> = text2.lines().collect();
-        let mut diff = String::new();
-        let mut diff_count = 0;
-
-        for (i, (l1, l2)) in lines1.iter().zip(lines2.iter()).enumerate() {
-            if l1 != l2 {
-                diff_count += 1;
-                if diff_count <= 3 {
-                    // Show up to 3 differences
-                    diff.push_str(&format!("Line {}: '{}' vs '{}'\n", i + 1, l1, l2));
-                }
-            }
-        }
 
-        if lines1.len() != lines2.len() {
-            diff.push_str(&format!(
-                "Files have different number of lines: {} vs {}\n",
-                lines1.len(),
-                lines2.len()
-            ));
-        }
-
-        if diff_count > 3 {
-            diff.push_str(&format!("... and {} more differences\n", diff_count - 3));
-        }
-
-        diff
+    /// Snapshot of the OpenAPI spec, in `src/snapshots/`.
+    ///
+    /// When the API changes, this test fails with a diff of the spec. Review and accept the new
+    /// snapshot with `cargo insta review` (from `cargo install cargo-insta`), or accept it directly
+    /// with `INSTA_UPDATE=always cargo test test_openapi_spec`, and commit the `.snap` file.
+    #[test]
+    fn test_openapi_spec() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut settings = insta::Settings::clone_current();
+        settings.set_sort_maps(true);
+        settings.bind(|| insta::assert_json_snapshot!("openapi_spec", spec));
     }
 
     #[test]
-    fn test_openapi_json() -> Result<(), Box<dyn std::error::Error>> {
-        // Create a new temporary directory
-        let temp_dir = TempDir::new()?;
-        let temp_openapi_path = temp_dir.path().join("openapi.json");
-
-        // Write the OpenAPI spec to the temporary file
-        write_openapi_to_file(&PathBuf::from(&temp_openapi_path))?;
-
-        // Read the content of the generated file
-        let generated_content = fs::read_to_string(&temp_openapi_path)
-            .map_err(|e| format!("Failed to load generate openapi spec: {}", e))?;
-
-        // Read the content of the existing file
-        // Assume you have a known good file to compare against
-        let existing_path = PathBuf::from("/mnt/lsproxy_root/openapi.json");
-        let existing_content = fs::read_to_string(existing_path)
-            .map_err(|e| format!("Failed to load existing openapi spec: {}", e))?;
-
-        // Compare the contents
-        if generated_content != existing_content {
-            let diff = simple_diff(&existing_content, &generated_content);
-            return Err(format!(
-                "Differences: {}
-                Generated OpenAPI JSON does not match existing content. Make sure to run ./scripts/generate_spec.sh",
-                diff
-            ).into());
-        }
-
+    fn test_write_openapi_yaml_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let json_path = dir.path().join("openapi.json");
+        let yaml_path = dir.path().join("openapi.yaml");
+        write_openapi_to_file(&json_path)?;
+        write_openapi_to_file_yaml(&yaml_path)?;
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(json_path)?)?;
+        let yaml: serde_json::Value = serde_yaml::from_str(&std::fs::read_to_string(yaml_path)?)?;
+        assert_eq!(yaml, json);
         Ok(())
     }
 
@@ -1,15 +1,30 @@
+use crate::api_types::{LspClientStatus, SupportedLanguages};
 use crate::lsp::json_rpc::JsonRpc;
 use crate::lsp::process::Process;
-use crate::lsp::{ExpectedMessageKey, JsonRpcHandler, ProcessHandler};
+use crate::lsp::{ExpectedMessageKey, JsonRpcError, JsonRpcHandler, ProcessHandler};
 use crate::utils::file_utils::{detect_language_string, search_directories};
 use async_trait::async_trait;
 use log::{debug, error, warn};
 use lsp_types::{
-    ClientCapabilities, DidOpenTextDocumentParams, DocumentSymbolClientCapabilities,
-    GotoDefinitionParams, GotoDefinitionResponse, InitializeParams, InitializeResult, Location,
-    PartialResultParams, Position, PublishDiagnosticsClientCapabilities, ReferenceContext,
-    ReferenceParams, TagSupport, TextDocumentClientCapabilities, TextDocumentIdentifier,
-    TextDocumentItem, TextDocumentPositionParams, Url, WorkDoneProgressParams, WorkspaceFolder,
+    ClientCapabilities, CodeActionClientCapabilities, CodeActionContext, CodeActionKind,
+    CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams, CodeActionResponse,
+    CodeLens, CodeLensClientCapabilities, CodeLensParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentFormattingParams, DocumentLink, DocumentLinkParams, DocumentSymbolClientCapabilities,
+    FileEvent, FileRename, FoldingRange, FoldingRangeClientCapabilities, FoldingRangeParams,
+    FormattingOptions, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverClientCapabilities, HoverParams, InitializeParams, InitializeResult, Location,
+    LogTraceParams, MarkupKind, PartialResultParams, Position, PrepareRenameResponse,
+    PublishDiagnosticsClientCapabilities, Range, ReferenceContext, ReferenceParams,
+    RenameClientCapabilities, RenameFilesParams, RenameParams, SelectionRange,
+    SelectionRangeParams, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensClientCapabilities, SemanticTokensClientCapabilitiesRequests,
+    SemanticTokensFullOptions, SemanticTokensParams, SetTraceParams, TagSupport,
+    TextDocumentClientCapabilities, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+    TextDocumentItem, TextDocumentPositionParams, TextEdit, TokenFormat, TraceValue, Url,
+    VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceClientCapabilities,
+    WorkspaceEdit, WorkspaceFileOperationsClientCapabilities, WorkspaceFolder,
+    WorkspaceSymbolParams, WorkspaceSymbolResponse,
 };
 use std::error::Error;
 use std::path::{Path, PathBuf};
@@ -41,28 +56,110 @@ pub trait LspClient: Send {
     }
 
     fn get_capabilities(&mut self) -> ClientCapabilities {
-        let mut capabilities = ClientCapabilities::default();
-        capabilities.text_document = Some(TextDocumentClientCapabilities {
-            document_symbol: Some(DocumentSymbolClientCapabilities {
-                dynamic_registration: Some(false),
-                hierarchical_document_symbol_support: Some(true),
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                document_symbol: Some(DocumentSymbolClientCapabilities {
+                    dynamic_registration: Some(false),
+                    hierarchical_document_symbol_support: Some(true),
+                    ..Default::default()
+                }),
+                // Ask for code action literals rather than bare commands so that edits are included
+                code_action: Some(CodeActionClientCapabilities {
+                    code_action_literal_support: Some(CodeActionLiteralSupport {
+                        code_action_kind: CodeActionKindLiteralSupport {
+                            value_set: vec![
+                                CodeActionKind::QUICKFIX.as_str().to_string(),
+                                CodeActionKind::REFACTOR.as_str().to_string(),
+                                CodeActionKind::REFACTOR_EXTRACT.as_str().to_string(),
+                                CodeActionKind::REFACTOR_INLINE.as_str().to_string(),
+                                CodeActionKind::REFACTOR_REWRITE.as_str().to_string(),
+                                CodeActionKind::SOURCE.as_str().to_string(),
+                                CodeActionKind::SOURCE_ORGANIZE_IMPORTS.as_str().to_string(),
+                            ],
+                        },
+                    }),
+                    ..Default::default()
+                }),
+                semantic_tokens: Some(SemanticTokensClientCapabilities {
+                    dynamic_registration: Some(false),
+                    requests: SemanticTokensClientCapabilitiesRequests {
+                        range: Some(false),
+                        full: Some(SemanticTokensFullOptions::Bool(true)),
+                    },
+                    token_types: vec![
+                        SemanticTokenType::NAMESPACE,
+                        SemanticTokenType::TYPE,
+                        SemanticTokenType::CLASS,
+                        SemanticTokenType::ENUM,
+                        SemanticTokenType::INTERFACE,
+                        SemanticTokenType::STRUCT,
+                        SemanticTokenType::TYPE_PARAMETER,
+                        SemanticTokenType::PARAMETER,
+                        SemanticTokenType::VARIABLE,
+                        SemanticTokenType::PROPERTY,
+                        SemanticTokenType::ENUM_MEMBER,
+                        SemanticTokenType::FUNCTION,
+                        SemanticTokenType::METHOD,
+                        SemanticTokenType::MACRO,
+                        SemanticTokenType::KEYWORD,
+                        SemanticTokenType::COMMENT,
+                        SemanticTokenType::STRING,
+                        SemanticTokenType::NUMBER,
+                        SemanticTokenType::OPERATOR,
+                    ],
+                    token_modifiers: vec![
+                        SemanticTokenModifier::DECLARATION,
+                        SemanticTokenModifier::DEFINITION,
+                        SemanticTokenModifier::READONLY,
+                        SemanticTokenModifier::STATIC,
+                        SemanticTokenModifier::DEPRECATED,
+                        SemanticTokenModifier::ASYNC,
+                        SemanticTokenModifier::MODIFICATION,
+                        SemanticTokenModifier::DEFAULT_LIBRARY,
+                    ],
+                    formats: vec![TokenFormat::RELATIVE],
+                    overlapping_token_support: Some(false),
+                    multiline_token_support: Some(false),
+                    ..Default::default()
+                }),
+                hover: Some(HoverClientCapabilities {
+                    dynamic_registration: Some(false),
+                    content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                }),
+                code_lens: Some(CodeLensClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
+                rename: Some(RenameClientCapabilities {
+                    dynamic_registration: Some(false),
+                    prepare_support: Some(true),
+                    ..Default::default()
+                }),
+                folding_range: Some(FoldingRangeClientCapabilities {
+                    dynamic_registration: Some(false),
+                    line_folding_only: Some(true),
+                    ..Default::default()
+                }),
+                publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
+                    related_information: Some(false),
+                    tag_support: Some(TagSupport { value_set: vec![] }),
+                    code_description_support: Some(false),
+                    data_support: Some(false),
+                    version_support: Some(false),
+                }),
                 ..Default::default()
             }),
-            // Turn off diagnostics for performance, we don't use them at the moment
-            publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
-                related_information: Some(false),
-                tag_support: Some(TagSupport { value_set: vec![] }),
-                code_description_support: Some(false),
-                data_support: Some(false),
-                version_support: Some(false),
+            workspace: Some(WorkspaceClientCapabilities {
+                file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                    will_rename: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
             }),
+            experimental: Some(serde_json::json!({
+                "serverStatusNotification": true
+            })),
             ..Default::default()
-        });
-
-        capabilities.experimental = Some(serde_json::json!({
-            "serverStatusNotification": true
-        }));
-        capabilities
+        }
     }
 
     async fn get_initialize_params(
@@ -116,39 +213,54 @@ pub trait LspClient: Send {
 
         tokio::spawn(async move {
             loop {
-                if let Ok(raw_response) = process.receive().await {
-                    if let Ok(message) = json_rpc.parse_message(&raw_response) {
-                        if let Some(id) = message.id {
-                            debug!("Received response for request {}", id);
-                            if let Ok(Some(sender)) = pending_requests.remove_request(id).await {
-                                if sender.send(message.clone()).is_err() {
-                                    error!("Failed to send response for request {}", id);
-                                }
-                            } else {
-                                debug!(
-                                    "Responding to server message {} - Message: {:?}",
-                                    id, message
-                                );
-                                let response = json_rpc.create_success_response(id);
-
-                                let message = format!(
-                                    "Content-Length: {}\r\n\r\n{}",
-                                    response.len(),
-                                    response
-                                );
-                                let _ = process.send(&message).await;
+                let raw_response = match process.receive().await {
+                    Ok(raw_response) => raw_response,
+                    Err(e) => {
+                        if e.downcast_ref::<std::io::Error>().map(|e| e.kind())
+                            == Some(std::io::ErrorKind::UnexpectedEof)
+                        {
+                            debug!("Language server output closed, stopping response listener");
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                if let Ok(message) = json_rpc.parse_message(&raw_response) {
+                    if let Some(id) = message.id {
+                        debug!("Received response for request {}", id);
+                        if let Ok(Some(sender)) = pending_requests.remove_request(id).await {
+                            if sender.send(message.clone()).is_err() {
+                                error!("Failed to send response for request {}", id);
                             }
-                        } else if let Some(params) = message.params.clone() {
-                            let message_key = ExpectedMessageKey {
-                                method: message.method.clone().unwrap(),
-                                params,
-                            };
-                            if let Some(sender) =
-                                pending_requests.remove_notification(message_key).await
-                            {
-                                sender.send(message).unwrap();
+                        } else {
+                            debug!(
+                                "Responding to server message {} - Message: {:?}",
+                                id, message
+                            );
+                            let response = json_rpc.create_success_response(id);
+
+                            let message =
+                                format!("Content-Length: {}\r\n\r\n{}", response.len(), response);
+                            let _ = process.send(&message).await;
+                        }
+                    } else if let Some(params) = message.params.clone() {
+                        if message.method.as_deref() == Some("$/logTrace") {
+                            log_trace(params.clone());
+                        }
+                        if message.method.as_deref() == Some("textDocument/publishDiagnostics") {
+                            if let Ok(params) = serde_json::from_value(params.clone()) {
+                                pending_requests.update_diagnostics(params).await;
                             }
                         }
+                        let message_key = ExpectedMessageKey {
+                            method: message.method.clone().unwrap(),
+                            params,
+                        };
+                        if let Some(sender) =
+                            pending_requests.remove_notification(message_key).await
+                        {
+                            sender.send(message).unwrap();
+                        }
                     }
                 }
             }
@@ -174,6 +286,9 @@ pub trait LspClient: Send {
         &mut self,
         item: lsp_types::TextDocumentItem,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.get_pending_requests()
+            .clear_diagnostics(&item.uri)
+            .await;
         let params = DidOpenTextDocumentParams {
             text_document: item,
         };
@@ -188,40 +303,278 @@ pub trait LspClient: Send {
         self.get_process().send(&message).await
     }
 
-    async fn text_document_definition(
+    async fn text_document_did_close(
         &mut self,
         file_path: &str,
-        position: Position,
-    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+        };
+        let notification = self
+            .get_json_rpc()
+            .create_notification("textDocument/didClose", serde_json::to_value(params)?);
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    /// Replaces the whole content of an open document.
+    async fn text_document_did_change(
+        &mut self,
+        file_path: &str,
+        text: String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let version = self
+            .get_workspace_documents()
+            .next_document_version(file_path);
+        let uri = Url::from_file_path(file_path).map_err(|_| "Invalid file path")?;
+        // The diagnostics of the previous content are stale until the server publishes new ones
+        self.get_pending_requests().clear_diagnostics(&uri).await;
+        let params = DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier { uri, version },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text,
+            }],
+        };
+        let notification = self
+            .get_json_rpc()
+            .create_notification("textDocument/didChange", serde_json::to_value(params)?);
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    /// Tells the server that files changed on disk, e.g. to make it re-index them.
+    async fn workspace_did_change_watched_files(
+        &mut self,
+        changes: Vec<FileEvent>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let params = DidChangeWatchedFilesParams { changes };
+        let notification = self.get_json_rpc().create_notification(
+            "workspace/didChangeWatchedFiles",
+            serde_json::to_value(params)?,
+        );
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    /// Sets how verbosely the server traces its protocol messages.
+    ///
+    /// The server sends its trace with `$/logTrace` notifications, which are logged at debug
+    /// level with the `lsp_trace` target. Does nothing for servers that don't support tracing.
+    async fn set_trace(&mut self, value: TraceValue) -> Result<(), Box<dyn Error + Send + Sync>> {
         debug!(
-            "Requesting goto definition for {}, line {}, character {}",
-            file_path, position.line, position.character
+            "Language server doesn't support tracing, ignoring {:?}",
+            value
+        );
+        Ok(())
+    }
+
+    /// Sends a `$/setTrace` notification, for clients whose server supports tracing.
+    async fn send_set_trace(
+        &mut self,
+        value: TraceValue,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let params = SetTraceParams { value };
+        let notification = self
+            .get_json_rpc()
+            .create_notification("$/setTrace", serde_json::to_value(params)?);
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    /// Asks the server for the edits to make before files are renamed, e.g. updating imports.
+    ///
+    /// `renames` pairs the absolute old and new path of each file. Returns `None` for servers
+    /// that don't compute such edits.
+    async fn workspace_will_rename_files(
+        &mut self,
+        renames: Vec<(String, String)>,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Language server doesn't support willRenameFiles, ignoring {:?}",
+            renames
         );
+        Ok(None)
+    }
+
+    /// Sends a `workspace/willRenameFiles` request, for clients whose server supports it.
+    ///
+    /// A rejected request is treated as the server having no edits to make.
+    async fn send_will_rename_files(
+        &mut self,
+        renames: Vec<(String, String)>,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting edits for renaming files {:?}", renames);
+
+        let files = renames
+            .into_iter()
+            .map(|(old_path, new_path)| {
+                Ok(FileRename {
+                    old_uri: Url::from_file_path(&old_path)
+                        .map_err(|_| format!("Invalid file path: {}", old_path))?
+                        .to_string(),
+                    new_uri: Url::from_file_path(&new_path)
+                        .map_err(|_| format!("Invalid file path: {}", new_path))?
+                        .to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let params = RenameFilesParams { files };
 
-        let needs_open = {
-            let workspace_documents = self.get_workspace_documents();
-            workspace_documents.get_did_open_configuration() == DidOpenConfiguration::Lazy
-                && !workspace_documents.is_did_open_document(file_path)
+        let result = match self
+            .send_request(
+                "workspace/willRenameFiles",
+                Some(serde_json::to_value(params)?),
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e) if e.downcast_ref::<JsonRpcError>().is_some() => {
+                debug!("willRenameFiles rejected by language server: {}", e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
         };
 
-        // If needed, read the document text and send didOpen
-        if needs_open {
-            let document_text = self
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(result)?))
+    }
+
+    /// Tells the server about new content written to a document, with a didChange notification
+    /// if the document is open and a didOpen notification otherwise.
+    async fn sync_document(
+        &mut self,
+        file_path: &str,
+        text: String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self
+            .get_workspace_documents()
+            .is_did_open_document(file_path)
+        {
+            return self.text_document_did_change(file_path, text).await;
+        }
+        self.text_document_did_open(TextDocumentItem {
+            uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            language_id: detect_language_string(file_path)?,
+            version: 1,
+            text,
+        })
+        .await?;
+        self.get_workspace_documents()
+            .add_did_open_document(file_path);
+        Ok(())
+    }
+
+    /// Sends a didOpen notification for the document if the server expects documents to be opened
+    /// lazily and this one hasn't been opened yet.
+    async fn did_open_if_needed(
+        &mut self,
+        file_path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.get_workspace_documents().get_did_open_configuration() == DidOpenConfiguration::Lazy
+        {
+            self.did_open(file_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Opens each of the files, skipping those that are already open, and returns how many were
+    /// newly opened.
+    ///
+    /// Files that fail to open are logged and skipped, so one unreadable file doesn't stop the
+    /// rest from being opened.
+    async fn open_all_files(
+        &mut self,
+        file_paths: &[String],
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut opened = 0;
+        for file_path in file_paths {
+            if self
                 .get_workspace_documents()
-                .read_text_document(&PathBuf::from(file_path), None)
-                .await?;
-
-            self.text_document_did_open(TextDocumentItem {
-                uri: Url::from_file_path(file_path).unwrap(),
-                language_id: detect_language_string(file_path)?,
-                version: 1,
-                text: document_text,
-            })
+                .is_did_open_document(file_path)
+            {
+                continue;
+            }
+            match self.did_open(file_path).await {
+                Ok(()) => opened += 1,
+                Err(e) => warn!("Failed to open {}: {}", file_path, e),
+            }
+        }
+        Ok(opened)
+    }
+
+    /// Sends a didOpen notification with the document's current content unless it's already
+    /// open, whatever the server's [`DidOpenConfiguration`].
+    async fn did_open(&mut self, file_path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self
+            .get_workspace_documents()
+            .is_did_open_document(file_path)
+        {
+            return Ok(());
+        }
+
+        let document_text = self
+            .get_workspace_documents()
+            .read_text_document(&PathBuf::from(file_path), None, None)
             .await?;
+        self.text_document_did_open(TextDocumentItem {
+            uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            language_id: detect_language_string(file_path)?,
+            version: 1,
+            text: document_text,
+        })
+        .await?;
+        self.get_workspace_documents()
+            .add_did_open_document(file_path);
+        Ok(())
+    }
 
-            self.get_workspace_documents()
-                .add_did_open_document(file_path);
+    /// Sends a didClose notification for the document if it's open.
+    async fn did_close(&mut self, file_path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self
+            .get_workspace_documents()
+            .is_did_open_document(file_path)
+        {
+            return Ok(());
         }
+        self.text_document_did_close(file_path).await?;
+        self.get_workspace_documents()
+            .remove_did_open_document(file_path);
+        Ok(())
+    }
+
+    async fn text_document_definition(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting goto definition for {}, line {}, character {}",
+            file_path, position.line, position.character
+        );
+
+        self.did_open_if_needed(file_path).await?;
 
         let params = GotoDefinitionParams {
             text_document_position_params: TextDocumentPositionParams {
@@ -256,32 +609,9 @@ pub trait LspClient: Send {
         &mut self,
         file_path: &str,
         position: Position,
+        include_declaration: bool,
     ) -> Result<Vec<Location>, Box<dyn Error + Send + Sync>> {
-        // Get the configuration and check if document is opened first
-        let needs_open = {
-            let workspace_documents = self.get_workspace_documents();
-            workspace_documents.get_did_open_configuration() == DidOpenConfiguration::Lazy
-                && !workspace_documents.is_did_open_document(file_path)
-        };
-
-        // If needed, read the document text and send didOpen
-        if needs_open {
-            let document_text = self
-                .get_workspace_documents()
-                .read_text_document(&PathBuf::from(file_path), None)
-                .await?;
-
-            self.text_document_did_open(TextDocumentItem {
-                uri: Url::from_file_path(file_path).unwrap(),
-                language_id: detect_language_string(file_path)?,
-                version: 1,
-                text: document_text,
-            })
-            .await?;
-
-            self.get_workspace_documents()
-                .add_did_open_document(file_path);
-        }
+        self.did_open_if_needed(file_path).await?;
 
         let params = ReferenceParams {
             text_document_position: TextDocumentPositionParams {
@@ -293,7 +623,7 @@ pub trait LspClient: Send {
             work_done_progress_params: WorkDoneProgressParams::default(),
             partial_result_params: PartialResultParams::default(),
             context: ReferenceContext {
-                include_declaration: true,
+                include_declaration,
             },
         };
 
@@ -313,6 +643,432 @@ pub trait LspClient: Send {
         Ok(ref_resp)
     }
 
+    async fn text_document_formatting(
+        &mut self,
+        file_path: &str,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> Result<Vec<TextEdit>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting formatting for {}", file_path);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = DocumentFormattingParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            options: FormattingOptions {
+                tab_size,
+                insert_spaces,
+                ..Default::default()
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/formatting",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let edits: Vec<TextEdit> = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received formatting response");
+        Ok(edits)
+    }
+
+    /// Opens a document if needed, so that the server publishes its diagnostics, and returns the
+    /// requests they are published to.
+    ///
+    /// Servers publish diagnostics asynchronously, so wait for them with
+    /// [`PendingRequests::wait_for_diagnostics`] after unlocking the client, so that other
+    /// requests to the server aren't held up meanwhile.
+    async fn prepare_document_diagnostics(
+        &mut self,
+        file_path: &str,
+    ) -> Result<PendingRequests, Box<dyn Error + Send + Sync>> {
+        self.did_open_if_needed(file_path).await?;
+        Ok(self.get_pending_requests().clone())
+    }
+
+    async fn text_document_code_action(
+        &mut self,
+        file_path: &str,
+        range: Range,
+        context: CodeActionContext,
+    ) -> Result<CodeActionResponse, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting code actions for {}, lines {}-{}",
+            file_path, range.start.line, range.end.line
+        );
+        self.did_open_if_needed(file_path).await?;
+
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            range,
+            context,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/codeAction",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let actions: CodeActionResponse = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received code action response");
+        Ok(actions)
+    }
+
+    /// Gets the delta-encoded semantic tokens for a whole document.
+    ///
+    /// The tokens are decoded with the legend the server sent in its initialize result.
+    async fn text_document_semantic_tokens_full(
+        &mut self,
+        file_path: &str,
+    ) -> Result<SemanticTokens, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting semantic tokens for {}", file_path);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = SemanticTokensParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/semanticTokens/full",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let tokens: SemanticTokens = if result.is_null() {
+            SemanticTokens::default()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received semantic tokens response");
+        Ok(tokens)
+    }
+
+    async fn text_document_folding_range(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Vec<FoldingRange>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting folding ranges for {}", file_path);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/foldingRange",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let ranges: Vec<FoldingRange> = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received {} folding ranges", ranges.len());
+        Ok(ranges)
+    }
+
+    async fn text_document_selection_range(
+        &mut self,
+        file_path: &str,
+        positions: Vec<Position>,
+    ) -> Result<Vec<SelectionRange>, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting selection ranges for {} positions in {}",
+            positions.len(),
+            file_path
+        );
+        self.did_open_if_needed(file_path).await?;
+
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            positions,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/selectionRange",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let ranges: Vec<SelectionRange> = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received {} selection ranges", ranges.len());
+        Ok(ranges)
+    }
+
+    async fn text_document_document_link(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Vec<DocumentLink>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting document links for {}", file_path);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = DocumentLinkParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request(
+                "textDocument/documentLink",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        let links: Vec<DocumentLink> = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+        debug!("Received {} document links", links.len());
+        Ok(links)
+    }
+
+    /// Gets the code lenses of a file, resolving the ones the server returns without a command.
+    ///
+    /// Lenses that fail to resolve are dropped.
+    async fn text_document_code_lens(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Vec<CodeLens>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting code lenses for {}", file_path);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request("textDocument/codeLens", Some(serde_json::to_value(params)?))
+            .await?;
+
+        let lenses: Vec<CodeLens> = if result.is_null() {
+            Vec::new()
+        } else {
+            serde_json::from_value(result)?
+        };
+
+        let mut resolved = Vec::with_capacity(lenses.len());
+        for lens in lenses {
+            if lens.command.is_some() {
+                resolved.push(lens);
+                continue;
+            }
+            match self
+                .send_request("codeLens/resolve", Some(serde_json::to_value(&lens)?))
+                .await
+                .and_then(|result| Ok(serde_json::from_value::<CodeLens>(result)?))
+            {
+                Ok(lens) if lens.command.is_some() => resolved.push(lens),
+                Ok(_) => debug!("Code lens resolved without a command"),
+                Err(e) => warn!("Failed to resolve code lens: {}", e),
+            }
+        }
+        debug!("Received {} code lenses", resolved.len());
+        Ok(resolved)
+    }
+
+    /// Gets the hover information at a position, e.g. the signature and documentation of the
+    /// symbol there.
+    async fn text_document_hover(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Option<Hover>, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting hover for {} at {:?}", file_path, position);
+        self.did_open_if_needed(file_path).await?;
+
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+                },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let result = self
+            .send_request("textDocument/hover", Some(serde_json::to_value(params)?))
+            .await?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(result)?))
+    }
+
+    /// Checks whether the symbol at a position can be renamed.
+    ///
+    /// Returns `None` if the server indicates that rename is not possible there, either with a
+    /// null result or with an error response.
+    async fn text_document_prepare_rename(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Option<PrepareRenameResponse>, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting prepare rename for {} at {:?}",
+            file_path, position
+        );
+        self.did_open_if_needed(file_path).await?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+            },
+            position,
+        };
+
+        let result = match self
+            .send_request(
+                "textDocument/prepareRename",
+                Some(serde_json::to_value(params)?),
+            )
+            .await
+        {
+            Ok(result) => result,
+            Err(e) if e.downcast_ref::<JsonRpcError>().is_some() => {
+                debug!("Rename rejected by language server: {}", e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(result)?))
+    }
+
+    /// Computes the edits renaming the symbol at a position to `new_name`, without applying them.
+    ///
+    /// Returns `None` if the server has nothing to rename there.
+    async fn text_document_rename(
+        &mut self,
+        file_path: &str,
+        position: Position,
+        new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting rename to {} for {} at {:?}",
+            new_name, file_path, position
+        );
+        self.did_open_if_needed(file_path).await?;
+
+        let params = RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::from_file_path(file_path).map_err(|_| "Invalid file path")?,
+                },
+                position,
+            },
+            new_name: new_name.to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let result = self
+            .send_request("textDocument/rename", Some(serde_json::to_value(params)?))
+            .await?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(result)?))
+    }
+
+    /// Searches the symbols of the whole workspace by name.
+    ///
+    /// The server decides how `query` is matched, usually fuzzily, so results need filtering
+    /// for an exact name.
+    async fn workspace_symbol(
+        &mut self,
+        query: &str,
+    ) -> Result<WorkspaceSymbolResponse, Box<dyn Error + Send + Sync>> {
+        debug!("Requesting workspace symbols for {}", query);
+
+        let params = WorkspaceSymbolParams {
+            query: query.to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = self
+            .send_request("workspace/symbol", Some(serde_json::to_value(params)?))
+            .await?;
+
+        if result.is_null() {
+            return Ok(WorkspaceSymbolResponse::Flat(Vec::new()));
+        }
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Reports the state of the language server process backing this client.
+    async fn client_status(
+        &mut self,
+        language: SupportedLanguages,
+        restart_count: u32,
+    ) -> LspClientStatus {
+        let process = self.get_process();
+        let status = if process.is_running().await {
+            "running"
+        } else {
+            "exited"
+        };
+        LspClientStatus {
+            language: language.to_string(),
+            status: status.to_string(),
+            pid: process.pid(),
+            uptime_secs: process.started_at().elapsed().as_secs(),
+            restart_count,
+        }
+    }
+
     fn get_process(&mut self) -> &mut ProcessHandler;
 
     fn get_json_rpc(&mut self) -> &mut JsonRpcHandler;
@@ -394,3 +1150,15 @@ pub trait LspClient: Send {
         Ok(workspace_folders.into_iter().collect())
     }
 }
+
+/// Logs a `$/logTrace` notification from a language server under the `lsp_trace` target.
+fn log_trace(params: serde_json::Value) {
+    match serde_json::from_value::<LogTraceParams>(params) {
+        Ok(LogTraceParams {
+            message,
+            verbose: Some(verbose),
+        }) => tracing::debug!(target: "lsp_trace", verbose = %verbose, "{}", message),
+        Ok(LogTraceParams { message, .. }) => tracing::debug!(target: "lsp_trace", "{}", message),
+        Err(e) => warn!("Invalid $/logTrace notification: {}", e),
+    }
+}
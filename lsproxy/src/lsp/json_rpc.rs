@@ -1,3 +1,4 @@
+use lsp_types::{Diagnostic, PublishDiagnosticsParams, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -5,8 +6,9 @@ use std::error::Error;
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast::{channel, Receiver, Sender};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
 pub trait JsonRpc: Send + Sync {
     fn create_success_response(&self, id: u64) -> String;
@@ -25,11 +27,6 @@ pub struct JsonRpcMessage {
     pub error: Option<JsonRpcError>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct InnerMessage {
-    pub message: String,
-    pub r#type: String,
-}
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JsonRpcError {
     pub code: i32,
@@ -108,6 +105,10 @@ pub struct ExpectedMessageKey {
 pub struct PendingRequests {
     request_channels: Arc<Mutex<HashMap<u64, Sender<JsonRpcMessage>>>>,
     notification_channels: Arc<Mutex<HashMap<ExpectedMessageKey, Sender<JsonRpcMessage>>>>,
+    /// The latest diagnostics published by the server for each document
+    diagnostics: Arc<Mutex<HashMap<Url, Vec<Diagnostic>>>>,
+    /// Wakes up the requests waiting for diagnostics whenever the server publishes some
+    diagnostics_published: Arc<Notify>,
 }
 
 impl PendingRequests {
@@ -115,6 +116,8 @@ impl PendingRequests {
         Self {
             request_channels: Arc::new(Mutex::new(HashMap::new())),
             notification_channels: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics_published: Arc::new(Notify::new()),
         }
     }
 
@@ -152,4 +155,93 @@ impl PendingRequests {
     ) -> Option<Sender<JsonRpcMessage>> {
         self.notification_channels.lock().await.remove(&pattern)
     }
+
+    pub async fn update_diagnostics(&self, params: PublishDiagnosticsParams) {
+        self.diagnostics
+            .lock()
+            .await
+            .insert(params.uri, params.diagnostics);
+        self.diagnostics_published.notify_waiters();
+    }
+
+    /// Forgets the diagnostics of a document whose content changed, since the server publishes
+    /// new ones for the new content.
+    pub async fn clear_diagnostics(&self, uri: &Url) {
+        self.diagnostics.lock().await.remove(uri);
+    }
+
+    /// Returns the latest diagnostics for a document, or None if none have been published yet.
+    pub async fn get_diagnostics(&self, uri: &Url) -> Option<Vec<Diagnostic>> {
+        self.diagnostics.lock().await.get(uri).cloned()
+    }
+
+    /// Waits up to `timeout` for the server to publish diagnostics for a document and returns
+    /// them, or an empty list if none arrive in time.
+    pub async fn wait_for_diagnostics(&self, uri: &Url, timeout: Duration) -> Vec<Diagnostic> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Registered before checking, so diagnostics published in between aren't missed
+            let published = self.diagnostics_published.notified();
+            tokio::pin!(published);
+            published.as_mut().enable();
+            if let Some(diagnostics) = self.get_diagnostics(uri).await {
+                return diagnostics;
+            }
+            if tokio::time::timeout_at(deadline, published).await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish(uri: &Url, message: &str) -> PublishDiagnosticsParams {
+        PublishDiagnosticsParams {
+            uri: uri.clone(),
+            diagnostics: vec![Diagnostic {
+                message: message.to_string(),
+                ..Default::default()
+            }],
+            version: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_diagnostics_published_later() {
+        let pending_requests = PendingRequests::new();
+        let uri = Url::parse("file:///mnt/workspace/main.py").unwrap();
+
+        let publisher = pending_requests.clone();
+        let published_uri = uri.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            publisher
+                .update_diagnostics(publish(&published_uri, "unused import"))
+                .await;
+        });
+
+        let diagnostics = pending_requests
+            .wait_for_diagnostics(&uri, Duration::from_secs(5))
+            .await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unused import");
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_cleared_diagnostics_times_out() {
+        let pending_requests = PendingRequests::new();
+        let uri = Url::parse("file:///mnt/workspace/main.py").unwrap();
+        pending_requests
+            .update_diagnostics(publish(&uri, "unused import"))
+            .await;
+        pending_requests.clear_diagnostics(&uri).await;
+
+        let diagnostics = pending_requests
+            .wait_for_diagnostics(&uri, Duration::from_millis(50))
+            .await;
+        assert!(diagnostics.is_empty());
+    }
 }
@@ -294,21 +294,17 @@ fn generate_compile_commands(
 
 fn parse_cmakelists(cmake_files: &[PathBuf]) -> Vec<String> {
     let mut flags = Vec::new();
+    let cxx_standard_re = regex::Regex::new(r"set\s*\(\s*CMAKE_CXX_STANDARD\s+(\d+)\s*\)").unwrap();
+    let compile_options_re = regex::Regex::new(r"add_compile_options\s*\((.*?)\)").unwrap();
     for cmake_path in cmake_files {
         if let Ok(content) = std::fs::read_to_string(cmake_path) {
             // Extract C++ standard (this part is fine)
-            if let Some(capture) = regex::Regex::new(r"set\s*\(\s*CMAKE_CXX_STANDARD\s+(\d+)\s*\)")
-                .unwrap()
-                .captures(&content)
-            {
+            if let Some(capture) = cxx_standard_re.captures(&content) {
                 flags.push(format!("-std=c++{}", &capture[1]));
             }
 
             // Extract compile options but skip generator expressions and variables
-            for caps in regex::Regex::new(r"add_compile_options\s*\((.*?)\)")
-                .unwrap()
-                .captures_iter(&content)
-            {
+            for caps in compile_options_re.captures_iter(&content) {
                 // Only take literal flags, skip anything with ${...} or $<...>
                 flags.extend(
                     caps[1]
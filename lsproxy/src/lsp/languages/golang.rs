@@ -7,7 +7,7 @@ use crate::{
 };
 use async_trait::async_trait;
 use log::error;
-use lsp_types::InitializeParams;
+use lsp_types::{InitializeParams, TraceValue};
 use notify_debouncer_mini::DebouncedEvent;
 use std::{error::Error, path::Path, process::Stdio};
 use tokio::{process::Command, sync::broadcast::Receiver};
@@ -19,6 +19,10 @@ pub struct GoplsClient {
 }
 #[async_trait]
 impl LspClient for GoplsClient {
+    async fn set_trace(&mut self, value: TraceValue) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send_set_trace(value).await
+    }
+
     fn get_process(&mut self) -> &mut ProcessHandler {
         &mut self.process
     }
@@ -3,7 +3,7 @@ use std::{error::Error, os::unix::fs::PermissionsExt, path::Path, process::Stdio
 use async_trait::async_trait;
 use glob::glob;
 use log::debug;
-use lsp_types::InitializeResult;
+use lsp_types::{InitializeResult, TraceValue};
 use notify_debouncer_mini::DebouncedEvent;
 use tokio::{process::Command, sync::broadcast::Receiver};
 
@@ -24,6 +24,10 @@ pub struct JdtlsClient {
 
 #[async_trait]
 impl LspClient for JdtlsClient {
+    async fn set_trace(&mut self, value: TraceValue) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send_set_trace(value).await
+    }
+
     fn get_process(&mut self) -> &mut ProcessHandler {
         &mut self.process
     }
@@ -76,14 +80,20 @@ impl LspClient for JdtlsClient {
     }
 }
 
+/// The directory JDT LS keeps its workspace data in when a single server covers the workspace.
+pub const JDTLS_WORKSPACE_DIR: &str = "/usr/src/app/jdtls_workspace";
+
 impl JdtlsClient {
+    /// Starts JDT LS for the project at `root_path`, keeping its workspace data in `data_dir`.
+    /// Servers running side by side, e.g. one per module of a multi-module build, each need
+    /// their own data directory.
     pub async fn new(
         root_path: &str,
         watch_events_rx: Receiver<DebouncedEvent>,
+        data_dir: &Path,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let workspace_dir = Path::new("/usr/src/app/jdtls_workspace");
-        tokio::fs::create_dir_all(&workspace_dir).await?;
-        tokio::fs::set_permissions(&workspace_dir, PermissionsExt::from_mode(0o777)).await?;
+        tokio::fs::create_dir_all(data_dir).await?;
+        tokio::fs::set_permissions(data_dir, PermissionsExt::from_mode(0o777)).await?;
 
         // Find the launcher jar dynamically
         let launcher_pattern = "/opt/jdtls/plugins/org.eclipse.equinox.launcher_*.jar";
@@ -121,7 +131,7 @@ impl JdtlsClient {
             .arg("-configuration")
             .arg("/opt/jdtls/config_linux")
             .arg("-data")
-            .arg(workspace_dir)
+            .arg(data_dir)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
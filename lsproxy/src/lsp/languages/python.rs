@@ -1,14 +1,25 @@
-use std::{path::Path, process::Stdio};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
 use async_trait::async_trait;
+use lsp_types::{
+    DocumentLink, Position, PrepareRenameResponse, Range, TextEdit, TraceValue, WorkspaceEdit,
+};
 use notify_debouncer_mini::DebouncedEvent;
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 use tokio::sync::broadcast::Receiver;
 
 use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
 
+use crate::utils::document_links::find_url_links;
+use crate::utils::identifiers::PYTHON_KEYWORDS;
+use crate::utils::text_edits::{end_position, text_in_range, word_range_at};
 use crate::utils::workspace_documents::{
-    DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
+    DidOpenConfiguration, WorkspaceDocuments, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
     PYTHON_FILE_PATTERNS, PYTHON_ROOT_FILES,
 };
 
@@ -21,6 +32,10 @@ pub struct JediClient {
 
 #[async_trait]
 impl LspClient for JediClient {
+    async fn set_trace(&mut self, value: TraceValue) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send_set_trace(value).await
+    }
+
     fn get_process(&mut self) -> &mut ProcessHandler {
         &mut self.process
     }
@@ -37,9 +52,101 @@ impl LspClient for JediClient {
         &mut self.workspace_documents
     }
 
+    /// jedi-language-server doesn't support document links, so the URLs in the document are returned instead.
+    async fn text_document_document_link(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Vec<DocumentLink>, Box<dyn Error + Send + Sync>> {
+        let content = self
+            .get_workspace_documents()
+            .read_text_document(&PathBuf::from(file_path), None, None)
+            .await?;
+        Ok(find_url_links(&content))
+    }
+
+    /// jedi-language-server doesn't support prepare rename, so any identifier that isn't a
+    /// keyword is considered renameable.
+    async fn text_document_prepare_rename(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Option<PrepareRenameResponse>, Box<dyn Error + Send + Sync>> {
+        let content = self
+            .get_workspace_documents()
+            .read_text_document(&PathBuf::from(file_path), None, None)
+            .await?;
+        let Some(range) = word_range_at(&content, position) else {
+            return Ok(None);
+        };
+        let word = text_in_range(&content, range);
+        if PYTHON_KEYWORDS.contains(&word) || word.starts_with(|c: char| c.is_numeric()) {
+            return Ok(None);
+        }
+        Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range,
+            placeholder: word.to_string(),
+        }))
+    }
+
+    async fn workspace_will_rename_files(
+        &mut self,
+        renames: Vec<(String, String)>,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        self.send_will_rename_files(renames).await
+    }
+
     fn get_pending_requests(&mut self) -> &mut PendingRequests {
         &mut self.pending_requests
     }
+
+    /// jedi-language-server doesn't support formatting, so the document is formatted with ruff and
+    /// returned as a single edit replacing the whole document.
+    async fn text_document_formatting(
+        &mut self,
+        file_path: &str,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> Result<Vec<TextEdit>, Box<dyn Error + Send + Sync>> {
+        let original = self
+            .get_workspace_documents()
+            .read_text_document(&PathBuf::from(file_path), None, None)
+            .await?;
+
+        let indent_style = if insert_spaces { "space" } else { "tab" };
+        let mut process = Command::new("ruff")
+            .arg("format")
+            .arg("--config")
+            .arg(format!("indent-width = {}", tab_size))
+            .arg("--config")
+            .arg(format!("format.indent-style = \"{}\"", indent_style))
+            .arg("--stdin-filename")
+            .arg(file_path)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = process.stdin.take() {
+            stdin.write_all(original.as_bytes()).await?;
+        }
+        let output = process.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(format!(
+                "ruff format failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into());
+        }
+
+        let formatted = String::from_utf8(output.stdout)?;
+        if formatted == original {
+            return Ok(Vec::new());
+        }
+        Ok(vec![TextEdit {
+            range: Range::new(Position::new(0, 0), end_position(&original)),
+            new_text: formatted,
+        }])
+    }
 }
 
 impl JediClient {
@@ -2,8 +2,8 @@ use std::{error::Error, path::Path, process::Stdio};
 
 use async_trait::async_trait;
 use lsp_types::{
-    ClientCapabilities, DocumentSymbolClientCapabilities, InitializeParams,
-    TextDocumentClientCapabilities,
+    ClientCapabilities, CodeLensClientCapabilities, DocumentSymbolClientCapabilities,
+    InitializeParams, RenameClientCapabilities, TextDocumentClientCapabilities, WorkspaceFolder,
 };
 use notify_debouncer_mini::DebouncedEvent;
 use tokio::process::Command;
@@ -12,6 +12,7 @@ use url::Url;
 
 use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
 
+use crate::utils::file_utils::parse_cargo_workspace;
 use crate::utils::workspace_documents::{
     DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS, RUST_FILE_PATTERNS,
     RUST_ROOT_FILES,
@@ -27,33 +28,57 @@ pub struct RustAnalyzerClient {
 #[async_trait]
 impl LspClient for RustAnalyzerClient {
     fn get_capabilities(&mut self) -> ClientCapabilities {
-        let mut capabilities = ClientCapabilities::default();
-        capabilities.text_document = Some(TextDocumentClientCapabilities {
-            document_symbol: Some(DocumentSymbolClientCapabilities {
-                hierarchical_document_symbol_support: Some(true),
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                document_symbol: Some(DocumentSymbolClientCapabilities {
+                    hierarchical_document_symbol_support: Some(true),
+                    ..Default::default()
+                }),
+                code_lens: Some(CodeLensClientCapabilities {
+                    dynamic_registration: Some(false),
+                }),
+                rename: Some(RenameClientCapabilities {
+                    dynamic_registration: Some(false),
+                    prepare_support: Some(true),
+                    ..Default::default()
+                }),
                 ..Default::default()
             }),
+            // rust-analyzer only emits run/debug/references lenses for commands the client declares
+            experimental: Some(serde_json::json!({
+                "serverStatusNotification": true,
+                "commands": {
+                    "commands": [
+                        "rust-analyzer.runSingle",
+                        "rust-analyzer.debugSingle",
+                        "rust-analyzer.showReferences",
+                        "rust-analyzer.gotoLocation"
+                    ]
+                }
+            })),
             ..Default::default()
-        });
-
-        capabilities.experimental = Some(serde_json::json!({
-            "serverStatusNotification": true
-        }));
-        capabilities
+        }
     }
 
     async fn get_initialize_params(
         &mut self,
         root_path: String,
     ) -> Result<InitializeParams, Box<dyn Error + Send + Sync>> {
+        let root_uri = Url::from_file_path(&root_path).map_err(|_| "Invalid root path")?;
+        // The members of a Cargo workspace are loaded through the workspace manifest, so they
+        // must not also be opened as separate projects
+        let workspace_folders = if parse_cargo_workspace(Path::new(&root_path)).is_empty() {
+            self.find_workspace_folders(root_path.clone()).await?
+        } else {
+            vec![WorkspaceFolder {
+                uri: root_uri.clone(),
+                name: root_path.clone(),
+            }]
+        };
         Ok(InitializeParams {
             capabilities: self.get_capabilities(),
-            workspace_folders: Some(
-                self.find_workspace_folders(root_path.clone())
-                    .await
-                    .unwrap(),
-            ),
-            root_uri: Some(Url::from_file_path(&root_path).map_err(|_| "Invalid root path")?),
+            workspace_folders: Some(workspace_folders),
+            root_uri: Some(root_uri),
             initialization_options: Some(serde_json::json!({
                 "cargo": {
                     "sysroot": serde_json::Value::Null
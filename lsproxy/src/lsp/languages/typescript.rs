@@ -1,9 +1,10 @@
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 use async_trait::async_trait;
-use lsp_types::InitializeParams;
+use log::{debug, warn};
+use lsp_types::{DocumentLink, InitializeParams, TraceValue, WorkspaceEdit};
 use notify_debouncer_mini::DebouncedEvent;
 use tokio::process::Command;
 use tokio::sync::broadcast::Receiver;
@@ -11,8 +12,10 @@ use url::Url;
 
 use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
 
+use crate::utils::document_links::find_url_links;
+use crate::utils::file_utils::{parse_npm_workspace, search_files};
 use crate::utils::workspace_documents::{
-    DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
+    DidOpenConfiguration, WorkspaceDocuments, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
     TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS, TYPESCRIPT_AND_JAVASCRIPT_ROOT_FILES,
 };
 
@@ -25,6 +28,10 @@ pub struct TypeScriptLanguageClient {
 
 #[async_trait]
 impl LspClient for TypeScriptLanguageClient {
+    async fn set_trace(&mut self, value: TraceValue) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.send_set_trace(value).await
+    }
+
     fn get_process(&mut self) -> &mut ProcessHandler {
         &mut self.process
     }
@@ -40,6 +47,13 @@ impl LspClient for TypeScriptLanguageClient {
             .collect()
     }
 
+    async fn workspace_will_rename_files(
+        &mut self,
+        renames: Vec<(String, String)>,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        self.send_will_rename_files(renames).await
+    }
+
     fn get_pending_requests(&mut self) -> &mut PendingRequests {
         &mut self.pending_requests
     }
@@ -48,6 +62,48 @@ impl LspClient for TypeScriptLanguageClient {
         &mut self.workspace_documents
     }
 
+    /// typescript-language-server doesn't support document links, so the URLs in the document are returned instead.
+    async fn text_document_document_link(
+        &mut self,
+        file_path: &str,
+    ) -> Result<Vec<DocumentLink>, Box<dyn Error + Send + Sync>> {
+        let content = self
+            .get_workspace_documents()
+            .read_text_document(&PathBuf::from(file_path), None, None)
+            .await?;
+        Ok(find_url_links(&content))
+    }
+
+    /// tsserver only loads the project of a package once one of its files is opened, so in an
+    /// npm/Yarn workspace a file of every package is opened to load each package's tsconfig.
+    /// A package whose file fails to open is logged and skipped.
+    async fn setup_workspace(
+        &mut self,
+        root_path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for package in parse_npm_workspace(Path::new(root_path)) {
+            let files = search_files(
+                &package,
+                TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS
+                    .iter()
+                    .map(|&s| s.to_string())
+                    .collect(),
+                DEFAULT_EXCLUDE_PATTERNS
+                    .iter()
+                    .map(|&s| s.to_string())
+                    .collect(),
+                true,
+            )?;
+            if let Some(file) = files.iter().min() {
+                debug!("Opening {:?} to load workspace package {:?}", file, package);
+                if let Err(e) = self.did_open_if_needed(&file.to_string_lossy()).await {
+                    warn!("Failed to load workspace package {:?}: {}", package, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn get_initialize_params(
         &mut self,
         root_path: String,
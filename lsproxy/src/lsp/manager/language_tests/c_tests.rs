@@ -16,6 +16,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 30,
                 character: 5,
             },
+            true,
         )
         .await?;
 
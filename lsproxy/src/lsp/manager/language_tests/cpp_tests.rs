@@ -211,3 +211,198 @@ async fn test_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(symbol_response, expected);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_cpp_file_symbols_header() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&cpp_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    sleep(Duration::from_secs(5)).await;
+
+    // The sample has no .h files; point.cpp is only ever #included, like a header
+    let file_path = "cpp_classes/point.cpp";
+    let file_symbols = manager.definitions_in_file_ast_grep(file_path).await?;
+    let symbol_response: SymbolResponse = file_symbols.into_iter().map(Symbol::from).collect();
+
+    let expected = vec![
+        Symbol {
+            name: String::from("point"),
+            kind: String::from("class"),
+            identifier_position: FilePosition {
+                path: String::from(file_path),
+                position: Position {
+                    line: 0,
+                    character: 6,
+                },
+            },
+            file_range: FileRange {
+                path: String::from(file_path),
+                range: Range {
+                    start: Position {
+                        line: 0,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 6,
+                        character: 1,
+                    },
+                },
+            },
+        },
+        Symbol {
+            name: String::from("point"),
+            kind: String::from("function-definition"),
+            identifier_position: FilePosition {
+                path: String::from(file_path),
+                position: Position {
+                    line: 2,
+                    character: 4,
+                },
+            },
+            file_range: FileRange {
+                path: String::from(file_path),
+                range: Range {
+                    start: Position {
+                        line: 2,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 51,
+                    },
+                },
+            },
+        },
+    ];
+    assert_eq!(symbol_response, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cpp_find_definition_from_call_site() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&cpp_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    sleep(Duration::from_secs(5)).await;
+
+    // `search` in `if( as.search( s, e, m ) ) {`
+    let def_response = manager
+        .find_definition(
+            "astar_search.cpp",
+            lsp_types::Position {
+                line: 7,
+                character: 11,
+            },
+        )
+        .await?;
+
+    let definitions = match def_response {
+        GotoDefinitionResponse::Scalar(loc) => vec![loc],
+        GotoDefinitionResponse::Array(locs) => locs,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_range,
+            })
+            .collect(),
+    };
+
+    let expected = vec![Location {
+        uri: Url::from_file_path(format!("{}/cpp_classes/astar.cpp", cpp_sample_path())).unwrap(),
+        range: lsp_types::Range {
+            start: lsp_types::Position {
+                line: 67,
+                character: 9,
+            },
+            end: lsp_types::Position {
+                line: 67,
+                character: 15,
+            },
+        },
+    }];
+    assert_eq!(definitions, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cpp_find_references_for_class() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&cpp_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    sleep(Duration::from_secs(5)).await;
+
+    // `point` in `point s, e( 7, 7 );`
+    let references = manager
+        .find_references(
+            "astar_search.cpp",
+            lsp_types::Position {
+                line: 4,
+                character: 4,
+            },
+            true,
+        )
+        .await?;
+
+    let location = |path: &str, line, character| Location {
+        uri: Url::from_file_path(format!("{}/{}", cpp_sample_path(), path)).unwrap(),
+        range: lsp_types::Range {
+            start: lsp_types::Position { line, character },
+            end: lsp_types::Position {
+                line,
+                character: character + 5,
+            },
+        },
+    };
+    for expected in [
+        // The declaration
+        location("cpp_classes/point.cpp", 0, 6),
+        // `point pos, parent;`
+        location("cpp_classes/node.cpp", 5, 4),
+        // `point neighbours[8];`
+        location("cpp_classes/astar.cpp", 98, 4),
+        // `point s, e( 7, 7 );`
+        location("astar_search.cpp", 4, 4),
+    ] {
+        assert!(
+            references.contains(&expected),
+            "missing reference {:?} in {:?}",
+            expected,
+            references
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cpp_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&cpp_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    sleep(Duration::from_secs(5)).await;
+
+    // The `search` method; there are no C++ reference rules yet
+    let result = manager
+        .find_referenced_symbols(
+            "cpp_classes/astar.cpp",
+            lsp_types::Position {
+                line: 67,
+                character: 9,
+            },
+            false,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(crate::lsp::manager::LspManagerError::NotImplemented(_))
+    ));
+    Ok(())
+}
@@ -252,6 +252,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 58,
                 character: 5,
             },
+            true,
         )
         .await?;
 
@@ -437,3 +438,193 @@ async fn test_definition() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(definitions, expected);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_go_start_manager() -> Result<(), Box<dyn std::error::Error>> {
+    TestContext::setup(&go_sample_path(), true).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_go_workspace_files() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&go_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let result = manager.list_files().await?;
+    let expected = vec![
+        "golang_astar/astar.go",
+        "golang_astar/grid.go",
+        "golang_astar/node.go",
+        "golang_astar/search.go",
+        "main.go",
+    ];
+    assert_eq!(result, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_go_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&go_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let mut symbols = Vec::new();
+    for file_path in ["golang_astar/node.go", "golang_astar/grid.go"] {
+        symbols.extend(
+            manager
+                .definitions_in_file_ast_grep(file_path)
+                .await?
+                .into_iter()
+                .map(Symbol::from),
+        );
+    }
+    let found: Vec<(&str, &str)> = symbols
+        .iter()
+        .map(|symbol| (symbol.name.as_str(), symbol.kind.as_str()))
+        .collect();
+
+    // Structs and other type declarations, e.g. interfaces, are all `type` symbols. The sample
+    // declares no interfaces.
+    for expected in [
+        ("Node", "type"),
+        ("Arc", "type"),
+        ("Cost", "type"),
+        ("Grid", "type"),
+        ("NewGrid", "function"),
+        ("Equal", "method"),
+        ("Heuristic", "method"),
+        ("GetNeighbors", "method"),
+    ] {
+        assert!(
+            found.contains(&expected),
+            "missing symbol {:?} in {:?}",
+            expected,
+            found
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_go_find_definition() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&go_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    // `Node` in `start := golang_astar.Node{0, 0}`
+    let def_response = manager
+        .find_definition(
+            "main.go",
+            lsp_types::Position {
+                line: 21,
+                character: 23,
+            },
+        )
+        .await?;
+
+    let definitions = match def_response {
+        GotoDefinitionResponse::Scalar(loc) => vec![loc],
+        GotoDefinitionResponse::Array(locs) => locs,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| Location {
+                uri: link.target_uri,
+                range: link.target_range,
+            })
+            .collect(),
+    };
+
+    let expected = vec![Location {
+        uri: format!("file://{}/golang_astar/node.go", go_sample_path())
+            .parse()
+            .unwrap(),
+        range: Range {
+            start: lsp_types::Position {
+                line: 5,
+                character: 5,
+            },
+            end: lsp_types::Position {
+                line: 5,
+                character: 9,
+            },
+        },
+    }];
+    assert_eq!(definitions, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_go_find_references() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&go_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    // The `NewGrid` function declaration
+    let mut references = manager
+        .find_references(
+            "golang_astar/grid.go",
+            lsp_types::Position {
+                line: 10,
+                character: 5,
+            },
+            true,
+        )
+        .await?;
+    references.sort_by_key(|location| location.uri.to_string());
+
+    let location = |path: &str, line, character| Location {
+        uri: format!("file://{}/{}", go_sample_path(), path)
+            .parse()
+            .unwrap(),
+        range: Range {
+            start: lsp_types::Position { line, character },
+            end: lsp_types::Position {
+                line,
+                character: character + 7,
+            },
+        },
+    };
+    let expected = vec![
+        location("golang_astar/grid.go", 10, 5),
+        // `grid := golang_astar.NewGrid(8, 8)`
+        location("main.go", 9, 22),
+    ];
+    assert_eq!(references, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_go_referenced_symbols() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&go_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    // The `FindPath` function; there are no Go reference rules yet, so workspace and
+    // standard-library symbols can't be told apart
+    let result = manager
+        .find_referenced_symbols(
+            "golang_astar/search.go",
+            lsp_types::Position {
+                line: 57,
+                character: 5,
+            },
+            false,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(crate::lsp::manager::LspManagerError::NotImplemented(_))
+    ));
+    Ok(())
+}
@@ -182,6 +182,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 10,
                 character: 13,
             },
+            true,
         )
         .await?;
 
@@ -270,3 +271,45 @@ async fn test_definition() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(definitions, expected);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_definition_in_auto_opened_file() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&java_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    // `Node` in `List<Node> path = as.findPathTo(7, 7);`, declared in Node.java
+    let definition_response = manager
+        .find_definition(
+            "Main.java",
+            LspPosition {
+                line: 19,
+                character: 13,
+            },
+        )
+        .await?;
+
+    let definitions = match definition_response {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(_links) => Vec::new(),
+    };
+    let expected = vec![Location {
+        uri: Url::parse("file:///mnt/lsproxy_root/sample_project/java/Node.java").unwrap(),
+        range: LspRange {
+            start: LspPosition {
+                line: 2,
+                character: 13,
+            },
+            end: LspPosition {
+                line: 2,
+                character: 17,
+            },
+        },
+    }];
+
+    assert_eq!(definitions, expected);
+    Ok(())
+}
@@ -38,6 +38,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 0,
                 character: 9,
             },
+            true,
         )
         .await?;
 
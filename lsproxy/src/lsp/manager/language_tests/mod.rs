@@ -3,8 +3,8 @@ use tokio::time::{sleep, Duration};
 
 use crate::test_utils::{
     c_sample_path, cpp_sample_path, csharp_sample_path, go_sample_path, java_sample_path,
-    js_sample_path, php_sample_path, python_sample_path, rust_sample_path, typescript_sample_path,
-    TestContext,
+    js_sample_path, php_sample_path, python_sample_path, ruby_sample_path, rust_sample_path,
+    typescript_sample_path, TestContext,
 };
 
 use crate::api_types::{FilePosition, FileRange, Position, Symbol, SymbolResponse};
@@ -20,3 +20,44 @@ mod python_tests;
 mod rust_tests;
 mod tsx_tests;
 mod typescript_tests;
+
+#[tokio::test]
+async fn test_definitions_in_file_ast_grep_paths_are_relative(
+) -> Result<(), Box<dyn std::error::Error>> {
+    for sample_path in [
+        c_sample_path(),
+        cpp_sample_path(),
+        csharp_sample_path(),
+        go_sample_path(),
+        java_sample_path(),
+        js_sample_path(),
+        php_sample_path(),
+        python_sample_path(),
+        ruby_sample_path(),
+        rust_sample_path(),
+        typescript_sample_path(),
+    ] {
+        let context = TestContext::setup(&sample_path, true).await?;
+        let manager = context
+            .manager
+            .as_ref()
+            .ok_or("Manager is not initialized")?;
+
+        for file_path in manager.list_files().await? {
+            let symbols = manager.definitions_in_file_ast_grep(&file_path).await?;
+            for symbol in symbols.into_iter().map(Symbol::from) {
+                for path in [&symbol.identifier_position.path, &symbol.file_range.path] {
+                    assert!(
+                        !path.starts_with('/'),
+                        "absolute path {} for {} in {}",
+                        path,
+                        symbol.name,
+                        sample_path
+                    );
+                    assert_eq!(path, &file_path);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -422,6 +422,7 @@ async fn test_php_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 3,
                 character: 6,
             },
+            true,
         )
         .await?;
 
@@ -1,4 +1,5 @@
 use crate::api_types;
+use crate::lsp::manager::{Manager, ManagerConfig};
 
 use super::*;
 
@@ -764,6 +765,77 @@ async fn test_file_symbols_decorators() -> Result<(), Box<dyn std::error::Error>
     Ok(())
 }
 
+#[tokio::test]
+async fn test_file_symbols_crlf() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join("crlf.py"),
+        "class User:\r\n    pass\r\n\r\n\r\ndef greet():\r\n    pass\r\n",
+    )?;
+    let context = TestContext::setup(dir.path().to_str().unwrap(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let file_symbols = manager.definitions_in_file_ast_grep("crlf.py").await?;
+
+    let symbol_response: SymbolResponse = file_symbols.into_iter().map(Symbol::from).collect();
+
+    let expected = vec![
+        Symbol {
+            name: String::from("User"),
+            kind: String::from("class"),
+            identifier_position: FilePosition {
+                path: String::from("crlf.py"),
+                position: Position {
+                    line: 0,
+                    character: 6,
+                },
+            },
+            file_range: FileRange {
+                path: String::from("crlf.py"),
+                range: api_types::Range {
+                    start: Position {
+                        line: 0,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 1,
+                        character: 8,
+                    },
+                },
+            },
+        },
+        Symbol {
+            name: String::from("greet"),
+            kind: String::from("function"),
+            identifier_position: FilePosition {
+                path: String::from("crlf.py"),
+                position: Position {
+                    line: 4,
+                    character: 4,
+                },
+            },
+            file_range: FileRange {
+                path: String::from("crlf.py"),
+                range: api_types::Range {
+                    start: Position {
+                        line: 4,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 5,
+                        character: 8,
+                    },
+                },
+            },
+        },
+    ];
+    assert_eq!(symbol_response, expected);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
     let context = TestContext::setup(&python_sample_path(), true).await?;
@@ -780,6 +852,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 12,
                 character: 6,
             },
+            true,
         )
         .await?;
 
@@ -923,3 +996,144 @@ async fn test_definition() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_custom_ast_grep_config() -> Result<(), Box<dyn std::error::Error>> {
+    let _context = TestContext::setup(&python_sample_path(), false).await?;
+
+    // A rule set that only knows about python functions, under its own rule id
+    let config_dir = tempfile::tempdir()?;
+    let rules_dir = config_dir.path().join("symbol/rules");
+    std::fs::create_dir_all(&rules_dir)?;
+    std::fs::write(
+        config_dir.path().join("symbol/config.yml"),
+        "ruleDirs:\n  - rules\n",
+    )?;
+    let function_rule =
+        std::fs::read_to_string("/usr/src/ast_grep/symbol/rules/python/function.yml")?;
+    std::fs::write(
+        rules_dir.join("function.yml"),
+        function_rule.replace("id: function", "id: custom-function"),
+    )?;
+
+    let manager = Manager::new(
+        &python_sample_path(),
+        ManagerConfig {
+            ast_grep_config_dirs: vec![config_dir.path().to_path_buf()],
+            ..Default::default()
+        },
+    )
+    .await?;
+    let symbols = manager.definitions_in_file_ast_grep("graph.py").await?;
+
+    assert!(!symbols.is_empty());
+    assert!(symbols.iter().all(|s| s.rule_id == "custom-function"));
+    assert!(symbols
+        .iter()
+        .any(|s| s.meta_variables.single.name.text == "__init__"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_definitions_invalidated_on_write() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let workspace = dir.path().to_str().unwrap();
+    std::fs::write(dir.path().join("main.py"), "def first():\n    pass\n")?;
+    let context = TestContext::setup(workspace, true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let names = |symbols: Vec<crate::ast_grep::types::AstGrepMatch>| -> Vec<String> {
+        symbols
+            .into_iter()
+            .map(|s| s.meta_variables.single.name.text)
+            .collect()
+    };
+
+    let symbols = manager
+        .definitions_in_file_ast_grep_cached("main.py")
+        .await?;
+    assert_eq!(names(symbols), vec!["first"]);
+
+    std::fs::write(
+        dir.path().join("main.py"),
+        "def first():\n    pass\n\n\ndef second():\n    pass\n",
+    )?;
+
+    // Wait for the debounced watch event to evict the cached entry
+    sleep(Duration::from_secs(4)).await;
+    let symbols = manager
+        .definitions_in_file_ast_grep_cached("main.py")
+        .await?;
+    assert_eq!(names(symbols), vec!["first", "second"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_clear_file_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let workspace = dir.path().to_str().unwrap();
+    std::fs::write(dir.path().join("main.py"), "def first():\n    pass\n")?;
+    let context = TestContext::setup(workspace, true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let symbols = manager
+        .definitions_in_file_ast_grep_cached("main.py")
+        .await?;
+    assert_eq!(symbols.len(), 1);
+
+    std::fs::write(
+        dir.path().join("main.py"),
+        "def first():\n    pass\n\n\ndef second():\n    pass\n",
+    )?;
+    // Evict explicitly instead of waiting for the watcher
+    manager.clear_file_cache("main.py").await;
+    let symbols = manager
+        .definitions_in_file_ast_grep_cached("main.py")
+        .await?;
+    assert_eq!(symbols.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_referenced_symbols_full_scan_is_superset() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&python_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    // `a_star_search` in search.py
+    let position = lsp_types::Position {
+        line: 16,
+        character: 4,
+    };
+
+    let reference_positions = |full_scan: bool| async move {
+        manager
+            .find_referenced_symbols("search.py", position, full_scan)
+            .await
+            .map(|references| {
+                references
+                    .iter()
+                    .map(|(reference, _)| {
+                        let start = reference.get_identifier_range().start;
+                        (start.line, start.column)
+                    })
+                    .collect::<Vec<_>>()
+            })
+    };
+    let targeted = reference_positions(false).await?;
+    let full = reference_positions(true).await?;
+
+    assert!(!targeted.is_empty());
+    assert!(targeted.iter().all(|position| full.contains(position)));
+    // The full scan also covers `initialize_search`, defined above `a_star_search`
+    assert!(full.iter().any(|(line, _)| *line < 15));
+    assert!(targeted.iter().all(|(line, _)| *line >= 15));
+    Ok(())
+}
@@ -160,6 +160,7 @@ async fn test_references() -> Result<(), Box<dyn std::error::Error>> {
                 line: 3,
                 character: 11,
             },
+            true,
         )
         .await?;
 
@@ -346,3 +347,30 @@ async fn test_definition() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_semantic_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let context = TestContext::setup(&rust_sample_path(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+
+    let tokens = manager.semantic_tokens("src/main.rs").await?;
+
+    // `fn main() {` is on line 10
+    let fn_token = tokens
+        .iter()
+        .find(|token| token.line == 10 && token.character == 0)
+        .ok_or("No token for the fn keyword")?;
+    assert_eq!(fn_token.length, 2);
+    assert_eq!(fn_token.token_type, "keyword");
+
+    let main_token = tokens
+        .iter()
+        .find(|token| token.line == 10 && token.character == 3)
+        .ok_or("No token for main")?;
+    assert_eq!(main_token.token_type, "function");
+    assert!(main_token.modifiers.contains(&String::from("declaration")));
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::api_types;
+use crate::utils::workspace_documents::WorkspaceDocuments;
 
 use super::*;
 
@@ -117,3 +118,46 @@ async fn test_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(symbol_response, expected);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_workspace_packages_opened() -> Result<(), Box<dyn std::error::Error>> {
+    // The default `.tmp` prefix would hide the workspace from the `**/.*` exclude pattern
+    let dir = tempfile::Builder::new().prefix("lsproxy-").tempdir()?;
+    let root = dir.path();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{ "workspaces": ["packages/*"] }"#,
+    )?;
+    for package in ["packages/core", "packages/ui"] {
+        std::fs::create_dir_all(root.join(package).join("src"))?;
+        std::fs::write(root.join(package).join("package.json"), "{}")?;
+        std::fs::write(root.join(package).join("tsconfig.json"), "{}")?;
+        std::fs::write(
+            root.join(package).join("src/index.ts"),
+            "export const name = 'package';\n",
+        )?;
+        std::fs::write(
+            root.join(package).join("src/util.ts"),
+            "export const util = 1;\n",
+        )?;
+    }
+    let context = TestContext::setup(root.to_str().unwrap(), true).await?;
+    let manager = context
+        .manager
+        .as_ref()
+        .ok_or("Manager is not initialized")?;
+    let client = manager
+        .get_client(crate::api_types::SupportedLanguages::TypeScriptJavaScript)
+        .ok_or("TypeScript client is not started")?;
+    let mut client = client.lock().await;
+
+    // The first file of every package is opened, and only that one
+    for package in ["packages/core", "packages/ui"] {
+        let documents = client.get_workspace_documents();
+        let index = root.join(package).join("src/index.ts");
+        let util = root.join(package).join("src/util.ts");
+        assert!(documents.is_did_open_document(index.to_str().unwrap()));
+        assert!(!documents.is_did_open_document(util.to_str().unwrap()));
+    }
+    Ok(())
+}
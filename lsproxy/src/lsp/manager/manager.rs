@@ -1,49 +1,297 @@
-use crate::api_types::{get_mount_dir, Identifier, SupportedLanguages, Symbol};
-use crate::ast_grep::client::AstGrepClient;
+use crate::api_types::{
+    get_mount_dir, CallTree, ChangeImpactReport, CodeLens, ConsistencyReport, DocumentLink,
+    ErrorCode, FileCoupling, FileMetadata, FileMetrics, FilePosition, FileRange, FileSymbolCounts,
+    FoldingRange, Identifier, LspClientStatus, Position as ApiPosition, PrepareRenameResponse,
+    Range as ApiRange, RenameSymbolResponse, SelectionRange, SemanticToken, SupportedLanguages,
+    Symbol, SymbolCountsResponse, TextSearchMatch, UnusedImport,
+};
+use crate::ast_grep::client::{AstGrepClient, DEFAULT_AST_GREP_CONFIG_DIR};
 use crate::ast_grep::types::AstGrepMatch;
 use crate::lsp::client::LspClient;
 use crate::lsp::languages::{
     CSharpClient, ClangdClient, GoplsClient, JdtlsClient, JediClient, PhpactorClient, RubyClient,
-    RustAnalyzerClient, TypeScriptLanguageClient,
+    RustAnalyzerClient, TypeScriptLanguageClient, JDTLS_WORKSPACE_DIR,
 };
-use crate::utils::file_utils::uri_to_relative_path_string;
 use crate::utils::file_utils::{
-    absolute_path_to_relative_path_string, detect_language, search_files,
+    absolute_path_to_relative_path_string, detect_language, java_module_roots, read_file_lines,
+    search_files,
 };
+use crate::utils::file_utils::{relative_path_string, uri_to_relative_path_string};
+use crate::utils::identifiers::validate_identifier_name;
+use crate::utils::text_edits::{apply_text_edits, text_in_range, word_range_at};
 use crate::utils::workspace_documents::{
-    WorkspaceDocuments, CSHARP_FILE_PATTERNS, C_AND_CPP_FILE_PATTERNS, DEFAULT_EXCLUDE_PATTERNS,
-    GOLANG_FILE_PATTERNS, JAVA_FILE_PATTERNS, PHP_FILE_PATTERNS, PYTHON_FILE_PATTERNS,
-    RUBY_FILE_PATTERNS, RUST_FILE_PATTERNS, TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS,
+    crlf_line_lengths, language_file_patterns, max_source_code_bytes, FileHashes,
+    WorkspaceDocuments, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS, TRUNCATION_MARKER,
 };
+use crate::utils::workspace_edit::{apply_workspace_edit, ApplyEditError};
+use futures::future::LocalBoxFuture;
 use log::{debug, error, warn};
-use lsp_types::{GotoDefinitionResponse, Location, Position, Range};
-use notify::RecursiveMode;
-use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent};
-use std::collections::HashMap;
+use lsp_types::{
+    CodeActionContext, CodeActionKind, CodeActionResponse, CodeActionTriggerKind, FileChangeType,
+    FileEvent, GotoDefinitionResponse, InitializeResult, Location, OneOf, Position, Range,
+    SemanticTokensLegend, SemanticTokensServerCapabilities, TextEdit, TraceValue, Url,
+    WorkspaceSymbolResponse,
+};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::broadcast::{channel, Sender};
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+use tokio::sync::broadcast::{channel, Receiver, Sender};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::timeout;
+
+/// A language server client shared between requests.
+type SharedClient = Arc<Mutex<Box<dyn LspClient>>>;
+
+/// Ranges spanning fewer lines than this are read line by line rather than from the whole file.
+const LINE_RANGE_READ_THRESHOLD: u32 = 1000;
+
+/// How long computed file metrics are reused before being recomputed.
+const FILE_METRICS_TTL: Duration = Duration::from_secs(60);
+
+/// How long workspace symbol counts are reused, unless a file changes first.
+const SYMBOL_COUNTS_TTL: Duration = Duration::from_secs(120);
+
+/// How long cached ast-grep definitions are reused, unless the file changes first.
+const AST_GREP_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// How long a code action request waits for the server to publish a file's diagnostics.
+const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of file pairs returned by a coupling analysis.
+const MAX_COUPLING_PAIRS: usize = 100;
+
+/// Default time a language server gets to initialize and set up its workspace.
+pub const DEFAULT_INITIALIZATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default maximum number of Java files opened in JDT LS once it has started.
+pub const DEFAULT_AUTO_OPEN_JAVA_FILES_LIMIT: usize = 500;
+
+/// Default naming conventions of test files, across the supported languages.
+pub const DEFAULT_TEST_FILE_PATTERNS: &[&str] = &[
+    "**/test_*.py",
+    "**/*_test.py",
+    "**/conftest.py",
+    "**/*.test.[jt]s",
+    "**/*.test.[jt]sx",
+    "**/*.spec.[jt]s",
+    "**/*.spec.[jt]sx",
+    "**/*_test.rs",
+    "**/*Test.java",
+    "**/*Tests.java",
+    "**/*_test.go",
+    "**/*Test.php",
+    "**/*_spec.rb",
+    "**/*_test.rb",
+    "**/*Tests.cs",
+    "**/*_test.cpp",
+    "**/*_test.cc",
+];
+
+/// Naming conventions marking the symbols of a language as tests, on top of the tests the
+/// ast-grep test rules find from attributes and framework calls.
+struct TestSymbolConfig {
+    /// Kinds of symbols the conventions apply to, e.g. `class`
+    kinds: &'static [&'static str],
+    prefixes: &'static [&'static str],
+    suffixes: &'static [&'static str],
+}
+
+impl TestSymbolConfig {
+    fn for_language(language: SupportedLanguages) -> Option<Self> {
+        match language {
+            // pytest collects `Test*` classes, their methods are matched by the test rules
+            SupportedLanguages::Python => Some(Self {
+                kinds: &["class"],
+                prefixes: &["Test"],
+                suffixes: &[],
+            }),
+            SupportedLanguages::Java => Some(Self {
+                kinds: &["class"],
+                prefixes: &[],
+                suffixes: &["Test", "Tests"],
+            }),
+            SupportedLanguages::Golang => Some(Self {
+                kinds: &["function"],
+                prefixes: &["Test", "Benchmark", "Fuzz"],
+                suffixes: &[],
+            }),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, symbol: &AstGrepMatch) -> bool {
+        let name = &symbol.meta_variables.single.name.text;
+        self.kinds.contains(&symbol.rule_id.as_str())
+            && (self.prefixes.iter().any(|prefix| name.starts_with(prefix))
+                || self.suffixes.iter().any(|suffix| name.ends_with(suffix)))
+    }
+}
+
+/// Cached ast-grep definitions per relative file path, with the time they were computed.
+type AstGrepCache = Arc<RwLock<HashMap<String, (Instant, Vec<AstGrepMatch>)>>>;
+
+/// Startup configuration for a [`Manager`].
+#[derive(Debug, Clone)]
+pub struct ManagerConfig {
+    /// Directories containing the ast-grep `symbol`, `identifier`, `reference`, `comment`,
+    /// `test` and `import` rule sets, e.g. one per language. Each file is scanned with the rules
+    /// of the first directory that has rules for its language.
+    pub ast_grep_config_dirs: Vec<PathBuf>,
+    /// How long each language server gets to initialize and set up its workspace.
+    pub initialization_timeout: Duration,
+    /// Whether to open the workspace's Java files once JDT LS has started, which makes its
+    /// results more accurate.
+    pub auto_open_java_files: bool,
+    /// The most Java files to open when `auto_open_java_files` is set.
+    pub auto_open_java_files_limit: usize,
+    /// Glob patterns, relative to the workspace root, of the files holding tests rather than
+    /// production code.
+    pub test_file_patterns: Vec<String>,
+    /// Whether `read_source_code` reads ranges of fewer than 1000 lines straight from disk, line
+    /// by line, instead of loading the whole file. Such reads bypass the cache of open documents.
+    pub line_range_reads: bool,
+}
+
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        Self {
+            ast_grep_config_dirs: vec![PathBuf::from(DEFAULT_AST_GREP_CONFIG_DIR)],
+            initialization_timeout: DEFAULT_INITIALIZATION_TIMEOUT,
+            auto_open_java_files: true,
+            auto_open_java_files_limit: DEFAULT_AUTO_OPEN_JAVA_FILES_LIMIT,
+            test_file_patterns: DEFAULT_TEST_FILE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            line_range_reads: false,
+        }
+    }
+}
+
+impl ManagerConfig {
+    /// Resolves the configuration from an optional CLI value, then the `AST_GREP_CONFIG`
+    /// environment variable, falling back to the defaults. Several ast-grep config directories
+    /// are separated with `:`, like in `PATH`.
+    ///
+    /// The initialization timeout is read from `LSP_INITIALIZATION_TIMEOUT_SECS`, and
+    /// `AUTO_OPEN_JAVA_FILES=false` turns off opening the Java files at startup.
+    /// `LINE_RANGE_READS=true` turns on reading small ranges line by line.
+    pub fn resolve(ast_grep_config: Option<&str>) -> Self {
+        let ast_grep_config_dirs = ast_grep_config_dirs(
+            ast_grep_config
+                .map(String::from)
+                .or_else(|| std::env::var("AST_GREP_CONFIG").ok())
+                .as_deref(),
+        );
+        let initialization_timeout = parse_initialization_timeout(
+            std::env::var("LSP_INITIALIZATION_TIMEOUT_SECS")
+                .ok()
+                .as_deref(),
+        );
+        let auto_open_java_files = std::env::var("AUTO_OPEN_JAVA_FILES")
+            .map(|value| !matches!(value.trim(), "false" | "0"))
+            .unwrap_or(true);
+        let line_range_reads =
+            line_range_reads_enabled(std::env::var("LINE_RANGE_READS").ok().as_deref());
+        Self {
+            ast_grep_config_dirs,
+            initialization_timeout,
+            auto_open_java_files,
+            line_range_reads,
+            ..Default::default()
+        }
+    }
+}
+
+/// The ast-grep config directories in a `:` separated value, falling back to the default
+/// directory when it is unset or empty.
+fn ast_grep_config_dirs(value: Option<&str>) -> Vec<PathBuf> {
+    value
+        .map(|paths| {
+            std::env::split_paths(paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| vec![PathBuf::from(DEFAULT_AST_GREP_CONFIG_DIR)])
+}
+
+/// The initialization timeout set by a `LSP_INITIALIZATION_TIMEOUT_SECS` value, falling back to
+/// the default when it is unset or invalid.
+fn parse_initialization_timeout(value: Option<&str>) -> Duration {
+    let Some(value) = value else {
+        return DEFAULT_INITIALIZATION_TIMEOUT;
+    };
+    value
+        .trim()
+        .parse()
+        .map(Duration::from_secs)
+        .unwrap_or_else(|_| {
+            warn!(
+                "Invalid LSP_INITIALIZATION_TIMEOUT_SECS value '{}', using default of {:?}",
+                value, DEFAULT_INITIALIZATION_TIMEOUT
+            );
+            DEFAULT_INITIALIZATION_TIMEOUT
+        })
+}
+
+/// Whether a `LINE_RANGE_READS` value turns on reading small ranges line by line.
+fn line_range_reads_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|value| matches!(value.trim(), "true" | "1"))
+}
 
 pub struct Manager {
     lsp_clients: HashMap<SupportedLanguages, Arc<Mutex<Box<dyn LspClient>>>>,
+    /// The JDT LS clients of a multi-module Java build, keyed by module root. A Java workspace
+    /// that isn't multi-module is served by the Java client in `lsp_clients` instead.
+    java_module_clients: BTreeMap<PathBuf, SharedClient>,
     watch_events_sender: Sender<DebouncedEvent>,
-    ast_grep: AstGrepClient,
+    /// Kept alive for the lifetime of the manager; dropping it stops the file watcher.
+    _debouncer: Debouncer<RecommendedWatcher>,
+    /// One ast-grep client per config directory, see [`Self::ast_grep_for`].
+    ast_grep: Vec<AstGrepClient>,
+    file_metrics_cache: Mutex<HashMap<String, (Instant, FileMetrics)>>,
+    semantic_tokens_legends: RwLock<HashMap<SupportedLanguages, SemanticTokensLegend>>,
+    symbol_counts_cache: Arc<Mutex<Option<(Instant, SymbolCountsResponse)>>>,
+    /// How many times each language server has been restarted since the manager started.
+    restart_counts: Mutex<HashMap<SupportedLanguages, u32>>,
+    /// Held while a language server restarts, so concurrent restarts don't overlap.
+    restart_lock: Mutex<()>,
+    ast_grep_cache: AstGrepCache,
+    initialization_timeout: Duration,
+    /// How many Java files to open once JDT LS has started, if any.
+    auto_open_java_files_limit: Option<usize>,
+    /// The workspace root the language servers are started in.
+    root_path: String,
+    test_file_patterns: Vec<glob::Pattern>,
+    /// Whether small ranges are read line by line, see [`ManagerConfig::line_range_reads`].
+    line_range_reads: bool,
 }
 
 impl Manager {
-    pub async fn new(root_path: &str) -> Result<Self, Box<dyn Error>> {
+    pub async fn new(root_path: &str, config: ManagerConfig) -> Result<Self, Box<dyn Error>> {
         let (tx, _) = channel(100);
         let event_sender = tx.clone();
+        // Saves that leave a file's content unchanged aren't passed on, so caches and language
+        // servers aren't invalidated for nothing
+        let file_hashes = std::sync::Mutex::new(FileHashes::default());
         let mut debouncer = new_debouncer(
             Duration::from_secs(2),
             move |res: DebounceEventResult| match res {
                 Ok(events) => {
+                    let mut file_hashes = file_hashes.lock().unwrap_or_else(|e| e.into_inner());
                     for event in events {
-                        let _ = tx.send(event.clone());
+                        if file_hashes.update(&event.path) {
+                            let _ = tx.send(event.clone());
+                        } else {
+                            debug!("Skipping unchanged file {:?}", event.path);
+                        }
                     }
                 }
                 Err(e) => error!("Debounce error: {:?}", e),
@@ -57,63 +305,94 @@ impl Manager {
             .watch(Path::new(root_path), RecursiveMode::Recursive)
             .expect("Failed to watch path");
 
-        let ast_grep = AstGrepClient {};
+        let symbol_counts_cache = Arc::new(Mutex::new(None));
+        let symbol_counts_cache_clone = Arc::clone(&symbol_counts_cache);
+        let mut watch_events_rx = event_sender.subscribe();
+        tokio::spawn(async move {
+            while let Ok(event) = watch_events_rx.recv().await {
+                debug!("Symbol counts invalidated by {:?}", event.path);
+                *symbol_counts_cache_clone.lock().await = None;
+            }
+        });
+
+        let ast_grep_cache: AstGrepCache = Arc::new(RwLock::new(HashMap::new()));
+        let ast_grep_cache_clone = Arc::clone(&ast_grep_cache);
+        let mut watch_events_rx = event_sender.subscribe();
+        let watch_root = PathBuf::from(root_path);
+        tokio::spawn(async move {
+            while let Ok(event) = watch_events_rx.recv().await {
+                let file_path = relative_path_string(&event.path, &watch_root);
+                if ast_grep_cache_clone
+                    .write()
+                    .await
+                    .remove(&file_path)
+                    .is_some()
+                {
+                    debug!("Cached definitions invalidated for {}", file_path);
+                }
+            }
+        });
+
+        let mut ast_grep: Vec<AstGrepClient> = config
+            .ast_grep_config_dirs
+            .iter()
+            .map(AstGrepClient::new)
+            .collect();
+        if ast_grep.is_empty() {
+            ast_grep.push(AstGrepClient::default());
+        }
         Ok(Self {
             lsp_clients: HashMap::new(),
+            java_module_clients: BTreeMap::new(),
             watch_events_sender: event_sender,
+            _debouncer: debouncer,
             ast_grep,
+            file_metrics_cache: Mutex::new(HashMap::new()),
+            semantic_tokens_legends: RwLock::new(HashMap::new()),
+            symbol_counts_cache,
+            restart_counts: Mutex::new(HashMap::new()),
+            restart_lock: Mutex::new(()),
+            ast_grep_cache,
+            initialization_timeout: config.initialization_timeout,
+            auto_open_java_files_limit: config
+                .auto_open_java_files
+                .then_some(config.auto_open_java_files_limit),
+            root_path: root_path.to_string(),
+            test_file_patterns: config
+                .test_file_patterns
+                .iter()
+                .filter_map(|pattern| {
+                    glob::Pattern::new(pattern)
+                        .map_err(|e| warn!("Invalid test file pattern '{}': {}", pattern, e))
+                        .ok()
+                })
+                .collect(),
+            line_range_reads: config.line_range_reads,
         })
     }
 
+    /// Whether a file, relative to the workspace root, holds tests according to the configured
+    /// test file patterns.
+    pub fn is_test_file(&self, file_path: &str) -> bool {
+        self.test_file_patterns
+            .iter()
+            .any(|pattern| pattern.matches(file_path))
+    }
+
+    /// Subscribes to debounced file-watch events for the workspace.
+    pub fn subscribe_watch_events(&self) -> Receiver<DebouncedEvent> {
+        self.watch_events_sender.subscribe()
+    }
+
     /// Detects the languages in the workspace by searching for files that match the language server's file patterns, before LSPs are started.
-    fn detect_languages_in_workspace(&self, root_path: &str) -> Vec<SupportedLanguages> {
+    ///
+    /// Returns the language servers to start, each with the root it serves: the workspace root,
+    /// or for a multi-module Java build, each module root, see [`java_module_roots`].
+    fn detect_languages_in_workspace(&self, root_path: &str) -> Vec<(SupportedLanguages, PathBuf)> {
         let mut lsps = Vec::new();
-        for lsp in [
-            SupportedLanguages::Python,
-            SupportedLanguages::TypeScriptJavaScript,
-            SupportedLanguages::Rust,
-            SupportedLanguages::CPP,
-            SupportedLanguages::CSharp,
-            SupportedLanguages::Java,
-            SupportedLanguages::Golang,
-            SupportedLanguages::PHP,
-            SupportedLanguages::Ruby,
-        ] {
-            let patterns = match lsp {
-                SupportedLanguages::Python => PYTHON_FILE_PATTERNS
-                    .iter()
-                    .map(|&s| s.to_string())
-                    .collect(),
-                SupportedLanguages::TypeScriptJavaScript => TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS
-                    .iter()
-                    .map(|&s| s.to_string())
-                    .collect(),
-                SupportedLanguages::Rust => {
-                    RUST_FILE_PATTERNS.iter().map(|&s| s.to_string()).collect()
-                }
-                SupportedLanguages::CPP => C_AND_CPP_FILE_PATTERNS
-                    .iter()
-                    .map(|&s| s.to_string())
-                    .collect(),
-                SupportedLanguages::CSharp => CSHARP_FILE_PATTERNS
-                    .iter()
-                    .map(|&s| s.to_string())
-                    .collect(),
-                SupportedLanguages::Java => {
-                    JAVA_FILE_PATTERNS.iter().map(|&s| s.to_string()).collect()
-                }
-                SupportedLanguages::Golang => GOLANG_FILE_PATTERNS
-                    .iter()
-                    .map(|&s| s.to_string())
-                    .collect(),
-                SupportedLanguages::PHP => {
-                    PHP_FILE_PATTERNS.iter().map(|&s| s.to_string()).collect()
-                }
-                SupportedLanguages::Ruby => {
-                    RUBY_FILE_PATTERNS.iter().map(|&s| s.to_string()).collect()
-                }
-            };
-            if !search_files(
+        for lsp in SupportedLanguages::iter() {
+            let patterns = language_file_patterns(lsp);
+            if search_files(
                 Path::new(root_path),
                 patterns,
                 DEFAULT_EXCLUDE_PATTERNS
@@ -126,7 +405,20 @@ impl Manager {
             .unwrap_or_default()
             .is_empty()
             {
-                lsps.push(lsp);
+                continue;
+            }
+            let module_roots = match lsp {
+                SupportedLanguages::Java => java_module_roots(Path::new(root_path)),
+                _ => Vec::new(),
+            };
+            if module_roots.is_empty() {
+                lsps.push((lsp, PathBuf::from(root_path)));
+            } else {
+                lsps.extend(
+                    module_roots
+                        .into_iter()
+                        .map(|module_root| (lsp, module_root)),
+                );
             }
         }
         debug!("Starting LSPs: {:?}", lsps);
@@ -138,325 +430,2673 @@ impl Manager {
         workspace_path: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let lsps = self.detect_languages_in_workspace(workspace_path);
-        for lsp in lsps {
-            if self.get_client(lsp).is_some() {
+        for (lsp, root) in lsps {
+            let module_root = (root != Path::new(workspace_path)).then_some(root);
+            let started = match &module_root {
+                Some(module_root) => self.java_module_clients.contains_key(module_root),
+                None => self.lsp_clients.contains_key(&lsp),
+            };
+            if started {
                 continue;
             }
-            debug!("Starting {:?} LSP", lsp);
-            let mut client: Box<dyn LspClient> = match lsp {
-                SupportedLanguages::Python => Box::new(
-                    JediClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::TypeScriptJavaScript => Box::new(
-                    TypeScriptLanguageClient::new(
-                        workspace_path,
-                        self.watch_events_sender.subscribe(),
-                    )
-                    .await
-                    .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::Rust => Box::new(
-                    RustAnalyzerClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::CPP => Box::new(
-                    ClangdClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::CSharp => Box::new(
-                    CSharpClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::Java => Box::new(
-                    JdtlsClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::Golang => Box::new(
-                    GoplsClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::PHP => Box::new(
-                    PhpactorClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-                SupportedLanguages::Ruby => Box::new(
-                    RubyClient::new(workspace_path, self.watch_events_sender.subscribe())
-                        .await
-                        .map_err(|e| e.to_string())?,
-                ),
-            };
-            client
-                .initialize(workspace_path.to_string())
+            let root_path = module_root
+                .as_ref()
+                .map_or(workspace_path.to_string(), |root| {
+                    root.to_string_lossy().into_owned()
+                });
+            debug!("Starting {} LSP in {}", lsp.display_name(), root_path);
+            let client = self
+                .create_client(lsp, &root_path, 0)
                 .await
                 .map_err(|e| e.to_string())?;
-            debug!("Setting up workspace");
-            client
-                .setup_workspace(workspace_path)
+            match self
+                .initialize_client(lsp, client, &root_path, module_root)
                 .await
-                .map_err(|e| e.to_string())?;
-            self.lsp_clients.insert(lsp, Arc::new(Mutex::new(client)));
+            {
+                Ok(()) => (),
+                // Keep starting the other languages rather than failing startup entirely
+                Err(e @ LspManagerError::InitializationTimeout(_)) => warn!("{}", e),
+                Err(e) => return Err(e.into()),
+            }
         }
         Ok(())
     }
 
-    pub async fn definitions_in_file_ast_grep(
+    /// Initializes a started client and registers it with the manager, as the client of the
+    /// Java module at `module_root` if one is given.
+    ///
+    /// Fails with [`LspManagerError::InitializationTimeout`] if the server takes longer than the
+    /// initialization timeout to initialize or to set up its workspace.
+    async fn initialize_client(
+        &mut self,
+        lsp: SupportedLanguages,
+        mut client: Box<dyn LspClient>,
+        workspace_path: &str,
+        module_root: Option<PathBuf>,
+    ) -> Result<(), LspManagerError> {
+        let init_result = self
+            .initialize_and_setup(lsp, &mut client, workspace_path)
+            .await?;
+        self.update_semantic_tokens_legend(lsp, init_result).await;
+        let client = Arc::new(Mutex::new(client));
+        match module_root {
+            Some(module_root) => {
+                self.java_module_clients.insert(module_root, client);
+            }
+            None => {
+                self.lsp_clients.insert(lsp, client);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the semantic tokens legend a language server announced when it was initialized,
+    /// which its semantic tokens are decoded with.
+    async fn update_semantic_tokens_legend(
         &self,
-        file_path: &str,
-    ) -> Result<Vec<AstGrepMatch>, LspManagerError> {
-        let workspace_files = self.list_files().await?;
-        if !workspace_files.contains(&file_path.to_string()) {
-            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        lsp: SupportedLanguages,
+        init_result: InitializeResult,
+    ) {
+        let mut legends = self.semantic_tokens_legends.write().await;
+        match init_result.capabilities.semantic_tokens_provider {
+            Some(SemanticTokensServerCapabilities::SemanticTokensOptions(options)) => {
+                legends.insert(lsp, options.legend);
+            }
+            Some(SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options)) => {
+                legends.insert(lsp, options.semantic_tokens_options.legend);
+            }
+            None => {
+                legends.remove(&lsp);
+            }
         }
-        let full_path = get_mount_dir().join(file_path);
-        let full_path_str = full_path.to_str().unwrap_or_default();
+    }
 
-        self.ast_grep
-            .get_file_symbols(full_path_str)
-            .await
-            .map_err(|e| LspManagerError::InternalError(format!("Symbol retrieval failed: {}", e)))
+    /// Initializes a started client and sets up its workspace, within the initialization timeout.
+    ///
+    /// On failure the server process is killed, since the caller drops the client and language
+    /// server processes aren't killed on drop.
+    async fn initialize_and_setup(
+        &self,
+        lsp: SupportedLanguages,
+        client: &mut Box<dyn LspClient>,
+        workspace_path: &str,
+    ) -> Result<InitializeResult, LspManagerError> {
+        let result = self
+            .try_initialize_and_setup(lsp, client, workspace_path)
+            .await;
+        if result.is_err() {
+            if let Err(e) = client.get_process().kill().await {
+                warn!(
+                    "Failed to kill the {} language server: {}",
+                    lsp.display_name(),
+                    e
+                );
+            }
+        }
+        result
     }
 
-    pub async fn get_symbol_from_position(
+    async fn try_initialize_and_setup(
         &self,
-        file_path: &str,
-        identifier_position: &lsp_types::Position,
-    ) -> Result<Symbol, LspManagerError> {
-        let full_path = get_mount_dir().join(file_path);
-        let full_path_str = full_path.to_str().unwrap_or_default();
-        match self
-            .ast_grep
-            .get_symbol_match_from_position(full_path_str, identifier_position)
+        lsp: SupportedLanguages,
+        client: &mut Box<dyn LspClient>,
+        workspace_path: &str,
+    ) -> Result<InitializeResult, LspManagerError> {
+        let init_result = timeout(
+            self.initialization_timeout,
+            client.initialize(workspace_path.to_string()),
+        )
+        .await
+        .map_err(|_| LspManagerError::InitializationTimeout(lsp))?
+        .map_err(|e| LspManagerError::InternalError(format!("Initialization failed: {}", e)))?;
+        debug!("Setting up workspace");
+        timeout(
+            self.initialization_timeout,
+            client.setup_workspace(workspace_path),
+        )
+        .await
+        .map_err(|_| LspManagerError::InitializationTimeout(lsp))?
+        .map_err(|e| LspManagerError::InternalError(format!("Workspace setup failed: {}", e)))?;
+        if lsp == SupportedLanguages::Java {
+            if let Some(limit) = self.auto_open_java_files_limit {
+                Self::open_java_files(client, limit).await;
+            }
+        }
+        Ok(init_result)
+    }
+
+    /// Opens up to `limit` of the workspace's Java files, since JDT LS resolves symbols across
+    /// files much more reliably once they are open.
+    async fn open_java_files(client: &mut Box<dyn LspClient>, limit: usize) {
+        let mut file_paths: Vec<String> = client
+            .get_workspace_documents()
+            .list_files()
             .await
-        {
-            Ok(ast_grep_symbol) => Ok(Symbol::from(ast_grep_symbol)),
-            Err(e) => Err(LspManagerError::InternalError(e.to_string())),
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        if file_paths.len() > limit {
+            warn!(
+                "Opening {} of {} Java files in JDT LS",
+                limit,
+                file_paths.len()
+            );
+            file_paths.truncate(limit);
+        }
+        match client.open_all_files(&file_paths).await {
+            Ok(opened) => debug!("Opened {} Java files in JDT LS", opened),
+            Err(e) => warn!("Failed to open Java files: {}", e),
         }
     }
 
-    pub async fn find_definition(
+    /// Restarts the language server for `lsp`, replacing its process with a freshly initialized
+    /// one. In a multi-module Java build, the server of every module is restarted.
+    ///
+    /// The new servers are initialized before the old ones are stopped, so requests keep being
+    /// served by the old servers in the meantime, and keep being served by them if any new
+    /// server fails to initialize. Fails with
+    /// [`LspManagerError::LspClientNotFound`] if the language server isn't running.
+    pub async fn restart_language_server(
         &self,
-        file_path: &str,
-        position: Position,
-    ) -> Result<GotoDefinitionResponse, LspManagerError> {
-        let workspace_files = self.list_files().await.map_err(|e| {
-            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
-        })?;
-        if !workspace_files.contains(&file_path.to_string()) {
-            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        lsp: SupportedLanguages,
+    ) -> Result<(), LspManagerError> {
+        let mut clients: Vec<(String, SharedClient)> = self
+            .lsp_clients
+            .get(&lsp)
+            .map(|client| (self.root_path.clone(), client.clone()))
+            .into_iter()
+            .collect();
+        if lsp == SupportedLanguages::Java {
+            clients.extend(
+                self.java_module_clients
+                    .iter()
+                    .map(|(module_root, client)| {
+                        (module_root.to_string_lossy().into_owned(), client.clone())
+                    }),
+            );
+        }
+        if clients.is_empty() {
+            return Err(LspManagerError::LspClientNotFound(lsp));
         }
-        let full_path = get_mount_dir().join(file_path);
-        let full_path_str = full_path.to_str().unwrap_or_default();
-        let lsp_type = detect_language(full_path_str).map_err(|e| {
-            LspManagerError::InternalError(format!("Language detection failed: {}", e))
-        })?;
 
-        let client = self
-            .get_client(lsp_type)
-            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
-        let mut locked_client = client.lock().await;
-        let mut definition = locked_client
-            .text_document_definition(full_path_str, position)
-            .await
-            .map_err(|e| {
-                LspManagerError::InternalError(format!("Definition retrieval failed: {}", e))
-            })?;
+        let _restarting = self.restart_lock.lock().await;
+        // Bumped before spawning, so a later restart never reuses the data directory of a server
+        // started by this one, even if this one fails
+        let generation = {
+            let mut restart_counts = self.restart_counts.lock().await;
+            let count = restart_counts.entry(lsp).or_insert(0);
+            *count += 1;
+            *count
+        };
 
-        // Sort the locations if there are multiple
-        match &mut definition {
-            GotoDefinitionResponse::Array(locations) => {
-                locations.sort_by(|a, b| {
-                    let path_a = uri_to_relative_path_string(&a.uri);
-                    let path_b = uri_to_relative_path_string(&b.uri);
-                    path_a
-                        .cmp(&path_b)
-                        .then(a.range.start.line.cmp(&b.range.start.line))
-                        .then(a.range.start.character.cmp(&b.range.start.character))
-                });
+        // Every replacement is initialized before any server is stopped, so a failure leaves all
+        // the old servers in place
+        let mut replacements = Vec::new();
+        for (root_path, client) in clients {
+            match self.start_replacement(lsp, &root_path, generation).await {
+                Ok((new_client, init_result)) => {
+                    replacements.push((client, new_client, init_result));
+                }
+                Err(e) => {
+                    for (_, mut new_client, _) in replacements {
+                        if let Err(e) = new_client.get_process().kill().await {
+                            warn!(
+                                "Failed to kill the {} language server: {}",
+                                lsp.display_name(),
+                                e
+                            );
+                        }
+                    }
+                    return Err(e);
+                }
             }
-            GotoDefinitionResponse::Link(links) => {
-                links.sort_by(|a, b| {
-                    let path_a = uri_to_relative_path_string(&a.target_uri);
-                    let path_b = uri_to_relative_path_string(&b.target_uri);
-                    path_a
-                        .cmp(&path_b)
-                        .then(a.target_range.start.line.cmp(&b.target_range.start.line))
-                        .then(
-                            a.target_range
-                                .start
-                                .character
-                                .cmp(&b.target_range.start.character),
-                        )
-                });
+        }
+
+        for (client, new_client, init_result) in replacements {
+            self.update_semantic_tokens_legend(lsp, init_result).await;
+            let mut old_client = std::mem::replace(&mut *client.lock().await, new_client);
+            if let Err(e) = old_client.get_process().kill().await {
+                warn!(
+                    "Failed to kill the {} language server: {}",
+                    lsp.display_name(),
+                    e
+                );
             }
-            _ => {}
         }
-        Ok(definition)
+
+        // Cached results may have come from the stale server
+        *self.symbol_counts_cache.lock().await = None;
+        Ok(())
     }
 
-    pub fn get_client(
+    /// Spawns and initializes the server replacing the one rooted at `root_path` in a restart.
+    /// The new server is killed if it fails to initialize.
+    async fn start_replacement(
         &self,
-        lsp_type: SupportedLanguages,
-    ) -> Option<Arc<Mutex<Box<dyn LspClient>>>> {
-        self.lsp_clients.get(&lsp_type).cloned()
+        lsp: SupportedLanguages,
+        root_path: &str,
+        generation: u32,
+    ) -> Result<(Box<dyn LspClient>, InitializeResult), LspManagerError> {
+        let mut new_client = self
+            .create_client(lsp, root_path, generation)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to start language server: {}", e))
+            })?;
+        let init_result = self
+            .initialize_and_setup(lsp, &mut new_client, root_path)
+            .await?;
+        Ok((new_client, init_result))
     }
 
-    pub async fn find_references(
+    /// Spawns the language server process for `lsp`, without initializing it. `generation` is
+    /// how many times the server has been restarted, including by this call.
+    async fn create_client(
         &self,
-        file_path: &str,
-        position: Position,
-    ) -> Result<Vec<Location>, LspManagerError> {
-        let workspace_files = self.list_files().await.map_err(|e| {
-            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
-        })?;
+        lsp: SupportedLanguages,
+        workspace_path: &str,
+        generation: u32,
+    ) -> Result<Box<dyn LspClient>, Box<dyn Error + Send + Sync>> {
+        let client: Box<dyn LspClient> = match lsp {
+            SupportedLanguages::Python => Box::new(
+                JediClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+            SupportedLanguages::TypeScriptJavaScript => Box::new(
+                TypeScriptLanguageClient::new(workspace_path, self.watch_events_sender.subscribe())
+                    .await?,
+            ),
+            SupportedLanguages::Rust => Box::new(
+                RustAnalyzerClient::new(workspace_path, self.watch_events_sender.subscribe())
+                    .await?,
+            ),
+            SupportedLanguages::CPP => Box::new(
+                ClangdClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+            SupportedLanguages::CSharp => Box::new(
+                CSharpClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+            SupportedLanguages::Java => Box::new(
+                JdtlsClient::new(
+                    workspace_path,
+                    self.watch_events_sender.subscribe(),
+                    &self.jdtls_data_dir(workspace_path, generation),
+                )
+                .await?,
+            ),
+            SupportedLanguages::Golang => Box::new(
+                GoplsClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+            SupportedLanguages::PHP => Box::new(
+                PhpactorClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+            SupportedLanguages::Ruby => Box::new(
+                RubyClient::new(workspace_path, self.watch_events_sender.subscribe()).await?,
+            ),
+        };
+        Ok(client)
+    }
 
-        if !workspace_files.contains(&file_path.to_string()) {
-            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+    /// The JDT LS data directory for a server rooted at `workspace_path`, separate for each
+    /// module of a multi-module build.
+    ///
+    /// A restarted server is initialized while the old one still holds its data directory, and a
+    /// restart that fails partway leaves the old servers running, so each generation gets its own
+    /// directory.
+    fn jdtls_data_dir(&self, workspace_path: &str, generation: u32) -> PathBuf {
+        let base = match generation {
+            0 => PathBuf::from(JDTLS_WORKSPACE_DIR),
+            _ => PathBuf::from(format!("{}_restart_{}", JDTLS_WORKSPACE_DIR, generation)),
+        };
+        match Path::new(workspace_path).strip_prefix(&self.root_path) {
+            Ok(module) if !module.as_os_str().is_empty() => base.join("modules").join(module),
+            _ => base,
         }
+    }
 
-        let full_path = get_mount_dir().join(file_path);
-        let full_path_str = full_path.to_str().unwrap_or_default();
-        let lsp_type = detect_language(full_path_str).map_err(|e| {
-            LspManagerError::InternalError(format!("Language detection failed: {}", e))
-        })?;
-        let client = self
-            .get_client(lsp_type)
-            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
-        let mut locked_client = client.lock().await;
+    /// Same as [`Self::definitions_in_file_ast_grep`], but reuses results until the file changes
+    /// on disk or [`AST_GREP_CACHE_TTL`] elapses.
+    pub async fn definitions_in_file_ast_grep_cached(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<AstGrepMatch>, LspManagerError> {
+        if let Some((computed_at, symbols)) = self.ast_grep_cache.read().await.get(file_path) {
+            if computed_at.elapsed() < AST_GREP_CACHE_TTL {
+                return Ok(symbols.clone());
+            }
+        }
 
-        locked_client
-            .text_document_reference(full_path_str, position)
+        let symbols = self.definitions_in_file_ast_grep(file_path).await?;
+        self.ast_grep_cache
+            .write()
             .await
-            .map_err(|e| {
-                LspManagerError::InternalError(format!("Reference retrieval failed: {}", e))
-            })
+            .insert(file_path.to_string(), (Instant::now(), symbols.clone()));
+        Ok(symbols)
     }
 
-    pub async fn find_referenced_symbols(
+    /// Sets how verbosely a language server traces its protocol messages.
+    ///
+    /// The trace is logged at debug level with the `lsp_trace` target. Fails with
+    /// [`LspManagerError::LspClientNotFound`] if the language server isn't running.
+    pub async fn set_trace(
         &self,
-        file_path: &str,
-        position: Position,
-        full_scan: bool,
-    ) -> Result<Vec<(AstGrepMatch, GotoDefinitionResponse)>, LspManagerError> {
-        let workspace_files = self.list_files().await.map_err(|e| {
-            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
-        })?;
-
-        if !workspace_files.iter().any(|f| f == file_path) {
-            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        lsp: SupportedLanguages,
+        value: TraceValue,
+    ) -> Result<(), LspManagerError> {
+        let clients = self.clients_for_language(lsp);
+        if clients.is_empty() {
+            return Err(LspManagerError::LspClientNotFound(lsp));
         }
+        for client in clients {
+            client.lock().await.set_trace(value).await.map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to set the trace level: {}", e))
+            })?;
+        }
+        Ok(())
+    }
 
-        let full_path = get_mount_dir().join(file_path);
-        let full_path_str = full_path.to_str().unwrap_or_default();
-
-        let lsp_type = detect_language(full_path_str).map_err(|e| {
-            LspManagerError::InternalError(format!("Language detection failed: {}", e))
-        })?;
+    /// Clears every cache the manager and its clients hold and asks the language servers to
+    /// re-index the workspace files, returning how many cache entries were dropped.
+    ///
+    /// Each cache is cleared under its lock, so requests already reading it finish first.
+    pub async fn clear_all_caches(&self) -> Result<usize, LspManagerError> {
+        let mut cleared_entries = 0;
+        cleared_entries += std::mem::take(&mut *self.ast_grep_cache.write().await).len();
+        cleared_entries += std::mem::take(&mut *self.file_metrics_cache.lock().await).len();
+        cleared_entries += usize::from(self.symbol_counts_cache.lock().await.take().is_some());
 
-        // Only Python and TypeScript/JavaScript are currently supported
-        match lsp_type {
+        for (_, client) in self.all_clients() {
+            let mut locked_client = client.lock().await;
+            let changes = locked_client
+                .get_workspace_documents()
+                .list_files()
+                .await
+                .into_iter()
+                .filter_map(|path| Url::from_file_path(path).ok())
+                .map(|uri| FileEvent::new(uri, FileChangeType::CHANGED))
+                .collect();
+            cleared_entries += locked_client.get_workspace_documents().clear_cache().await;
+            locked_client
+                .workspace_did_change_watched_files(changes)
+                .await
+                .map_err(|e| {
+                    LspManagerError::InternalError(format!(
+                        "Failed to notify language server: {}",
+                        e
+                    ))
+                })?;
+        }
+        Ok(cleared_entries)
+    }
+
+    /// Drops the cached ast-grep definitions for `file_path`.
+    pub async fn clear_file_cache(&self, file_path: &str) {
+        self.ast_grep_cache.write().await.remove(file_path);
+    }
+
+    /// Compares the files each client lists against the files on disk, e.g. to catch deletions
+    /// the file watcher hasn't reported yet.
+    ///
+    /// Stale files are dropped from the listed files, and closed in their language server if
+    /// they were open. Files missing from the listing are added to it.
+    pub async fn check_workspace_consistency(&self) -> Result<ConsistencyReport, LspManagerError> {
+        let mut stale_files = HashSet::new();
+        let mut missing_from_lsp = HashSet::new();
+        for (_, client) in self.all_clients() {
+            let mut locked_client = client.lock().await;
+            let documents = locked_client.get_workspace_documents();
+            let listed: HashSet<PathBuf> = documents.list_files().await.into_iter().collect();
+            let on_disk: HashSet<PathBuf> =
+                documents.list_files_on_disk().await.into_iter().collect();
+
+            for path in on_disk.difference(&listed) {
+                documents.invalidate_document(path).await;
+                missing_from_lsp.insert(absolute_path_to_relative_path_string(path));
+            }
+            for path in listed.difference(&on_disk) {
+                locked_client
+                    .get_workspace_documents()
+                    .remove_document(path)
+                    .await;
+                locked_client
+                    .did_close(path.to_str().unwrap_or_default())
+                    .await
+                    .map_err(|e| {
+                        LspManagerError::InternalError(format!("Failed to close file: {}", e))
+                    })?;
+                let file_path = absolute_path_to_relative_path_string(path);
+                self.clear_file_cache(&file_path).await;
+                stale_files.insert(file_path);
+            }
+        }
+
+        let mut stale_files: Vec<String> = stale_files.into_iter().collect();
+        stale_files.sort();
+        let mut missing_from_lsp: Vec<String> = missing_from_lsp.into_iter().collect();
+        missing_from_lsp.sort();
+        Ok(ConsistencyReport {
+            stale_files,
+            missing_from_lsp,
+        })
+    }
+
+    #[tracing::instrument(skip(self), fields(language = tracing::field::Empty), err(level = "debug"))]
+    pub async fn definitions_in_file_ast_grep(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<AstGrepMatch>, LspManagerError> {
+        let workspace_files = self.list_files().await?;
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        if let Ok(lsp_type) = detect_language(file_path) {
+            tracing::Span::current().record("language", lsp_type.to_string());
+        }
+
+        let mut symbols = self
+            .ast_grep_for(full_path_str)
+            .get_file_symbols(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Symbol retrieval failed: {}", e))
+            })?;
+        self.clamp_to_normalized_lines(file_path, &mut symbols)
+            .await?;
+        Ok(symbols)
+    }
+
+    /// Gets the comments in a file marking work to do, e.g. `# TODO: ...`, as matches with the
+    /// rule id `todo-comment`. The comment's text is its name.
+    pub async fn todo_comments_in_file(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<AstGrepMatch>, LspManagerError> {
+        let workspace_files = self.list_files().await?;
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let mut comments = self
+            .ast_grep_for(full_path_str)
+            .get_file_todo_comments(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Comment retrieval failed: {}", e))
+            })?;
+        self.clamp_to_normalized_lines(file_path, &mut comments)
+            .await?;
+        Ok(comments)
+    }
+
+    /// Finds the tests of the workspace: the functions, methods and calls the ast-grep test
+    /// rules match, e.g. `#[test]` functions, plus the symbols following the test naming
+    /// conventions of their language, e.g. `Test*` classes in Python.
+    pub async fn find_test_symbols(&self) -> Result<Vec<Symbol>, LspManagerError> {
+        let mut tests: Vec<Symbol> = Vec::new();
+        for file_path in self.list_files().await? {
+            let full_path = get_mount_dir().join(&file_path);
+            let full_path_str = full_path.to_str().unwrap_or_default();
+            let mut matches = self
+                .ast_grep_for(full_path_str)
+                .get_file_test_symbols(full_path_str)
+                .await
+                .map_err(|e| {
+                    LspManagerError::InternalError(format!("Test retrieval failed: {}", e))
+                })?;
+            self.clamp_to_normalized_lines(&file_path, &mut matches)
+                .await?;
+
+            if let Some(config) = detect_language(&file_path)
+                .ok()
+                .and_then(TestSymbolConfig::for_language)
+            {
+                matches.extend(
+                    self.definitions_in_file_ast_grep_cached(&file_path)
+                        .await?
+                        .into_iter()
+                        .filter(|symbol| config.matches(symbol)),
+                );
+            }
+
+            for symbol in matches.into_iter().map(Symbol::from) {
+                if !tests
+                    .iter()
+                    .any(|test| test.identifier_position == symbol.identifier_position)
+                {
+                    tests.push(symbol);
+                }
+            }
+        }
+        sort_symbols(&mut tests);
+        Ok(tests)
+    }
+
+    /// Finds the names a file imports but never uses.
+    ///
+    /// Each imported name is resolved to its definition, and the references to that definition,
+    /// or to the import itself when the definition is outside the workspace, are counted in the
+    /// file. A name referenced only by its import is unused. Only Python imports are detected.
+    pub async fn detect_unused_imports(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<UnusedImport>, LspManagerError> {
+        let workspace_files = self.list_files().await?;
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+        if detect_language(file_path)? != SupportedLanguages::Python {
+            return Err(LspManagerError::NotImplemented(
+                "Unused imports are only detected for Python".to_string(),
+            ));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let mut imports = self
+            .ast_grep_for(full_path_str)
+            .get_file_imports(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Import retrieval failed: {}", e))
+            })?;
+        self.clamp_to_normalized_lines(file_path, &mut imports)
+            .await?;
+
+        let mount_dir = get_mount_dir();
+        let mut unused_imports = Vec::new();
+        for import in imports {
+            let position = Position::from(&import);
+            let definition =
+                first_definition_location(self.find_definition(file_path, position).await?)
+                    .map(|location| FilePosition::from_lsp_location(location, &mount_dir))
+                    .filter(|definition| workspace_files.contains(&definition.path));
+            let references = match definition {
+                Some(definition) => {
+                    self.find_references_as_file_ranges(
+                        &definition.path,
+                        definition.to_lsp_position(),
+                        true,
+                    )
+                    .await?
+                }
+                None => {
+                    self.find_references_as_file_ranges(file_path, position, true)
+                        .await?
+                }
+            };
+
+            let references_in_file = references
+                .into_iter()
+                .filter(|reference| reference.path == file_path)
+                .count();
+            if references_in_file <= 1 {
+                let import_range = import.get_context_range();
+                unused_imports.push(UnusedImport {
+                    name: import.meta_variables.single.name.text.clone(),
+                    import_range: FileRange {
+                        path: file_path.to_string(),
+                        range: ApiRange {
+                            start: ApiPosition {
+                                line: import_range.start.line,
+                                character: import_range.start.column,
+                            },
+                            end: ApiPosition {
+                                line: import_range.end.line,
+                                character: import_range.end.column,
+                            },
+                        },
+                    },
+                });
+            }
+        }
+        Ok(unused_imports)
+    }
+
+    /// Clamps ast-grep matches to the lines of a file once its CRLF line endings are normalized,
+    /// if its client normalizes them.
+    async fn clamp_to_normalized_lines(
+        &self,
+        file_path: &str,
+        matches: &mut [AstGrepMatch],
+    ) -> Result<(), LspManagerError> {
+        if !self.normalizes_line_endings(file_path).await {
+            return Ok(());
+        }
+        let bytes = tokio::fs::read(get_mount_dir().join(file_path))
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Source code retrieval failed: {}", e))
+            })?;
+        let line_lengths = crlf_line_lengths(&String::from_utf8_lossy(&bytes));
+        if !line_lengths.is_empty() {
+            for ast_match in matches.iter_mut() {
+                ast_match.clamp_to_line_lengths(&line_lengths);
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the symbols defined in a file whose ranges overlap the given range.
+    ///
+    /// Ranges are end-exclusive, so a symbol ending where the range starts is not included.
+    pub async fn definitions_in_range(
+        &self,
+        file_path: &str,
+        range: Range,
+    ) -> Result<Vec<AstGrepMatch>, LspManagerError> {
+        let range = ApiRange::from(range);
+        let symbols = self.definitions_in_file_ast_grep(file_path).await?;
+        Ok(symbols
+            .into_iter()
+            .filter(|symbol| ApiRange::from(symbol.get_context_range()).overlaps(&range))
+            .collect())
+    }
+
+    /// Gets the symbols defined in every file of the workspace, sorted by file and then position.
+    ///
+    /// Only symbols whose kind is in `kind_filter` are kept. Without a filter, local variables
+    /// are left out, as in the symbols of a single file.
+    pub async fn definitions_in_workspace(
+        &self,
+        kind_filter: Option<Vec<String>>,
+    ) -> Result<Vec<Symbol>, LspManagerError> {
+        let mut symbols = Vec::new();
+        for file_path in self.list_files().await? {
+            symbols.extend(
+                self.definitions_in_file_ast_grep_cached(&file_path)
+                    .await?
+                    .into_iter()
+                    .filter(|symbol| match &kind_filter {
+                        Some(kinds) => kinds.contains(&symbol.rule_id),
+                        None => symbol.rule_id != "local-variable",
+                    })
+                    .map(Symbol::from),
+            );
+        }
+        sort_symbols(&mut symbols);
+        Ok(symbols)
+    }
+
+    /// Finds the symbols named exactly `name`, optionally only those of `kind`, e.g. `class`.
+    ///
+    /// The language servers are asked first with `workspace/symbol`, and the symbols they find
+    /// are resolved with ast-grep so that they have the same shape as `definitions_in_file`
+    /// results. If no server finds a match, or none supports the request, every workspace file
+    /// is searched with ast-grep instead.
+    pub async fn find_symbol_by_name(
+        &self,
+        name: &str,
+        kind: Option<&str>,
+    ) -> Result<Vec<Symbol>, LspManagerError> {
+        let matches_kind = |symbol: &Symbol| kind.is_none_or(|kind| symbol.kind == kind);
+
+        let mut symbols: Vec<Symbol> = self
+            .workspace_symbols_named(name)
+            .await?
+            .into_iter()
+            .filter(matches_kind)
+            .collect();
+        if symbols.is_empty() {
+            debug!(
+                "No workspace symbols named {}, searching with ast-grep",
+                name
+            );
+            symbols = self
+                .definitions_in_workspace(kind.map(|kind| vec![kind.to_string()]))
+                .await?
+                .into_iter()
+                .filter(|symbol| symbol.name == name)
+                .collect();
+        }
+
+        sort_symbols(&mut symbols);
+        symbols.dedup();
+        Ok(symbols)
+    }
+
+    /// Asks every language server for the workspace symbols named exactly `name`, resolving
+    /// them to the ast-grep symbols at their locations. Servers that fail the request are
+    /// skipped.
+    async fn workspace_symbols_named(&self, name: &str) -> Result<Vec<Symbol>, LspManagerError> {
+        let mut locations: Vec<(String, Option<ApiPosition>)> = Vec::new();
+        for (language, client) in self.all_clients() {
+            let response = match client.lock().await.workspace_symbol(name).await {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("{} workspace symbol request failed: {}", language, e);
+                    continue;
+                }
+            };
+            match response {
+                WorkspaceSymbolResponse::Flat(symbols) => locations.extend(
+                    symbols
+                        .into_iter()
+                        .filter(|symbol| symbol.name == name)
+                        .map(|symbol| {
+                            (
+                                uri_to_relative_path_string(&symbol.location.uri),
+                                Some(ApiPosition::from(symbol.location.range.start)),
+                            )
+                        }),
+                ),
+                WorkspaceSymbolResponse::Nested(symbols) => locations.extend(
+                    symbols
+                        .into_iter()
+                        .filter(|symbol| symbol.name == name)
+                        .map(|symbol| match symbol.location {
+                            OneOf::Left(location) => (
+                                uri_to_relative_path_string(&location.uri),
+                                Some(ApiPosition::from(location.range.start)),
+                            ),
+                            OneOf::Right(location) => {
+                                (uri_to_relative_path_string(&location.uri), None)
+                            }
+                        }),
+                ),
+            }
+        }
+
+        // Servers also report symbols of installed libraries, which are left out
+        let files = self.list_files().await?;
+        let mut symbols = Vec::new();
+        for (file_path, position) in locations {
+            if !files.contains(&file_path) {
+                continue;
+            }
+            symbols.extend(
+                self.definitions_in_file_ast_grep_cached(&file_path)
+                    .await?
+                    .into_iter()
+                    .map(Symbol::from)
+                    .filter(|symbol| {
+                        symbol.name == name
+                            && position.as_ref().is_none_or(|position| {
+                                symbol.file_range.range.contains_position(position)
+                            })
+                    }),
+            );
+        }
+        Ok(symbols)
+    }
+
+    /// Whether the client serving this file returns source code with CRLF line endings normalized.
+    async fn normalizes_line_endings(&self, file_path: &str) -> bool {
+        match detect_language(file_path)
+            .ok()
+            .and_then(|lsp_type| self.get_client_for_file(lsp_type, file_path))
+        {
+            Some(client) => client
+                .lock()
+                .await
+                .get_workspace_documents()
+                .get_normalize_line_endings(),
+            None => true,
+        }
+    }
+
+    /// Gets the symbol whose identifier starts exactly at the given position, e.g. the position
+    /// a definition request returns. `None` if no symbol is defined there, e.g. for a parameter.
+    pub async fn get_symbol_from_position(
+        &self,
+        file_path: &str,
+        identifier_position: &lsp_types::Position,
+    ) -> Result<Option<Symbol>, LspManagerError> {
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        self.ast_grep_for(full_path_str)
+            .find_symbol_match_at_position(full_path_str, identifier_position)
+            .await
+            .map(|symbol| symbol.map(Symbol::from))
+            .map_err(|e| LspManagerError::InternalError(e.to_string()))
+    }
+
+    /// Gets the innermost symbol defined in a file whose range contains the given position.
+    pub async fn get_innermost_symbol_at_position(
+        &self,
+        file_path: &str,
+        position: &lsp_types::Position,
+    ) -> Result<Option<Symbol>, LspManagerError> {
+        let position = ApiPosition::from(*position);
+        let symbol = self
+            .definitions_in_file_ast_grep(file_path)
+            .await?
+            .into_iter()
+            .map(Symbol::from)
+            .filter(|symbol| symbol.file_range.range.contains_position(&position))
+            .max_by(|a, b| {
+                let (a, b) = (&a.file_range.range, &b.file_range.range);
+                (a.start.line, a.start.character)
+                    .cmp(&(b.start.line, b.start.character))
+                    .then((b.end.line, b.end.character).cmp(&(a.end.line, a.end.character)))
+            });
+        Ok(symbol)
+    }
+
+    /// Gets the chain of symbols enclosing a position, from the outermost, e.g. a class, to the
+    /// innermost, i.e. the one [`Self::get_innermost_symbol_at_position`] returns.
+    ///
+    /// Empty if the position is outside every symbol.
+    pub async fn get_symbol_enclosing_chain(
+        &self,
+        file_path: &str,
+        position: &lsp_types::Position,
+    ) -> Result<Vec<Symbol>, LspManagerError> {
+        let position = ApiPosition::from(*position);
+        let mut chain: Vec<Symbol> = self
+            .definitions_in_file_ast_grep(file_path)
+            .await?
+            .into_iter()
+            .map(Symbol::from)
+            .filter(|symbol| symbol.file_range.range.contains_position(&position))
+            .collect();
+        chain.sort_by(|a, b| {
+            let (a, b) = (&a.file_range.range, &b.file_range.range);
+            (a.start.line, a.start.character)
+                .cmp(&(b.start.line, b.start.character))
+                .then((b.end.line, b.end.character).cmp(&(a.end.line, a.end.character)))
+        });
+        Ok(chain)
+    }
+
+    /// Opens a file in its language server, so that the server tracks it as an open document.
+    ///
+    /// Does nothing if the file is already open.
+    pub async fn open_file(&self, file_path: &str) -> Result<(), LspManagerError> {
+        let workspace_files = self.list_files().await?;
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str)?;
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let mut locked_client = client.lock().await;
+        locked_client
+            .did_open(full_path_str)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Failed to open file: {}", e)))
+    }
+
+    #[tracing::instrument(skip(self), fields(language = tracing::field::Empty), err(level = "debug"))]
+    pub async fn find_definition(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<GotoDefinitionResponse, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        tracing::Span::current().record("language", lsp_type.to_string());
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let mut locked_client = client.lock().await;
+        // JDT LS only resolves definitions accurately in open files
+        if lsp_type == SupportedLanguages::Java {
+            locked_client.did_open(full_path_str).await.map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to open file: {}", e))
+            })?;
+        }
+        let mut definition = locked_client
+            .text_document_definition(full_path_str, position)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Definition retrieval failed: {}", e))
+            })?;
+
+        // Sort the locations if there are multiple
+        match &mut definition {
+            GotoDefinitionResponse::Array(locations) => {
+                locations.sort_by(|a, b| {
+                    let path_a = uri_to_relative_path_string(&a.uri);
+                    let path_b = uri_to_relative_path_string(&b.uri);
+                    path_a
+                        .cmp(&path_b)
+                        .then(a.range.start.line.cmp(&b.range.start.line))
+                        .then(a.range.start.character.cmp(&b.range.start.character))
+                });
+            }
+            GotoDefinitionResponse::Link(links) => {
+                links.sort_by(|a, b| {
+                    let path_a = uri_to_relative_path_string(&a.target_uri);
+                    let path_b = uri_to_relative_path_string(&b.target_uri);
+                    path_a
+                        .cmp(&path_b)
+                        .then(a.target_range.start.line.cmp(&b.target_range.start.line))
+                        .then(
+                            a.target_range
+                                .start
+                                .character
+                                .cmp(&b.target_range.start.character),
+                        )
+                });
+            }
+            _ => {}
+        }
+        Ok(definition)
+    }
+
+    /// Follows the definition at a position hop by hop, e.g. through re-exports and type
+    /// aliases, until the original definition.
+    ///
+    /// The chain starts at `position` and ends once a definition resolves to itself, leaves the
+    /// workspace, revisits a location or `max_hops` definitions have been followed. Only the
+    /// first definition of each hop is followed.
+    pub async fn find_definition_chain(
+        &self,
+        file_path: &str,
+        position: Position,
+        max_hops: u32,
+    ) -> Result<Vec<FilePosition>, LspManagerError> {
+        let files = self.list_files().await?;
+        let mount_dir = get_mount_dir();
+
+        let mut chain = vec![FilePosition::from_lsp_position(
+            file_path.to_string(),
+            position,
+        )];
+        for _ in 0..max_hops {
+            let current = chain.last().expect("chain starts with the position");
+            let definition = first_definition_location(
+                self.find_definition(&current.path, current.to_lsp_position())
+                    .await?,
+            )
+            .map(|location| FilePosition::from_lsp_location(location, &mount_dir));
+            match definition {
+                Some(definition)
+                    if files.contains(&definition.path) && !chain.contains(&definition) =>
+                {
+                    chain.push(definition)
+                }
+                _ => break,
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Reports the process state of every running language server client, sorted by language.
+    pub async fn lsp_status(&self) -> Vec<LspClientStatus> {
+        let mut statuses = Vec::new();
+        for (language, client) in self.all_clients() {
+            let restart_count = self
+                .restart_counts
+                .lock()
+                .await
+                .get(&language)
+                .copied()
+                .unwrap_or(0);
+            let status = client
+                .lock()
+                .await
+                .client_status(language, restart_count)
+                .await;
+            statuses.push(status);
+        }
+        statuses.sort_by(|a, b| a.language.cmp(&b.language));
+        statuses
+    }
+
+    /// Registers a client without starting or initializing it, e.g. a mock in tests.
+    #[cfg(test)]
+    pub(crate) fn insert_client(&mut self, lsp: SupportedLanguages, client: Box<dyn LspClient>) {
+        self.lsp_clients.insert(lsp, Arc::new(Mutex::new(client)));
+    }
+
+    /// Gets a client serving `lsp_type`. A multi-module Java build has a client per module, of
+    /// which the first is returned; see [`Self::get_client_for_file`] to get the one serving a
+    /// file.
+    pub fn get_client(
+        &self,
+        lsp_type: SupportedLanguages,
+    ) -> Option<Arc<Mutex<Box<dyn LspClient>>>> {
+        self.clients_for_language(lsp_type).into_iter().next()
+    }
+
+    /// Gets the client serving a file of language `lsp_type`. In a multi-module Java build, this
+    /// is the client of the module containing the file.
+    pub fn get_client_for_file(
+        &self,
+        lsp_type: SupportedLanguages,
+        file_path: &str,
+    ) -> Option<Arc<Mutex<Box<dyn LspClient>>>> {
+        if lsp_type == SupportedLanguages::Java {
+            let full_path = get_mount_dir().join(file_path);
+            // Nested module roots sort after their parents, so the innermost module is found first
+            if let Some((_, client)) = self
+                .java_module_clients
+                .iter()
+                .rev()
+                .find(|(module_root, _)| full_path.starts_with(module_root))
+            {
+                return Some(client.clone());
+            }
+        }
+        self.get_client(lsp_type)
+    }
+
+    /// Every running client with the language it serves, including each Java module's client.
+    fn all_clients(&self) -> Vec<(SupportedLanguages, SharedClient)> {
+        let mut clients: Vec<_> = self
+            .lsp_clients
+            .iter()
+            .map(|(language, client)| (*language, client.clone()))
+            .collect();
+        clients.extend(
+            self.java_module_clients
+                .values()
+                .map(|client| (SupportedLanguages::Java, client.clone())),
+        );
+        clients
+    }
+
+    /// The clients serving `lsp`: one per module of a multi-module Java build, or else at most
+    /// one.
+    fn clients_for_language(&self, lsp: SupportedLanguages) -> Vec<SharedClient> {
+        self.all_clients()
+            .into_iter()
+            .filter(|(language, _)| *language == lsp)
+            .map(|(_, client)| client)
+            .collect()
+    }
+
+    /// Finds the references to the symbol at a position.
+    ///
+    /// When `include_declaration` is false, the declaration of the symbol is left out. Not every
+    /// language server honors this, so locations matching the symbol's definition are also removed.
+    #[tracing::instrument(skip(self), fields(language = tracing::field::Empty), err(level = "debug"))]
+    pub async fn find_references(
+        &self,
+        file_path: &str,
+        position: Position,
+        include_declaration: bool,
+    ) -> Result<Vec<Location>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+
+        if !workspace_files.contains(&file_path.to_string()) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        tracing::Span::current().record("language", lsp_type.to_string());
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let mut locked_client = client.lock().await;
+
+        let references = locked_client
+            .text_document_reference(full_path_str, position, include_declaration)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Reference retrieval failed: {}", e))
+            })?;
+        if include_declaration {
+            return Ok(references);
+        }
+
+        let definitions: Vec<(Url, Position)> = match locked_client
+            .text_document_definition(full_path_str, position)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Definition retrieval failed: {}", e))
+            })? {
+            GotoDefinitionResponse::Scalar(location) => {
+                vec![(location.uri, location.range.start)]
+            }
+            GotoDefinitionResponse::Array(locations) => locations
+                .into_iter()
+                .map(|location| (location.uri, location.range.start))
+                .collect(),
+            GotoDefinitionResponse::Link(links) => links
+                .into_iter()
+                .map(|link| (link.target_uri, link.target_selection_range.start))
+                .collect(),
+        };
+        Ok(references
+            .into_iter()
+            .filter(|reference| {
+                !definitions
+                    .iter()
+                    .any(|(uri, start)| *uri == reference.uri && *start == reference.range.start)
+            })
+            .collect())
+    }
+
+    /// Like [`Manager::find_references`], with each location converted to a range relative to
+    /// the workspace root.
+    pub async fn find_references_as_file_ranges(
+        &self,
+        file_path: &str,
+        position: Position,
+        include_declaration: bool,
+    ) -> Result<Vec<FileRange>, LspManagerError> {
+        let mount_dir = get_mount_dir();
+        Ok(self
+            .find_references(file_path, position, include_declaration)
+            .await?
+            .into_iter()
+            .map(|location| FileRange::from_lsp_location(location, &mount_dir))
+            .collect())
+    }
+
+    /// Finds the references to every symbol defined in a file, keyed by symbol name.
+    ///
+    /// References include the declarations; references outside the workspace files, e.g. in
+    /// installed packages, are left out. Symbols sharing a name, e.g. methods of different
+    /// classes, share an entry. The symbols are looked up one after the other, as the client
+    /// handles one request at a time.
+    pub async fn get_all_references_in_file(
+        &self,
+        file_path: &str,
+    ) -> Result<HashMap<String, Vec<FileRange>>, LspManagerError> {
+        let symbols = self.definitions_in_file_ast_grep(file_path).await?;
+        let files: HashSet<String> = self.list_files().await?.into_iter().collect();
+        let mount_dir = get_mount_dir();
+        let full_path = mount_dir.join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+
+        let mut references: HashMap<String, Vec<FileRange>> = HashMap::new();
+        for symbol in symbols {
+            let locations = client
+                .lock()
+                .await
+                .text_document_reference(full_path_str, Position::from(&symbol), true)
+                .await
+                .map_err(|e| {
+                    LspManagerError::InternalError(format!("Reference retrieval failed: {}", e))
+                })?;
+            let entry = references
+                .entry(symbol.meta_variables.single.name.text)
+                .or_default();
+            for reference in locations
+                .into_iter()
+                .map(|location| FileRange::from_lsp_location(location, &mount_dir))
+                .filter(|reference| files.contains(&reference.path))
+            {
+                if !entry.contains(&reference) {
+                    entry.push(reference);
+                }
+            }
+        }
+
+        for ranges in references.values_mut() {
+            ranges.sort_by(|a, b| (&a.path, &a.range.start).cmp(&(&b.path, &b.range.start)));
+        }
+        Ok(references)
+    }
+
+    /// Finds the symbols whose code references the symbol at a position, e.g. the functions
+    /// calling a function.
+    ///
+    /// Each reference is attributed to the innermost symbol containing it, ignoring local
+    /// variables. References outside any symbol, e.g. module-level imports, and the symbol's
+    /// references to itself are left out.
+    pub async fn find_callers(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Vec<Symbol>, LspManagerError> {
+        let files = self.list_files().await?;
+        let target = FilePosition::from_lsp_position(file_path.to_string(), position);
+
+        let mut callers: Vec<Symbol> = Vec::new();
+        for reference in self
+            .find_references_as_file_ranges(file_path, position, false)
+            .await?
+        {
+            let reference = FilePosition {
+                path: reference.path,
+                position: reference.range.start,
+            };
+            if !files.contains(&reference.path) {
+                continue;
+            }
+            let caller = self
+                .definitions_in_file_ast_grep_cached(&reference.path)
+                .await?
+                .into_iter()
+                .filter(|symbol| symbol.rule_id != "local-variable")
+                .map(Symbol::from)
+                .filter(|symbol| symbol.file_range.contains(reference.clone()))
+                .max_by(|a, b| {
+                    let (a, b) = (&a.file_range.range, &b.file_range.range);
+                    a.start.cmp(&b.start).then(b.end.cmp(&a.end))
+                });
+            if let Some(caller) = caller {
+                if caller.identifier_position != target && !callers.contains(&caller) {
+                    callers.push(caller);
+                }
+            }
+        }
+        sort_symbols(&mut callers);
+        Ok(callers)
+    }
+
+    /// Builds the tree of the symbols calling the symbol at a position, their callers in turn,
+    /// and so on for `max_depth` levels. See [`Manager::find_callers`] for what counts as a
+    /// caller.
+    ///
+    /// A caller already on the path from the root, i.e. a call cycle, isn't followed again, but
+    /// a symbol reached through several paths appears under each of them. The callers of each
+    /// symbol are only looked up once per tree.
+    pub async fn find_symbol_callers_recursive(
+        &self,
+        file_path: &str,
+        position: Position,
+        max_depth: u32,
+    ) -> Result<CallTree, LspManagerError> {
+        let symbol = self
+            .get_symbol_from_position(file_path, &position)
+            .await?
+            .ok_or_else(|| {
+                LspManagerError::SymbolNotFound(format!(
+                    "{}:{}:{}",
+                    file_path, position.line, position.character
+                ))
+            })?;
+        self.call_tree(symbol, max_depth, &mut Vec::new(), &mut HashMap::new())
+            .await
+    }
+
+    /// Builds the call tree of `symbol`, with `path` holding the symbols from the root to it and
+    /// `known_callers` the callers already looked up for this tree.
+    fn call_tree<'a>(
+        &'a self,
+        symbol: Symbol,
+        depth: u32,
+        path: &'a mut Vec<FilePosition>,
+        known_callers: &'a mut HashMap<FilePosition, Vec<Symbol>>,
+    ) -> LocalBoxFuture<'a, Result<CallTree, LspManagerError>> {
+        Box::pin(async move {
+            let mut callers = Vec::new();
+            if depth > 0 {
+                let symbol_callers = match known_callers.get(&symbol.identifier_position) {
+                    Some(symbol_callers) => symbol_callers.clone(),
+                    None => {
+                        let symbol_callers = self
+                            .find_callers(
+                                &symbol.identifier_position.path,
+                                symbol.identifier_position.to_lsp_position(),
+                            )
+                            .await?;
+                        known_callers
+                            .insert(symbol.identifier_position.clone(), symbol_callers.clone());
+                        symbol_callers
+                    }
+                };
+                path.push(symbol.identifier_position.clone());
+                for caller in symbol_callers {
+                    if !path.contains(&caller.identifier_position) {
+                        callers.push(
+                            self.call_tree(caller, depth - 1, path, known_callers)
+                                .await?,
+                        );
+                    }
+                }
+                path.pop();
+            }
+            Ok(CallTree { symbol, callers })
+        })
+    }
+
+    /// Estimates the impact of changing the symbol at a position from its callers, up to two
+    /// levels deep.
+    pub async fn symbol_change_impact(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<ChangeImpactReport, LspManagerError> {
+        let target = FilePosition::from_lsp_position(file_path.to_string(), position);
+        let direct_callers = self.find_callers(file_path, position).await?;
+
+        let mut transitive_callers: Vec<Symbol> = Vec::new();
+        for caller in &direct_callers {
+            for transitive_caller in self
+                .find_callers(
+                    &caller.identifier_position.path,
+                    caller.identifier_position.to_lsp_position(),
+                )
+                .await?
+            {
+                if transitive_caller.identifier_position != target
+                    && !direct_callers.contains(&transitive_caller)
+                    && !transitive_callers.contains(&transitive_caller)
+                {
+                    transitive_callers.push(transitive_caller);
+                }
+            }
+        }
+        sort_symbols(&mut transitive_callers);
+
+        Ok(ChangeImpactReport {
+            total_impact: direct_callers.len() + transitive_callers.len(),
+            direct_callers,
+            transitive_callers,
+        })
+    }
+
+    /// Measures how strongly the workspace files depend on each other.
+    ///
+    /// For each pair of files, counts the symbols defined in the first that are referenced in the
+    /// second. Symbols sharing a name within a file count once. Returns the
+    /// [`MAX_COUPLING_PAIRS`] most coupled pairs, highest score first.
+    pub async fn analyze_symbol_coupling(&self) -> Result<Vec<FileCoupling>, LspManagerError> {
+        let scores = self.cross_file_reference_counts().await?;
+        let mut couplings: Vec<FileCoupling> = scores
+            .into_iter()
+            .map(|((file_a, file_b), coupling_score)| FileCoupling {
+                file_a,
+                file_b,
+                coupling_score,
+            })
+            .collect();
+        couplings.sort_by(|a, b| {
+            b.coupling_score
+                .cmp(&a.coupling_score)
+                .then_with(|| (&a.file_a, &a.file_b).cmp(&(&b.file_a, &b.file_b)))
+        });
+        couplings.truncate(MAX_COUPLING_PAIRS);
+        Ok(couplings)
+    }
+
+    /// Builds the dependency graph between the workspace files.
+    ///
+    /// Each edge `(a, b)` means `b` references symbols defined in `a`. Edges are sorted by
+    /// file path.
+    pub async fn import_graph(&self) -> Result<Vec<(String, String)>, LspManagerError> {
+        let mut edges: Vec<(String, String)> = self
+            .cross_file_reference_counts()
+            .await?
+            .into_keys()
+            .collect();
+        edges.sort();
+        Ok(edges)
+    }
+
+    /// Counts, for each pair of workspace files, the symbols defined in the first that are
+    /// referenced in the second.
+    async fn cross_file_reference_counts(
+        &self,
+    ) -> Result<HashMap<(String, String), usize>, LspManagerError> {
+        let files = self.list_files().await?;
+        let mut scores: HashMap<(String, String), usize> = HashMap::new();
+        for file_a in &files {
+            for references in self.get_all_references_in_file(file_a).await?.into_values() {
+                let referencing_files: HashSet<String> = references
+                    .into_iter()
+                    .map(|reference| reference.path)
+                    .filter(|path| path != file_a && files.contains(path))
+                    .collect();
+                for file_b in referencing_files {
+                    *scores.entry((file_a.clone(), file_b)).or_default() += 1;
+                }
+            }
+        }
+        Ok(scores)
+    }
+
+    /// Finds the symbols referenced within a range of a file, e.g. a whole function body.
+    ///
+    /// Runs [`Self::find_referenced_symbols`] for every symbol defined in the range and merges the
+    /// results, keeping each reference inside the range once, in document order.
+    pub async fn find_referenced_symbols_in_range(
+        &self,
+        file_path: &str,
+        range: Range,
+        full_scan: bool,
+    ) -> Result<Vec<(AstGrepMatch, GotoDefinitionResponse)>, LspManagerError> {
+        let symbols = self.definitions_in_range(file_path, range).await?;
+        let range = ApiRange::from(range);
+
+        let mut seen = HashSet::new();
+        let mut referenced_symbols = Vec::new();
+        for symbol in symbols {
+            let position = lsp_types::Position::from(&symbol);
+            let references = match self
+                .find_referenced_symbols(file_path, position, full_scan)
+                .await
+            {
+                Ok(references) => references,
+                Err(e @ LspManagerError::NotImplemented(_)) => return Err(e),
+                Err(e) => {
+                    // Keep the references found for the other symbols
+                    warn!(
+                        "Failed to find referenced symbols for {}: {}",
+                        symbol.meta_variables.single.name.text, e
+                    );
+                    continue;
+                }
+            };
+            for (reference, definition) in references {
+                let start = ApiPosition::from(lsp_types::Position::from(&reference));
+                let in_range = range.contains_position(&start);
+                let key = (
+                    start.line,
+                    start.character,
+                    reference.meta_variables.single.name.text.clone(),
+                );
+                if in_range && seen.insert(key) {
+                    referenced_symbols.push((reference, definition));
+                }
+            }
+            // A full scan covers the whole file, so the other symbols would add nothing
+            if full_scan {
+                break;
+            }
+        }
+        referenced_symbols.sort_by_key(|(reference, _)| lsp_types::Position::from(reference));
+        Ok(referenced_symbols)
+    }
+
+    /// Counts the references to the symbol at a position, excluding its declaration.
+    pub async fn get_symbol_references_count(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<usize, LspManagerError> {
+        Ok(self
+            .find_references(file_path, position, false)
+            .await?
+            .len())
+    }
+
+    #[tracing::instrument(skip(self), fields(language = tracing::field::Empty), err(level = "debug"))]
+    pub async fn find_referenced_symbols(
+        &self,
+        file_path: &str,
+        position: Position,
+        full_scan: bool,
+    ) -> Result<Vec<(AstGrepMatch, GotoDefinitionResponse)>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        tracing::Span::current().record("language", lsp_type.to_string());
+
+        // Only Python and TypeScript/JavaScript are currently supported
+        match lsp_type {
             SupportedLanguages::Python | SupportedLanguages::TypeScriptJavaScript | SupportedLanguages::CSharp => (),
             _ => return Err(LspManagerError::NotImplemented(
-                "Find referenced symbols is only implemented for Python, TypeScript/JavaScript, and C#"
+                "Find referenced symbols is only implemented for Python, TypeScript/JavaScript, and C#"
+                    .to_string(),
+            )),
+        }
+
+        // Get the symbol and its references
+        let (_, references_to_symbols) = match self
+            .ast_grep_for(full_path_str)
+            .get_symbol_and_references(full_path_str, &position, full_scan)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                return Err(LspManagerError::InternalError(format!(
+                    "Failed to find referenced symbols, {}",
+                    e
+                )));
+            }
+        };
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let mut locked_client = client.lock().await;
+        let mut definitions = Vec::new();
+
+        // Get direct definitions for each reference
+        for ast_match in references_to_symbols.iter() {
+            match locked_client
+                .text_document_definition(full_path_str, lsp_types::Position::from(ast_match))
+                .await
+            {
+                Ok(definition) => {
+                    definitions.push((ast_match.clone(), definition));
+                }
+                Err(e) => {
+                    // Log the error but continue processing other references
+                    log::warn!(
+                        "Definition retrieval failed for reference: {}, error: {}",
+                        ast_match.meta_variables.single.name.text,
+                        e
+                    );
+                }
+            }
+        }
+
+        // Only return an error if we couldn't get any definitions at all
+        if definitions.is_empty() && !references_to_symbols.is_empty() {
+            return Err(LspManagerError::InternalError(
+                "Failed to retrieve any definitions for the referenced symbols".to_string(),
+            ));
+        }
+
+        Ok(definitions)
+    }
+
+    /// Lists the files of every language client, sorted. Files covered by several clients, e.g.
+    /// C headers, are only listed once.
+    #[tracing::instrument(skip(self), err(level = "debug"))]
+    pub async fn list_files(&self) -> Result<Vec<String>, LspManagerError> {
+        let mut files = HashSet::new();
+        for (_, client) in self.all_clients() {
+            let mut locked_client = client.lock().await;
+            files.extend(
+                locked_client
+                    .get_workspace_documents()
+                    .list_files()
+                    .await
+                    .iter()
+                    .map(|path| absolute_path_to_relative_path_string(path)),
+            );
+        }
+        let mut files: Vec<String> = files.into_iter().collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Lists the workspace files like [`Manager::list_files`], each with the language of the
+    /// client that owns it.
+    ///
+    /// A file listed by several clients, e.g. a header served by both a C and a C++ server, is
+    /// owned by the client of the language detected for it, or else by the first client in
+    /// language order.
+    pub async fn list_files_with_owner(
+        &self,
+    ) -> Result<Vec<(String, SupportedLanguages)>, LspManagerError> {
+        let mut candidates: HashMap<String, Vec<SupportedLanguages>> = HashMap::new();
+        for language in SupportedLanguages::iter() {
+            for client in self.clients_for_language(language) {
+                let mut locked_client = client.lock().await;
+                for path in locked_client.get_workspace_documents().list_files().await {
+                    candidates
+                        .entry(absolute_path_to_relative_path_string(&path))
+                        .or_default()
+                        .push(language);
+                }
+            }
+        }
+        let mut files: Vec<(String, SupportedLanguages)> = candidates
+            .into_iter()
+            .map(|(path, languages)| {
+                let owner = file_owner(&path, &languages);
+                (path, owner)
+            })
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    }
+
+    /// Lists the workspace files by the language of the client owning them, see
+    /// [`Manager::list_files_with_owner`]. The files of each language are sorted.
+    pub async fn list_files_grouped(
+        &self,
+    ) -> Result<HashMap<SupportedLanguages, Vec<String>>, LspManagerError> {
+        let mut groups: HashMap<SupportedLanguages, Vec<String>> = HashMap::new();
+        for (path, language) in self.list_files_with_owner().await? {
+            groups.entry(language).or_default().push(path);
+        }
+        Ok(groups)
+    }
+
+    pub async fn list_files_by_language(
+        &self,
+        language: SupportedLanguages,
+    ) -> Result<Vec<String>, LspManagerError> {
+        let clients = self.clients_for_language(language);
+        if clients.is_empty() {
+            return Err(LspManagerError::LspClientNotFound(language));
+        }
+        let mut files = Vec::new();
+        for client in clients {
+            let mut locked_client = client.lock().await;
+            files.extend(
+                locked_client
+                    .get_workspace_documents()
+                    .list_files()
+                    .await
+                    .iter()
+                    .map(|path| absolute_path_to_relative_path_string(path)),
+            );
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Lists the workspace files with their size and modification time, sorted by path.
+    pub async fn list_files_with_metadata(&self) -> Result<Vec<FileMetadata>, LspManagerError> {
+        let files = self.list_files().await?;
+        files_metadata(files)
+    }
+
+    /// Lists the files of a language with their size and modification time, sorted by path.
+    pub async fn list_files_by_language_with_metadata(
+        &self,
+        language: SupportedLanguages,
+    ) -> Result<Vec<FileMetadata>, LspManagerError> {
+        let files = self.list_files_by_language(language).await?;
+        files_metadata(files)
+    }
+
+    /// Searches the workspace files for literal text, returning the matches sorted by file path
+    /// and position. Only files matching `file_pattern` are searched, if given, and the search
+    /// stops after `max_results` matches. Files larger than [`max_source_code_bytes`] are only
+    /// searched up to that size, and files that can't be read are skipped.
+    pub async fn search_text(
+        &self,
+        query: &str,
+        case_sensitive: bool,
+        file_pattern: Option<&glob::Pattern>,
+        max_results: Option<usize>,
+    ) -> Result<Vec<TextSearchMatch>, LspManagerError> {
+        let matcher = regex::RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| LspManagerError::InternalError(format!("Invalid query: {}", e)))?;
+        let max_results = max_results.unwrap_or(usize::MAX);
+
+        let mut matches = Vec::new();
+        for file_path in self.list_files().await? {
+            if matches.len() >= max_results {
+                break;
+            }
+            if file_pattern.is_some_and(|pattern| !pattern.matches(&file_path)) {
+                continue;
+            }
+            // Large files, e.g. minified bundles, are only searched up to the size limit
+            let content = match self
+                .read_source_code(&file_path, None, Some(max_source_code_bytes()))
+                .await
+            {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Skipping {} in text search: {}", file_path, e);
+                    continue;
+                }
+            };
+            let content = content.strip_suffix(TRUNCATION_MARKER).unwrap_or(&content);
+            matches.extend(
+                text_matches(&file_path, content, &matcher)
+                    .into_iter()
+                    .take(max_results - matches.len()),
+            );
+        }
+        Ok(matches)
+    }
+
+    /// Detects the language whose server handles a file. Workspace files listed by a client
+    /// belong to it, see [`Manager::list_files_with_owner`]; other files are detected from
+    /// their extension or, failing that, their content. Returns `None` if no language server
+    /// handles the file.
+    pub async fn get_language_for_file(&self, file_path: &str) -> Option<SupportedLanguages> {
+        let full_path = get_mount_dir().join(file_path);
+        let mut languages = Vec::new();
+        for language in SupportedLanguages::iter() {
+            for client in self.clients_for_language(language) {
+                let mut locked_client = client.lock().await;
+                if locked_client
+                    .get_workspace_documents()
+                    .list_files()
+                    .await
+                    .contains(&full_path)
+                {
+                    languages.push(language);
+                }
+            }
+        }
+        if languages.is_empty() {
+            return detect_language(file_path).ok();
+        }
+        Some(file_owner(file_path, &languages))
+    }
+
+    /// The ast-grep client for a file: the first one whose rules are written for the file's
+    /// language, or the first one if none is, e.g. for files of no known language.
+    fn ast_grep_for(&self, file_path: &str) -> &AstGrepClient {
+        let language = detect_language(file_path).ok();
+        self.ast_grep
+            .iter()
+            .find(|client| language.is_some_and(|language| client.handles(language)))
+            .unwrap_or(&self.ast_grep[0])
+    }
+
+    /// Detects the language of the file at a location returned by a language server, which may
+    /// be outside the workspace, e.g. in the standard library. Workspace files are detected from
+    /// their path relative to the mount dir, other files from their absolute path. Returns `None`
+    /// if the URI isn't a file or its language can't be detected, e.g. for C++ standard headers
+    /// without an extension.
+    pub fn detect_language_for_location(&self, uri: &Url) -> Option<SupportedLanguages> {
+        let path = uri.to_file_path().ok()?;
+        let mount_dir = get_mount_dir();
+        let path = path.strip_prefix(&mount_dir).unwrap_or(&path);
+        detect_language(path.to_str()?).ok()
+    }
+
+    /// Reads a file, or a range of it. Files larger than `max_bytes` are truncated, see
+    /// [`WorkspaceDocuments::read_text_document`]. With [`ManagerConfig::line_range_reads`] set,
+    /// ranges of fewer than 1000 lines are read line by line unless `max_bytes` is given.
+    pub async fn read_source_code(
+        &self,
+        file_path: &str,
+        range: Option<Range>,
+        max_bytes: Option<usize>,
+    ) -> Result<String, LspManagerError> {
+        // Small ranges are read line by line instead of loading the whole file
+        if let Some(range) = range.filter(|_| self.line_range_reads && max_bytes.is_none()) {
+            let line_count = range.end.line.saturating_sub(range.start.line);
+            if range.start.line <= range.end.line && line_count < LINE_RANGE_READ_THRESHOLD {
+                let lines = self
+                    .read_source_code_lines(file_path, range.start.line, range.end.line)
+                    .await?;
+                let relative_range = Range::new(
+                    Position::new(0, range.start.character),
+                    Position::new(line_count, range.end.character),
+                );
+                return WorkspaceDocumentsHandler::extract_range(&lines, relative_range).map_err(
+                    |e| {
+                        LspManagerError::InternalError(format!(
+                            "Source code retrieval failed: {}",
+                            e
+                        ))
+                    },
+                );
+            }
+        }
+
+        let client = self
+            .get_client_for_file(detect_language(file_path)?, file_path)
+            .ok_or(LspManagerError::LspClientNotFound(detect_language(
+                file_path,
+            )?))?;
+        let full_path = get_mount_dir().join(file_path);
+        let mut locked_client = client.lock().await;
+        locked_client
+            .get_workspace_documents()
+            .read_text_document(&full_path, range, max_bytes)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Source code retrieval failed: {}", e))
+            })
+    }
+
+    pub async fn format_document(
+        &self,
+        file_path: &str,
+        tab_size: u32,
+        insert_spaces: bool,
+    ) -> Result<Vec<TextEdit>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Python
+            | SupportedLanguages::TypeScriptJavaScript
+            | SupportedLanguages::Rust => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Formatting is only implemented for Python, TypeScript/JavaScript, and Rust"
+                        .to_string(),
+                ))
+            }
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let mut locked_client = client.lock().await;
+        locked_client
+            .text_document_formatting(full_path_str, tab_size, insert_spaces)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Formatting failed: {}", e)))
+    }
+
+    /// Gets the code actions available for a range in a file.
+    ///
+    /// The diagnostics the server has published for the range are passed along so that quick fixes
+    /// for them are included.
+    pub async fn code_actions(
+        &self,
+        file_path: &str,
+        range: Range,
+        only: Option<Vec<String>>,
+    ) -> Result<CodeActionResponse, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+
+        let pending_requests = client
+            .lock()
+            .await
+            .prepare_document_diagnostics(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Diagnostics retrieval failed: {}", e))
+            })?;
+        let uri = Url::from_file_path(&full_path).map_err(|_| {
+            LspManagerError::InternalError(format!("Invalid file path: {}", full_path_str))
+        })?;
+        // Waited for with the client unlocked, so other requests to the server aren't held up
+        let diagnostics = pending_requests
+            .wait_for_diagnostics(&uri, DIAGNOSTICS_TIMEOUT)
+            .await
+            .into_iter()
+            .filter(|diagnostic| {
+                diagnostic.range.start <= range.end && range.start <= diagnostic.range.end
+            })
+            .collect();
+        let context = CodeActionContext {
+            diagnostics,
+            only: only.map(|kinds| kinds.into_iter().map(CodeActionKind::from).collect()),
+            trigger_kind: Some(CodeActionTriggerKind::INVOKED),
+        };
+
+        let mut locked_client = client.lock().await;
+        locked_client
+            .text_document_code_action(full_path_str, range, context)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Code action retrieval failed: {}", e))
+            })
+    }
+
+    /// Gets the semantic tokens for a file, decoded into absolute positions.
+    pub async fn semantic_tokens(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<SemanticToken>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        let legend = self
+            .semantic_tokens_legends
+            .read()
+            .await
+            .get(&lsp_type)
+            .cloned()
+            .ok_or_else(|| {
+                LspManagerError::NotImplemented(format!(
+                    "The {} language server does not support semantic tokens",
+                    lsp_type
+                ))
+            })?;
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let tokens = client
+            .lock()
+            .await
+            .text_document_semantic_tokens_full(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Semantic token retrieval failed: {}", e))
+            })?;
+        Ok(decode_semantic_tokens(&tokens.data, &legend))
+    }
+
+    /// Gets the folding ranges of a file, sorted by start line.
+    pub async fn folding_ranges(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<FoldingRange>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Python
+            | SupportedLanguages::TypeScriptJavaScript
+            | SupportedLanguages::Rust => (),
+            _ => return Err(LspManagerError::NotImplemented(
+                "Folding ranges are only implemented for Python, TypeScript/JavaScript, and Rust"
                     .to_string(),
             )),
         }
 
-        // Get the symbol and its references
-        let (_, references_to_symbols) = match self
-            .ast_grep
-            .get_symbol_and_references(full_path_str, &position, full_scan)
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let ranges = client
+            .lock()
+            .await
+            .text_document_folding_range(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Folding range retrieval failed: {}", e))
+            })?;
+
+        let mut ranges: Vec<FoldingRange> = ranges.into_iter().map(Into::into).collect();
+        ranges.sort_by_key(|range| (range.start_line, range.end_line));
+        Ok(ranges)
+    }
+
+    /// Gets the selection range hierarchy for each position in a file, in the same order.
+    pub async fn selection_ranges(
+        &self,
+        file_path: &str,
+        positions: Vec<Position>,
+    ) -> Result<Vec<SelectionRange>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::TypeScriptJavaScript | SupportedLanguages::Rust => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Selection ranges are only implemented for TypeScript/JavaScript and Rust"
+                        .to_string(),
+                ))
+            }
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let ranges = client
+            .lock()
             .await
-        {
-            Ok(result) => result,
-            Err(e) => {
-                return Err(LspManagerError::InternalError(format!(
-                    "Failed to find referenced symbols, {}",
-                    e
-                )));
+            .text_document_selection_range(full_path_str, positions)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Selection range retrieval failed: {}", e))
+            })?;
+
+        Ok(ranges
+            .into_iter()
+            .map(|range| SelectionRange::from_lsp(file_path, range))
+            .collect())
+    }
+
+    /// Gets the links in a file, such as URLs in doc comments. Links without a target are skipped.
+    pub async fn document_links(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<DocumentLink>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Python | SupportedLanguages::TypeScriptJavaScript => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Document links are only implemented for Python and TypeScript/JavaScript"
+                        .to_string(),
+                ))
+            }
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let links = client
+            .lock()
+            .await
+            .text_document_document_link(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Document link retrieval failed: {}", e))
+            })?;
+
+        Ok(links
+            .into_iter()
+            .filter_map(|link| {
+                Some(DocumentLink {
+                    target: link.target?.to_string(),
+                    range: FileRange::from_lsp_range(file_path.to_string(), link.range),
+                })
+            })
+            .collect())
+    }
+
+    /// Gets the code lenses of a file, sorted by position.
+    pub async fn code_lenses(&self, file_path: &str) -> Result<Vec<CodeLens>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Rust | SupportedLanguages::Java => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Code lenses are only implemented for Rust and Java".to_string(),
+                ))
+            }
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let lenses = client
+            .lock()
+            .await
+            .text_document_code_lens(full_path_str)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Code lens retrieval failed: {}", e))
+            })?;
+
+        let mut lenses: Vec<CodeLens> = lenses
+            .into_iter()
+            .filter_map(|lens| {
+                let command = lens.command?;
+                Some(CodeLens {
+                    range: FileRange::from_lsp_range(file_path.to_string(), lens.range),
+                    title: command.title,
+                    command: Some(command.command).filter(|command| !command.is_empty()),
+                })
+            })
+            .collect();
+        lenses.sort_by_key(|lens| {
+            (
+                lens.range.range.start.line,
+                lens.range.range.start.character,
+            )
+        });
+        Ok(lenses)
+    }
+
+    /// Checks whether the symbol at a position can be renamed, returning `None` if it can't.
+    pub async fn prepare_rename(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Option<PrepareRenameResponse>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Rust
+            | SupportedLanguages::TypeScriptJavaScript
+            | SupportedLanguages::Python => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Prepare rename is only implemented for Rust, TypeScript/JavaScript and Python"
+                        .to_string(),
+                ))
+            }
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let response = client
+            .lock()
+            .await
+            .text_document_prepare_rename(full_path_str, position)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Prepare rename failed: {}", e)))?;
+        let Some(response) = response else {
+            return Ok(None);
+        };
+
+        let (range, placeholder) = match response {
+            lsp_types::PrepareRenameResponse::RangeWithPlaceholder { range, placeholder } => {
+                (range, placeholder)
+            }
+            response => {
+                // The placeholder defaults to the text being renamed
+                let content = tokio::fs::read_to_string(&full_path).await.map_err(|e| {
+                    LspManagerError::InternalError(format!("Failed to read file: {}", e))
+                })?;
+                let range = match response {
+                    lsp_types::PrepareRenameResponse::Range(range) => Some(range),
+                    _ => word_range_at(&content, position),
+                };
+                let Some(range) = range else {
+                    return Ok(None);
+                };
+                (range, text_in_range(&content, range).to_string())
+            }
+        };
+
+        Ok(Some(PrepareRenameResponse {
+            range: FileRange::from_lsp_range(file_path.to_string(), range),
+            placeholder,
+        }))
+    }
+
+    /// Renames the symbol at a position to `new_name`, applying the edits unless `dry_run` is set.
+    ///
+    /// Returns `None` if the language server has nothing to rename at the position. Fails with
+    /// [`LspManagerError::InvalidEdit`] if `new_name` isn't a valid identifier of the language.
+    pub async fn rename_symbol(
+        &self,
+        file_path: &str,
+        position: Position,
+        new_name: &str,
+        dry_run: bool,
+    ) -> Result<Option<RenameSymbolResponse>, LspManagerError> {
+        let workspace_files = self.list_files().await.map_err(|e| {
+            LspManagerError::InternalError(format!("Workspace file retrieval failed: {}", e))
+        })?;
+        if !workspace_files.iter().any(|f| f == file_path) {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        }
+
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+
+        match lsp_type {
+            SupportedLanguages::Rust
+            | SupportedLanguages::TypeScriptJavaScript
+            | SupportedLanguages::Python => (),
+            _ => {
+                return Err(LspManagerError::NotImplemented(
+                    "Rename is only implemented for Rust, TypeScript/JavaScript and Python"
+                        .to_string(),
+                ))
             }
+        }
+        if !validate_identifier_name(new_name, lsp_type) {
+            return Err(LspManagerError::InvalidEdit(format!(
+                "'{}' is not a valid {} identifier",
+                new_name,
+                lsp_type.display_name()
+            )));
+        }
+
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let edit = client
+            .lock()
+            .await
+            .text_document_rename(full_path_str, position, new_name)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Rename failed: {}", e)))?;
+        let Some(edit) = edit else {
+            return Ok(None);
+        };
+
+        let modified_files = if dry_run {
+            Vec::new()
+        } else {
+            self.apply_workspace_edit(&edit).await?
         };
+        Ok(Some(RenameSymbolResponse {
+            edit: edit.into(),
+            modified_files,
+        }))
+    }
 
+    /// Gets the hover text of every symbol defined in a file, e.g. to generate its documentation.
+    ///
+    /// Local variables are left out. The hover text is usually markdown with the signature and
+    /// documentation of the symbol, and `None` if the language server has nothing to show. The
+    /// symbols are looked up one after the other, as the client handles one request at a time.
+    pub async fn get_hover_for_all_symbols(
+        &self,
+        file_path: &str,
+    ) -> Result<Vec<(Symbol, Option<String>)>, LspManagerError> {
+        let symbols = self.definitions_in_file_ast_grep(file_path).await?;
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
         let client = self
-            .get_client(lsp_type)
+            .get_client_for_file(lsp_type, full_path_str)
             .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
-        let mut locked_client = client.lock().await;
-        let mut definitions = Vec::new();
 
-        // Get direct definitions for each reference
-        for ast_match in references_to_symbols.iter() {
-            match locked_client
-                .text_document_definition(full_path_str, lsp_types::Position::from(ast_match))
+        let symbols = symbols
+            .into_iter()
+            .filter(|symbol| symbol.rule_id != "local-variable")
+            .map(Symbol::from);
+        let mut hovers = Vec::new();
+        for symbol in symbols {
+            let hover = client
+                .lock()
                 .await
-            {
-                Ok(definition) => {
-                    definitions.push((ast_match.clone(), definition));
-                }
-                Err(e) => {
-                    // Log the error but continue processing other references
-                    log::warn!(
-                        "Definition retrieval failed for reference: {}, error: {}",
-                        ast_match.meta_variables.single.name.text,
-                        e
-                    );
-                }
+                .text_document_hover(
+                    full_path_str,
+                    symbol.identifier_position.position.clone().into(),
+                )
+                .await
+                .map_err(|e| {
+                    LspManagerError::InternalError(format!("Hover retrieval failed: {}", e))
+                })?;
+            hovers.push((symbol, hover.and_then(|hover| hover_text(hover.contents))));
+        }
+
+        hovers.sort_by(|(a, _), (b, _)| {
+            a.identifier_position
+                .position
+                .cmp(&b.identifier_position.position)
+        });
+        Ok(hovers)
+    }
+
+    /// Gets the hover text the language server shows for the symbol at a position, `None` if it
+    /// has nothing to show.
+    pub async fn get_hover(
+        &self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<Option<String>, LspManagerError> {
+        let full_path = get_mount_dir().join(file_path);
+        let full_path_str = full_path.to_str().unwrap_or_default();
+        let lsp_type = detect_language(full_path_str).map_err(|e| {
+            LspManagerError::InternalError(format!("Language detection failed: {}", e))
+        })?;
+        let client = self
+            .get_client_for_file(lsp_type, full_path_str)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+        let hover = client
+            .lock()
+            .await
+            .text_document_hover(full_path_str, position)
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Hover retrieval failed: {}", e))
+            })?;
+        Ok(hover.and_then(|hover| hover_text(hover.contents)))
+    }
+
+    /// Applies text edits to a file on disk.
+    pub async fn apply_text_edits(
+        &self,
+        file_path: &str,
+        edits: &[TextEdit],
+    ) -> Result<(), LspManagerError> {
+        let full_path = get_mount_dir().join(file_path);
+        let content = tokio::fs::read_to_string(&full_path).await.map_err(|e| {
+            LspManagerError::InternalError(format!("Source code retrieval failed: {}", e))
+        })?;
+        self.write_source_code(file_path, &apply_text_edits(&content, edits), false)
+            .await
+    }
+
+    /// Writes the content of a file in the workspace and tells its language server about it, so
+    /// that it's re-indexed immediately.
+    ///
+    /// The file is created if it's missing and `create_if_missing` is set. Paths that are absolute
+    /// or contain `..` are rejected.
+    pub async fn write_source_code(
+        &self,
+        file_path: &str,
+        content: &str,
+        create_if_missing: bool,
+    ) -> Result<(), LspManagerError> {
+        if Path::new(file_path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(LspManagerError::InvalidPath(file_path.to_string()));
+        }
+        let lsp_type = detect_language(file_path)
+            .map_err(|_| LspManagerError::UnsupportedFileType(file_path.to_string()))?;
+        let client = self
+            .get_client_for_file(lsp_type, file_path)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
+
+        let full_path = get_mount_dir().join(file_path);
+        if full_path.exists() {
+            // Existing files must be part of the workspace, e.g. not in node_modules
+            if !self.list_files().await?.iter().any(|f| f == file_path) {
+                return Err(LspManagerError::FileNotFound(file_path.to_string()));
             }
+        } else if !create_if_missing {
+            return Err(LspManagerError::FileNotFound(file_path.to_string()));
+        } else if let Some(parent) = full_path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to create directory: {}", e))
+            })?;
         }
 
-        // Only return an error if we couldn't get any definitions at all
-        if definitions.is_empty() && !references_to_symbols.is_empty() {
-            return Err(LspManagerError::InternalError(
-                "Failed to retrieve any definitions for the referenced symbols".to_string(),
-            ));
+        tokio::fs::write(&full_path, content)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Failed to write file: {}", e)))?;
+
+        self.notify_file_written(&client, file_path, content).await
+    }
+
+    /// Renames a file in the workspace, first applying the edits its language server computes
+    /// for the rename, e.g. updating the imports of the file. Returns the paths of the files
+    /// modified by those edits.
+    ///
+    /// The new path must be relative, without `..`, and must not exist yet.
+    pub async fn rename_file(
+        &self,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<Vec<String>, LspManagerError> {
+        if Path::new(new_path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(LspManagerError::InvalidPath(new_path.to_string()));
+        }
+        if !self.list_files().await?.iter().any(|f| f == old_path) {
+            return Err(LspManagerError::FileNotFound(old_path.to_string()));
+        }
+        let full_old_path = get_mount_dir().join(old_path);
+        let full_new_path = get_mount_dir().join(new_path);
+        if full_new_path.exists() {
+            return Err(LspManagerError::InvalidPath(new_path.to_string()));
         }
+        let lsp_type = detect_language(old_path)?;
+        let client = self
+            .get_client_for_file(lsp_type, old_path)
+            .ok_or(LspManagerError::LspClientNotFound(lsp_type))?;
 
-        Ok(definitions)
+        let edit = client
+            .lock()
+            .await
+            .workspace_will_rename_files(vec![(
+                full_old_path.to_string_lossy().into_owned(),
+                full_new_path.to_string_lossy().into_owned(),
+            )])
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to prepare file rename: {}", e))
+            })?;
+        let modified_files = match edit {
+            Some(edit) => self.apply_workspace_edit(&edit).await?,
+            None => Vec::new(),
+        };
+
+        if let Some(parent) = full_new_path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to create directory: {}", e))
+            })?;
+        }
+        tokio::fs::rename(&full_old_path, &full_new_path)
+            .await
+            .map_err(|e| LspManagerError::InternalError(format!("Failed to rename file: {}", e)))?;
+
+        // The file lists only pick up the rename once the watcher sees it, so they're dropped
+        for (_, client) in self.all_clients() {
+            client
+                .lock()
+                .await
+                .get_workspace_documents()
+                .clear_cache()
+                .await;
+        }
+        self.clear_file_cache(old_path).await;
+        self.file_metrics_cache.lock().await.remove(old_path);
+        *self.symbol_counts_cache.lock().await = None;
+
+        let modified_files = modified_files
+            .into_iter()
+            .map(|file| {
+                if file == old_path {
+                    new_path.to_string()
+                } else {
+                    file
+                }
+            })
+            .collect();
+        Ok(modified_files)
     }
 
-    pub async fn list_files(&self) -> Result<Vec<String>, LspManagerError> {
-        let mut files = Vec::new();
-        for client in self.lsp_clients.values() {
-            let mut locked_client = client.lock().await;
-            files.extend(
-                locked_client
-                    .get_workspace_documents()
-                    .list_files()
-                    .await
-                    .iter()
-                    .filter_map(|f| Some(absolute_path_to_relative_path_string(f)))
-                    .collect::<Vec<String>>(),
-            );
+    /// Applies a workspace edit to the files on disk and tells their language servers about the
+    /// new content, returning the paths of the modified files.
+    ///
+    /// Nothing is written if any of the edited files is missing or outside the workspace, or if
+    /// edits to a file overlap.
+    pub async fn apply_workspace_edit(
+        &self,
+        edit: &lsp_types::WorkspaceEdit,
+    ) -> Result<Vec<String>, LspManagerError> {
+        let modified_files = apply_workspace_edit(edit, &get_mount_dir())?;
+        for file_path in &modified_files {
+            let Some(client) = detect_language(file_path)
+                .ok()
+                .and_then(|lsp_type| self.get_client_for_file(lsp_type, file_path))
+            else {
+                self.clear_file_cache(file_path).await;
+                continue;
+            };
+            let content = tokio::fs::read_to_string(get_mount_dir().join(file_path))
+                .await
+                .map_err(|e| {
+                    LspManagerError::InternalError(format!("Source code retrieval failed: {}", e))
+                })?;
+            self.notify_file_written(&client, file_path, &content)
+                .await?;
         }
-        files.sort();
-        Ok(files)
+        Ok(modified_files)
     }
 
-    pub async fn read_source_code(
+    /// Sends the new content of a written file to its language server and drops the results
+    /// cached for it.
+    async fn notify_file_written(
         &self,
+        client: &Arc<Mutex<Box<dyn LspClient>>>,
         file_path: &str,
-        range: Option<Range>,
-    ) -> Result<String, LspManagerError> {
-        let client = self.get_client(detect_language(file_path)?).ok_or(
-            LspManagerError::LspClientNotFound(detect_language(file_path)?),
-        )?;
+        content: &str,
+    ) -> Result<(), LspManagerError> {
         let full_path = get_mount_dir().join(file_path);
         let mut locked_client = client.lock().await;
         locked_client
             .get_workspace_documents()
-            .read_text_document(&full_path, range)
+            .invalidate_document(&full_path)
+            .await;
+        locked_client
+            .sync_document(full_path.to_str().unwrap_or_default(), content.to_string())
+            .await
+            .map_err(|e| {
+                LspManagerError::InternalError(format!("Failed to notify language server: {}", e))
+            })?;
+        drop(locked_client);
+
+        self.file_metrics_cache.lock().await.remove(file_path);
+        *self.symbol_counts_cache.lock().await = None;
+        self.clear_file_cache(file_path).await;
+        Ok(())
+    }
+
+    /// Reads lines `start_line..=end_line` (0-indexed) of a file without loading the whole file.
+    pub async fn read_source_code_lines(
+        &self,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+    ) -> Result<String, LspManagerError> {
+        let lsp_type = detect_language(file_path)?;
+        if self.get_client_for_file(lsp_type, file_path).is_none() {
+            return Err(LspManagerError::LspClientNotFound(lsp_type));
+        }
+        let full_path = get_mount_dir().join(file_path);
+        read_file_lines(&full_path, start_line, end_line)
             .await
             .map_err(|e| {
                 LspManagerError::InternalError(format!("Source code retrieval failed: {}", e))
             })
     }
 
+    /// Computes line and symbol metrics for a file, reusing results younger than 60 seconds.
+    /// Local variables aren't counted as symbols.
+    pub async fn get_file_metrics(&self, file_path: &str) -> Result<FileMetrics, LspManagerError> {
+        if let Some((computed_at, metrics)) = self.file_metrics_cache.lock().await.get(file_path) {
+            if computed_at.elapsed() < FILE_METRICS_TTL {
+                return Ok(metrics.clone());
+            }
+        }
+
+        let source_code = self.read_source_code(file_path, None, None).await?;
+        let symbol_count = self
+            .definitions_in_file_ast_grep(file_path)
+            .await?
+            .iter()
+            .filter(|s| s.rule_id != "local-variable")
+            .count();
+
+        let mut metrics = count_lines(&source_code);
+        metrics.path = file_path.to_string();
+        metrics.symbol_count = symbol_count;
+        metrics.symbols_per_100_lines = if metrics.total_lines == 0 {
+            0.0
+        } else {
+            symbol_count as f32 * 100.0 / metrics.total_lines as f32
+        };
+
+        self.file_metrics_cache
+            .lock()
+            .await
+            .insert(file_path.to_string(), (Instant::now(), metrics.clone()));
+        Ok(metrics)
+    }
+
+    /// Computes the metrics of every file in the workspace.
+    pub async fn get_workspace_file_metrics(&self) -> Result<Vec<FileMetrics>, LspManagerError> {
+        let mut metrics = Vec::new();
+        for file_path in self.list_files().await? {
+            metrics.push(self.get_file_metrics(&file_path).await?);
+        }
+        Ok(metrics)
+    }
+
+    /// Counts the symbols defined in each file of the workspace by kind. Local variables aren't
+    /// counted as symbols, as in [`Self::get_file_metrics`].
+    ///
+    /// Results are reused for 120 seconds, or until a file in the workspace changes.
+    pub async fn get_symbol_counts(&self) -> Result<SymbolCountsResponse, LspManagerError> {
+        if let Some((computed_at, counts)) = self.symbol_counts_cache.lock().await.as_ref() {
+            if computed_at.elapsed() < SYMBOL_COUNTS_TTL {
+                return Ok(counts.clone());
+            }
+        }
+
+        let mut by_file = Vec::new();
+        let mut by_kind: HashMap<String, usize> = HashMap::new();
+        for file_path in self.list_files().await? {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for symbol in self
+                .definitions_in_file_ast_grep(&file_path)
+                .await?
+                .into_iter()
+                .filter(|s| s.rule_id != "local-variable")
+            {
+                *counts.entry(symbol.rule_id.clone()).or_default() += 1;
+                *by_kind.entry(symbol.rule_id).or_default() += 1;
+            }
+            by_file.push(FileSymbolCounts {
+                path: file_path,
+                counts,
+            });
+        }
+
+        let counts = SymbolCountsResponse { by_file, by_kind };
+        *self.symbol_counts_cache.lock().await = Some((Instant::now(), counts.clone()));
+        Ok(counts)
+    }
+
     pub async fn get_file_identifiers(
         &self,
         file_path: &str,
@@ -470,7 +3110,7 @@ impl Manager {
         }
         let full_path_str = full_path.to_str().unwrap_or_default();
         let ast_grep_result = self
-            .ast_grep
+            .ast_grep_for(full_path_str)
             .get_file_identifiers(full_path_str)
             .await
             .map_err(|e| {
@@ -480,6 +3120,81 @@ impl Manager {
     }
 }
 
+/// Picks the language owning a file among those of the clients listing it, in language order:
+/// the one detected for the file if it's among them, else the first one.
+fn file_owner(file_path: &str, languages: &[SupportedLanguages]) -> SupportedLanguages {
+    detect_language(file_path)
+        .ok()
+        .filter(|language| languages.contains(language))
+        .unwrap_or(languages[0])
+}
+
+/// The first location of a definition response, pointing at the definition's name for links.
+fn first_definition_location(definition: GotoDefinitionResponse) -> Option<Location> {
+    match definition {
+        GotoDefinitionResponse::Scalar(location) => Some(location),
+        GotoDefinitionResponse::Array(locations) => locations.into_iter().next(),
+        GotoDefinitionResponse::Link(links) => links.into_iter().next().map(|link| Location {
+            uri: link.target_uri,
+            range: link.target_selection_range,
+        }),
+    }
+}
+
+/// Sorts symbols by file path and identifier position.
+fn sort_symbols(symbols: &mut [Symbol]) {
+    symbols.sort_by(|a, b| {
+        a.file_range.path.cmp(&b.file_range.path).then_with(|| {
+            a.identifier_position
+                .position
+                .cmp(&b.identifier_position.position)
+        })
+    });
+}
+
+/// Counts the total, blank and comment lines of a file's content.
+///
+/// Comment lines are approximated as lines starting with `#`, `//`, `*` or `--` once trimmed.
+fn count_lines(content: &str) -> FileMetrics {
+    let mut metrics = FileMetrics {
+        path: String::new(),
+        total_lines: 0,
+        blank_lines: 0,
+        comment_lines: 0,
+        symbol_count: 0,
+        symbols_per_100_lines: 0.0,
+    };
+    for line in content.lines() {
+        metrics.total_lines += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            metrics.blank_lines += 1;
+        } else if ["#", "//", "*", "--"]
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+        {
+            metrics.comment_lines += 1;
+        }
+    }
+    metrics
+}
+
+/// Finds the matches of `matcher` in each line of a file's content.
+fn text_matches(file_path: &str, content: &str, matcher: &regex::Regex) -> Vec<TextSearchMatch> {
+    let mut matches = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        for found in matcher.find_iter(line) {
+            matches.push(TextSearchMatch {
+                path: file_path.to_string(),
+                line: line_number as u32,
+                character: line[..found.start()].encode_utf16().count() as u32,
+                line_text: line.to_string(),
+            });
+        }
+    }
+    matches
+}
+
 #[derive(Debug)]
 pub enum LspManagerError {
     FileNotFound(String),
@@ -487,6 +3202,11 @@ pub enum LspManagerError {
     InternalError(String),
     UnsupportedFileType(String),
     NotImplemented(String),
+    InvalidPath(String),
+    InvalidEdit(String),
+    InitializationTimeout(SupportedLanguages),
+    /// No symbol is defined at the given `path:line:character`.
+    SymbolNotFound(String),
 }
 
 impl fmt::Display for LspManagerError {
@@ -496,7 +3216,7 @@ impl fmt::Display for LspManagerError {
                 write!(f, "File '{}' not found in workspace", path)
             }
             LspManagerError::LspClientNotFound(lang) => {
-                write!(f, "LSP client not found for {:?}", lang)
+                write!(f, "LSP client not found for {}", lang.display_name())
             }
             LspManagerError::InternalError(msg) => write!(f, "Internal error: {}", msg),
             LspManagerError::UnsupportedFileType(path) => {
@@ -505,8 +3225,1063 @@ impl fmt::Display for LspManagerError {
             LspManagerError::NotImplemented(msg) => {
                 write!(f, "Not implemented: {}", msg)
             }
+            LspManagerError::InvalidPath(path) => {
+                write!(f, "Invalid path: {}", path)
+            }
+            LspManagerError::InvalidEdit(msg) => write!(f, "Invalid edit: {}", msg),
+            LspManagerError::InitializationTimeout(lang) => {
+                write!(
+                    f,
+                    "LSP client for {} timed out during initialization",
+                    lang.display_name()
+                )
+            }
+            LspManagerError::SymbolNotFound(location) => {
+                write!(f, "No symbol found at {}", location)
+            }
+        }
+    }
+}
+
+impl LspManagerError {
+    /// The machine-readable code of the error, returned as `error_code` in error responses.
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            LspManagerError::FileNotFound(_) => ErrorCode::FileNotFound,
+            LspManagerError::LspClientNotFound(_) => ErrorCode::LspClientNotFound,
+            LspManagerError::InternalError(_) => ErrorCode::InternalError,
+            LspManagerError::UnsupportedFileType(_) => ErrorCode::UnsupportedFileType,
+            LspManagerError::NotImplemented(_) => ErrorCode::NotImplemented,
+            LspManagerError::InvalidPath(_) => ErrorCode::InvalidPath,
+            LspManagerError::InvalidEdit(_) => ErrorCode::InvalidEdit,
+            LspManagerError::InitializationTimeout(_) => ErrorCode::Timeout,
+            LspManagerError::SymbolNotFound(_) => ErrorCode::SymbolNotFound,
         }
     }
 }
 
 impl std::error::Error for LspManagerError {}
+
+impl From<ApplyEditError> for LspManagerError {
+    fn from(error: ApplyEditError) -> Self {
+        match error {
+            ApplyEditError::FileNotFound(path) => LspManagerError::FileNotFound(path),
+            ApplyEditError::OutsideWorkspace(path) => LspManagerError::InvalidPath(path),
+            ApplyEditError::OverlappingEdits(_) => LspManagerError::InvalidEdit(error.to_string()),
+            ApplyEditError::Io(_) => LspManagerError::InternalError(error.to_string()),
+        }
+    }
+}
+
+/// Reads the size and modification time of workspace files, keeping their order.
+fn files_metadata(files: Vec<String>) -> Result<Vec<FileMetadata>, LspManagerError> {
+    let mount_dir = get_mount_dir();
+    files
+        .into_iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(mount_dir.join(&path)).map_err(|e| {
+                LspManagerError::InternalError(format!(
+                    "Failed to read metadata of {}: {}",
+                    path, e
+                ))
+            })?;
+            let last_modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs());
+            Ok(FileMetadata {
+                path,
+                size_bytes: metadata.len(),
+                last_modified,
+            })
+        })
+        .collect()
+}
+
+/// The text of hover contents, with marked code blocks fenced as markdown. `None` if empty.
+fn hover_text(contents: lsp_types::HoverContents) -> Option<String> {
+    fn marked_string_text(marked: lsp_types::MarkedString) -> String {
+        match marked {
+            lsp_types::MarkedString::String(text) => text,
+            lsp_types::MarkedString::LanguageString(code) => {
+                format!("```{}\n{}\n```", code.language, code.value)
+            }
+        }
+    }
+
+    let text = match contents {
+        lsp_types::HoverContents::Scalar(marked) => marked_string_text(marked),
+        lsp_types::HoverContents::Array(marked) => marked
+            .into_iter()
+            .map(marked_string_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        lsp_types::HoverContents::Markup(markup) => markup.value,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Decodes LSP's relative token positions into absolute ones and resolves the token type and
+/// modifier indices against the server's legend.
+fn decode_semantic_tokens(
+    data: &[lsp_types::SemanticToken],
+    legend: &SemanticTokensLegend,
+) -> Vec<SemanticToken> {
+    let mut line = 0;
+    let mut character = 0;
+    data.iter()
+        .map(|token| {
+            if token.delta_line > 0 {
+                line += token.delta_line;
+                character = token.delta_start;
+            } else {
+                character += token.delta_start;
+            }
+            let modifiers = legend
+                .token_modifiers
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i < 32 && token.token_modifiers_bitset & (1 << i) != 0)
+                .map(|(_, modifier)| modifier.as_str().to_string())
+                .collect();
+            SemanticToken {
+                line,
+                character,
+                length: token.length,
+                token_type: legend
+                    .token_types
+                    .get(token.token_type as usize)
+                    .map(|token_type| token_type.as_str().to_string())
+                    .unwrap_or_default(),
+                modifiers,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api_types::{set_thread_local_mount_dir, unset_thread_local_mount_dir};
+    use crate::lsp::json_rpc::JsonRpc;
+    use crate::lsp::process::Process;
+    use crate::lsp::{JsonRpcHandler, PendingRequests, ProcessHandler};
+    use crate::test_utils::LogBuffer;
+    use crate::utils::workspace_documents::DidOpenConfiguration;
+    use async_trait::async_trait;
+    use lsp_types::InitializeResult;
+    use std::process::Stdio;
+
+    /// A client backed by a `cat` process, whose server takes `delay` to initialize and which
+    /// lists the files matching `include_patterns`.
+    struct MockClient {
+        process: ProcessHandler,
+        json_rpc: JsonRpcHandler,
+        workspace_documents: WorkspaceDocumentsHandler,
+        pending_requests: PendingRequests,
+        delay: Duration,
+    }
+
+    impl MockClient {
+        async fn boxed(
+            root_path: &Path,
+            include_patterns: &[&str],
+            delay: Duration,
+        ) -> Box<dyn LspClient> {
+            let process = tokio::process::Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .unwrap();
+            let (_, watch_events_rx) = channel(1);
+            Box::new(Self {
+                process: ProcessHandler::new(process).await.unwrap(),
+                json_rpc: JsonRpcHandler::new(),
+                workspace_documents: WorkspaceDocumentsHandler::new(
+                    root_path,
+                    include_patterns.iter().map(|s| s.to_string()).collect(),
+                    vec![],
+                    watch_events_rx,
+                    DidOpenConfiguration::None,
+                ),
+                pending_requests: PendingRequests::new(),
+                delay,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LspClient for MockClient {
+        async fn initialize(
+            &mut self,
+            _root_path: String,
+        ) -> Result<InitializeResult, Box<dyn Error + Send + Sync>> {
+            tokio::time::sleep(self.delay).await;
+            Ok(InitializeResult::default())
+        }
+
+        fn get_process(&mut self) -> &mut ProcessHandler {
+            &mut self.process
+        }
+
+        fn get_json_rpc(&mut self) -> &mut JsonRpcHandler {
+            &mut self.json_rpc
+        }
+
+        fn get_pending_requests(&mut self) -> &mut PendingRequests {
+            &mut self.pending_requests
+        }
+
+        fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
+            &mut self.workspace_documents
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_files_deduplicates_across_clients() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        for file in ["shared.h", "main.cpp", "util.c"] {
+            std::fs::write(dir.path().join(file), "")?;
+        }
+        set_thread_local_mount_dir(dir.path());
+        let mut manager =
+            Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+
+        // Both clients cover the header
+        let cpp = MockClient::boxed(dir.path(), &["**/*.h", "**/*.cpp"], Duration::ZERO).await;
+        let c = MockClient::boxed(dir.path(), &["**/*.h", "**/*.c"], Duration::ZERO).await;
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::CPP, Arc::new(Mutex::new(cpp)));
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::CSharp, Arc::new(Mutex::new(c)));
+
+        let files = manager.list_files().await?;
+        unset_thread_local_mount_dir();
+        assert_eq!(files, vec!["main.cpp", "shared.h", "util.c"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_files_with_owner() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        for file in ["shared.h", "main.cpp", "util.c"] {
+            std::fs::write(dir.path().join(file), "")?;
+        }
+        set_thread_local_mount_dir(dir.path());
+        let mut manager =
+            Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+
+        // Both clients cover the header, the C sources are only served by the second one
+        let cpp = MockClient::boxed(dir.path(), &["**/*.h", "**/*.cpp"], Duration::ZERO).await;
+        let c = MockClient::boxed(dir.path(), &["**/*.h", "**/*.c"], Duration::ZERO).await;
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::CPP, Arc::new(Mutex::new(cpp)));
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::CSharp, Arc::new(Mutex::new(c)));
+
+        let files = manager.list_files_with_owner().await?;
+        let util_language = manager.get_language_for_file("util.c").await;
+        let header_language = manager.get_language_for_file("shared.h").await;
+        let new_file_language = manager.get_language_for_file("new.c").await;
+        unset_thread_local_mount_dir();
+
+        // The header belongs to the client of the language detected for it
+        assert_eq!(
+            files,
+            vec![
+                (String::from("main.cpp"), SupportedLanguages::CPP),
+                (String::from("shared.h"), SupportedLanguages::CPP),
+                (String::from("util.c"), SupportedLanguages::CSharp),
+            ]
+        );
+        // Served files belong to their client, whatever their extension
+        assert_eq!(util_language, Some(SupportedLanguages::CSharp));
+        assert_eq!(header_language, Some(SupportedLanguages::CPP));
+        assert_eq!(new_file_language, Some(SupportedLanguages::CPP));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_find_definition_span() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("main.py"), "")?;
+        set_thread_local_mount_dir(dir.path());
+        let mut manager =
+            Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+
+        // The file is listed, but there's no Python client to resolve the definition
+        let client = MockClient::boxed(dir.path(), &["**/*.py"], Duration::ZERO).await;
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::CPP, Arc::new(Mutex::new(client)));
+        let result = manager
+            .find_definition("main.py", Position::new(0, 0))
+            .await;
+        unset_thread_local_mount_dir();
+
+        assert!(matches!(
+            result,
+            Err(LspManagerError::LspClientNotFound(
+                SupportedLanguages::Python
+            ))
+        ));
+        assert!(logs_contain("find_definition{"));
+        assert!(logs_contain(r#"file_path="main.py""#));
+        assert!(logs_contain(r#"language="python""#));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_trace_logs_server_trace() -> Result<(), Box<dyn Error>> {
+        // The trace is logged by the response listener task, outside any test span
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let dir = tempfile::tempdir()?;
+        let mut manager =
+            Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        let mut client = MockClient::boxed(dir.path(), &["**/*.py"], Duration::ZERO).await;
+        client.start_response_listener().await.unwrap();
+        let client = Arc::new(Mutex::new(client));
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::Python, Arc::clone(&client));
+
+        // The mock server doesn't support tracing, so this does nothing
+        manager
+            .set_trace(SupportedLanguages::Python, TraceValue::Verbose)
+            .await?;
+        assert!(matches!(
+            manager
+                .set_trace(SupportedLanguages::Rust, TraceValue::Verbose)
+                .await,
+            Err(LspManagerError::LspClientNotFound(SupportedLanguages::Rust))
+        ));
+
+        // `cat` echoes the notification back, as if the server had sent it
+        let mut locked_client = client.lock().await;
+        let notification = locked_client.get_json_rpc().create_notification(
+            "$/logTrace",
+            serde_json::json!({ "message": "Received request 'textDocument/definition - (1)'" }),
+        );
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        locked_client.get_process().send(&message).await.unwrap();
+        drop(locked_client);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(logs.contains("lsp_trace: Received request 'textDocument/definition - (1)'"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clear_all_caches() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("main.py");
+        std::fs::write(&file_path, "old = 1\n")?;
+        let mut manager =
+            Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        let client = MockClient::boxed(dir.path(), &["**/*.py"], Duration::ZERO).await;
+        manager
+            .lsp_clients
+            .insert(SupportedLanguages::Python, Arc::new(Mutex::new(client)));
+        manager
+            .ast_grep_cache
+            .write()
+            .await
+            .insert(String::from("main.py"), (Instant::now(), Vec::new()));
+        *manager.symbol_counts_cache.lock().await = Some((
+            Instant::now(),
+            SymbolCountsResponse {
+                by_file: Vec::new(),
+                by_kind: HashMap::new(),
+            },
+        ));
+
+        let client = manager.get_client(SupportedLanguages::Python).unwrap();
+        let read = || async {
+            client
+                .lock()
+                .await
+                .get_workspace_documents()
+                .read_text_document(&file_path, None, None)
+                .await
+                .unwrap()
+        };
+        assert_eq!(read().await, "old = 1\n");
+        // The document is cached, so changes on disk aren't seen until the cache is cleared
+        std::fs::write(&file_path, "new = 2\n")?;
+        assert_eq!(read().await, "old = 1\n");
+
+        // The listed file and its content, the ast-grep definitions and the symbol counts
+        assert_eq!(manager.clear_all_caches().await?, 3);
+        assert_eq!(read().await, "new = 2\n");
+        assert!(manager.ast_grep_cache.read().await.is_empty());
+        assert!(manager.symbol_counts_cache.lock().await.is_none());
+        assert_eq!(manager.clear_all_caches().await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auto_open_java_files() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        for file in ["Main.java", "Node.java", "AStar.java"] {
+            std::fs::write(dir.path().join(file), "class A {}")?;
+        }
+        let root_path = dir.path().to_str().unwrap();
+        let mut manager = Manager::new(
+            root_path,
+            ManagerConfig {
+                auto_open_java_files_limit: 2,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let client = MockClient::boxed(dir.path(), &["**/*.java"], Duration::ZERO).await;
+        manager
+            .initialize_client(SupportedLanguages::Java, client, root_path, None)
+            .await?;
+
+        let client = manager.get_client(SupportedLanguages::Java).unwrap();
+        let mut locked_client = client.lock().await;
+        let documents = locked_client.get_workspace_documents();
+        let opened = ["Main.java", "Node.java", "AStar.java"]
+            .iter()
+            .filter(|file| documents.is_did_open_document(dir.path().join(file).to_str().unwrap()))
+            .count();
+        assert_eq!(opened, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_language_for_file() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let manager = Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+
+        let cases = [
+            ("main.py", Some(SupportedLanguages::Python)),
+            ("types.pyi", Some(SupportedLanguages::Python)),
+            ("app.ts", Some(SupportedLanguages::TypeScriptJavaScript)),
+            ("App.tsx", Some(SupportedLanguages::TypeScriptJavaScript)),
+            ("index.js", Some(SupportedLanguages::TypeScriptJavaScript)),
+            ("App.jsx", Some(SupportedLanguages::TypeScriptJavaScript)),
+            ("src/main.rs", Some(SupportedLanguages::Rust)),
+            ("main.c", Some(SupportedLanguages::CPP)),
+            ("main.cpp", Some(SupportedLanguages::CPP)),
+            ("graph.hpp", Some(SupportedLanguages::CPP)),
+            ("AStar.cs", Some(SupportedLanguages::CSharp)),
+            ("Main.java", Some(SupportedLanguages::Java)),
+            ("main.go", Some(SupportedLanguages::Golang)),
+            ("index.php", Some(SupportedLanguages::PHP)),
+            ("app.rb", Some(SupportedLanguages::Ruby)),
+            ("notes.xyz", None),
+            ("Makefile", None),
+        ];
+        for (file, expected) in cases {
+            assert_eq!(
+                manager.get_language_for_file(file).await,
+                expected,
+                "{}",
+                file
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_initialization_timeout() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let root_path = dir.path().to_str().unwrap();
+        let mut manager = Manager::new(
+            root_path,
+            ManagerConfig {
+                initialization_timeout: Duration::from_millis(200),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let mut slow = MockClient::boxed(dir.path(), &[], Duration::from_secs(30)).await;
+        let slow_process = slow.get_process().clone();
+        let result = manager
+            .initialize_client(SupportedLanguages::Java, slow, root_path, None)
+            .await;
+        assert!(matches!(
+            result,
+            Err(LspManagerError::InitializationTimeout(
+                SupportedLanguages::Java
+            ))
+        ));
+        assert!(manager.get_client(SupportedLanguages::Java).is_none());
+        // The server that timed out isn't left running
+        assert!(!slow_process.is_running().await);
+
+        // Other languages still initialize
+        let fast = MockClient::boxed(dir.path(), &[], Duration::ZERO).await;
+        manager
+            .initialize_client(SupportedLanguages::Python, fast, root_path, None)
+            .await?;
+        assert!(manager.get_client(SupportedLanguages::Python).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ast_grep_config_dirs() {
+        assert_eq!(
+            ast_grep_config_dirs(None),
+            [PathBuf::from(DEFAULT_AST_GREP_CONFIG_DIR)]
+        );
+        assert_eq!(
+            ast_grep_config_dirs(Some(":")),
+            [PathBuf::from(DEFAULT_AST_GREP_CONFIG_DIR)]
+        );
+        assert_eq!(
+            ast_grep_config_dirs(Some("/opt/rules")),
+            [PathBuf::from("/opt/rules")]
+        );
+        assert_eq!(
+            ast_grep_config_dirs(Some("/opt/python_rules:/opt/typescript_rules")),
+            [
+                PathBuf::from("/opt/python_rules"),
+                PathBuf::from("/opt/typescript_rules")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_initialization_timeout() {
+        assert_eq!(
+            parse_initialization_timeout(None),
+            DEFAULT_INITIALIZATION_TIMEOUT
+        );
+        assert_eq!(
+            parse_initialization_timeout(Some(" 300 ")),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            parse_initialization_timeout(Some("five minutes")),
+            DEFAULT_INITIALIZATION_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn test_line_range_reads_enabled() {
+        assert!(!line_range_reads_enabled(None));
+        assert!(!line_range_reads_enabled(Some("false")));
+        assert!(line_range_reads_enabled(Some("true")));
+        assert!(line_range_reads_enabled(Some(" 1 ")));
+    }
+
+    #[test]
+    fn test_count_lines() {
+        let metrics = count_lines(
+            "# module docs\nimport os\n\n   \ndef main():\n    // not python\n    return 1\n",
+        );
+        assert_eq!(metrics.total_lines, 7);
+        assert_eq!(metrics.blank_lines, 2);
+        assert_eq!(metrics.comment_lines, 2);
+    }
+
+    fn symbol_match(rule_id: &str, name: &str) -> AstGrepMatch {
+        let range = serde_json::json!({
+            "byteOffset": {"start": 0, "end": 0},
+            "start": {"line": 0, "column": 0},
+            "end": {"line": 0, "column": name.len()},
+        });
+        serde_json::from_value(serde_json::json!({
+            "text": name,
+            "range": range,
+            "file": "main.py",
+            "lines": "",
+            "charCount": {"leading": 0, "trailing": 0},
+            "language": "Python",
+            "metaVariables": {
+                "single": {"NAME": {"text": name, "range": range}},
+                "multi": {"secondary": null},
+            },
+            "ruleId": rule_id,
+            "labels": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_test_symbol_config() {
+        let python = TestSymbolConfig::for_language(SupportedLanguages::Python).unwrap();
+        assert!(python.matches(&symbol_match("class", "TestGraph")));
+        assert!(!python.matches(&symbol_match("class", "Graph")));
+        assert!(!python.matches(&symbol_match("variable", "TestGraph")));
+
+        let java = TestSymbolConfig::for_language(SupportedLanguages::Java).unwrap();
+        assert!(java.matches(&symbol_match("class", "GraphTests")));
+        assert!(!java.matches(&symbol_match("method", "graphTest")));
+
+        assert!(TestSymbolConfig::for_language(SupportedLanguages::Rust).is_none());
+    }
+
+    #[test]
+    fn test_hover_text() {
+        use lsp_types::{HoverContents, LanguageString, MarkedString, MarkupContent, MarkupKind};
+
+        let markup = HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: String::from("```rust\npub fn new() -> Self\n```\n"),
+        });
+        assert_eq!(
+            hover_text(markup).as_deref(),
+            Some("```rust\npub fn new() -> Self\n```")
+        );
+
+        let marked = HoverContents::Array(vec![
+            MarkedString::LanguageString(LanguageString {
+                language: String::from("python"),
+                value: String::from("def search()"),
+            }),
+            MarkedString::String(String::from("Finds a path.")),
+        ]);
+        assert_eq!(
+            hover_text(marked).as_deref(),
+            Some("```python\ndef search()\n```\n\nFinds a path.")
+        );
+
+        let empty = HoverContents::Scalar(MarkedString::String(String::from("  \n")));
+        assert_eq!(hover_text(empty), None);
+    }
+
+    #[test]
+    fn test_text_matches() {
+        let matcher = regex::RegexBuilder::new(&regex::escape("heap.q"))
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let matches = text_matches(
+            "search.py",
+            "import heapq\r\nµ = Heap.Q(heapq)  # heap.q\n",
+            &matcher,
+        );
+        assert_eq!(
+            matches,
+            vec![
+                TextSearchMatch {
+                    path: String::from("search.py"),
+                    line: 1,
+                    character: 4,
+                    line_text: String::from("µ = Heap.Q(heapq)  # heap.q"),
+                },
+                TextSearchMatch {
+                    path: String::from("search.py"),
+                    line: 1,
+                    character: 21,
+                    line_text: String::from("µ = Heap.Q(heapq)  # heap.q"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_semantic_tokens() {
+        let legend = SemanticTokensLegend {
+            token_types: vec![
+                lsp_types::SemanticTokenType::KEYWORD,
+                lsp_types::SemanticTokenType::FUNCTION,
+            ],
+            token_modifiers: vec![
+                lsp_types::SemanticTokenModifier::DECLARATION,
+                lsp_types::SemanticTokenModifier::STATIC,
+            ],
+        };
+        let token = |delta_line, delta_start, length, token_type, token_modifiers_bitset| {
+            lsp_types::SemanticToken {
+                delta_line,
+                delta_start,
+                length,
+                token_type,
+                token_modifiers_bitset,
+            }
+        };
+        let tokens = decode_semantic_tokens(
+            &[
+                token(1, 4, 2, 0, 0),
+                token(0, 3, 4, 1, 0b11),
+                token(2, 1, 4, 1, 0),
+            ],
+            &legend,
+        );
+        let decoded: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.line, t.character, t.length, t.token_type.as_str()))
+            .collect();
+        assert_eq!(
+            decoded,
+            vec![
+                (1, 4, 2, "keyword"),
+                (1, 7, 4, "function"),
+                (3, 1, 4, "function")
+            ]
+        );
+        assert_eq!(tokens[1].modifiers, vec!["declaration", "static"]);
+        assert!(tokens[0].modifiers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_detect_language_for_location() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        set_thread_local_mount_dir(dir.path());
+        let manager = Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        std::fs::write(
+            dir.path().join("manage"),
+            "#!/usr/bin/env python3\nprint('hi')\n",
+        )?;
+        let workspace_uri =
+            |file_path: &str| Url::from_file_path(dir.path().join(file_path)).unwrap();
+
+        // Workspace files, detected from their content without an extension
+        assert_eq!(
+            manager.detect_language_for_location(&workspace_uri("src/graph.py")),
+            Some(SupportedLanguages::Python)
+        );
+        assert_eq!(
+            manager.detect_language_for_location(&workspace_uri("manage")),
+            Some(SupportedLanguages::Python)
+        );
+
+        // Standard library files outside the workspace
+        let stdlib_uri = Url::from_file_path("/usr/lib/python3.12/typing.py").unwrap();
+        assert_eq!(
+            manager.detect_language_for_location(&stdlib_uri),
+            Some(SupportedLanguages::Python)
+        );
+        let stdlib_uri =
+            Url::from_file_path("/rustlib/src/rust/library/core/src/option.rs").unwrap();
+        assert_eq!(
+            manager.detect_language_for_location(&stdlib_uri),
+            Some(SupportedLanguages::Rust)
+        );
+
+        // No extension and no content to detect from
+        let header_uri = Url::from_file_path("/opt/toolchain/include/c++/13/vector").unwrap();
+        assert_eq!(manager.detect_language_for_location(&header_uri), None);
+        let remote_uri = Url::parse("https://example.com/graph.py")?;
+        assert_eq!(manager.detect_language_for_location(&remote_uri), None);
+        unset_thread_local_mount_dir();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ast_grep_for() -> Result<(), Box<dyn Error>> {
+        let write_rule = |config_dir: &Path, language: &str| {
+            let rules_dir = config_dir.join("symbol/rules");
+            std::fs::create_dir_all(&rules_dir)?;
+            std::fs::write(
+                rules_dir.join("function.yml"),
+                format!(
+                    "id: function\nlanguage: {}\nrule:\n  kind: identifier\n",
+                    language
+                ),
+            )
+        };
+        let python_config = tempfile::tempdir()?;
+        write_rule(python_config.path(), "python")?;
+        let typescript_config = tempfile::tempdir()?;
+        write_rule(typescript_config.path(), "tsx")?;
+
+        let dir = tempfile::tempdir()?;
+        let manager = Manager::new(
+            dir.path().to_str().unwrap(),
+            ManagerConfig {
+                ast_grep_config_dirs: vec![
+                    python_config.path().to_path_buf(),
+                    typescript_config.path().to_path_buf(),
+                ],
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        assert!(std::ptr::eq(
+            manager.ast_grep_for("src/graph.py"),
+            &manager.ast_grep[0]
+        ));
+        assert!(std::ptr::eq(
+            manager.ast_grep_for("src/graph.ts"),
+            &manager.ast_grep[1]
+        ));
+        // No rules are written for Rust
+        assert!(std::ptr::eq(
+            manager.ast_grep_for("src/main.rs"),
+            &manager.ast_grep[0]
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_is_test_file() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let manager = Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        for file_path in [
+            "test_utils.py",
+            "tests/test_graph.py",
+            "src/graph_test.rs",
+            "src/test/java/AStarTest.java",
+            "src/graph.spec.ts",
+            "web/app.test.jsx",
+            "graph_test.go",
+        ] {
+            assert!(manager.is_test_file(file_path), "{}", file_path);
+        }
+        for file_path in [
+            "utils.py",
+            "src/testing.rs",
+            "src/AStar.java",
+            "src/graph.ts",
+        ] {
+            assert!(!manager.is_test_file(file_path), "{}", file_path);
+        }
+
+        let manager = Manager::new(
+            dir.path().to_str().unwrap(),
+            ManagerConfig {
+                test_file_patterns: vec![String::from("fixtures/**")],
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert!(manager.is_test_file("fixtures/users.py"));
+        assert!(!manager.is_test_file("test_utils.py"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_references_as_file_ranges() -> Result<(), Box<dyn Error>> {
+        use crate::test_utils::{mocks::MockFile, TestContext};
+
+        let files = vec![
+            MockFile::new("graph.py", "class Graph:\n    pass\n"),
+            MockFile::new("main.py", "from graph import Graph\n\nGraph()\n"),
+        ];
+        let context = TestContext::setup_mock_with(SupportedLanguages::Python, files, |client| {
+            let references = vec![
+                client.location("graph.py", Position::new(0, 6), Position::new(0, 11)),
+                client.location("main.py", Position::new(0, 18), Position::new(0, 23)),
+                client.location("main.py", Position::new(2, 0), Position::new(2, 5)),
+            ];
+            client.with_references("main.py", Position::new(2, 2), references)
+        })
+        .await?;
+        let manager = context.manager.as_ref().unwrap();
+
+        let ranges = manager
+            .find_references_as_file_ranges("main.py", Position::new(2, 2), true)
+            .await?;
+        let locations = manager
+            .find_references("main.py", Position::new(2, 2), true)
+            .await?;
+        assert_eq!(
+            ranges,
+            vec![
+                FileRange::from_lsp_range(
+                    String::from("graph.py"),
+                    lsp_types::Range::new(Position::new(0, 6), Position::new(0, 11))
+                ),
+                FileRange::from_lsp_range(
+                    String::from("main.py"),
+                    lsp_types::Range::new(Position::new(0, 18), Position::new(0, 23))
+                ),
+                FileRange::from_lsp_range(
+                    String::from("main.py"),
+                    lsp_types::Range::new(Position::new(2, 0), Position::new(2, 5))
+                ),
+            ]
+        );
+        // The same ranges the handlers built from the locations themselves
+        assert_eq!(
+            ranges,
+            locations
+                .into_iter()
+                .map(|location| FileRange::from_lsp_range(
+                    uri_to_relative_path_string(&location.uri),
+                    location.range
+                ))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    /// Writes a Maven build with the modules `api` and `core`, each with a Java file.
+    fn write_multi_module_build(root: &Path) -> std::io::Result<()> {
+        std::fs::write(
+            root.join("pom.xml"),
+            "<project><modules><module>api</module><module>core</module></modules></project>",
+        )?;
+        for module in ["api", "core"] {
+            std::fs::create_dir_all(root.join(module).join("src/main/java"))?;
+            std::fs::write(root.join(module).join("pom.xml"), "<project></project>")?;
+            std::fs::write(
+                root.join(module).join("src/main/java/Main.java"),
+                "class Main {}\n",
+            )?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_detect_java_module_roots() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::Builder::new().prefix("lsproxy-").tempdir()?;
+        let root = dir.path();
+        write_multi_module_build(root)?;
+        std::fs::write(root.join("setup.py"), "")?;
+        let manager = Manager::new(root.to_str().unwrap(), ManagerConfig::default()).await?;
+
+        // One JDT LS per module, and one server at the root for the other languages
+        assert_eq!(
+            manager.detect_languages_in_workspace(root.to_str().unwrap()),
+            vec![
+                (SupportedLanguages::Python, root.to_path_buf()),
+                (SupportedLanguages::Java, root.join("api")),
+                (SupportedLanguages::Java, root.join("core")),
+            ]
+        );
+        assert_eq!(
+            manager.jdtls_data_dir(root.join("core").to_str().unwrap(), 0),
+            Path::new(JDTLS_WORKSPACE_DIR).join("modules/core")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_detect_java_single_project() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::Builder::new().prefix("lsproxy-").tempdir()?;
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/main/java"))?;
+        std::fs::write(root.join("pom.xml"), "<project></project>")?;
+        std::fs::write(root.join("src/main/java/Main.java"), "class Main {}\n")?;
+        let manager = Manager::new(root.to_str().unwrap(), ManagerConfig::default()).await?;
+
+        // A project without modules gets exactly one JDT LS, at the workspace root
+        assert_eq!(
+            manager.detect_languages_in_workspace(root.to_str().unwrap()),
+            vec![(SupportedLanguages::Java, root.to_path_buf())]
+        );
+        assert_eq!(
+            manager.jdtls_data_dir(root.to_str().unwrap(), 0),
+            Path::new(JDTLS_WORKSPACE_DIR)
+        );
+        // A restarted server doesn't share the data directory of the one it replaces
+        assert_ne!(
+            manager.jdtls_data_dir(root.to_str().unwrap(), 1),
+            manager.jdtls_data_dir(root.to_str().unwrap(), 0)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_java_module_client_for_file() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::Builder::new().prefix("lsproxy-").tempdir()?;
+        let root = dir.path();
+        write_multi_module_build(root)?;
+        set_thread_local_mount_dir(root);
+        let mut manager = Manager::new(root.to_str().unwrap(), ManagerConfig::default()).await?;
+        for module in ["api", "core"] {
+            let client =
+                MockClient::boxed(&root.join(module), &["**/*.java"], Duration::ZERO).await;
+            manager
+                .java_module_clients
+                .insert(root.join(module), Arc::new(Mutex::new(client)));
+        }
+
+        // Each file is served by the client of its module
+        for module in ["api", "core"] {
+            let file_path = format!("{}/src/main/java/Main.java", module);
+            let client = manager
+                .get_client_for_file(SupportedLanguages::Java, &file_path)
+                .ok_or("No client for the file")?;
+            assert!(Arc::ptr_eq(
+                &client,
+                &manager.java_module_clients[&root.join(module)]
+            ));
+        }
+        // The files of every module are listed
+        assert_eq!(
+            manager
+                .list_files_by_language(SupportedLanguages::Java)
+                .await?,
+            vec![
+                "api/src/main/java/Main.java",
+                "core/src/main/java/Main.java"
+            ]
+        );
+        unset_thread_local_mount_dir();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_semantic_tokens_legend() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let manager = Manager::new(dir.path().to_str().unwrap(), ManagerConfig::default()).await?;
+        let legend = |token_type: &'static str| SemanticTokensLegend {
+            token_types: vec![lsp_types::SemanticTokenType::new(token_type)],
+            token_modifiers: Vec::new(),
+        };
+        let init_result = |legend: Option<SemanticTokensLegend>| InitializeResult {
+            capabilities: lsp_types::ServerCapabilities {
+                semantic_tokens_provider: legend.map(|legend| {
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp_types::SemanticTokensOptions {
+                            legend,
+                            ..Default::default()
+                        },
+                    )
+                }),
+                ..Default::default()
+            },
+            server_info: None,
+        };
+
+        manager
+            .update_semantic_tokens_legend(
+                SupportedLanguages::Python,
+                init_result(Some(legend("class"))),
+            )
+            .await;
+        // A restarted server's legend replaces the old one
+        manager
+            .update_semantic_tokens_legend(
+                SupportedLanguages::Python,
+                init_result(Some(legend("function"))),
+            )
+            .await;
+        assert_eq!(
+            manager
+                .semantic_tokens_legends
+                .read()
+                .await
+                .get(&SupportedLanguages::Python),
+            Some(&legend("function"))
+        );
+        // And a server without semantic tokens drops it
+        manager
+            .update_semantic_tokens_legend(SupportedLanguages::Python, init_result(None))
+            .await;
+        assert!(manager.semantic_tokens_legends.read().await.is_empty());
+        Ok(())
+    }
+}
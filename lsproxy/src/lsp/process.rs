@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
 use tokio::sync::Mutex;
@@ -14,6 +15,9 @@ pub trait Process: Send + Sync {
 pub struct ProcessHandler {
     pub stdin: Arc<Mutex<ChildStdin>>,
     pub stdout: Arc<Mutex<BufReader<ChildStdout>>>,
+    child: Arc<Mutex<Child>>,
+    pid: Option<u32>,
+    started_at: Instant,
 }
 
 impl ProcessHandler {
@@ -23,8 +27,31 @@ impl ProcessHandler {
         Ok(Self {
             stdin: Arc::new(Mutex::new(stdin)),
             stdout: Arc::new(Mutex::new(BufReader::new(stdout))),
+            pid: child.id(),
+            child: Arc::new(Mutex::new(child)),
+            started_at: Instant::now(),
         })
     }
+
+    /// OS process ID of the language server, if it was available at spawn time.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// When the language server process was spawned.
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Returns `true` while the language server process has not exited.
+    pub async fn is_running(&self) -> bool {
+        matches!(self.child.lock().await.try_wait(), Ok(None))
+    }
+
+    /// Kills the language server process and waits for it to exit.
+    pub async fn kill(&self) -> std::io::Result<()> {
+        self.child.lock().await.kill().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -44,7 +71,10 @@ impl Process for ProcessHandler {
             let mut stdout = self.stdout.lock().await;
             let n = stdout.read_until(b'\n', &mut buffer).await?;
             if n == 0 {
-                continue;
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Language server closed its output",
+                )));
             }
 
             let line = String::from_utf8_lossy(&buffer[buffer.len() - n..]);
@@ -1,10 +1,18 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 use log::{error, info};
 use lsproxy::{
     initialize_app_state_with_mount_dir, run_server_with_port_and_host, write_openapi_to_file,
+    write_openapi_to_file_yaml,
 };
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
 use std::path::PathBuf;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
+use tracing_subscriber::util::SubscriberInitExt;
 
 /// Command line interface for LSProxy server
 #[derive(Parser, Debug)]
@@ -14,6 +22,11 @@ struct Cli {
     #[arg(short, long)]
     write_openapi: bool,
 
+    /// Format of the OpenAPI specification written with --write-openapi: openapi.json,
+    /// openapi.yaml, or both
+    #[arg(long, value_enum, default_value_t = OpenApiFormat::Json)]
+    openapi_format: OpenApiFormat,
+
     /// Host address to bind the server to
     #[arg(long, default_value = "0.0.0.0")]
     host: String,
@@ -22,11 +35,25 @@ struct Cli {
     #[arg(long)]
     mount_dir: Option<String>,
 
+    /// Directory containing the ast-grep rule sets (`symbol`, `identifier`, `reference`), or
+    /// several separated with `:`, e.g. one per language.
+    /// Falls back to the AST_GREP_CONFIG environment variable, then /usr/src/ast_grep
+    #[arg(long)]
+    ast_grep_config: Option<String>,
+
     /// Port number to bind the server to
-    #[arg(long, default_value_t = 4444)]
+    #[arg(short, long, env = "PORT", default_value_t = 4444)]
     port: u16,
 }
 
+/// File formats the OpenAPI specification can be written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OpenApiFormat {
+    Json,
+    Yaml,
+    Both,
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Set up panic handler for better error reporting
@@ -34,11 +61,20 @@ async fn main() -> std::io::Result<()> {
         error!("Server panicked: {:?}", panic_info);
     }));
 
-    // Initialize tracing subscriber for better logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+    // Initialize tracing subscriber for better logging, exporting spans when OTLP is configured.
+    // The filter is reloadable so that the log level can be changed through the API.
+    let tracer_provider = otlp_tracer_provider();
+    let (log_filter_layer, log_filter) = reload::Layer::new(
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+    );
+    tracing_subscriber::registry()
+        .with(log_filter_layer)
+        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracer_provider.as_ref().map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer("lsproxy"))
+            }),
         )
         .init();
 
@@ -47,20 +83,87 @@ async fn main() -> std::io::Result<()> {
 
     // Handle OpenAPI spec generation if requested
     if cli.write_openapi {
-        if let Err(e) = write_openapi_to_file(&PathBuf::from("openapi.json")) {
-            error!("Error: Failed to write the openapi.json to a file. Please see error for more details.");
-            return Err(e);
+        if cli.openapi_format != OpenApiFormat::Yaml {
+            if let Err(e) = write_openapi_to_file(&PathBuf::from("openapi.json")) {
+                error!("Error: Failed to write the openapi.json to a file. Please see error for more details.");
+                return Err(e);
+            }
+        }
+        if cli.openapi_format != OpenApiFormat::Json {
+            if let Err(e) = write_openapi_to_file_yaml(&PathBuf::from("openapi.yaml")) {
+                error!("Error: Failed to write the openapi.yaml to a file. Please see error for more details.");
+                return Err(e);
+            }
         }
         return Ok(());
     }
 
     // Initialize application state with optional mount directory override
-    let app_state = initialize_app_state_with_mount_dir(cli.mount_dir.as_deref())
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    let app_state = initialize_app_state_with_mount_dir(
+        cli.mount_dir.as_deref(),
+        cli.ast_grep_config.as_deref(),
+        Some(log_filter),
+    )
+    .await
+    .map_err(|e| std::io::Error::other(e.to_string()))?;
 
     // Run the server with specified host
     info!("Starting on port {}", cli.port);
 
-    run_server_with_port_and_host(app_state, cli.port, &cli.host).await
+    let result = run_server_with_port_and_host(app_state, cli.port, &cli.host).await;
+    if let Some(provider) = tracer_provider {
+        // Flush the spans that haven't been exported yet
+        if let Err(e) = provider.shutdown() {
+            error!("Failed to shut down the OTLP exporter: {}", e);
+        }
+    }
+    result
+}
+
+/// Builds a tracer provider exporting spans over OTLP/HTTP if `OTEL_EXPORTER_OTLP_ENDPOINT` is
+/// set. The exporter reads its other settings, e.g. `OTEL_EXPORTER_OTLP_HEADERS`, from the
+/// standard OpenTelemetry environment variables.
+fn otlp_tracer_provider() -> Option<SdkTracerProvider> {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+    let exporter = match SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!(
+                "Failed to create the OTLP exporter, spans won't be exported: {}",
+                e
+            );
+            return None;
+        }
+    };
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| String::from("lsproxy"));
+    Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(service_name).build())
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_port_short_flag() {
+        let cli = Cli::try_parse_from(["lsproxy", "-p", "5000"]).unwrap();
+        assert_eq!(cli.port, 5000);
+    }
+
+    #[test]
+    fn test_port_env_fallback() {
+        std::env::set_var("PORT", "5001");
+        let from_env = Cli::try_parse_from(["lsproxy"]).map(|cli| cli.port);
+        // The flag wins over the environment
+        let from_flag = Cli::try_parse_from(["lsproxy", "--port", "5002"]).map(|cli| cli.port);
+        std::env::remove_var("PORT");
+
+        assert_eq!(from_env.unwrap(), 5001);
+        assert_eq!(from_flag.unwrap(), 5002);
+    }
 }
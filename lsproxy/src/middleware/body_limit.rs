@@ -0,0 +1,49 @@
+use crate::api_types::{ErrorCode, ErrorResponse};
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::web::{JsonConfig, PayloadConfig};
+use actix_web::HttpResponse;
+
+/// Default maximum request body size (10 MB).
+pub const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Returns the maximum accepted request body size in bytes.
+///
+/// Configurable through the `MAX_REQUEST_BODY_BYTES` environment variable; falls back to
+/// [`DEFAULT_MAX_BODY_SIZE`] when unset or invalid.
+pub fn max_body_size() -> usize {
+    match std::env::var("MAX_REQUEST_BODY_BYTES") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            log::warn!(
+                "Invalid MAX_REQUEST_BODY_BYTES value '{}', using default of {} bytes",
+                value,
+                DEFAULT_MAX_BODY_SIZE
+            );
+            DEFAULT_MAX_BODY_SIZE
+        }),
+        Err(_) => DEFAULT_MAX_BODY_SIZE,
+    }
+}
+
+/// JSON extractor config that rejects bodies larger than `limit` with a 413 `ErrorResponse`.
+pub fn json_config(limit: usize) -> JsonConfig {
+    JsonConfig::default()
+        .limit(limit)
+        .error_handler(move |err, _req| match err {
+            JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+                let response = HttpResponse::PayloadTooLarge().json(ErrorResponse::new(
+                    ErrorCode::PayloadTooLarge,
+                    format!(
+                        "Request body too large: maximum allowed size is {} bytes",
+                        limit
+                    ),
+                ));
+                InternalError::from_response(err, response).into()
+            }
+            err => err.into(),
+        })
+}
+
+/// Raw payload extractor config with the same size limit as [`json_config`].
+pub fn payload_config(limit: usize) -> PayloadConfig {
+    PayloadConfig::new(limit)
+}
@@ -0,0 +1,8 @@
+use std::env;
+
+/// Response compression is opt-in via `ENABLE_COMPRESSION=true`.
+pub fn is_compression_enabled() -> bool {
+    env::var("ENABLE_COMPRESSION")
+        .map(|v| v == "true")
+        .unwrap_or(false)
+}
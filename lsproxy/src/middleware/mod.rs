@@ -1,5 +1,13 @@
+pub mod body_limit;
+pub mod compression;
 pub mod jwt;
+pub mod request_id;
 #[cfg(test)]
 mod tests;
+pub mod timeouts;
 
+pub use body_limit::{json_config, max_body_size, payload_config};
+pub use compression::is_compression_enabled;
 pub use jwt::{is_auth_enabled, validate_jwt_config, JwtMiddleware};
+pub use request_id::RequestIdMiddleware;
+pub use timeouts::{disconnect_timeout, request_timeout};
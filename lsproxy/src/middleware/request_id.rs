@@ -0,0 +1,135 @@
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use futures_util::future::{ready, Ready};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Tags every request with an `X-Request-ID`, taken from the incoming request or generated.
+///
+/// The ID is recorded on the request's tracing span, echoed in the response headers and
+/// added to JSON error bodies as `request_id`.
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddlewareService { service }))
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path()
+        );
+        span.in_scope(|| tracing::debug!("Handling request {}", request_id));
+
+        let fut = span.in_scope(|| self.service.call(req));
+        Box::pin(
+            async move {
+                match fut.await {
+                    Ok(res) => {
+                        let (http_req, response) = res.into_parts();
+                        let response =
+                            tag_response(response.map_into_boxed_body(), &request_id).await;
+                        Ok(ServiceResponse::new(http_req, response))
+                    }
+                    // Errors from inner middleware (e.g. auth) are rendered here so that they
+                    // carry the request ID too.
+                    Err(e) => {
+                        let response = tag_response(e.error_response(), &request_id).await;
+                        Err(InternalError::from_response(e, response).into())
+                    }
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Adds the request ID header and, for JSON object error bodies, a `request_id` field.
+async fn tag_response(response: HttpResponse, request_id: &str) -> HttpResponse {
+    let mut response = if response.status().is_client_error() || response.status().is_server_error()
+    {
+        add_request_id_to_error_body(response, request_id).await
+    } else {
+        response
+    };
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
+
+async fn add_request_id_to_error_body(response: HttpResponse, request_id: &str) -> HttpResponse {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return response;
+    }
+
+    let (mut head, body) = response.into_parts();
+    let bytes = match to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let bytes = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut body)) => {
+            body.insert(
+                String::from("request_id"),
+                serde_json::Value::String(request_id.to_string()),
+            );
+            serde_json::to_vec(&body).map(Into::into).unwrap_or(bytes)
+        }
+        _ => bytes,
+    };
+    head.headers_mut().remove(header::CONTENT_LENGTH);
+    head.set_body(BoxBody::new(bytes))
+}
@@ -1,4 +1,9 @@
+use super::body_limit::json_config;
 use super::jwt::{Claims, JwtMiddleware};
+use super::request_id::{RequestIdMiddleware, REQUEST_ID_HEADER};
+use super::timeouts::{disconnect_timeout, parse_timeout, DEFAULT_REQUEST_TIMEOUT};
+use crate::api_types::{ErrorCode, ErrorResponse};
+use actix_web::http::StatusCode;
 use actix_web::test::{self, TestRequest};
 use actix_web::{web, App, HttpResponse};
 use jsonwebtoken::{encode, EncodingKey, Header};
@@ -99,3 +104,188 @@ async fn test_missing_jwt_secret() {
     let resp = err.error_response();
     assert_eq!(resp.status().as_u16(), 500);
 }
+
+#[derive(serde::Deserialize)]
+struct EchoRequest {
+    content: String,
+}
+
+async fn echo_handler(body: web::Json<EchoRequest>) -> HttpResponse {
+    HttpResponse::Ok().body(body.into_inner().content)
+}
+
+#[actix_web::test]
+async fn test_body_within_limit() {
+    let app = test::init_service(
+        App::new()
+            .app_data(json_config(1024))
+            .route("/", web::post().to(echo_handler)),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/")
+        .set_json(serde_json::json!({ "content": "hello" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_body_exceeding_limit() {
+    let app = test::init_service(
+        App::new()
+            .app_data(json_config(1024))
+            .route("/", web::post().to(echo_handler)),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/")
+        .set_json(serde_json::json!({ "content": "a".repeat(4096) }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let body: ErrorResponse = test::read_body_json(resp).await;
+    assert!(body.error.contains("1024 bytes"));
+}
+
+async fn failing_handler() -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse::new(
+        ErrorCode::BadRequest,
+        String::from("boom"),
+    ))
+}
+
+#[actix_web::test]
+async fn test_request_id_generated_when_missing() {
+    let app = test::init_service(
+        App::new()
+            .wrap(RequestIdMiddleware)
+            .route("/", web::get().to(test_handler)),
+    )
+    .await;
+
+    let req = TestRequest::get().uri("/").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    let request_id = resp
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .expect("request id header")
+        .to_str()
+        .unwrap();
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[actix_web::test]
+async fn test_request_id_echoed_unchanged() {
+    let app = test::init_service(
+        App::new()
+            .wrap(RequestIdMiddleware)
+            .route("/", web::get().to(test_handler)),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/")
+        .insert_header(("X-Request-ID", "agent-run-42"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get(REQUEST_ID_HEADER).unwrap(),
+        "agent-run-42"
+    );
+}
+
+#[actix_web::test]
+async fn test_request_id_added_to_error_response() {
+    let app = test::init_service(
+        App::new()
+            .wrap(RequestIdMiddleware)
+            .route("/", web::get().to(failing_handler)),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/")
+        .insert_header(("X-Request-ID", "agent-run-42"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get(REQUEST_ID_HEADER).unwrap(),
+        "agent-run-42"
+    );
+
+    let body: ErrorResponse = test::read_body_json(resp).await;
+    assert_eq!(body.error, "boom");
+    assert_eq!(body.request_id.as_deref(), Some("agent-run-42"));
+}
+
+#[actix_web::test]
+async fn test_request_id_on_middleware_errors() {
+    std::env::set_var("JWT_SECRET", "test_secret");
+
+    let app = test::init_service(
+        App::new().wrap(RequestIdMiddleware).service(
+            web::scope("")
+                .wrap(JwtMiddleware)
+                .route("/", web::get().to(test_handler)),
+        ),
+    )
+    .await;
+
+    let req = TestRequest::get().uri("/").to_request();
+    let err = test::try_call_service(&app, req).await.unwrap_err();
+    let resp = err.error_response();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().get(REQUEST_ID_HEADER).is_some());
+}
+
+#[actix_web::test]
+async fn test_slow_client_disconnected() {
+    use actix_web::HttpServer;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    let server = HttpServer::new(|| App::new().route("/", web::get().to(test_handler)))
+        .client_request_timeout(Duration::from_secs(1))
+        .client_disconnect_timeout(disconnect_timeout())
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+
+    // Only the start of the request line, never the rest of the headers
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut response))
+        .await
+        .expect("the server kept the connection open")
+        .unwrap();
+    let response = String::from_utf8_lossy(&response);
+    assert!(response.starts_with("HTTP/1.1 408"), "{}", response);
+
+    handle.stop(true).await;
+}
+
+#[test]
+fn test_parse_timeout() {
+    use std::time::Duration;
+
+    let parse = |value| parse_timeout("REQUEST_TIMEOUT_SECS", value, DEFAULT_REQUEST_TIMEOUT);
+    assert_eq!(parse(None), DEFAULT_REQUEST_TIMEOUT);
+    assert_eq!(parse(Some(" 1 ")), Duration::from_secs(1));
+    assert_eq!(parse(Some("soon")), DEFAULT_REQUEST_TIMEOUT);
+}
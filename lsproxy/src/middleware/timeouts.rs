@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+/// Default time a client gets to send the headers of a request (30 seconds).
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default time a client gets to acknowledge the connection closing (5 seconds).
+pub const DEFAULT_DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns how long a client may take to send a request's headers before the server answers 408
+/// and closes the connection, so that slow clients can't hold connections open indefinitely.
+///
+/// Configurable in seconds through the `REQUEST_TIMEOUT_SECS` environment variable; falls back
+/// to [`DEFAULT_REQUEST_TIMEOUT`] when unset or invalid.
+pub fn request_timeout() -> Duration {
+    timeout_from_env("REQUEST_TIMEOUT_SECS", DEFAULT_REQUEST_TIMEOUT)
+}
+
+/// Returns how long the server waits for a client to close its side of a connection being shut
+/// down.
+///
+/// Configurable in seconds through the `DISCONNECT_TIMEOUT_SECS` environment variable; falls back
+/// to [`DEFAULT_DISCONNECT_TIMEOUT`] when unset or invalid.
+pub fn disconnect_timeout() -> Duration {
+    timeout_from_env("DISCONNECT_TIMEOUT_SECS", DEFAULT_DISCONNECT_TIMEOUT)
+}
+
+fn timeout_from_env(variable: &str, default: Duration) -> Duration {
+    parse_timeout(variable, std::env::var(variable).ok().as_deref(), default)
+}
+
+/// Parses the value of a timeout variable, in seconds.
+pub(super) fn parse_timeout(variable: &str, value: Option<&str>, default: Duration) -> Duration {
+    match value {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Duration::from_secs)
+            .unwrap_or_else(|_| {
+                log::warn!(
+                    "Invalid {} value '{}', using default of {:?}",
+                    variable,
+                    value,
+                    default
+                );
+                default
+            }),
+        None => default,
+    }
+}
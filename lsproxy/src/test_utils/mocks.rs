@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use lsp_types::{
+    GotoDefinitionResponse, InitializeResult, Location, Position, SymbolInformation, SymbolKind,
+    WorkspaceEdit, WorkspaceSymbolResponse,
+};
+use notify_debouncer_mini::DebouncedEvent;
+use tokio::sync::broadcast::Receiver;
+
+use crate::api_types::SupportedLanguages;
+use crate::lsp::client::LspClient;
+use crate::lsp::{JsonRpcHandler, PendingRequests, ProcessHandler};
+use crate::utils::workspace_documents::{
+    language_file_patterns, DidOpenConfiguration, WorkspaceDocumentsHandler,
+    DEFAULT_EXCLUDE_PATTERNS,
+};
+
+/// A file written to the workspace of a mock test context.
+pub struct MockFile {
+    /// Path relative to the workspace root
+    pub path: String,
+    pub content: String,
+}
+
+impl MockFile {
+    pub fn new(path: &str, content: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+/// Identifies a canned response by absolute file path, line and character.
+type ResponseKey = (PathBuf, u32, u32);
+
+fn response_key(path: &Path, position: Position) -> ResponseKey {
+    (path.to_path_buf(), position.line, position.character)
+}
+
+/// An [`LspClient`] that answers definition, reference, rename, workspace symbol and
+/// willRenameFiles requests from canned responses.
+///
+/// There's no language server behind it: notifications such as didOpen are written to a process
+/// that discards them, and positions without a canned response resolve to nothing.
+pub struct MockLspClient {
+    process: ProcessHandler,
+    json_rpc: JsonRpcHandler,
+    pending_requests: PendingRequests,
+    workspace_documents: WorkspaceDocumentsHandler,
+    root_path: PathBuf,
+    definitions: HashMap<ResponseKey, Vec<Location>>,
+    references: HashMap<ResponseKey, Vec<Location>>,
+    renames: HashMap<ResponseKey, WorkspaceEdit>,
+    workspace_symbols: Vec<SymbolInformation>,
+    will_rename_files_edit: Option<WorkspaceEdit>,
+}
+
+impl MockLspClient {
+    /// Creates a client for the files of `language` under `root_path`.
+    pub async fn new(
+        root_path: &Path,
+        language: SupportedLanguages,
+        watch_events_rx: Receiver<DebouncedEvent>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let process = tokio::process::Command::new("sh")
+            .args(["-c", "cat > /dev/null"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        Ok(Self {
+            process: ProcessHandler::new(process).await?,
+            json_rpc: JsonRpcHandler::new(),
+            pending_requests: PendingRequests::new(),
+            workspace_documents: WorkspaceDocumentsHandler::new(
+                root_path,
+                language_file_patterns(language),
+                DEFAULT_EXCLUDE_PATTERNS
+                    .iter()
+                    .map(|&s| s.to_string())
+                    .collect(),
+                watch_events_rx,
+                DidOpenConfiguration::None,
+            ),
+            root_path: root_path.to_path_buf(),
+            definitions: HashMap::new(),
+            references: HashMap::new(),
+            renames: HashMap::new(),
+            workspace_symbols: Vec::new(),
+            will_rename_files_edit: None,
+        })
+    }
+
+    /// Resolves the definition at `position` in `file_path` to `locations`.
+    ///
+    /// `file_path` is relative to the workspace root.
+    pub fn with_definition(
+        mut self,
+        file_path: &str,
+        position: Position,
+        locations: Vec<Location>,
+    ) -> Self {
+        self.definitions.insert(
+            response_key(&self.root_path.join(file_path), position),
+            locations,
+        );
+        self
+    }
+
+    /// Resolves the references at `position` in `file_path` to `locations`.
+    ///
+    /// `file_path` is relative to the workspace root.
+    pub fn with_references(
+        mut self,
+        file_path: &str,
+        position: Position,
+        locations: Vec<Location>,
+    ) -> Self {
+        self.references.insert(
+            response_key(&self.root_path.join(file_path), position),
+            locations,
+        );
+        self
+    }
+
+    /// Answers renames of the symbol at `position` in `file_path` with `edit`, whatever the new
+    /// name.
+    ///
+    /// `file_path` is relative to the workspace root.
+    pub fn with_rename(mut self, file_path: &str, position: Position, edit: WorkspaceEdit) -> Self {
+        self.renames.insert(
+            response_key(&self.root_path.join(file_path), position),
+            edit,
+        );
+        self
+    }
+
+    /// Adds a symbol to the workspace symbols, which are returned for queries contained in
+    /// their name.
+    pub fn with_workspace_symbol(
+        mut self,
+        name: &str,
+        kind: SymbolKind,
+        location: Location,
+    ) -> Self {
+        #[allow(deprecated)] // `deprecated` has to be set, tags replace it
+        self.workspace_symbols.push(SymbolInformation {
+            name: name.to_string(),
+            kind,
+            tags: None,
+            deprecated: None,
+            location,
+            container_name: None,
+        });
+        self
+    }
+
+    /// Answers every willRenameFiles request with `edit`.
+    pub fn with_will_rename_files_edit(mut self, edit: WorkspaceEdit) -> Self {
+        self.will_rename_files_edit = Some(edit);
+        self
+    }
+
+    /// A location in the mock's workspace, e.g. for canned responses.
+    pub fn location(&self, file_path: &str, start: Position, end: Position) -> Location {
+        Location {
+            uri: lsp_types::Url::from_file_path(self.root_path.join(file_path)).unwrap(),
+            range: lsp_types::Range::new(start, end),
+        }
+    }
+}
+
+#[async_trait]
+impl LspClient for MockLspClient {
+    async fn initialize(
+        &mut self,
+        _root_path: String,
+    ) -> Result<InitializeResult, Box<dyn Error + Send + Sync>> {
+        Ok(InitializeResult::default())
+    }
+
+    async fn text_document_definition(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+        let locations = self
+            .definitions
+            .get(&response_key(Path::new(file_path), position))
+            .cloned()
+            .unwrap_or_default();
+        Ok(GotoDefinitionResponse::Array(locations))
+    }
+
+    async fn text_document_reference(
+        &mut self,
+        file_path: &str,
+        position: Position,
+        include_declaration: bool,
+    ) -> Result<Vec<Location>, Box<dyn Error + Send + Sync>> {
+        let mut locations = self
+            .references
+            .get(&response_key(Path::new(file_path), position))
+            .cloned()
+            .unwrap_or_default();
+        if !include_declaration {
+            let definitions = self
+                .definitions
+                .get(&response_key(Path::new(file_path), position))
+                .cloned()
+                .unwrap_or_default();
+            locations.retain(|location| !definitions.contains(location));
+        }
+        Ok(locations)
+    }
+
+    async fn text_document_rename(
+        &mut self,
+        file_path: &str,
+        position: Position,
+        _new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .renames
+            .get(&response_key(Path::new(file_path), position))
+            .cloned())
+    }
+
+    async fn workspace_symbol(
+        &mut self,
+        query: &str,
+    ) -> Result<WorkspaceSymbolResponse, Box<dyn Error + Send + Sync>> {
+        Ok(WorkspaceSymbolResponse::Flat(
+            self.workspace_symbols
+                .iter()
+                .filter(|symbol| symbol.name.contains(query))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    async fn workspace_will_rename_files(
+        &mut self,
+        _renames: Vec<(String, String)>,
+    ) -> Result<Option<WorkspaceEdit>, Box<dyn Error + Send + Sync>> {
+        Ok(self.will_rename_files_edit.clone())
+    }
+
+    fn get_process(&mut self) -> &mut ProcessHandler {
+        &mut self.process
+    }
+
+    fn get_json_rpc(&mut self) -> &mut JsonRpcHandler {
+        &mut self.json_rpc
+    }
+
+    fn get_pending_requests(&mut self) -> &mut PendingRequests {
+        &mut self.pending_requests
+    }
+
+    fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
+        &mut self.workspace_documents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Instant;
+
+    use crate::test_utils::TestContext;
+
+    fn files() -> Vec<MockFile> {
+        vec![
+            MockFile::new("graph.py", "class Graph:\n    pass\n"),
+            MockFile::new("main.py", "from graph import Graph\n\nGraph()\n"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_mock_canned_responses() -> Result<(), Box<dyn std::error::Error>> {
+        let started = Instant::now();
+        let mut declaration = None;
+        let mut usages = Vec::new();
+        let context = TestContext::setup_mock_with(SupportedLanguages::Python, files(), |client| {
+            let definition = client.location("graph.py", Position::new(0, 6), Position::new(0, 11));
+            let import = client.location("main.py", Position::new(0, 18), Position::new(0, 23));
+            let call = client.location("main.py", Position::new(2, 0), Position::new(2, 5));
+            declaration = Some(definition.clone());
+            usages = vec![import.clone(), call.clone()];
+            client
+                .with_definition("main.py", Position::new(2, 2), vec![definition.clone()])
+                .with_references(
+                    "main.py",
+                    Position::new(2, 2),
+                    vec![definition, import, call],
+                )
+        })
+        .await?;
+        let manager = context.manager.as_ref().unwrap();
+
+        assert_eq!(manager.list_files().await?, vec!["graph.py", "main.py"]);
+        assert_eq!(
+            manager
+                .find_definition("main.py", Position::new(2, 2))
+                .await?,
+            GotoDefinitionResponse::Array(vec![declaration.unwrap()])
+        );
+        assert_eq!(
+            manager
+                .find_references("main.py", Position::new(2, 2), false)
+                .await?,
+            usages
+        );
+
+        // No canned response
+        assert_eq!(
+            manager
+                .find_definition("graph.py", Position::new(0, 6))
+                .await?,
+            GotoDefinitionResponse::Array(vec![])
+        );
+        assert!(started.elapsed().as_millis() < 100);
+        Ok(())
+    }
+}
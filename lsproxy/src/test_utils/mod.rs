@@ -0,0 +1,190 @@
+use crate::api_types::{
+    set_thread_local_mount_dir, unset_thread_local_mount_dir, SupportedLanguages,
+};
+use crate::lsp::manager::{Manager, ManagerConfig};
+use crate::AppState;
+use actix_web::web::Data;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+pub mod mocks;
+
+use mocks::{MockFile, MockLspClient};
+
+pub fn python_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/python".to_string()
+}
+
+pub fn js_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/js".to_string()
+}
+
+pub fn java_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/java".to_string()
+}
+
+pub fn rust_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/rust".to_string()
+}
+
+pub fn go_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/go".to_string()
+}
+
+pub fn typescript_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/typescript".to_string()
+}
+
+pub fn csharp_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/csharp".to_string()
+}
+
+pub fn cpp_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/cpp".to_string()
+}
+
+pub fn c_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/c".to_string()
+}
+
+pub fn php_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/php".to_string()
+}
+
+pub fn ruby_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/ruby".to_string()
+}
+
+pub struct TestContext {
+    pub manager: Option<Manager>,
+    /// The temporary workspace of a mock context, removed when the context is dropped
+    workspace: Option<TempDir>,
+}
+
+impl TestContext {
+    pub async fn setup(file_path: &str, manager: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        set_thread_local_mount_dir(file_path);
+        if manager {
+            let mut manager = Manager::new(file_path, ManagerConfig::default()).await?;
+            if let Err(e) = manager.start_langservers(file_path).await {
+                unset_thread_local_mount_dir();
+                return Err(e);
+            }
+            return Ok(Self {
+                manager: Some(manager),
+                workspace: None,
+            });
+        }
+        Ok(Self {
+            manager: None,
+            workspace: None,
+        })
+    }
+
+    /// Sets up a temporary workspace with `files`, served by a [`MockLspClient`] for `language`
+    /// instead of a real language server.
+    pub async fn setup_mock(
+        language: SupportedLanguages,
+        files: Vec<MockFile>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::setup_mock_with(language, files, |client| client).await
+    }
+
+    /// Like [`TestContext::setup_mock`], with `configure` adding canned responses to the client.
+    pub async fn setup_mock_with(
+        language: SupportedLanguages,
+        files: Vec<MockFile>,
+        configure: impl FnOnce(MockLspClient) -> MockLspClient,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // The default `.tmp` prefix would hide the workspace from the `**/.*` exclude pattern
+        let workspace = tempfile::Builder::new().prefix("lsproxy-").tempdir()?;
+        for file in files {
+            let path = workspace.path().join(&file.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, file.content)?;
+        }
+        set_thread_local_mount_dir(workspace.path());
+
+        let root_path = workspace.path().to_str().unwrap_or_default();
+        let mut manager = Manager::new(root_path, ManagerConfig::default()).await?;
+        let client =
+            MockLspClient::new(workspace.path(), language, manager.subscribe_watch_events())
+                .await
+                .map_err(|e| e.to_string())?;
+        manager.insert_client(language, Box::new(configure(client)));
+        Ok(Self {
+            manager: Some(manager),
+            workspace: Some(workspace),
+        })
+    }
+
+    /// Adds a [`MockLspClient`] for another language to a mock context, e.g. for a workspace
+    /// mixing languages.
+    pub async fn add_mock_client(
+        &mut self,
+        language: SupportedLanguages,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let workspace_path = self.workspace_path().to_path_buf();
+        let manager = self
+            .manager
+            .as_mut()
+            .expect("the context was set up without a manager");
+        let client =
+            MockLspClient::new(&workspace_path, language, manager.subscribe_watch_events())
+                .await
+                .map_err(|e| e.to_string())?;
+        manager.insert_client(language, Box::new(client));
+        Ok(())
+    }
+
+    /// The root of the temporary workspace of a mock context.
+    pub fn workspace_path(&self) -> &std::path::Path {
+        self.workspace
+            .as_ref()
+            .expect("only mock contexts have a temporary workspace")
+            .path()
+    }
+
+    /// Builds the app state around the context's manager, without starting language servers.
+    pub fn app_state(&mut self) -> Data<AppState> {
+        let manager = self
+            .manager
+            .take()
+            .expect("the context was set up without a manager");
+        Data::new(AppState {
+            manager: Arc::new(manager),
+            etags: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            log_filter: None,
+        })
+    }
+}
+
+impl Drop for TestContext {
+    fn drop(&mut self) {
+        unset_thread_local_mount_dir();
+    }
+}
+
+/// Collects everything the subscriber writes.
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl LogBuffer {
+    pub fn contains(&self, text: &str) -> bool {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).contains(text)
+    }
+}
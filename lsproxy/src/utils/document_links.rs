@@ -0,0 +1,70 @@
+use lsp_types::{DocumentLink, Position, Range, Url};
+use regex::Regex;
+use std::sync::OnceLock;
+
+fn url_regex() -> &'static Regex {
+    static URL_REGEX: OnceLock<Regex> = OnceLock::new();
+    URL_REGEX.get_or_init(|| Regex::new(r#"https?://[^\s<>"'`()\[\]{}]+"#).unwrap())
+}
+
+/// Finds the http(s) URLs in `content`, e.g. in doc comments, as document links.
+///
+/// Used for language servers that don't implement `textDocument/documentLink`. Trailing
+/// punctuation is not considered part of the URL. Characters are counted in UTF-16 code units.
+pub fn find_url_links(content: &str) -> Vec<DocumentLink> {
+    let mut links = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        for url_match in url_regex().find_iter(line) {
+            let text = url_match
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let Ok(target) = Url::parse(text) else {
+                continue;
+            };
+            let start = line[..url_match.start()].encode_utf16().count() as u32;
+            let end = start + text.encode_utf16().count() as u32;
+            links.push(DocumentLink {
+                range: Range::new(
+                    Position::new(line_number as u32, start),
+                    Position::new(line_number as u32, end),
+                ),
+                target: Some(target),
+                tooltip: None,
+                data: None,
+            });
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_url_links() {
+        let links = find_url_links(
+            "/**\n * Euclidean distance.\n * @see https://en.wikipedia.org/wiki/Euclidean_distance.\n */\n// see (http://example.com/a?b=1)\n",
+        );
+        assert_eq!(links.len(), 2);
+        assert_eq!(
+            links[0].target.as_ref().unwrap().as_str(),
+            "https://en.wikipedia.org/wiki/Euclidean_distance"
+        );
+        assert_eq!(
+            links[0].range,
+            Range::new(Position::new(2, 8), Position::new(2, 56))
+        );
+        assert_eq!(
+            links[1].target.as_ref().unwrap().as_str(),
+            "http://example.com/a?b=1"
+        );
+        assert_eq!(links[1].range.start, Position::new(4, 8));
+    }
+
+    #[test]
+    fn test_find_url_links_utf16_columns() {
+        let links = find_url_links("# 🦀 https://rust-lang.org\n");
+        assert_eq!(links[0].range.start, Position::new(0, 5));
+    }
+}
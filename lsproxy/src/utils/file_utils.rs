@@ -4,14 +4,16 @@ use crate::{
 };
 use ignore::WalkBuilder;
 use log::{debug, error, warn};
-use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use strum::IntoEnumIterator;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use url::Url;
 
 use super::workspace_documents::{
-    CPP_EXTENSIONS, CSHARP_EXTENSIONS, C_AND_CPP_EXTENSIONS, C_EXTENSIONS, GOLANG_EXTENSIONS,
-    JAVASCRIPTREACT_EXTENSIONS, JAVASCRIPT_EXTENSIONS, JAVA_EXTENSIONS, PHP_EXTENSIONS,
-    PYTHON_EXTENSIONS, RUBY_EXTENSIONS, RUST_EXTENSIONS, TYPESCRIPTREACT_EXTENSIONS,
-    TYPESCRIPT_AND_JAVASCRIPT_EXTENSIONS, TYPESCRIPT_EXTENSIONS,
+    C_EXTENSIONS, JAVASCRIPTREACT_EXTENSIONS, JAVASCRIPT_EXTENSIONS, TYPESCRIPTREACT_EXTENSIONS,
 };
 
 pub fn search_files(
@@ -95,46 +97,371 @@ fn build_walk(path: &Path, exclude_patterns: Vec<String>, respect_gitignore: boo
     walk
 }
 
+/// Reads lines `start_line..=end_line` (0-indexed) of a file without loading the whole file.
+///
+/// Lines are joined with `\n` and stripped of their line endings. Reading stops early at the end
+/// of the file.
+pub async fn read_file_lines(
+    file_path: &Path,
+    start_line: u32,
+    end_line: u32,
+) -> std::io::Result<String> {
+    let mut reader = BufReader::new(File::open(file_path).await?);
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    while line_number <= end_line {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).await? == 0 {
+            break;
+        }
+        if line_number >= start_line {
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            lines.push(line.strip_suffix('\r').unwrap_or(line).to_string());
+        }
+        line_number += 1;
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Returns the absolute paths of the members of the Cargo workspace rooted at `root`.
+///
+/// Members are read from `[workspace].members` of `root/Cargo.toml`, with glob members like
+/// `crates/*` expanded and `[workspace].exclude` applied. Returns an empty list if `root` has no
+/// `Cargo.toml` or it doesn't define a workspace.
+pub fn parse_cargo_workspace(root: &Path) -> Vec<PathBuf> {
+    let manifest_path = root.join("Cargo.toml");
+    let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+        return Vec::new();
+    };
+    let manifest: toml::Value = match manifest.parse() {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            warn!("Failed to parse {:?}: {}", manifest_path, e);
+            return Vec::new();
+        }
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let paths = |key: &str| -> Vec<PathBuf> {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|member| member.as_str())
+            .flat_map(|member| {
+                let pattern = root.join(member);
+                match glob::glob(&pattern.to_string_lossy()) {
+                    Ok(paths) => paths.filter_map(Result::ok).collect(),
+                    Err(_) => vec![pattern],
+                }
+            })
+            .collect()
+    };
+
+    let excluded = paths("exclude");
+    let mut members: Vec<PathBuf> = paths("members")
+        .into_iter()
+        .filter(|member| member.join("Cargo.toml").is_file() && !excluded.contains(member))
+        .collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+/// Returns the absolute paths of the packages of the npm/Yarn workspace rooted at `root`.
+///
+/// Packages are read from the `workspaces` field of `root/package.json`, either a list of globs
+/// or Yarn's `{ "packages": [...] }` form. Globs starting with `!` exclude packages. Only
+/// directories containing a `package.json` are returned.
+pub fn parse_npm_workspace(root: &Path) -> Vec<PathBuf> {
+    let manifest_path = root.join("package.json");
+    let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+        return Vec::new();
+    };
+    let manifest: serde_json::Value = match serde_json::from_str(&manifest) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            warn!("Failed to parse {:?}: {}", manifest_path, e);
+            return Vec::new();
+        }
+    };
+    let workspaces = match manifest.get("workspaces") {
+        Some(serde_json::Value::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+    let globs: Vec<&str> = workspaces
+        .and_then(|globs| globs.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|glob| glob.as_str())
+        .collect();
+    let expand = |pattern: &str| -> Vec<PathBuf> {
+        glob::glob(&root.join(pattern).to_string_lossy())
+            .map(|paths| paths.filter_map(Result::ok).collect())
+            .unwrap_or_default()
+    };
+
+    let excluded: Vec<PathBuf> = globs
+        .iter()
+        .filter_map(|glob| glob.strip_prefix('!'))
+        .flat_map(expand)
+        .collect();
+    let mut packages: Vec<PathBuf> = globs
+        .iter()
+        .filter(|glob| !glob.starts_with('!'))
+        .flat_map(|glob| expand(glob))
+        .filter(|package| package.join("package.json").is_file() && !excluded.contains(package))
+        .collect();
+    packages.sort();
+    packages.dedup();
+    packages
+}
+
+/// Returns the absolute paths of the modules of the Maven project rooted at `root`.
+///
+/// Modules are read from the `<module>` entries of `root/pom.xml`, including those of nested
+/// aggregator modules. Returns an empty list if `root` has no `pom.xml` or it has no modules.
+pub fn parse_maven_modules(root: &Path) -> Vec<PathBuf> {
+    let mut modules = Vec::new();
+    let mut visited = HashSet::from([root.canonicalize().unwrap_or_else(|_| root.into())]);
+    collect_maven_modules(root, &mut modules, &mut visited);
+    modules.sort();
+    modules
+}
+
+fn collect_maven_modules(root: &Path, modules: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    let Ok(pom) = std::fs::read_to_string(root.join("pom.xml")) else {
+        return;
+    };
+    let module_re = regex::Regex::new(r"<module>\s*([^<]+?)\s*</module>").unwrap();
+    for captures in module_re.captures_iter(&pom) {
+        let mut module = root.join(&captures[1]);
+        // A module may also point at its pom file rather than its directory
+        if module.extension().is_some_and(|ext| ext == "xml") {
+            module.pop();
+        }
+        // Skip missing modules, and modules already seen through a `..` entry
+        let Ok(canonical) = module.canonicalize() else {
+            continue;
+        };
+        if module.join("pom.xml").is_file() && visited.insert(canonical) {
+            collect_maven_modules(&module, modules, visited);
+            modules.push(module);
+        }
+    }
+}
+
+/// Returns the absolute paths of the subprojects of the Gradle build rooted at `root`.
+///
+/// Subprojects are read from the `include` statements of `root/settings.gradle` or
+/// `root/settings.gradle.kts`, mapping project paths like `:libs:core` to `libs/core`. Returns an
+/// empty list if `root` has no settings file or it includes no subprojects.
+pub fn parse_gradle_modules(root: &Path) -> Vec<PathBuf> {
+    let Some(settings) = ["settings.gradle", "settings.gradle.kts"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(root.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    let include_re = regex::Regex::new(r"(?m)^\s*include\b(.*)$").unwrap();
+    let project_re = regex::Regex::new(r#"["']([^"']+)["']"#).unwrap();
+    let mut modules: Vec<PathBuf> = include_re
+        .captures_iter(&settings)
+        .flat_map(|include| {
+            project_re
+                .captures_iter(include.get(1).map_or("", |m| m.as_str()))
+                .map(|project| root.join(project[1].trim_start_matches(':').replace(':', "/")))
+                .collect::<Vec<_>>()
+        })
+        .filter(|module| module.is_dir())
+        .collect();
+    modules.sort();
+    modules.dedup();
+    modules
+}
+
+/// Returns the absolute paths of the Java modules of the Maven or Gradle build rooted at `root`,
+/// each of which gets its own JDT LS.
+///
+/// Aggregator modules, which only list other modules, are left out. Returns an empty list if the
+/// build has fewer than two modules, in which case a single JDT LS serves the whole workspace.
+pub fn java_module_roots(root: &Path) -> Vec<PathBuf> {
+    let mut modules = parse_maven_modules(root);
+    modules.extend(parse_gradle_modules(root));
+    modules.sort();
+    modules.dedup();
+    let leaves: Vec<PathBuf> = modules
+        .iter()
+        .filter(|module| {
+            !modules
+                .iter()
+                .any(|other| other != *module && other.starts_with(module))
+        })
+        .cloned()
+        .collect();
+    if leaves.len() < 2 {
+        return Vec::new();
+    }
+    leaves
+}
+
 pub fn uri_to_relative_path_string(uri: &Url) -> String {
+    uri_relative_path_string(uri, &get_mount_dir())
+}
+
+/// Like [`uri_to_relative_path_string`], but relative to an explicit `root`.
+pub fn uri_relative_path_string(uri: &Url, root: &Path) -> String {
     let path = uri.to_file_path().unwrap_or_else(|e| {
         warn!("Failed to convert URI to file path: {:?}", e);
         PathBuf::from(uri.path())
     });
 
-    absolute_path_to_relative_path_string(&path)
+    relative_path_string(&path, root)
 }
 
-pub fn absolute_path_to_relative_path_string(path: &PathBuf) -> String {
-    let mount_dir = get_mount_dir();
-    path.strip_prefix(mount_dir)
-        .map(|p| p.to_string_lossy().into_owned())
-        .unwrap_or_else(|e| {
-            debug!("Failed to strip prefix from {:?}: {:?}", path, e);
+/// Converts an absolute path to a string relative to the mount dir.
+///
+/// If the path isn't lexically under the mount dir, e.g. because one of them goes through a
+/// symlink, both are canonicalized and compared again.
+pub fn absolute_path_to_relative_path_string(path: &Path) -> String {
+    relative_path_string(path, &get_mount_dir())
+}
+
+/// Like [`absolute_path_to_relative_path_string`], but relative to an explicit `root`.
+///
+/// Use this off the request thread (e.g. in spawned tasks), where the mount dir may not be set.
+pub fn relative_path_string(path: &Path, root: &Path) -> String {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return relative.to_string_lossy().into_owned();
+    }
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(canonical_path), Ok(canonical_root)) => canonical_path
+            .strip_prefix(&canonical_root)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|e| {
+                debug!("Failed to strip prefix from {:?}: {:?}", path, e);
+                path.to_string_lossy().into_owned()
+            }),
+        _ => {
+            debug!("Failed to strip prefix {:?} from {:?}", root, path);
             path.to_string_lossy().into_owned()
-        })
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SanitizeError {
+    NullByte,
+    OutsideWorkspace(String),
 }
 
+impl fmt::Display for SanitizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanitizeError::NullByte => write!(f, "path contains a null byte"),
+            SanitizeError::OutsideWorkspace(path) => {
+                write!(f, "'{}' is outside the workspace", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanitizeError {}
+
+/// Resolves a workspace-relative `path` against `mount_dir`, rejecting paths that escape it.
+///
+/// Both the path as given and its percent-decoded form must stay inside the mount dir, so that
+/// payloads like `..%2F..%2Fetc%2Fpasswd` are rejected too. Symlinks are resolved for the part
+/// of the path that exists, which may not be all of it when a file is about to be created.
+pub fn sanitize_file_path(path: &str, mount_dir: &Path) -> Result<PathBuf, SanitizeError> {
+    let decoded = percent_decode(path);
+    if path.contains('\0') || decoded.contains('\0') {
+        return Err(SanitizeError::NullByte);
+    }
+
+    let root = mount_dir
+        .canonicalize()
+        .unwrap_or_else(|_| mount_dir.to_path_buf());
+    let outside = || SanitizeError::OutsideWorkspace(path.to_string());
+    resolve_within(&root, &decoded).ok_or_else(outside)?;
+    resolve_within(&root, path).ok_or_else(outside)
+}
+
+fn resolve_within(root: &Path, path: &str) -> Option<PathBuf> {
+    let mut resolved = root.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+        if !resolved.starts_with(root) {
+            return None;
+        }
+    }
+
+    let existing = resolved.ancestors().find(|ancestor| ancestor.exists())?;
+    let canonical = existing.canonicalize().ok()?;
+    if !canonical.starts_with(root) {
+        return None;
+    }
+    let missing = resolved.strip_prefix(existing).ok()?;
+    if missing.as_os_str().is_empty() {
+        // Joining an empty path would add a trailing separator
+        return Some(canonical);
+    }
+    Some(canonical.join(missing))
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Detects the language of a file from its extension, falling back to its content when the
+/// extension is missing or unknown. Relative paths are resolved against the mount dir.
 pub fn detect_language(file_path: &str) -> Result<SupportedLanguages, LspManagerError> {
+    detect_language_from_extension(file_path).or_else(|e| {
+        let full_path = get_mount_dir().join(file_path);
+        SupportedLanguages::detect_from_file_content(&full_path.to_string_lossy()).ok_or(e)
+    })
+}
+
+fn detect_language_from_extension(file_path: &str) -> Result<SupportedLanguages, LspManagerError> {
     let path = PathBuf::from(file_path);
     let extension = path
         .extension()
         .and_then(|ext| ext.to_str())
         .ok_or_else(|| LspManagerError::UnsupportedFileType(file_path.to_string()))?;
 
-    match extension {
-        ext if PYTHON_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::Python),
-        ext if TYPESCRIPT_AND_JAVASCRIPT_EXTENSIONS.contains(&ext) => {
-            Ok(SupportedLanguages::TypeScriptJavaScript)
-        }
-        ext if RUST_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::Rust),
-        ext if C_AND_CPP_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::CPP),
-        ext if CSHARP_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::CSharp),
-        ext if JAVA_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::Java),
-        ext if GOLANG_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::Golang),
-        ext if PHP_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::PHP),
-        ext if RUBY_EXTENSIONS.contains(&ext) => Ok(SupportedLanguages::Ruby),
-        _ => Err(LspManagerError::UnsupportedFileType(file_path.to_string())),
-    }
+    SupportedLanguages::iter()
+        .find(|language| language.file_extensions().contains(&extension))
+        .ok_or_else(|| LspManagerError::UnsupportedFileType(file_path.to_string()))
 }
 
 pub fn detect_language_string(file_path: &str) -> Result<String, LspManagerError> {
@@ -144,20 +471,364 @@ pub fn detect_language_string(file_path: &str) -> Result<String, LspManagerError
         .and_then(|ext| ext.to_str())
         .ok_or_else(|| LspManagerError::UnsupportedFileType(file_path.to_string()))?;
 
+    // The TypeScript and C/C++ servers tell their languages apart by id
     match extension {
-        ext if PYTHON_EXTENSIONS.contains(&ext) => Ok("python".to_string()),
-        ext if TYPESCRIPT_EXTENSIONS.contains(&ext) => Ok("typescript".to_string()),
         ext if TYPESCRIPTREACT_EXTENSIONS.contains(&ext) => Ok("typescriptreact".to_string()),
         ext if JAVASCRIPT_EXTENSIONS.contains(&ext) => Ok("javascript".to_string()),
         ext if JAVASCRIPTREACT_EXTENSIONS.contains(&ext) => Ok("javascriptreact".to_string()),
-        ext if RUST_EXTENSIONS.contains(&ext) => Ok("rust".to_string()),
         ext if C_EXTENSIONS.contains(&ext) => Ok("c".to_string()),
-        ext if CPP_EXTENSIONS.contains(&ext) => Ok("cpp".to_string()),
-        ext if CSHARP_EXTENSIONS.contains(&ext) => Ok("csharp".to_string()),
-        ext if JAVA_EXTENSIONS.contains(&ext) => Ok("java".to_string()),
-        ext if GOLANG_EXTENSIONS.contains(&ext) => Ok("golang".to_string()),
-        ext if PHP_EXTENSIONS.contains(&ext) => Ok("php".to_string()),
-        ext if RUBY_EXTENSIONS.contains(&ext) => Ok("ruby".to_string()),
-        _ => Err(LspManagerError::UnsupportedFileType(file_path.to_string())),
+        ext => SupportedLanguages::iter()
+            .find(|language| language.file_extensions().contains(&ext))
+            .map(|language| language.lsp_language_id().to_string())
+            .ok_or_else(|| LspManagerError::UnsupportedFileType(file_path.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_read_file_lines() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("lines.txt");
+        fs::write(&file_path, "zero\none\r\ntwo\nthree")?;
+
+        assert_eq!(read_file_lines(&file_path, 0, 0).await?, "zero");
+        assert_eq!(read_file_lines(&file_path, 1, 2).await?, "one\ntwo");
+        // Reading past the end of the file stops at the last line
+        assert_eq!(read_file_lines(&file_path, 2, 10).await?, "two\nthree");
+        assert_eq!(read_file_lines(&file_path, 5, 10).await?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_language_extension_is_detected() {
+        for language in SupportedLanguages::iter() {
+            assert!(!language.file_extensions().is_empty(), "{}", language);
+            for extension in language.file_extensions() {
+                assert_eq!(
+                    detect_language(&format!("src/file.{}", extension)).ok(),
+                    Some(language)
+                );
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_detect_language_any_extension(extension in "[^/.]{0,8}") {
+            let file_path = format!("src/file.{}", extension);
+            match detect_language(&file_path) {
+                Ok(language) => proptest::prop_assert!(
+                    language.file_extensions().contains(&extension.as_str())
+                ),
+                Err(e) => proptest::prop_assert!(
+                    matches!(e, LspManagerError::UnsupportedFileType(ref path) if *path == file_path)
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_cargo_workspace() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"cli\", \"crates/*\"]\nexclude = [\"crates/legacy\"]\n",
+        )?;
+        for member in ["cli", "crates/core", "crates/legacy", "crates/server"] {
+            fs::create_dir_all(root.join(member))?;
+            fs::write(root.join(member).join("Cargo.toml"), "[package]\n")?;
+        }
+        // Directories matched by a glob member without a manifest aren't crates
+        fs::create_dir_all(root.join("crates/assets"))?;
+
+        assert_eq!(
+            parse_cargo_workspace(root),
+            vec![
+                root.join("cli"),
+                root.join("crates/core"),
+                root.join("crates/server")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cargo_workspace_single_crate() {
+        // The Rust sample is a single package rather than a workspace
+        let sample = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sample_project/rust");
+        assert!(sample.join("Cargo.toml").is_file());
+        assert!(parse_cargo_workspace(&sample).is_empty());
+        assert!(parse_cargo_workspace(&sample.join("src")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_npm_workspace() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        for package in [
+            "apps/web",
+            "packages/core",
+            "packages/ui",
+            "packages/deprecated",
+        ] {
+            fs::create_dir_all(root.join(package).join("src"))?;
+            fs::write(root.join(package).join("package.json"), "{}")?;
+            fs::write(root.join(package).join("tsconfig.json"), "{}")?;
+            fs::write(root.join(package).join("src/index.ts"), "export {};")?;
+        }
+        let expected = vec![
+            root.join("apps/web"),
+            root.join("packages/core"),
+            root.join("packages/ui"),
+        ];
+
+        // npm form
+        fs::write(
+            root.join("package.json"),
+            r#"{ "workspaces": ["packages/*", "apps/web", "!packages/deprecated"] }"#,
+        )?;
+        assert_eq!(parse_npm_workspace(root), expected);
+
+        // Yarn form
+        fs::write(
+            root.join("package.json"),
+            r#"{ "workspaces": { "packages": ["packages/*", "apps/*", "!packages/deprecated"] } }"#,
+        )?;
+        assert_eq!(parse_npm_workspace(root), expected);
+
+        // Not a workspace
+        fs::write(root.join("package.json"), r#"{ "name": "app" }"#)?;
+        assert!(parse_npm_workspace(root).is_empty());
+        assert!(parse_npm_workspace(&root.join("apps")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_maven_modules() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        fs::write(
+            root.join("pom.xml"),
+            "<project>\n  <modules>\n    <module>api</module>\n    <module> services </module>\n    <module>missing</module>\n    <module>api/..</module>\n  </modules>\n</project>\n",
+        )?;
+        fs::create_dir_all(root.join("api"))?;
+        fs::write(root.join("api/pom.xml"), "<project></project>")?;
+        fs::create_dir_all(root.join("services/billing"))?;
+        fs::write(
+            root.join("services/pom.xml"),
+            "<project><modules><module>billing/pom.xml</module></modules></project>",
+        )?;
+        fs::write(root.join("services/billing/pom.xml"), "<project></project>")?;
+
+        assert_eq!(
+            parse_maven_modules(root),
+            vec![
+                root.join("api"),
+                root.join("services"),
+                root.join("services/billing")
+            ]
+        );
+        // A single-module project has no modules
+        assert!(parse_maven_modules(&root.join("api")).is_empty());
+        assert!(parse_maven_modules(&root.join("missing")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_gradle_modules() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        for module in ["app", "libs/core", "libs/util"] {
+            fs::create_dir_all(root.join(module))?;
+        }
+
+        fs::write(
+            root.join("settings.gradle"),
+            "rootProject.name = 'demo'\ninclude 'app', ':libs:core'\ninclude(\":libs:util\")\n// include 'commented'\n",
+        )?;
+        let expected = vec![
+            root.join("app"),
+            root.join("libs/core"),
+            root.join("libs/util"),
+        ];
+        assert_eq!(parse_gradle_modules(root), expected);
+
+        fs::remove_file(root.join("settings.gradle"))?;
+        fs::write(
+            root.join("settings.gradle.kts"),
+            "include(\"app\")\ninclude(\":libs:core\", \":libs:util\")\n",
+        )?;
+        assert_eq!(parse_gradle_modules(root), expected);
+        assert!(parse_gradle_modules(&root.join("app")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_java_module_roots() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        fs::write(
+            root.join("pom.xml"),
+            "<project><modules><module>api</module><module>services</module></modules></project>",
+        )?;
+        fs::create_dir_all(root.join("api"))?;
+        fs::write(root.join("api/pom.xml"), "<project></project>")?;
+        fs::create_dir_all(root.join("services/billing"))?;
+        fs::write(
+            root.join("services/pom.xml"),
+            "<project><modules><module>billing</module></modules></project>",
+        )?;
+        fs::write(root.join("services/billing/pom.xml"), "<project></project>")?;
+
+        // The `services` aggregator only lists `billing`
+        assert_eq!(
+            java_module_roots(root),
+            vec![root.join("api"), root.join("services/billing")]
+        );
+
+        // A single module is served from the workspace root
+        fs::write(
+            root.join("pom.xml"),
+            "<project><modules><module>api</module></modules></project>",
+        )?;
+        assert!(java_module_roots(root).is_empty());
+        assert!(java_module_roots(&root.join("api")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_file_path() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path().canonicalize()?;
+        fs::create_dir(root.join("src"))?;
+        fs::write(root.join("src/main.py"), "")?;
+
+        assert_eq!(
+            sanitize_file_path("src/main.py", &root)?,
+            root.join("src/main.py")
+        );
+        assert_eq!(
+            sanitize_file_path("./src/../src/main.py", &root)?,
+            root.join("src/main.py")
+        );
+        // Files that don't exist yet are allowed, e.g. for writes that create them
+        assert_eq!(
+            sanitize_file_path("src/new/file.py", &root)?,
+            root.join("src/new/file.py")
+        );
+        // Percent signs that don't decode to anything dangerous are kept as is
+        assert_eq!(
+            sanitize_file_path("src/100%25.py", &root)?,
+            root.join("src/100%25.py")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_file_path_rejects_traversal() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path().join("workspace");
+        fs::create_dir(&root)?;
+        fs::write(dir.path().join("secret.py"), "")?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path(), root.join("escape"))?;
+
+        let payloads = [
+            "..",
+            "../secret.py",
+            "../../../../etc/passwd",
+            "src/../../secret.py",
+            "./../workspace/../secret.py",
+            "/etc/passwd",
+            "..%2Fsecret.py",
+            "..%2fsecret.py",
+            "%2e%2e/secret.py",
+            "%2E%2E%2F%2E%2E%2Fetc%2Fpasswd",
+            "src%2F..%2F..%2Fsecret.py",
+            #[cfg(unix)]
+            "escape/secret.py",
+        ];
+        for payload in payloads {
+            assert_eq!(
+                sanitize_file_path(payload, &root),
+                Err(SanitizeError::OutsideWorkspace(payload.to_string())),
+                "{}",
+                payload
+            );
+        }
+
+        for payload in ["main.py\0", "main.py\0../../etc/passwd", "main.py%00.txt"] {
+            assert_eq!(
+                sanitize_file_path(payload, &root),
+                Err(SanitizeError::NullByte),
+                "{:?}",
+                payload
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_language_content_fallback() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(
+            dir.path().join("build"),
+            "#!/usr/bin/env python3\nprint('building')\n",
+        )?;
+        fs::write(
+            dir.path().join("lint.cjs.in"),
+            "// @ts-check\nmodule.exports = {};\n",
+        )?;
+        fs::write(dir.path().join("notes"), "remember to build\n")?;
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+        assert_eq!(detect_language(&path("build"))?, SupportedLanguages::Python);
+        assert_eq!(
+            detect_language(&path("lint.cjs.in"))?,
+            SupportedLanguages::TypeScriptJavaScript
+        );
+        assert!(matches!(
+            detect_language(&path("notes")),
+            Err(LspManagerError::UnsupportedFileType(_))
+        ));
+        // The extension takes precedence over the content
+        fs::write(dir.path().join("main.rb"), "#!/usr/bin/env python3\n")?;
+        assert_eq!(detect_language(&path("main.rb"))?, SupportedLanguages::Ruby);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_file_lines_missing_file() {
+        let dir = tempdir().unwrap();
+        assert!(read_file_lines(&dir.path().join("missing.txt"), 0, 1)
+            .await
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_absolute_path_to_relative_path_string_through_symlink(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(workspace.join("src"))?;
+        fs::write(workspace.join("src/main.py"), "")?;
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&workspace, &link)?;
+
+        crate::api_types::set_thread_local_mount_dir(&link);
+        let lexical = absolute_path_to_relative_path_string(&link.join("src/main.py"));
+        let resolved = absolute_path_to_relative_path_string(&workspace.join("src/main.py"));
+        crate::api_types::unset_thread_local_mount_dir();
+
+        assert_eq!(lexical, "src/main.py");
+        assert_eq!(resolved, "src/main.py");
+        Ok(())
     }
 }
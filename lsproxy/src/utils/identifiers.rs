@@ -0,0 +1,398 @@
+use crate::api_types::SupportedLanguages;
+
+pub(crate) const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+/// Includes `_`, which is a pattern in Rust rather than a name.
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "_",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "while",
+];
+
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "else",
+    "enum",
+    "false",
+    "finally",
+    "for",
+    "foreach",
+    "if",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "out",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "using",
+    "virtual",
+    "void",
+    "while",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "boolean",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "void",
+    "while",
+];
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+const PHP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "array",
+    "as",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "echo",
+    "else",
+    "elseif",
+    "extends",
+    "final",
+    "finally",
+    "fn",
+    "for",
+    "foreach",
+    "function",
+    "global",
+    "if",
+    "implements",
+    "interface",
+    "namespace",
+    "new",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "switch",
+    "throw",
+    "trait",
+    "try",
+    "use",
+    "while",
+];
+
+const RUBY_KEYWORDS: &[&str] = &[
+    "alias", "and", "begin", "break", "case", "class", "def", "do", "else", "elsif", "end",
+    "ensure", "false", "for", "if", "in", "module", "next", "nil", "not", "or", "redo", "rescue",
+    "retry", "return", "self", "super", "then", "true", "undef", "unless", "until", "when",
+    "while", "yield",
+];
+
+/// Whether `name` is a valid name for a symbol of `language`, e.g. as the new name of a rename.
+///
+/// Names are made of letters, digits and underscores, don't start with a digit and aren't
+/// keywords. Languages also accept their own sigils, e.g. `$` in TypeScript/JavaScript and Java,
+/// a leading `$` for PHP variables, a leading `@` for C# verbatim identifiers, `@`/`@@`/`$` for
+/// Ruby variables and a trailing `?`, `!` or `=` for Ruby methods.
+pub fn validate_identifier_name(name: &str, language: SupportedLanguages) -> bool {
+    let (name, keywords, extra_chars): (&str, &[&str], &[char]) = match language {
+        SupportedLanguages::Python => (name, PYTHON_KEYWORDS, &[]),
+        SupportedLanguages::TypeScriptJavaScript => (name, TYPESCRIPT_KEYWORDS, &['$']),
+        // Raw identifiers may use keywords, apart from the path ones
+        SupportedLanguages::Rust => match name.strip_prefix("r#") {
+            Some(raw) => (raw, &["crate", "self", "Self", "super", "_"], &[]),
+            None => (name, RUST_KEYWORDS, &[]),
+        },
+        SupportedLanguages::CPP => (name, CPP_KEYWORDS, &[]),
+        // Verbatim identifiers may use keywords
+        SupportedLanguages::CSharp => match name.strip_prefix('@') {
+            Some(verbatim) => (verbatim, &[], &[]),
+            None => (name, CSHARP_KEYWORDS, &[]),
+        },
+        SupportedLanguages::Java => (name, JAVA_KEYWORDS, &['$']),
+        SupportedLanguages::Golang => (name, GO_KEYWORDS, &[]),
+        SupportedLanguages::PHP => match name.strip_prefix('$') {
+            Some(variable) => (variable, &["this"], &[]),
+            None => (name, PHP_KEYWORDS, &[]),
+        },
+        SupportedLanguages::Ruby => {
+            let variable = ["@@", "@", "$"]
+                .iter()
+                .find_map(|sigil| name.strip_prefix(sigil));
+            match variable {
+                Some(variable) => (variable, &[], &[]),
+                None => (
+                    name.strip_suffix(['?', '!', '=']).unwrap_or(name),
+                    RUBY_KEYWORDS,
+                    &[],
+                ),
+            }
+        }
+    };
+
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || extra_chars.contains(&c);
+    let Some(first) = name.chars().next() else {
+        return false;
+    };
+    !first.is_numeric() && name.chars().all(is_name_char) && !keywords.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_common_identifier_names() {
+        for language in SupportedLanguages::iter() {
+            for name in ["distance", "AStarGraph", "_private", "point2", "größe"] {
+                assert!(
+                    validate_identifier_name(name, language),
+                    "{} {}",
+                    language,
+                    name
+                );
+            }
+            for name in ["", "2d", "a-b", "a b", "a.b", "a("] {
+                assert!(
+                    !validate_identifier_name(name, language),
+                    "{} {}",
+                    language,
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_language_identifier_names() {
+        let cases = [
+            (SupportedLanguages::Python, "lambda", false),
+            (SupportedLanguages::Python, "$value", false),
+            (SupportedLanguages::TypeScriptJavaScript, "$value", true),
+            (SupportedLanguages::TypeScriptJavaScript, "function", false),
+            (SupportedLanguages::Rust, "fn", false),
+            (SupportedLanguages::Rust, "r#fn", true),
+            (SupportedLanguages::Rust, "r#self", false),
+            (SupportedLanguages::Rust, "_", false),
+            (SupportedLanguages::CPP, "nullptr", false),
+            (SupportedLanguages::CSharp, "class", false),
+            (SupportedLanguages::CSharp, "@class", true),
+            (SupportedLanguages::Java, "inner$1", true),
+            (SupportedLanguages::Java, "void", false),
+            (SupportedLanguages::Golang, "func", false),
+            (SupportedLanguages::PHP, "$user", true),
+            (SupportedLanguages::PHP, "$this", false),
+            (SupportedLanguages::PHP, "function", false),
+            (SupportedLanguages::Ruby, "@name", true),
+            (SupportedLanguages::Ruby, "@@count", true),
+            (SupportedLanguages::Ruby, "empty?", true),
+            (SupportedLanguages::Ruby, "save!", true),
+            (SupportedLanguages::Ruby, "end", false),
+            (SupportedLanguages::Ruby, "a?b", false),
+        ];
+        for (language, name, valid) in cases {
+            assert_eq!(
+                validate_identifier_name(name, language),
+                valid,
+                "{} {}",
+                language,
+                name
+            );
+        }
+    }
+}
@@ -1,2 +1,6 @@
+pub(crate) mod document_links;
 pub(crate) mod file_utils;
+pub(crate) mod identifiers;
+pub(crate) mod text_edits;
 pub(crate) mod workspace_documents;
+pub(crate) mod workspace_edit;
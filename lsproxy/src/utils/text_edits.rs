@@ -0,0 +1,170 @@
+use lsp_types::{Position, Range, TextEdit};
+
+/// Converts an LSP position into a byte offset within `content`.
+///
+/// Characters are counted in UTF-16 code units, as in the LSP specification. Positions past the
+/// end of a line are clamped to the end of that line, and lines past the end of the content are
+/// clamped to the end of the content.
+pub fn position_to_offset(content: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match content[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return content.len(),
+        }
+    }
+
+    let line = content[line_start..].split('\n').next().unwrap_or_default();
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut utf16_count = 0;
+    for (byte_index, c) in line.char_indices() {
+        if utf16_count >= position.character {
+            return line_start + byte_index;
+        }
+        utf16_count += c.len_utf16() as u32;
+    }
+    line_start + line.len()
+}
+
+/// Returns the position just past the last character of `content`.
+pub fn end_position(content: &str) -> Position {
+    let line = content.matches('\n').count() as u32;
+    let last_line = content.rsplit('\n').next().unwrap_or_default();
+    Position {
+        line,
+        character: last_line.encode_utf16().count() as u32,
+    }
+}
+
+/// Returns the text of `content` within `range`.
+pub fn text_in_range(content: &str, range: Range) -> &str {
+    let start = position_to_offset(content, range.start);
+    let end = position_to_offset(content, range.end).max(start);
+    &content[start..end]
+}
+
+/// Returns the range of the word (a run of alphanumerics and underscores) touching `position`,
+/// or `None` if there is no word at or directly before it.
+pub fn word_range_at(content: &str, position: Position) -> Option<Range> {
+    let offset = position_to_offset(content, position);
+    let line_start = content[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let start = content[line_start..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_word_char(*c))
+        .last()
+        .map_or(offset, |(index, _)| line_start + index);
+    let end = content[offset..]
+        .char_indices()
+        .find(|(_, c)| !is_word_char(*c))
+        .map_or(content.len(), |(index, _)| offset + index);
+    if start == end {
+        return None;
+    }
+
+    let character = |byte_offset: usize| content[line_start..byte_offset].encode_utf16().count();
+    Some(Range::new(
+        Position::new(position.line, character(start) as u32),
+        Position::new(position.line, character(end) as u32),
+    ))
+}
+
+/// Applies text edits to `content`.
+///
+/// Edits are applied from the end of the document backwards so that the positions of the
+/// remaining edits stay valid. Edits starting at the same position are applied so that their
+/// text ends up in the order they were given, as the LSP spec requires.
+pub fn apply_text_edits(content: &str, edits: &[TextEdit]) -> String {
+    let mut edits: Vec<(usize, &TextEdit)> = edits.iter().enumerate().collect();
+    edits.sort_by(|(a_index, a), (b_index, b)| {
+        (b.range.start.line, b.range.start.character, b_index).cmp(&(
+            a.range.start.line,
+            a.range.start.character,
+            a_index,
+        ))
+    });
+
+    let mut result = content.to_string();
+    for (_, edit) in edits {
+        let start = position_to_offset(&result, edit.range.start);
+        let end = position_to_offset(&result, edit.range.end).max(start);
+        result.replace_range(start..end, &edit.new_text);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_position_to_offset() {
+        let content = "ab\r\nc😀d\n";
+        assert_eq!(position_to_offset(content, Position::new(0, 1)), 1);
+        // Past the end of the line is clamped before the line ending
+        assert_eq!(position_to_offset(content, Position::new(0, 10)), 2);
+        // The emoji takes two UTF-16 code units
+        assert_eq!(position_to_offset(content, Position::new(1, 3)), 9);
+        assert_eq!(
+            position_to_offset(content, Position::new(5, 0)),
+            content.len()
+        );
+    }
+
+    #[test]
+    fn test_end_position() {
+        assert_eq!(end_position(""), Position::new(0, 0));
+        assert_eq!(end_position("a\nbc"), Position::new(1, 2));
+        assert_eq!(end_position("a\nbc\n"), Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_word_range_at() {
+        let content = "from graph import AStarGraph\nx = ƒoo(1)\n";
+        let range = Range::new(Position::new(0, 18), Position::new(0, 28));
+        assert_eq!(word_range_at(content, Position::new(0, 20)), Some(range));
+        // A position just past the end of a word still touches it
+        assert_eq!(word_range_at(content, Position::new(0, 28)), Some(range));
+        assert_eq!(text_in_range(content, range), "AStarGraph");
+        assert_eq!(
+            word_range_at(content, Position::new(1, 4)),
+            Some(Range::new(Position::new(1, 4), Position::new(1, 7)))
+        );
+        assert_eq!(word_range_at(content, Position::new(1, 2)), None);
+    }
+
+    #[test]
+    fn test_apply_text_edits() {
+        let content = "def main( ):\n  return 1\n";
+        let edits = vec![
+            edit((0, 9), (0, 10), ""),
+            edit((1, 0), (1, 2), "    "),
+            edit((2, 0), (2, 0), "\nmain()\n"),
+        ];
+        assert_eq!(
+            apply_text_edits(content, &edits),
+            "def main():\n    return 1\n\nmain()\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_text_edits_at_same_position() {
+        let edits = vec![
+            edit((0, 0), (0, 0), "a"),
+            edit((0, 0), (0, 0), "b"),
+            edit((0, 0), (0, 3), "bar"),
+        ];
+        assert_eq!(apply_text_edits("foo()", &edits), "abbar()");
+    }
+}
@@ -1,19 +1,45 @@
+use crate::api_types::SupportedLanguages;
 use crate::utils::file_utils::search_files;
 use log::{debug, error, warn};
 use lsp_types::Range;
 use notify_debouncer_mini::DebouncedEvent;
+use sha2::{Digest, Sha256};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     error::Error,
     path::{Path, PathBuf},
     sync::Arc,
 };
 use tokio::{
-    fs::read,
+    fs::{metadata, read, File},
+    io::AsyncReadExt,
     sync::{broadcast::Receiver, RwLock},
 };
 use url::Url;
 
+/// Default maximum size of source code returned to clients (5 MB).
+pub const DEFAULT_MAX_SOURCE_CODE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Appended to documents that were cut off at the maximum size.
+pub const TRUNCATION_MARKER: &str = "\n// ... truncated";
+
+/// Returns the maximum size in bytes of source code returned to clients.
+///
+/// Configurable through the `MAX_SOURCE_CODE_BYTES` environment variable; falls back to
+/// [`DEFAULT_MAX_SOURCE_CODE_BYTES`] when unset or invalid.
+pub fn max_source_code_bytes() -> usize {
+    match std::env::var("MAX_SOURCE_CODE_BYTES") {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            warn!(
+                "Invalid MAX_SOURCE_CODE_BYTES value '{}', using default of {} bytes",
+                value, DEFAULT_MAX_SOURCE_CODE_BYTES
+            );
+            DEFAULT_MAX_SOURCE_CODE_BYTES
+        }),
+        Err(_) => DEFAULT_MAX_SOURCE_CODE_BYTES,
+    }
+}
+
 pub const DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[
     "**/node_modules",
     "**/__pycache__",
@@ -41,7 +67,6 @@ pub const TYPESCRIPT_AND_JAVASCRIPT_ROOT_FILES: &[&str] =
 
 pub const TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS: &[&str] =
     &["**/*.ts", "**/*.tsx", "**/*.js", "**/*.jsx"];
-pub const TYPESCRIPT_EXTENSIONS: &[&str] = &["ts"];
 pub const TYPESCRIPTREACT_EXTENSIONS: &[&str] = &["tsx"];
 pub const JAVASCRIPT_EXTENSIONS: &[&str] = &["js"];
 pub const JAVASCRIPTREACT_EXTENSIONS: &[&str] = &["jsx"];
@@ -75,7 +100,6 @@ pub const C_AND_CPP_FILE_PATTERNS: &[&str] = &[
 ];
 
 pub const C_EXTENSIONS: &[&str] = &["c", "h"];
-pub const CPP_EXTENSIONS: &[&str] = &["cpp", "cc", "cxx", "h", "hpp", "hxx", "hh"];
 pub const C_AND_CPP_EXTENSIONS: &[&str] = &["cpp", "cc", "c", "cxx", "h", "hpp", "hxx", "hh"];
 
 pub const CSHARP_ROOT_FILES: &[&str] = &["*.sln", "*.csproj", "*.vcxproj"];
@@ -109,6 +133,25 @@ pub const PHP_FILE_PATTERNS: &[&str] = &[
 ];
 pub const PHP_EXTENSIONS: &[&str] = &["php", "phtml", "phps", "php5", "php7", "php8"];
 
+/// Returns the patterns of the files handled by a language's server.
+///
+/// The match has no wildcard arm, so a new language can't be left out.
+#[deny(unreachable_patterns)]
+pub fn language_file_patterns(language: SupportedLanguages) -> Vec<String> {
+    let patterns = match language {
+        SupportedLanguages::Python => PYTHON_FILE_PATTERNS,
+        SupportedLanguages::TypeScriptJavaScript => TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS,
+        SupportedLanguages::Rust => RUST_FILE_PATTERNS,
+        SupportedLanguages::CPP => C_AND_CPP_FILE_PATTERNS,
+        SupportedLanguages::CSharp => CSHARP_FILE_PATTERNS,
+        SupportedLanguages::Java => JAVA_FILE_PATTERNS,
+        SupportedLanguages::Golang => GOLANG_FILE_PATTERNS,
+        SupportedLanguages::PHP => PHP_FILE_PATTERNS,
+        SupportedLanguages::Ruby => RUBY_FILE_PATTERNS,
+    };
+    patterns.iter().map(|&s| s.to_string()).collect()
+}
+
 #[derive(Clone, PartialEq)]
 pub enum DidOpenConfiguration {
     Lazy,
@@ -117,23 +160,97 @@ pub enum DidOpenConfiguration {
 
 #[async_trait::async_trait]
 pub trait WorkspaceDocuments: Send + Sync {
+    /// Reads a document, or a range of it.
+    ///
+    /// Documents larger than `max_bytes` are only read up to that size, and end with
+    /// [`TRUNCATION_MARKER`]. A range is extracted from the whole document, and only the
+    /// extracted text is cut off at `max_bytes`.
     async fn read_text_document(
         &self,
-        full_file_path: &PathBuf,
+        full_file_path: &Path,
         range: Option<Range>,
+        max_bytes: Option<usize>,
     ) -> Result<String, Box<dyn Error + Send + Sync>>;
     async fn list_files(&self) -> Vec<PathBuf>;
+    /// Lists the files currently on disk, bypassing the cached file list.
+    async fn list_files_on_disk(&self) -> Vec<PathBuf>;
+    async fn invalidate_document(&self, full_file_path: &Path);
+    /// Drops a document from the listed files, e.g. once it's been deleted.
+    async fn remove_document(&self, full_file_path: &Path);
+    async fn clear_cache(&self) -> usize;
     fn get_did_open_configuration(&self) -> DidOpenConfiguration;
     fn is_did_open_document(&self, file_path: &str) -> bool;
     fn add_did_open_document(&mut self, file_path: &str);
+    fn remove_did_open_document(&mut self, file_path: &str);
+    fn next_document_version(&mut self, file_path: &str) -> i32;
+    fn get_normalize_line_endings(&self) -> bool;
+    #[allow(unused)] // Line endings are normalized for every language client at the moment
+    fn set_normalize_line_endings(&mut self, normalize_line_endings: bool);
+}
+
+/// Converts CRLF line endings to LF.
+pub fn normalize_line_endings(content: &str) -> String {
+    content.replace("\r\n", "\n")
+}
+
+/// Cuts text longer than `max_bytes` off at that size, without splitting a character, and appends
+/// [`TRUNCATION_MARKER`].
+fn truncate_content(mut content: String, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content;
+    }
+    let mut end = max_bytes;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    content.truncate(end);
+    content.push_str(TRUNCATION_MARKER);
+    content
+}
+
+/// Returns the length, without the trailing `\r`, of every line that ends in CRLF, keyed by
+/// 0-indexed line number.
+pub fn crlf_line_lengths(content: &str) -> HashMap<u32, u32> {
+    content
+        .split('\n')
+        .enumerate()
+        .filter_map(|(i, line)| {
+            line.strip_suffix('\r')
+                .map(|line| (i as u32, line.chars().count() as u32))
+        })
+        .collect()
+}
+
+/// Remembers the content hash of files that changed on disk, to tell real changes apart from
+/// saves that leave a file as it was.
+#[derive(Default)]
+pub struct FileHashes {
+    hashes: HashMap<PathBuf, [u8; 32]>,
+}
+
+impl FileHashes {
+    /// Records the current hash of `path`, returning whether its content changed since it was
+    /// last recorded. Files seen for the first time, and files that can't be read, e.g. because
+    /// they were deleted, count as changed.
+    pub fn update(&mut self, path: &Path) -> bool {
+        match WorkspaceDocumentsHandler::file_hash(path) {
+            Ok(hash) => self.hashes.insert(path.to_path_buf(), hash) != Some(hash),
+            Err(_) => {
+                self.hashes.remove(path);
+                true
+            }
+        }
+    }
 }
 
 pub struct WorkspaceDocumentsHandler {
     cache: Arc<RwLock<HashMap<PathBuf, Option<String>>>>,
     patterns: Arc<RwLock<(Vec<String>, Vec<String>)>>,
     root_path: PathBuf,
-    did_open_text_documents: HashSet<Url>,
+    /// The documents opened with the language server, along with their latest version
+    did_open_text_documents: HashMap<Url, i32>,
     did_open_configuration: DidOpenConfiguration,
+    normalize_line_endings: bool,
 }
 
 impl WorkspaceDocumentsHandler {
@@ -166,11 +283,17 @@ impl WorkspaceDocumentsHandler {
             cache,
             patterns,
             root_path,
-            did_open_text_documents: HashSet::new(),
+            did_open_text_documents: HashMap::new(),
             did_open_configuration,
+            normalize_line_endings: true,
         }
     }
 
+    /// Computes the SHA-256 hash of a file's contents.
+    pub fn file_hash(path: &Path) -> std::io::Result<[u8; 32]> {
+        Ok(Sha256::digest(std::fs::read(path)?).into())
+    }
+
     async fn matches_patterns(
         path: &Path,
         patterns: &Arc<RwLock<(Vec<String>, Vec<String>)>>,
@@ -189,7 +312,7 @@ impl WorkspaceDocumentsHandler {
 
     async fn get_content(
         &self,
-        full_file_path: &PathBuf,
+        full_file_path: &Path,
     ) -> Result<String, Box<dyn Error + Send + Sync>> {
         let mut cache = self.cache.write().await;
         match cache.get(full_file_path) {
@@ -202,13 +325,43 @@ impl WorkspaceDocumentsHandler {
                 }
 
                 let content = String::from_utf8_lossy(&bytes).into_owned();
-                cache.insert(full_file_path.clone(), Some(content.clone()));
+                cache.insert(full_file_path.to_path_buf(), Some(content.clone()));
                 Ok(content)
             }
         }
     }
 
-    fn extract_range(content: &str, range: Range) -> Result<String, Box<dyn Error + Send + Sync>> {
+    /// Reads the first `max_bytes` of a file without caching it, dropping a trailing partial
+    /// UTF-8 character.
+    async fn read_truncated(
+        full_file_path: &Path,
+        max_bytes: usize,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Truncating {:?} to its first {} bytes",
+            full_file_path, max_bytes
+        );
+        let mut bytes = Vec::with_capacity(max_bytes);
+        File::open(full_file_path)
+            .await?
+            .take(max_bytes as u64)
+            .read_to_end(&mut bytes)
+            .await?;
+
+        if let Err(e) = std::str::from_utf8(&bytes) {
+            if e.error_len().is_none() {
+                bytes.truncate(e.valid_up_to());
+            }
+        }
+        let mut content = String::from_utf8_lossy(&bytes).into_owned();
+        content.push_str(TRUNCATION_MARKER);
+        Ok(content)
+    }
+
+    pub(crate) fn extract_range(
+        content: &str,
+        range: Range,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
         let lines: Vec<&str> = content.lines().collect();
         let total_lines = lines.len();
 
@@ -267,12 +420,27 @@ impl WorkspaceDocumentsHandler {
 impl WorkspaceDocuments for WorkspaceDocumentsHandler {
     async fn read_text_document(
         &self,
-        full_file_path: &PathBuf,
+        full_file_path: &Path,
         range: Option<Range>,
+        max_bytes: Option<usize>,
     ) -> Result<String, Box<dyn Error + Send + Sync>> {
-        let content = self.get_content(full_file_path).await?;
+        let mut content = match (range, max_bytes) {
+            (None, Some(max_bytes)) if metadata(full_file_path).await?.len() > max_bytes as u64 => {
+                Self::read_truncated(full_file_path, max_bytes).await?
+            }
+            _ => self.get_content(full_file_path).await?,
+        };
+        if self.normalize_line_endings {
+            content = normalize_line_endings(&content);
+        }
         match range {
-            Some(range) => Self::extract_range(&content, range),
+            Some(range) => {
+                let extracted = Self::extract_range(&content, range)?;
+                Ok(match max_bytes {
+                    Some(max_bytes) => truncate_content(extracted, max_bytes),
+                    None => extracted,
+                })
+            }
             None => Ok(content),
         }
     }
@@ -281,13 +449,7 @@ impl WorkspaceDocuments for WorkspaceDocumentsHandler {
         let cache_read = self.cache.read().await;
         if cache_read.is_empty() {
             drop(cache_read);
-            let (include_patterns, exclude_patterns) = self.patterns.read().await.clone();
-            let file_paths =
-                search_files(&self.root_path, include_patterns, exclude_patterns, true)
-                    .unwrap_or_else(|err| {
-                        error!("Error searching files: {}", err);
-                        Vec::new()
-                    });
+            let file_paths = self.list_files_on_disk().await;
             let mut cache_write = self.cache.write().await;
             for file_path in file_paths {
                 cache_write.insert(file_path, None);
@@ -298,18 +460,74 @@ impl WorkspaceDocuments for WorkspaceDocumentsHandler {
         }
     }
 
+    async fn list_files_on_disk(&self) -> Vec<PathBuf> {
+        let (include_patterns, exclude_patterns) = self.patterns.read().await.clone();
+        search_files(&self.root_path, include_patterns, exclude_patterns, true).unwrap_or_else(
+            |err| {
+                error!("Error searching files: {}", err);
+                Vec::new()
+            },
+        )
+    }
+
+    /// Drops the cached content of a document so that it's read from disk again.
+    ///
+    /// New documents are added to the listed files, unless the files haven't been listed yet.
+    async fn invalidate_document(&self, full_file_path: &Path) {
+        let mut cache = self.cache.write().await;
+        if !cache.is_empty() {
+            cache.insert(full_file_path.to_path_buf(), None);
+        }
+    }
+
+    async fn remove_document(&self, full_file_path: &Path) {
+        self.cache.write().await.remove(full_file_path);
+    }
+
+    /// Drops the cached file list and document contents, returning how many entries were
+    /// cached. Files are listed and read from disk again on their next use.
+    async fn clear_cache(&self) -> usize {
+        let mut cache = self.cache.write().await;
+        let cleared_entries = cache.len();
+        cache.clear();
+        cleared_entries
+    }
+
     fn get_did_open_configuration(&self) -> DidOpenConfiguration {
         self.did_open_configuration.clone()
     }
 
     fn is_did_open_document(&self, file_path: &str) -> bool {
         self.did_open_text_documents
-            .contains(&Url::from_file_path(file_path).unwrap())
+            .contains_key(&Url::from_file_path(file_path).unwrap())
     }
 
     fn add_did_open_document(&mut self, file_path: &str) {
         self.did_open_text_documents
-            .insert(Url::from_file_path(file_path).unwrap());
+            .insert(Url::from_file_path(file_path).unwrap(), 1);
+    }
+
+    fn remove_did_open_document(&mut self, file_path: &str) {
+        self.did_open_text_documents
+            .remove(&Url::from_file_path(file_path).unwrap());
+    }
+
+    /// Increments and returns the version of an open document, for didChange notifications.
+    fn next_document_version(&mut self, file_path: &str) -> i32 {
+        let version = self
+            .did_open_text_documents
+            .entry(Url::from_file_path(file_path).unwrap())
+            .or_insert(1);
+        *version += 1;
+        *version
+    }
+
+    fn get_normalize_line_endings(&self) -> bool {
+        self.normalize_line_endings
+    }
+
+    fn set_normalize_line_endings(&mut self, normalize_line_endings: bool) {
+        self.normalize_line_endings = normalize_line_endings;
     }
 }
 
@@ -343,7 +561,7 @@ mod tests {
         );
 
         // Test reading the entire document
-        let content = handler.read_text_document(&file_path, None).await?;
+        let content = handler.read_text_document(&file_path, None, None).await?;
         assert_eq!(content, "Hello, world!\nThis is a test.");
 
         // Test reading a specific range
@@ -357,7 +575,9 @@ mod tests {
                 character: 12,
             },
         };
-        let extracted = handler.read_text_document(&file_path, Some(range)).await?;
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), None)
+            .await?;
         assert_eq!(extracted, "world");
 
         Ok(())
@@ -430,7 +650,9 @@ mod tests {
                 character: 10,
             },
         };
-        let extracted = handler.read_text_document(&file_path, Some(range)).await?;
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), None)
+            .await?;
         assert_eq!(extracted, "");
 
         Ok(())
@@ -465,7 +687,9 @@ mod tests {
                 character: 200,
             },
         };
-        let extracted = handler.read_text_document(&file_path, Some(range)).await?;
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), None)
+            .await?;
         assert_eq!(extracted, "");
 
         Ok(())
@@ -489,7 +713,7 @@ mod tests {
         );
 
         // Test reading the entire empty document
-        let content = handler.read_text_document(&file_path, None).await?;
+        let content = handler.read_text_document(&file_path, None, None).await?;
         assert_eq!(content, "");
 
         // Test reading with any range on empty file
@@ -503,12 +727,211 @@ mod tests {
                 character: 10,
             },
         };
-        let extracted = handler.read_text_document(&file_path, Some(range)).await?;
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), None)
+            .await?;
         assert_eq!(extracted, "");
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_read_text_document_crlf() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Setup temporary directory and a file with Windows line endings
+        let dir = tempdir()?;
+        let file_path = dir.path().join("crlf.txt");
+        fs::write(&file_path, "Hello, world!\r\nThis is a test.\r\n")?;
+        let (_, rx) = create_test_watcher_channels();
+        let mut handler = WorkspaceDocumentsHandler::new(
+            dir.path(),
+            vec!["*.txt".to_string()],
+            vec![],
+            rx,
+            DidOpenConfiguration::None,
+        );
+
+        // Line endings are normalized by default
+        assert!(handler.get_normalize_line_endings());
+        let content = handler.read_text_document(&file_path, None, None).await?;
+        assert_eq!(content, "Hello, world!\nThis is a test.\n");
+
+        // Ranges don't include the carriage return
+        let range = Range {
+            start: lsp_types::Position {
+                line: 0,
+                character: 7,
+            },
+            end: lsp_types::Position {
+                line: 1,
+                character: 100,
+            },
+        };
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), None)
+            .await?;
+        assert_eq!(extracted, "world!\nThis is a test.");
+
+        // The raw content is returned when normalization is turned off
+        handler.set_normalize_line_endings(false);
+        let content = handler.read_text_document(&file_path, None, None).await?;
+        assert_eq!(content, "Hello, world!\r\nThis is a test.\r\n");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_text_document_truncated() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("bundle.min.js");
+        let content = "var a=1;".repeat(1000);
+        fs::write(&file_path, &content)?;
+        let (_, rx) = create_test_watcher_channels();
+        let handler = WorkspaceDocumentsHandler::new(
+            dir.path(),
+            vec!["*.js".to_string()],
+            vec![],
+            rx,
+            DidOpenConfiguration::None,
+        );
+
+        let truncated = handler
+            .read_text_document(&file_path, None, Some(100))
+            .await?;
+        assert_eq!(
+            truncated,
+            format!("{}{}", &content[..100], TRUNCATION_MARKER)
+        );
+        assert!(truncated.ends_with("// ... truncated"));
+
+        // Files within the limit, and reads without a limit, are returned in full
+        let full = handler
+            .read_text_document(&file_path, None, Some(content.len()))
+            .await?;
+        assert_eq!(full, content);
+        let full = handler.read_text_document(&file_path, None, None).await?;
+        assert_eq!(full, content);
+
+        // The limit still applies once the full document is cached
+        let truncated_again = handler
+            .read_text_document(&file_path, None, Some(100))
+            .await?;
+        assert_eq!(truncated_again, truncated);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_text_document_range_truncated() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("generated.py");
+        let content: String = (0..100).map(|i| format!("value_{} = {}\n", i, i)).collect();
+        fs::write(&file_path, &content)?;
+        let (_, rx) = create_test_watcher_channels();
+        let handler = WorkspaceDocumentsHandler::new(
+            dir.path(),
+            vec!["*.py".to_string()],
+            vec![],
+            rx,
+            DidOpenConfiguration::None,
+        );
+
+        // A range past the limit is read from the whole file
+        let range = Range::new(
+            lsp_types::Position::new(90, 0),
+            lsp_types::Position::new(91, 12),
+        );
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), Some(100))
+            .await?;
+        assert_eq!(extracted, "value_90 = 90\nvalue_91 = 9");
+
+        // Only the extracted text is cut off at the limit
+        let range = Range::new(
+            lsp_types::Position::new(0, 0),
+            lsp_types::Position::new(99, 0),
+        );
+        let extracted = handler
+            .read_text_document(&file_path, Some(range), Some(20))
+            .await?;
+        assert_eq!(
+            extracted,
+            format!("{}{}", &content[..20], TRUNCATION_MARKER)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_text_document_truncated_utf8() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("emoji.txt");
+        // Each emoji is 4 bytes, so a 6 byte limit ends in the middle of the second one
+        fs::write(&file_path, "😀😀😀")?;
+        let (_, rx) = create_test_watcher_channels();
+        let handler = WorkspaceDocumentsHandler::new(
+            dir.path(),
+            vec!["*.txt".to_string()],
+            vec![],
+            rx,
+            DidOpenConfiguration::None,
+        );
+
+        let truncated = handler
+            .read_text_document(&file_path, None, Some(6))
+            .await?;
+        assert_eq!(truncated, format!("😀{}", TRUNCATION_MARKER));
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_hash() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let first = dir.path().join("first.py");
+        let second = dir.path().join("second.py");
+        fs::write(&first, "def main():\n    pass\n")?;
+        fs::write(&second, "def main():\n    pass\n")?;
+
+        let hash = WorkspaceDocumentsHandler::file_hash(&first)?;
+        assert_eq!(hash, WorkspaceDocumentsHandler::file_hash(&second)?);
+
+        fs::write(&second, "def main():\n    return 1\n")?;
+        assert_ne!(hash, WorkspaceDocumentsHandler::file_hash(&second)?);
+
+        assert!(WorkspaceDocumentsHandler::file_hash(&dir.path().join("missing.py")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_hashes_skip_unchanged_saves() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let file = dir.path().join("main.py");
+        let mut hashes = FileHashes::default();
+
+        fs::write(&file, "def main():\n    pass\n")?;
+        assert!(hashes.update(&file), "a new file counts as changed");
+        assert!(!hashes.update(&file));
+
+        // Saving the same content again is not a change
+        fs::write(&file, "def main():\n    pass\n")?;
+        assert!(!hashes.update(&file));
+
+        fs::write(&file, "def main():\n    return 1\n")?;
+        assert!(hashes.update(&file));
+        assert!(!hashes.update(&file));
+
+        fs::remove_file(&file)?;
+        assert!(hashes.update(&file), "a deleted file counts as changed");
+
+        // Recreating it with its old content is a change, since the deletion was passed on
+        fs::write(&file, "def main():\n    return 1\n")?;
+        assert!(hashes.update(&file));
+        Ok(())
+    }
+
+    #[test]
+    fn test_crlf_line_lengths() {
+        let lengths = crlf_line_lengths("def a():\r\n    pass\n\r\nx = 1");
+        assert_eq!(lengths, HashMap::from([(0, 8), (2, 0)]));
+    }
+
     #[tokio::test]
     async fn test_list_files_no_matching_files() -> Result<(), Box<dyn Error + Send + Sync>> {
         // Setup temporary directory without matching files
@@ -530,4 +953,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_invalidate_new_document() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("existing.txt"), "old")?;
+        let (_, rx) = create_test_watcher_channels();
+        let mut handler = WorkspaceDocumentsHandler::new(
+            dir.path(),
+            vec!["*.txt".to_string()],
+            vec![],
+            rx,
+            DidOpenConfiguration::None,
+        );
+        assert_eq!(handler.list_files().await.len(), 1);
+
+        let existing = dir.path().join("existing.txt");
+        assert_eq!(
+            handler.read_text_document(&existing, None, None).await?,
+            "old"
+        );
+        fs::write(&existing, "new")?;
+        handler.invalidate_document(&existing).await;
+        assert_eq!(
+            handler.read_text_document(&existing, None, None).await?,
+            "new"
+        );
+
+        let created = dir.path().join("created.txt");
+        fs::write(&created, "created")?;
+        handler.invalidate_document(&created).await;
+        assert_eq!(handler.list_files().await.len(), 2);
+
+        let created_str = created.to_str().unwrap();
+        handler.add_did_open_document(created_str);
+        assert_eq!(handler.next_document_version(created_str), 2);
+        assert_eq!(handler.next_document_version(created_str), 3);
+        Ok(())
+    }
 }
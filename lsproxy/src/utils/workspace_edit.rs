@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use lsp_types::{DocumentChangeOperation, DocumentChanges, OneOf, TextEdit, Url, WorkspaceEdit};
+
+use crate::utils::file_utils::{
+    relative_path_string, sanitize_file_path, uri_relative_path_string, SanitizeError,
+};
+use crate::utils::text_edits::apply_text_edits;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ApplyEditError {
+    FileNotFound(String),
+    OutsideWorkspace(String),
+    /// Two edits to the file replace some of the same text.
+    OverlappingEdits(String),
+    Io(String),
+}
+
+impl fmt::Display for ApplyEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyEditError::FileNotFound(path) => write!(f, "File not found: {}", path),
+            ApplyEditError::OutsideWorkspace(path) => {
+                write!(f, "'{}' is outside the workspace", path)
+            }
+            ApplyEditError::OverlappingEdits(path) => {
+                write!(f, "Overlapping edits in {}", path)
+            }
+            ApplyEditError::Io(message) => write!(f, "Failed to write edits: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ApplyEditError {}
+
+/// Applies the text edits of a workspace edit to the files on disk, returning the paths of the
+/// modified files relative to `mount_dir`, sorted.
+///
+/// Edits are taken from both `changes` and `document_changes`; resource operations (creating,
+/// renaming, or deleting files) are not applied. Every file is checked before any is written,
+/// so an edit to a missing file, a file outside `mount_dir`, or overlapping edits leave the
+/// workspace untouched.
+pub fn apply_workspace_edit(
+    edit: &WorkspaceEdit,
+    mount_dir: &Path,
+) -> Result<Vec<String>, ApplyEditError> {
+    let mut files = Vec::new();
+    for (uri, edits) in text_edits_by_uri(edit) {
+        let path = uri_relative_path_string(&uri, mount_dir);
+        let full_path = sanitize_file_path(&path, mount_dir).map_err(|e| match e {
+            SanitizeError::NullByte | SanitizeError::OutsideWorkspace(_) => {
+                ApplyEditError::OutsideWorkspace(path.clone())
+            }
+        })?;
+        if !full_path.is_file() {
+            return Err(ApplyEditError::FileNotFound(path));
+        }
+        if has_overlapping_edits(&edits) {
+            return Err(ApplyEditError::OverlappingEdits(path));
+        }
+        files.push((full_path, edits));
+    }
+
+    let mut modified_files = Vec::new();
+    for (full_path, edits) in files {
+        let content =
+            std::fs::read_to_string(&full_path).map_err(|e| ApplyEditError::Io(e.to_string()))?;
+        std::fs::write(&full_path, apply_text_edits(&content, &edits))
+            .map_err(|e| ApplyEditError::Io(e.to_string()))?;
+        modified_files.push(relative_path_string(&full_path, mount_dir));
+    }
+    modified_files.sort();
+    Ok(modified_files)
+}
+
+/// Collects the text edits of a workspace edit by document.
+fn text_edits_by_uri(edit: &WorkspaceEdit) -> BTreeMap<Url, Vec<TextEdit>> {
+    let mut edits_by_uri: BTreeMap<Url, Vec<TextEdit>> = BTreeMap::new();
+    for (uri, edits) in edit.changes.iter().flatten() {
+        edits_by_uri
+            .entry(uri.clone())
+            .or_default()
+            .extend(edits.iter().cloned());
+    }
+
+    let document_edits = match &edit.document_changes {
+        Some(DocumentChanges::Edits(edits)) => edits.iter().collect(),
+        Some(DocumentChanges::Operations(operations)) => operations
+            .iter()
+            .filter_map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => Some(edit),
+                DocumentChangeOperation::Op(_) => None,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    for document_edit in document_edits {
+        let edits = document_edit.edits.iter().map(|edit| match edit {
+            OneOf::Left(edit) => edit.clone(),
+            OneOf::Right(annotated) => annotated.text_edit.clone(),
+        });
+        edits_by_uri
+            .entry(document_edit.text_document.uri.clone())
+            .or_default()
+            .extend(edits);
+    }
+    edits_by_uri
+}
+
+/// Whether any two edits replace some of the same text. Insertions at the same position don't
+/// overlap.
+fn has_overlapping_edits(edits: &[TextEdit]) -> bool {
+    let mut ranges: Vec<_> = edits.iter().map(|edit| edit.range).collect();
+    ranges.sort_by_key(|range| (range.start, range.end));
+    ranges.windows(2).any(|pair| pair[0].end > pair[1].start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use lsp_types::{OptionalVersionedTextDocumentIdentifier, Position, Range, TextDocumentEdit};
+    use tempfile::TempDir;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.py"), "def foo():\n    return foo\n").unwrap();
+        std::fs::create_dir(dir.path().join("pkg")).unwrap();
+        std::fs::write(dir.path().join("pkg/b.py"), "from a import foo\nfoo()\n").unwrap();
+        dir
+    }
+
+    fn uri(dir: &TempDir, path: &str) -> Url {
+        Url::from_file_path(dir.path().join(path)).unwrap()
+    }
+
+    fn read(dir: &TempDir, path: &str) -> String {
+        std::fs::read_to_string(dir.path().join(path)).unwrap()
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_across_files() {
+        let dir = workspace();
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri(&dir, "a.py"),
+                vec![edit((0, 4), (0, 7), "bar"), edit((1, 11), (1, 14), "bar")],
+            )])),
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri(&dir, "pkg/b.py"),
+                    version: None,
+                },
+                edits: vec![
+                    OneOf::Left(edit((1, 0), (1, 3), "bar")),
+                    OneOf::Left(edit((0, 14), (0, 17), "bar")),
+                ],
+            }])),
+            change_annotations: None,
+        };
+
+        let modified = apply_workspace_edit(&edit, dir.path()).unwrap();
+
+        assert_eq!(modified, vec!["a.py", "pkg/b.py"]);
+        assert_eq!(read(&dir, "a.py"), "def bar():\n    return bar\n");
+        assert_eq!(read(&dir, "pkg/b.py"), "from a import bar\nbar()\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_overlapping_edits() {
+        let dir = workspace();
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([
+                (uri(&dir, "a.py"), vec![edit((0, 4), (0, 7), "bar")]),
+                (
+                    uri(&dir, "pkg/b.py"),
+                    vec![edit((0, 0), (0, 6), "import"), edit((0, 5), (0, 8), "x")],
+                ),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            apply_workspace_edit(&edit, dir.path()),
+            Err(ApplyEditError::OverlappingEdits("pkg/b.py".to_string()))
+        );
+        assert_eq!(read(&dir, "a.py"), "def foo():\n    return foo\n");
+        assert_eq!(read(&dir, "pkg/b.py"), "from a import foo\nfoo()\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_insertions_at_same_position() {
+        let dir = workspace();
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri(&dir, "pkg/b.py"),
+                vec![edit((1, 0), (1, 0), "x = "), edit((1, 0), (1, 3), "bar")],
+            )])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            apply_workspace_edit(&edit, dir.path()).unwrap(),
+            vec!["pkg/b.py"]
+        );
+        assert_eq!(read(&dir, "pkg/b.py"), "from a import foo\nx = bar()\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_missing_file() {
+        let dir = workspace();
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([
+                (uri(&dir, "a.py"), vec![edit((0, 4), (0, 7), "bar")]),
+                (uri(&dir, "missing.py"), vec![edit((0, 0), (0, 0), "x")]),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            apply_workspace_edit(&edit, dir.path()),
+            Err(ApplyEditError::FileNotFound("missing.py".to_string()))
+        );
+        assert_eq!(read(&dir, "a.py"), "def foo():\n    return foo\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_outside_workspace() {
+        let dir = workspace();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("c.py"), "foo\n").unwrap();
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                Url::from_file_path(outside.path().join("c.py")).unwrap(),
+                vec![edit((0, 0), (0, 3), "bar")],
+            )])),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            apply_workspace_edit(&edit, dir.path()),
+            Err(ApplyEditError::OutsideWorkspace(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(outside.path().join("c.py")).unwrap(),
+            "foo\n"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use lsproxy::api_types::set_global_mount_dir;
+use lsproxy::{initialize_app_state, run_server_with_port_and_host};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_server_on_custom_port() -> Result<(), Box<dyn std::error::Error>> {
+    // An empty workspace, so no language servers need to start
+    let mount_dir = tempfile::tempdir()?;
+    let mount_path = mount_dir.path().to_string_lossy().into_owned();
+
+    // Let the OS pick a free port, then release it for the server
+    let port = TcpListener::bind("0.0.0.0:0")?.local_addr()?.port();
+
+    let (tx, rx) = mpsc::channel();
+    let _server_thread = thread::spawn(move || {
+        std::env::set_var("USE_AUTH", "false");
+        set_global_mount_dir(&mount_path);
+
+        let system = actix_web::rt::System::new();
+        if let Err(e) = system.block_on(async {
+            match initialize_app_state().await {
+                Ok(app_state) => run_server_with_port_and_host(app_state, port, "127.0.0.1").await,
+                Err(e) => {
+                    tx.send(format!("Failed to initialize app state: {}", e))
+                        .unwrap();
+                    Ok(())
+                }
+            }
+        }) {
+            tx.send(format!("System error: {}", e)).unwrap();
+        }
+    });
+
+    let client = reqwest::blocking::Client::new();
+    let health_url = format!("http://127.0.0.1:{}/v1/system/health", port);
+    for _ in 0..30 {
+        if let Ok(error_msg) = rx.try_recv() {
+            return Err(error_msg.into());
+        }
+        if let Ok(response) = client.get(&health_url).send() {
+            assert_eq!(response.status(), 200);
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+    Err(format!(
+        "Server was not reachable on port {} within 30 seconds",
+        port
+    )
+    .into())
+}